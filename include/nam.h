@@ -0,0 +1,38 @@
+/*
+	C interface of the nam interpreter, built as a shared library
+	with `cargo build --release` (libnam.so, libnam.dylib or nam.dll).
+*/
+
+#ifndef NAM_H
+#define NAM_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct NamEngine NamEngine;
+
+/*
+	Creates an engine with its own variables, release it with nam_engine_free.
+	An engine may be used from several threads, statements are evaluated one at a time.
+*/
+NamEngine *nam_engine_new(void);
+
+/* Releases an engine, does nothing for NULL. */
+void nam_engine_free(NamEngine *engine);
+
+/*
+	Evaluates one statement and returns a JSON object, release it with nam_string_free:
+	{"ok": true, "value": ...} or {"ok": false, "error": "..."}.
+	Matrices are arrays of rows, NaN and infinities are null.
+*/
+char *nam_eval(const NamEngine *engine, const char *code);
+
+/* Releases a string returned by nam_eval, does nothing for NULL. */
+void nam_string_free(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,128 @@
+use crate::{
+	ast::{ASTNode, ParsingError},
+	bytecode::Compiled,
+	i18n::{tr, tr_with},
+	lexer::{self, LexerConfig, Token, TokenizationError},
+};
+
+/// Another name for a function (`alias det2 = det`),
+/// or a short expression called like a function (`alias sq(x) = x*x`).
+#[derive(Debug, Clone)]
+pub enum Alias {
+	Function(String),
+	Expr {
+		params: Vec<String>,
+		body: ASTNode,
+		source: String,
+		/// The body as compiled by the bytecode backend, on the first call.
+		compiled: Compiled,
+	},
+}
+
+impl Alias {
+	/// Parses the definition of an alias, everything after the `alias` keyword.
+	pub fn parse(definition: &str, config: &LexerConfig) -> Result<(String, Self), AliasError> {
+		let (lhs, rhs) = definition
+			.split_once('=')
+			.ok_or(AliasError::MissingDefinition)?;
+		let rhs = rhs.trim();
+
+		let lhs = lexer::try_tokenize_with(0, lhs, config)?;
+		let (name, params) = match lhs.as_slice() {
+			[Token::Identifier(name), Token::EndOfFile] => (name.clone(), None),
+
+			[Token::Identifier(name), Token::OpenParen, rest @ ..] => {
+				let mut params = vec![];
+				let mut rest = rest;
+				loop {
+					match rest {
+						[Token::CloseParen, Token::EndOfFile] if params.is_empty() => break,
+						[Token::Identifier(param), Token::CloseParen, Token::EndOfFile] => {
+							params.push(param.clone());
+							break;
+						},
+						[Token::Identifier(param), Token::Comma, tail @ ..] => {
+							params.push(param.clone());
+							rest = tail;
+						},
+						_ => return Err(AliasError::InvalidSignature),
+					}
+				}
+				(name.clone(), Some(params))
+			},
+
+			_ => return Err(AliasError::InvalidSignature),
+		};
+
+		let tokens = lexer::try_tokenize_with(0, rhs, config)?;
+		if let (None, [Token::Identifier(target), Token::EndOfFile]) = (&params, &tokens[..]) {
+			return Ok((name, Self::Function(target.clone())));
+		}
+
+		let mut body = ASTNode::try_from(&tokens)?;
+
+		// Only the value is needed, not printing it nor storing it in ans
+		body.silence();
+		body.store_in_ans = false;
+
+		let alias = Self::Expr {
+			params: params.unwrap_or_default(),
+			body,
+			source: rhs.to_string(),
+			compiled: Compiled::default(),
+		};
+
+		Ok((name, alias))
+	}
+}
+
+impl std::fmt::Display for Alias {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Function(target) => write!(f, "{target}"),
+			Self::Expr { source, .. } => write!(f, "{source}"),
+		}
+	}
+}
+
+////////////////////////////////
+//       Error Handling       //
+////////////////////////////////
+
+#[derive(Debug)]
+pub enum AliasError {
+	MissingDefinition,
+	InvalidSignature,
+	Cycle(String),
+	Tokenization(TokenizationError),
+	Parsing(ParsingError),
+}
+
+impl From<TokenizationError> for AliasError {
+	fn from(value: TokenizationError) -> Self {
+		Self::Tokenization(value)
+	}
+}
+
+impl From<ParsingError> for AliasError {
+	fn from(value: ParsingError) -> Self {
+		Self::Parsing(value)
+	}
+}
+
+impl std::error::Error for AliasError {}
+impl std::fmt::Display for AliasError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::MissingDefinition => write!(f, "{}", tr("Expected 'alias name = definition'")),
+			Self::InvalidSignature => {
+				write!(f, "{}", tr("An alias is named 'name' or 'name(a, b, ...)'"))
+			},
+			Self::Cycle(name) => {
+				write!(f, "{}", tr_with("The alias {0} refers to itself", &[name]))
+			},
+			Self::Tokenization(e) => write!(f, "{e}"),
+			Self::Parsing(e) => write!(f, "{e}"),
+		}
+	}
+}
@@ -0,0 +1,357 @@
+use crate::{
+	ast::BinaryOpKind,
+	eval::{EvaluationError, Operation},
+	gpu, linalg,
+	matrix::Matrix,
+	settings::Coercion,
+	state::{RuntimeVal, State},
+	warning::Warning,
+};
+
+/*
+	Arithmetic on numbers and matrices. `+` and `-` work element by element and
+	broadcast scalars, rows and columns like Octave. `*` is the matrix product and
+	`/` the right division `A / B = A * inv(B)`, both element by element when
+	one operand is a scalar. `^` is the matrix power of a square matrix by a
+	scalar. The comparisons `==`, `~=`, `<`, `>`, `<=` and `>=` work element by
+	element and broadcast like `+`, giving 1 where true and 0 elsewhere, so NaN
+	is only different from everything. The logical `&` and `|` also work element by
+	element, and `~` negates every element, taking nonzero as true. NaN is neither
+	true nor false, so it is an error in them. Big integers are demoted to floats.
+*/
+
+enum Operand {
+	Scalar(f64),
+	Matrix(Matrix),
+}
+
+/// Applies a binary operator to two evaluated operands,
+/// divisions by nearly singular matrices are reported to `state`.
+pub fn binary(
+	op: &BinaryOpKind,
+	lhs: RuntimeVal,
+	rhs: RuntimeVal,
+	state: &mut State,
+) -> Result<RuntimeVal, EvaluationError> {
+	let (lhs, rhs) = (operand(lhs)?, operand(rhs)?);
+	if matches!(op, BinaryOpKind::And | BinaryOpKind::Or) && (lhs.has_nan() || rhs.has_nan()) {
+		return Err(EvaluationError::LogicalNaN);
+	}
+
+	let f: fn(f64, f64) -> f64 = match op {
+		BinaryOpKind::Add => |x, y| x + y,
+		BinaryOpKind::Subtract => |x, y| x - y,
+		BinaryOpKind::Multiply => |x, y| x * y,
+		BinaryOpKind::Divide => |x, y| x / y,
+		BinaryOpKind::Power => f64::powf,
+		BinaryOpKind::Equal => |x, y| (x == y) as u8 as f64,
+		BinaryOpKind::NotEqual => |x, y| (x != y) as u8 as f64,
+		BinaryOpKind::Less => |x, y| (x < y) as u8 as f64,
+		BinaryOpKind::Greater => |x, y| (x > y) as u8 as f64,
+		BinaryOpKind::LessEqual => |x, y| (x <= y) as u8 as f64,
+		BinaryOpKind::GreaterEqual => |x, y| (x >= y) as u8 as f64,
+		BinaryOpKind::And => |x, y| (x != 0.0 && y != 0.0) as u8 as f64,
+		BinaryOpKind::Or => |x, y| (x != 0.0 || y != 0.0) as u8 as f64,
+	};
+
+	let (a, b) = match (lhs, rhs) {
+		(Operand::Scalar(x), Operand::Scalar(y)) => return Ok(RuntimeVal::Number(f(x, y))),
+		(Operand::Scalar(x), Operand::Matrix(b)) => (Matrix::from(x), b),
+		(Operand::Matrix(a), Operand::Scalar(y)) => (a, Matrix::from(y)),
+		(Operand::Matrix(a), Operand::Matrix(b)) => (a, b),
+	};
+
+	let res = match apply(op, f, &a, &b, state) {
+		Err(e @ EvaluationError::DimensionsMismatch(_))
+			if state.settings().coercion == Coercion::Permissive =>
+		{
+			transposed(op, f, &a, &b, state).ok_or(e)?
+		},
+		res => res?,
+	};
+
+	Ok(RuntimeVal::Matrix(res))
+}
+
+fn apply(
+	op: &BinaryOpKind,
+	f: fn(f64, f64) -> f64,
+	a: &Matrix,
+	b: &Matrix,
+	state: &mut State,
+) -> Result<Matrix, EvaluationError> {
+	let elementwise = match op {
+		BinaryOpKind::Add
+		| BinaryOpKind::Subtract
+		| BinaryOpKind::Equal
+		| BinaryOpKind::NotEqual
+		| BinaryOpKind::Less
+		| BinaryOpKind::Greater
+		| BinaryOpKind::LessEqual
+		| BinaryOpKind::GreaterEqual
+		| BinaryOpKind::And
+		| BinaryOpKind::Or => true,
+		BinaryOpKind::Multiply => a.len() == 1 || b.len() == 1,
+		BinaryOpKind::Divide => b.len() == 1,
+		BinaryOpKind::Power => a.len() == 1 && b.len() == 1,
+	};
+
+	let operation = match op {
+		BinaryOpKind::Add => Operation::Add,
+		BinaryOpKind::Subtract => Operation::Subtract,
+		BinaryOpKind::Multiply => Operation::Multiply,
+		BinaryOpKind::Divide => Operation::Divide,
+		BinaryOpKind::Power => Operation::Power,
+		BinaryOpKind::Equal
+		| BinaryOpKind::NotEqual
+		| BinaryOpKind::Less
+		| BinaryOpKind::Greater
+		| BinaryOpKind::LessEqual
+		| BinaryOpKind::GreaterEqual => Operation::Compare,
+		BinaryOpKind::And | BinaryOpKind::Or => Operation::Combine,
+	};
+
+	let gpu = state.settings().gpu;
+	if elementwise {
+		if let Some(res) = gpu.then(|| gpu::elementwise(op, a, b)).flatten() {
+			return Ok(res);
+		}
+		a.broadcast(b, f)
+			.map_err(|_| EvaluationError::mismatch(operation, a, b))
+	} else if matches!(op, BinaryOpKind::Multiply) {
+		if a.ncols() != b.nrows() {
+			return Err(EvaluationError::mismatch(operation, a, b));
+		}
+		if let Some(res) = gpu.then(|| gpu::matmul(a, b)).flatten() {
+			return Ok(res);
+		}
+		Ok(linalg::matmul(a, b))
+	} else if matches!(op, BinaryOpKind::Power) {
+		if !a.is_square() || b.len() != 1 {
+			return Err(EvaluationError::mismatch(operation, a, b));
+		}
+		matrix_power(a, b.as_slice()[0])
+	} else {
+		right_divide(a, b, state)
+	}
+}
+
+// In permissive mode, retries an operation whose shapes do not fit with the right
+// vector transposed, then the left one, e.g. `A * v` with v a row
+fn transposed(
+	op: &BinaryOpKind,
+	f: fn(f64, f64) -> f64,
+	a: &Matrix,
+	b: &Matrix,
+	state: &mut State,
+) -> Option<Matrix> {
+	let is_vector = |m: &Matrix| m.len() > 1 && (m.nrows() == 1 || m.ncols() == 1);
+
+	let (res, operand) = if is_vector(b) {
+		(apply(op, f, a, &b.transpose(), state).ok()?, "right")
+	} else if is_vector(a) {
+		(apply(op, f, &a.transpose(), b, state).ok()?, "left")
+	} else {
+		return None;
+	};
+
+	state.warn(Warning::Transposed { operand });
+	Some(res)
+}
+
+/// Computes `c1 * x1 + c2 * x2 + ...` from left to right. When the matrices among
+/// the terms have the same shape, every element is computed in a single pass
+/// without intermediate matrices, otherwise the terms are added one by one.
+/// The terms come with the name of their variable, if any, for errors.
+pub fn linear_combination(
+	terms: Vec<(f64, RuntimeVal, Option<String>)>,
+	state: &mut State,
+) -> Result<RuntimeVal, EvaluationError> {
+	let terms = terms
+		.into_iter()
+		.map(|(c, x, name)| Ok((c, operand(x)?, name)))
+		.collect::<Result<Vec<_>, EvaluationError>>()?;
+
+	let mut shapes = terms.iter().filter_map(|(_, x, _)| match x {
+		Operand::Matrix(m) => Some((m.nrows(), m.ncols())),
+		Operand::Scalar(_) => None,
+	});
+	let shape = shapes.next();
+	let fused = shapes.all(|other| Some(other) == shape);
+
+	let Some((nrows, ncols)) = shape.filter(|_| fused) else {
+		return add_one_by_one(terms, state);
+	};
+
+	if state.settings().gpu {
+		if let Some(res) = linear_combination_on_gpu(&terms) {
+			return Ok(RuntimeVal::Matrix(res));
+		}
+	}
+
+	let element = |x: &Operand, k: usize| match x {
+		Operand::Scalar(x) => *x,
+		Operand::Matrix(m) => m.as_slice()[k],
+	};
+
+	let data = (0..nrows * ncols)
+		.map(|k| {
+			let mut terms = terms.iter();
+			let (c, x, _) = terms.next().unwrap();
+			terms.fold(c * element(x, k), |acc, (c, x, _)| acc + c * element(x, k))
+		})
+		.collect();
+
+	Ok(RuntimeVal::Matrix(Matrix::new(nrows, ncols, data).unwrap()))
+}
+
+// Scalars take part as 1x1 matrices, broadcast by the device
+fn linear_combination_on_gpu(terms: &[(f64, Operand, Option<String>)]) -> Option<Matrix> {
+	let scalars: Vec<Matrix> = terms
+		.iter()
+		.filter_map(|(_, x, _)| match x {
+			Operand::Scalar(x) => Some(Matrix::from(*x)),
+			Operand::Matrix(_) => None,
+		})
+		.collect();
+	let mut scalars = scalars.iter();
+	let terms: Vec<(f64, &Matrix)> = terms
+		.iter()
+		.map(|(c, x, _)| match x {
+			Operand::Scalar(_) => (*c, scalars.next().unwrap()),
+			Operand::Matrix(m) => (*c, m),
+		})
+		.collect();
+	gpu::linear_combination(&terms)
+}
+
+// Negative terms after the first are subtracted, so errors name the operator written
+fn add_one_by_one(
+	terms: Vec<(f64, Operand, Option<String>)>,
+	state: &mut State,
+) -> Result<RuntimeVal, EvaluationError> {
+	let mut res: Option<(RuntimeVal, Option<String>)> = None;
+	for (c, x, name) in terms {
+		let x = match x {
+			Operand::Scalar(x) => RuntimeVal::Number(x),
+			Operand::Matrix(m) => RuntimeVal::Matrix(m),
+		};
+
+		let (op, c) = match &res {
+			Some(_) if c < 0.0 => (BinaryOpKind::Subtract, -c),
+			_ => (BinaryOpKind::Add, c),
+		};
+		// Only a variable taken as is keeps its name
+		let name = name.filter(|_| c == 1.0);
+		let term = binary(&BinaryOpKind::Multiply, RuntimeVal::Number(c), x, state)?;
+
+		res = Some(match res {
+			Some((acc, acc_name)) => (
+				binary(&op, acc, term, state).map_err(|e| e.with_operand_names(acc_name, name))?,
+				None,
+			),
+			None => (term, name),
+		});
+	}
+
+	Ok(res.map_or(RuntimeVal::Number(0.0), |(res, _)| res))
+}
+
+// X = A / B solves X * B = A, that is B' * X' = A'
+fn right_divide(a: &Matrix, b: &Matrix, state: &mut State) -> Result<Matrix, EvaluationError> {
+	if a.ncols() != b.ncols() || !b.is_square() {
+		return Err(EvaluationError::mismatch(Operation::Divide, a, b));
+	}
+
+	let (x, rcond) = linalg::solve_with_rcond(&b.transpose(), &a.transpose())
+		.ok_or(EvaluationError::SingularMatrix)?;
+	if let Some(warning) = Warning::check_rcond(rcond) {
+		state.warn(warning);
+	}
+	Ok(x.transpose())
+}
+
+/// `a^p` for a square `a`. Integer powers are repeated products, and fractional
+/// ones the principal power, defined when no eigenvalue of `a` is negative or 0.
+pub fn matrix_power(a: &Matrix, p: f64) -> Result<Matrix, EvaluationError> {
+	if p.fract() == 0.0 && p.abs() < i64::MAX as f64 {
+		return linalg::matpow(a, p as i64).ok_or(EvaluationError::SingularMatrix);
+	}
+	if !p.is_finite() {
+		return Err(EvaluationError::NoRealPower(p));
+	}
+
+	let eigenvalues = linalg::eigenvalues(a).ok_or(EvaluationError::NoRealPower(p))?;
+	let scale = eigenvalues
+		.iter()
+		.fold(0.0f64, |acc, &(re, im)| acc.max(re.hypot(im)));
+	let threshold = f64::EPSILON * scale * a.nrows() as f64;
+	if eigenvalues
+		.iter()
+		.any(|&(re, im)| im == 0.0 && re <= threshold)
+	{
+		return Err(EvaluationError::NoRealPower(p));
+	}
+
+	linalg::powm(a, p).ok_or(EvaluationError::NoRealPower(p))
+}
+
+/// `~x`, 1 where x is 0 and 0 elsewhere.
+pub fn not(value: RuntimeVal) -> Result<RuntimeVal, EvaluationError> {
+	let x = operand(value)?;
+	if x.has_nan() {
+		return Err(EvaluationError::LogicalNaN);
+	}
+
+	let not = |x: f64| (x == 0.0) as u8 as f64;
+	Ok(match x {
+		Operand::Scalar(x) => RuntimeVal::Number(not(x)),
+		Operand::Matrix(m) => {
+			let data = m.as_slice().iter().map(|&x| not(x)).collect();
+			RuntimeVal::Matrix(Matrix::new(m.nrows(), m.ncols(), data).unwrap())
+		},
+	})
+}
+
+/// Whether an operand of the short-circuit `op`, `&&` or `||`, is true.
+/// It has to be a single number.
+pub fn truth(value: RuntimeVal, op: &'static str) -> Result<bool, EvaluationError> {
+	let x = match operand(value)? {
+		Operand::Scalar(x) => x,
+		Operand::Matrix(m) if m.len() == 1 => m.as_slice()[0],
+		Operand::Matrix(_) => return Err(EvaluationError::NotALogicalScalar(op)),
+	};
+	if x.is_nan() {
+		return Err(EvaluationError::LogicalNaN);
+	}
+	Ok(x != 0.0)
+}
+
+/// Whether the condition of the statement `keyword`, like `if`, is true.
+/// Like the operands of `&&` it has to be a single number.
+pub fn condition(value: RuntimeVal, keyword: &'static str) -> Result<bool, EvaluationError> {
+	truth(value, keyword).map_err(|e| match e {
+		EvaluationError::NotALogicalScalar(_) => EvaluationError::NonScalarCondition(keyword),
+		e => e,
+	})
+}
+
+impl Operand {
+	fn has_nan(&self) -> bool {
+		match self {
+			Self::Scalar(x) => x.is_nan(),
+			Self::Matrix(m) => m.as_slice().iter().any(|x| x.is_nan()),
+		}
+	}
+}
+
+fn operand(value: RuntimeVal) -> Result<Operand, EvaluationError> {
+	match value {
+		RuntimeVal::Number(n) => Ok(Operand::Scalar(n)),
+		RuntimeVal::BigInt(n) => Ok(Operand::Scalar(n.to_f64())),
+		RuntimeVal::Matrix(m) => Ok(Operand::Matrix(m)),
+		RuntimeVal::String(_) | RuntimeVal::Table(_) | RuntimeVal::Function(_) => {
+			Err(EvaluationError::NotANumber)
+		},
+	}
+}
@@ -1,34 +1,121 @@
+use crate::i18n::{tr, tr_with};
 use crate::lexer::Token;
 use color_eyre::eyre::Result;
 use reedline::Span;
+use std::ops::Range;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ASTNode {
 	pub kind: ASTNodeKind,
 	pub store_in_ans: bool,
 	pub print_result: bool,
 
+	// reedline's Span is not serializable, it is None after deserializing
 	#[allow(unused)]
+	#[cfg_attr(feature = "serde", serde(skip))]
 	pub span: Option<Span>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ASTNodeKind {
 	Variable(String),
 	Number(f64),
+	String(String),
 	Matrix(Vec<Vec<ASTNode>>),
+	Range(Box<ASTNode>, Option<Box<ASTNode>>, Box<ASTNode>),
+	Colon,
+	/// The last index of the dimension being indexed, `A(end, :)`.
+	End,
 
 	Assignment(String, Box<ASTNode>),
-
+	/// `A(i, j) = value`, or `A(i, :) = []` to delete rows.
+	IndexedAssignment(String, Vec<ASTNode>, Box<ASTNode>),
+	MultiAssignment(Vec<String>, Box<ASTNode>),
+	Call(String, Vec<ASTNode>),
+	/// A named argument of a call, `header = false` in `readcsv(path, header = false)`.
+	KeywordArg(String, Box<ASTNode>),
+	Field(Box<ASTNode>, String),
+	/// A builtin or an alias used as a value, `@polyval`.
+	FunctionRef(String),
+
+	/// `-x`, it binds tighter than `*` and `/` but not `^` like in Octave.
+	Negate(Box<ASTNode>),
+	/// `+x`, the value of x, which has to be numeric like for `-x`.
+	Plus(Box<ASTNode>),
+	/// `A'`, it binds like `^` so `-A'` is `-(A')` and `A^2'` is `(A^2)'`.
+	Transpose(Box<ASTNode>),
+	/// `~x` or `!x`, 1 where x is 0 and 0 elsewhere, it binds like `-x`.
+	Not(Box<ASTNode>),
+	/// `a && b`, b is only evaluated when a is true. Both have to be scalars.
+	ShortAnd(Box<ASTNode>, Box<ASTNode>),
+	/// `a || b`, b is only evaluated when a is false. Both have to be scalars.
+	ShortOr(Box<ASTNode>, Box<ASTNode>),
 	BinaryExpr(BinaryOpKind, Box<ASTNode>, Box<ASTNode>),
+
+	/// `if c ... elseif d ... else ... end`, the statements of the first branch whose
+	/// condition is true are evaluated, or the ones after `else` if none is.
+	If(Vec<(ASTNode, Vec<ASTNode>)>, Vec<ASTNode>),
+	/// `while c ... end`, the statements are evaluated again as long as c is true.
+	While(Box<ASTNode>, Vec<ASTNode>),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BinaryOpKind {
 	Add,
 	Subtract,
 	Multiply,
 	Divide,
+	/// The matrix power, `A^p` for a square A and a scalar p.
+	Power,
+	/// Element by element, 1 where equal and 0 elsewhere.
+	Equal,
+	/// Element by element like `==`, also written `!=`.
+	NotEqual,
+	Less,
+	Greater,
+	LessEqual,
+	GreaterEqual,
+	/// Element by element, 1 where both are nonzero, `&`.
+	And,
+	/// Element by element, 1 where either is nonzero, `|`.
+	Or,
+}
+
+impl BinaryOpKind {
+	/// Whether the operator compares, giving 1 where true and 0 elsewhere.
+	pub fn is_comparison(&self) -> bool {
+		matches!(
+			self,
+			Self::Equal
+				| Self::NotEqual
+				| Self::Less | Self::Greater
+				| Self::LessEqual
+				| Self::GreaterEqual
+		)
+	}
+}
+
+impl std::fmt::Display for BinaryOpKind {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Add => write!(f, "+"),
+			Self::Subtract => write!(f, "-"),
+			Self::Multiply => write!(f, "*"),
+			Self::Divide => write!(f, "/"),
+			Self::Power => write!(f, "^"),
+			Self::Equal => write!(f, "=="),
+			Self::NotEqual => write!(f, "~="),
+			Self::Less => write!(f, "<"),
+			Self::Greater => write!(f, ">"),
+			Self::LessEqual => write!(f, "<="),
+			Self::GreaterEqual => write!(f, ">="),
+			Self::And => write!(f, "&"),
+			Self::Or => write!(f, "|"),
+		}
+	}
 }
 
 impl TryFrom<Token> for BinaryOpKind {
@@ -40,6 +127,15 @@ impl TryFrom<Token> for BinaryOpKind {
 			Token::OpSubtract => Ok(Self::Subtract),
 			Token::OpMultiply => Ok(Self::Multiply),
 			Token::OpDivide => Ok(Self::Divide),
+			Token::OpPower => Ok(Self::Power),
+			Token::OpEqual => Ok(Self::Equal),
+			Token::OpNotEqual => Ok(Self::NotEqual),
+			Token::OpLess => Ok(Self::Less),
+			Token::OpGreater => Ok(Self::Greater),
+			Token::OpLessEqual => Ok(Self::LessEqual),
+			Token::OpGreaterEqual => Ok(Self::GreaterEqual),
+			Token::OpAnd => Ok(Self::And),
+			Token::OpOr => Ok(Self::Or),
 
 			_ => Err(ParsingError::UnexpectedToken {
 				expected: Some("Operator".to_string()),
@@ -57,12 +153,24 @@ impl TryFrom<&Token> for BinaryOpKind {
 	}
 }
 
+/// The statements of a source, each with the range of its tokens.
+pub type Statements = Vec<(Range<usize>, ASTNode)>;
+
 impl TryFrom<&Vec<Token>> for ASTNode {
 	type Error = ParsingError;
 
+	// A single statement, anything after it but line breaks is an error
 	fn try_from(tokens: &Vec<Token>) -> Result<Self, Self::Error> {
-		let (_, res) = Self::parse_program(0, tokens)?;
-		Ok(res)
+		let (len, res) = Self::parse_stmt(0, tokens, false)?;
+
+		let mut rest = tokens.iter().skip(len + 1);
+		match rest.find(|token| **token != Token::EndOfLine) {
+			Some(Token::EndOfFile) | None => Ok(res),
+			Some(token) => Err(ParsingError::UnexpectedToken {
+				expected: Some(Token::EndOfFile.stringify()),
+				found: Some(token.stringify()),
+			}),
+		}
 	}
 }
 
@@ -78,18 +186,78 @@ impl From<ASTNodeKind> for ASTNode {
 }
 
 impl ASTNode {
-	fn parse_program(idx: usize, tokens: &[Token]) -> Result<(usize, Self), ParsingError> {
-		Self::parse_stmt(idx, tokens)
+	/// Keeps the statement, and the statements of its blocks, from printing their results.
+	pub fn silence(&mut self) {
+		self.print_result = false;
+		match &mut self.kind {
+			ASTNodeKind::If(branches, otherwise) => {
+				let bodies = branches.iter_mut().flat_map(|(_, body)| body);
+				for stmt in bodies.chain(otherwise) {
+					stmt.silence();
+				}
+			},
+			ASTNodeKind::While(_, body) => body.iter_mut().for_each(Self::silence),
+			_ => {},
+		}
+	}
+
+	/// Parses the statements of a whole source, separated by line breaks or `;`.
+	/// Empty lines are skipped, and a statement may span several lines inside
+	/// brackets or parentheses, see the lexer.
+	pub fn parse_program(tokens: &[Token]) -> Result<Vec<Self>, ParsingError> {
+		let stmts = Self::parse_statements(tokens).map_err(|(_, e)| e)?;
+		Ok(stmts.into_iter().map(|(_, stmt)| stmt).collect())
 	}
 
-	fn parse_stmt(idx: usize, tokens: &[Token]) -> Result<(usize, Self), ParsingError> {
-		let (res_len, mut res) = Self::parse_expr(idx, tokens)?;
+	/// Like `parse_program`, with the tokens of each statement without its terminator
+	/// to tell where it is in the source. An error comes with the index of the first
+	/// token of the statement that failed.
+	pub fn parse_statements(tokens: &[Token]) -> Result<Statements, (usize, ParsingError)> {
+		let mut res = vec![];
+		let mut idx = 0;
+
+		loop {
+			match tokens.get(idx) {
+				Some(Token::EndOfLine) => idx += 1,
+				Some(Token::EndOfFile) | None => break,
+				Some(_) => {
+					let (stmt_len, stmt) =
+						Self::parse_stmt(idx, tokens, false).map_err(|e| (idx, e))?;
+					res.push((idx..idx + stmt_len, stmt));
+					// The statement and its terminator
+					idx += stmt_len + 1;
+				},
+			}
+		}
+
+		Ok(res)
+	}
+
+	// Inside a block a statement may also end with a `,`, which prints its result
+	// like a line break, as in `if x > 0, y = 1, end`
+	fn parse_stmt(
+		idx: usize,
+		tokens: &[Token],
+		in_block: bool,
+	) -> Result<(usize, Self), ParsingError> {
+		let (res_len, mut res) = match keyword(tokens.get(idx)) {
+			Some("if") => Self::parse_if(idx, tokens)?,
+			Some("while") => Self::parse_while(idx, tokens)?,
+			Some(keyword @ ("elseif" | "else")) => {
+				return Err(ParsingError::UnmatchedKeyword(keyword.to_string()))
+			},
+			_ => Self::parse_expr(idx, tokens)?,
+		};
 
 		match tokens.get(idx + res_len) {
 			Some(Token::EndOfFile) | Some(Token::EndOfLine) => {
 				res.print_result = true;
 			},
 
+			Some(Token::Comma) if in_block => {
+				res.print_result = true;
+			},
+
 			Some(Token::SemiColon) => {
 				res.print_result = false;
 			},
@@ -106,22 +274,153 @@ impl ASTNode {
 
 		res.store_in_ans = match res.kind {
 			ASTNodeKind::Number(_) => true,
+			ASTNodeKind::String(_) => true,
 			ASTNodeKind::Matrix(_) => true,
+			ASTNodeKind::Negate(_) => true,
+			ASTNodeKind::Plus(_) => true,
+			ASTNodeKind::Transpose(_) => true,
+			ASTNodeKind::Not(_) => true,
+			ASTNodeKind::ShortAnd(_, _) => true,
+			ASTNodeKind::ShortOr(_, _) => true,
 			ASTNodeKind::BinaryExpr(_, _, _) => true,
+			ASTNodeKind::Range(_, _, _) => true,
+			ASTNodeKind::Call(_, _) => true,
+			ASTNodeKind::Field(_, _) => true,
+			ASTNodeKind::FunctionRef(_) => true,
 
 			ASTNodeKind::Variable(_) => false,
+			ASTNodeKind::Colon => false,
+			ASTNodeKind::End => false,
 			ASTNodeKind::Assignment(_, _) => false,
+			ASTNodeKind::IndexedAssignment(_, _, _) => false,
+			ASTNodeKind::MultiAssignment(_, _) => false,
+			ASTNodeKind::KeywordArg(_, _) => false,
+			ASTNodeKind::If(_, _) => false,
+			ASTNodeKind::While(_, _) => false,
 		};
 
 		Ok((res_len, res))
 	}
 
+	// The branches of an `if` up to its `end`
+	fn parse_if(idx: usize, tokens: &[Token]) -> Result<(usize, Self), ParsingError> {
+		let mut branches = vec![];
+		// Consume the `if`
+		let mut consumed_len = 1;
+
+		loop {
+			let (condition_len, condition) =
+				Self::parse_condition(idx + consumed_len, tokens, "if")?;
+			consumed_len += condition_len;
+
+			let (body_len, body) = Self::parse_block(idx + consumed_len, tokens, "if")?;
+			consumed_len += body_len;
+			branches.push((condition, body));
+
+			// Consume the `elseif`, `else` or `end` ending the body
+			consumed_len += 1;
+			match keyword(tokens.get(idx + consumed_len - 1)) {
+				Some("elseif") => continue,
+				Some("else") => {
+					let (body_len, body) = Self::parse_block(idx + consumed_len, tokens, "if")?;
+					consumed_len += body_len;
+
+					if keyword(tokens.get(idx + consumed_len)) != Some("end") {
+						return Err(ParsingError::UnexpectedToken {
+							expected: Some(Token::Identifier(String::from("end")).stringify()),
+							found: tokens.get(idx + consumed_len).map(Token::stringify),
+						});
+					}
+					consumed_len += 1;
+					return Ok((consumed_len, ASTNodeKind::If(branches, body).into()));
+				},
+				_ => return Ok((consumed_len, ASTNodeKind::If(branches, vec![]).into())),
+			}
+		}
+	}
+
+	fn parse_while(idx: usize, tokens: &[Token]) -> Result<(usize, Self), ParsingError> {
+		// Consume the `while`
+		let mut consumed_len = 1;
+
+		let (condition_len, condition) =
+			Self::parse_condition(idx + consumed_len, tokens, "while")?;
+		consumed_len += condition_len;
+
+		let (body_len, body) = Self::parse_block(idx + consumed_len, tokens, "while")?;
+		consumed_len += body_len;
+
+		if keyword(tokens.get(idx + consumed_len)) != Some("end") {
+			return Err(ParsingError::UnexpectedToken {
+				expected: Some(Token::Identifier(String::from("end")).stringify()),
+				found: tokens.get(idx + consumed_len).map(Token::stringify),
+			});
+		}
+		// Consume the `end`
+		consumed_len += 1;
+
+		Ok((
+			consumed_len,
+			ASTNodeKind::While(Box::new(condition), body).into(),
+		))
+	}
+
+	// The condition of a block and the line break, `;` or `,` after it
+	fn parse_condition(
+		idx: usize,
+		tokens: &[Token],
+		block: &str,
+	) -> Result<(usize, Self), ParsingError> {
+		let (condition_len, condition) = Self::parse_expr(idx, tokens)?;
+
+		match tokens.get(idx + condition_len) {
+			Some(Token::EndOfLine | Token::SemiColon | Token::Comma) => {
+				Ok((condition_len + 1, condition))
+			},
+			None | Some(Token::EndOfFile) => {
+				Err(ParsingError::UnterminatedBlock(block.to_string()))
+			},
+			Some(token) => Err(ParsingError::UnexpectedToken {
+				expected: Some(Token::EndOfLine.stringify()),
+				found: Some(token.stringify()),
+			}),
+		}
+	}
+
+	// The statements of a block up to the keyword ending it, `end`, `else` or `elseif`,
+	// which is not consumed
+	fn parse_block(
+		idx: usize,
+		tokens: &[Token],
+		block: &str,
+	) -> Result<(usize, Vec<Self>), ParsingError> {
+		let mut consumed_len = 0;
+		let mut res = vec![];
+
+		loop {
+			match tokens.get(idx + consumed_len) {
+				Some(Token::EndOfLine | Token::SemiColon | Token::Comma) => consumed_len += 1,
+				None | Some(Token::EndOfFile) => {
+					return Err(ParsingError::UnterminatedBlock(block.to_string()))
+				},
+				token if matches!(keyword(token), Some("end" | "else" | "elseif")) => {
+					return Ok((consumed_len, res))
+				},
+				Some(_) => {
+					let (stmt_len, stmt) = Self::parse_stmt(idx + consumed_len, tokens, true)?;
+					res.push(stmt);
+					consumed_len += stmt_len;
+				},
+			}
+		}
+	}
+
 	fn parse_expr(idx: usize, tokens: &[Token]) -> Result<(usize, Self), ParsingError> {
 		Self::parse_assignment_expr(idx, tokens)
 	}
 
 	fn parse_assignment_expr(idx: usize, tokens: &[Token]) -> Result<(usize, Self), ParsingError> {
-		let (primary_len, primary) = Self::parse_additive_expr(idx, tokens)?;
+		let (primary_len, primary) = Self::parse_short_or_expr(idx, tokens)?;
 
 		// Assignment Statement (x = 5)
 		if let ASTNodeKind::Variable(lhs) = &primary.kind {
@@ -134,9 +433,165 @@ impl ASTNode {
 			}
 		}
 
+		// Indexed Assignment Statement (A(1, :) = [1 2])
+		if matches!(primary.kind, ASTNodeKind::Call(_, _))
+			&& tokens.get(idx + primary_len) == Some(&Token::OpAssign)
+		{
+			let ASTNodeKind::Call(var_name, args) = primary.kind else {
+				unreachable!()
+			};
+			if args
+				.iter()
+				.any(|arg| matches!(arg.kind, ASTNodeKind::KeywordArg(_, _)))
+			{
+				return Err(ParsingError::InvalidAssignmentTarget);
+			}
+
+			let (rhs_len, rhs) = Self::parse_expr(idx + primary_len + 1, tokens)?;
+			return Ok((
+				primary_len + 1 + rhs_len,
+				ASTNodeKind::IndexedAssignment(var_name, args, Box::new(rhs)).into(),
+			));
+		}
+
+		// Multiple Assignment Statement ([y, fs] = wavread("a.wav"))
+		if let ASTNodeKind::Matrix(mat) = &primary.kind {
+			if tokens.get(idx + primary_len) == Some(&Token::OpAssign) {
+				let mut lhs = vec![];
+				for cell in mat.iter().flatten() {
+					match &cell.kind {
+						ASTNodeKind::Variable(var_name) if mat.len() == 1 => {
+							lhs.push(var_name.clone())
+						},
+						_ => return Err(ParsingError::InvalidAssignmentTarget),
+					}
+				}
+
+				let (rhs_len, rhs) = Self::parse_expr(idx + primary_len + 1, tokens)?;
+				return Ok((
+					primary_len + 1 + rhs_len,
+					ASTNodeKind::MultiAssignment(lhs, Box::new(rhs)).into(),
+				));
+			}
+		}
+
 		Ok((primary_len, primary))
 	}
 
+	// The logical operators bind looser than comparisons, from the loosest `||`, `&&`,
+	// `|` to `&`, so `a > 0 && b < 5 || c` is `((a > 0) && (b < 5)) || c` like in Octave
+	fn parse_short_or_expr(idx: usize, tokens: &[Token]) -> Result<(usize, Self), ParsingError> {
+		let (mut consumed_len, mut lhs) = Self::parse_short_and_expr(idx, tokens)?;
+
+		while tokens.get(idx + consumed_len) == Some(&Token::OpShortOr) {
+			// Consume the operator
+			consumed_len += 1;
+
+			let (consumed_rhs, rhs) = Self::parse_short_and_expr(idx + consumed_len, tokens)?;
+			consumed_len += consumed_rhs;
+
+			lhs = ASTNodeKind::ShortOr(Box::new(lhs), Box::new(rhs)).into();
+		}
+
+		Ok((consumed_len, lhs))
+	}
+
+	fn parse_short_and_expr(idx: usize, tokens: &[Token]) -> Result<(usize, Self), ParsingError> {
+		let (mut consumed_len, mut lhs) = Self::parse_or_expr(idx, tokens)?;
+
+		while tokens.get(idx + consumed_len) == Some(&Token::OpShortAnd) {
+			// Consume the operator
+			consumed_len += 1;
+
+			let (consumed_rhs, rhs) = Self::parse_or_expr(idx + consumed_len, tokens)?;
+			consumed_len += consumed_rhs;
+
+			lhs = ASTNodeKind::ShortAnd(Box::new(lhs), Box::new(rhs)).into();
+		}
+
+		Ok((consumed_len, lhs))
+	}
+
+	fn parse_or_expr(idx: usize, tokens: &[Token]) -> Result<(usize, Self), ParsingError> {
+		let (mut consumed_len, mut lhs) = Self::parse_and_expr(idx, tokens)?;
+
+		while tokens.get(idx + consumed_len) == Some(&Token::OpOr) {
+			// Consume the operator
+			consumed_len += 1;
+
+			let (consumed_rhs, rhs) = Self::parse_and_expr(idx + consumed_len, tokens)?;
+			consumed_len += consumed_rhs;
+
+			lhs = ASTNodeKind::BinaryExpr(BinaryOpKind::Or, Box::new(lhs), Box::new(rhs)).into();
+		}
+
+		Ok((consumed_len, lhs))
+	}
+
+	fn parse_and_expr(idx: usize, tokens: &[Token]) -> Result<(usize, Self), ParsingError> {
+		let (mut consumed_len, mut lhs) = Self::parse_comparison_expr(idx, tokens)?;
+
+		while tokens.get(idx + consumed_len) == Some(&Token::OpAnd) {
+			// Consume the operator
+			consumed_len += 1;
+
+			let (consumed_rhs, rhs) = Self::parse_comparison_expr(idx + consumed_len, tokens)?;
+			consumed_len += consumed_rhs;
+
+			lhs = ASTNodeKind::BinaryExpr(BinaryOpKind::And, Box::new(lhs), Box::new(rhs)).into();
+		}
+
+		Ok((consumed_len, lhs))
+	}
+
+	// Comparisons bind looser than ranges, `1:3 == x` compares the whole range,
+	// and chain from the left, `a < b < c` is `(a < b) < c` like in Octave
+	fn parse_comparison_expr(idx: usize, tokens: &[Token]) -> Result<(usize, Self), ParsingError> {
+		let (mut consumed_len, mut lhs) = Self::parse_range_expr(idx, tokens)?;
+
+		while let Some(token) = tokens.get(idx + consumed_len) {
+			if !BinaryOpKind::try_from(token).is_ok_and(|op| op.is_comparison()) {
+				break;
+			}
+			// Consume the operator
+			consumed_len += 1;
+
+			let (consumed_rhs, rhs) = Self::parse_range_expr(idx + consumed_len, tokens)?;
+			consumed_len += consumed_rhs;
+
+			lhs = ASTNodeKind::BinaryExpr(token.try_into()?, Box::new(lhs), Box::new(rhs)).into();
+		}
+
+		Ok((consumed_len, lhs))
+	}
+
+	fn parse_range_expr(idx: usize, tokens: &[Token]) -> Result<(usize, Self), ParsingError> {
+		let (mut consumed_len, start) = Self::parse_additive_expr(idx, tokens)?;
+
+		if tokens.get(idx + consumed_len) != Some(&Token::Colon) {
+			return Ok((consumed_len, start));
+		}
+		// Consume the colon
+		consumed_len += 1;
+
+		let (second_len, second) = Self::parse_additive_expr(idx + consumed_len, tokens)?;
+		consumed_len += second_len;
+
+		// Range with a step (start:step:end)
+		if tokens.get(idx + consumed_len) == Some(&Token::Colon) {
+			consumed_len += 1;
+
+			let (end_len, end) = Self::parse_additive_expr(idx + consumed_len, tokens)?;
+			consumed_len += end_len;
+
+			let kind = ASTNodeKind::Range(Box::new(start), Some(Box::new(second)), Box::new(end));
+			return Ok((consumed_len, kind.into()));
+		}
+
+		let kind = ASTNodeKind::Range(Box::new(start), None, Box::new(second));
+		Ok((consumed_len, kind.into()))
+	}
+
 	fn parse_additive_expr(idx: usize, tokens: &[Token]) -> Result<(usize, Self), ParsingError> {
 		let (mut consumed_len, mut lhs) = Self::parse_multiplicative_expr(idx, tokens)?;
 
@@ -160,7 +615,7 @@ impl ASTNode {
 		idx: usize,
 		tokens: &[Token],
 	) -> Result<(usize, Self), ParsingError> {
-		let (mut consumed_len, mut lhs) = Self::parse_parenthesised_expr(idx, tokens)?;
+		let (mut consumed_len, mut lhs) = Self::parse_unary_expr(idx, tokens)?;
 
 		while let Some(token) = tokens.get(idx + consumed_len) {
 			if *token != Token::OpMultiply && *token != Token::OpDivide {
@@ -169,7 +624,7 @@ impl ASTNode {
 			// Consume the operator
 			consumed_len += 1;
 
-			let (consumed_rhs, rhs) = Self::parse_parenthesised_expr(idx + consumed_len, tokens)?;
+			let (consumed_rhs, rhs) = Self::parse_unary_expr(idx + consumed_len, tokens)?;
 			consumed_len += consumed_rhs;
 
 			lhs = ASTNodeKind::BinaryExpr(token.try_into()?, Box::new(lhs), Box::new(rhs)).into();
@@ -178,6 +633,60 @@ impl ASTNode {
 		Ok((consumed_len, lhs))
 	}
 
+	fn parse_unary_expr(idx: usize, tokens: &[Token]) -> Result<(usize, Self), ParsingError> {
+		let unary: fn(Box<ASTNode>) -> ASTNodeKind = match tokens.get(idx) {
+			Some(Token::OpSubtract) => ASTNodeKind::Negate,
+			Some(Token::OpAdd) => ASTNodeKind::Plus,
+			Some(Token::OpNot) => ASTNodeKind::Not,
+			_ => return Self::parse_power_expr(idx, tokens),
+		};
+
+		let (operand_len, operand) = Self::parse_unary_expr(idx + 1, tokens)?;
+		Ok((1 + operand_len, unary(Box::new(operand)).into()))
+	}
+
+	// `^` and the postfix `'` are left associative like in Octave,
+	// `2^3^2` is 64, `-2^2` is -4 and `A''` is A
+	fn parse_power_expr(idx: usize, tokens: &[Token]) -> Result<(usize, Self), ParsingError> {
+		let (mut consumed_len, mut lhs) = Self::parse_parenthesised_expr(idx, tokens)?;
+
+		loop {
+			match tokens.get(idx + consumed_len) {
+				Some(Token::OpTranspose) => {
+					consumed_len += 1;
+					lhs = ASTNodeKind::Transpose(Box::new(lhs)).into();
+				},
+				Some(Token::OpPower) => {
+					// Consume the operator
+					consumed_len += 1;
+
+					let (consumed_rhs, rhs) = Self::parse_exponent(idx + consumed_len, tokens)?;
+					consumed_len += consumed_rhs;
+
+					lhs =
+						ASTNodeKind::BinaryExpr(BinaryOpKind::Power, Box::new(lhs), Box::new(rhs))
+							.into();
+				},
+				_ => break,
+			}
+		}
+
+		Ok((consumed_len, lhs))
+	}
+
+	// An exponent may have a sign or a negation, `A^-1`
+	fn parse_exponent(idx: usize, tokens: &[Token]) -> Result<(usize, Self), ParsingError> {
+		let unary: fn(Box<ASTNode>) -> ASTNodeKind = match tokens.get(idx) {
+			Some(Token::OpSubtract) => ASTNodeKind::Negate,
+			Some(Token::OpAdd) => ASTNodeKind::Plus,
+			Some(Token::OpNot) => ASTNodeKind::Not,
+			_ => return Self::parse_parenthesised_expr(idx, tokens),
+		};
+
+		let (operand_len, operand) = Self::parse_exponent(idx + 1, tokens)?;
+		Ok((1 + operand_len, unary(Box::new(operand)).into()))
+	}
+
 	fn parse_parenthesised_expr(
 		idx: usize,
 		tokens: &[Token],
@@ -222,8 +731,43 @@ impl ASTNode {
 		};
 
 		let kind = match token {
+			Token::Identifier(_) if tokens.get(idx + 1) == Some(&Token::OpenParen) => {
+				return Self::parse_call(idx, tokens);
+			},
+
+			Token::Identifier(var_name) if tokens.get(idx + 1) == Some(&Token::Dot) => {
+				let field_name = match tokens.get(idx + 2) {
+					Some(Token::Identifier(field_name)) => field_name.clone(),
+					None => return Err(ParsingError::UnexpectedEndOfInput),
+					Some(token) => {
+						return Err(ParsingError::UnexpectedToken {
+							expected: Some(Token::Identifier(String::new()).stringify()),
+							found: Some(token.stringify()),
+						})
+					},
+				};
+
+				let var = ASTNodeKind::Variable(var_name.clone()).into();
+				return Ok((3, ASTNodeKind::Field(Box::new(var), field_name).into()));
+			},
+
+			Token::At => match tokens.get(idx + 1) {
+				Some(Token::Identifier(func_name)) => {
+					return Ok((2, ASTNodeKind::FunctionRef(func_name.clone()).into()));
+				},
+				None => return Err(ParsingError::UnexpectedEndOfInput),
+				Some(token) => {
+					return Err(ParsingError::UnexpectedToken {
+						expected: Some(Token::Identifier(String::new()).stringify()),
+						found: Some(token.stringify()),
+					})
+				},
+			},
+
+			Token::Identifier(var_name) if var_name == "end" => ASTNodeKind::End,
 			Token::Identifier(var_name) => ASTNodeKind::Variable(var_name.clone()),
 			Token::NumericLiteral(n) => ASTNodeKind::Number(*n),
+			Token::StringLiteral(string) => ASTNodeKind::String(string.clone()),
 			Token::OpenBrace => return Self::parse_matrix(idx, tokens),
 
 			token => {
@@ -244,6 +788,72 @@ impl ASTNode {
 		Ok((1, res))
 	}
 
+	fn parse_call(idx: usize, tokens: &[Token]) -> Result<(usize, Self), ParsingError> {
+		let func_name = match tokens.get(idx) {
+			Some(Token::Identifier(func_name)) => func_name.clone(),
+			_ => unreachable!(),
+		};
+
+		// Consume the function name and the open paren
+		let mut consumed_len = 2;
+		let mut args = vec![];
+
+		if tokens.get(idx + consumed_len) == Some(&Token::CloseParen) {
+			consumed_len += 1;
+			return Ok((consumed_len, ASTNodeKind::Call(func_name, args).into()));
+		}
+
+		loop {
+			// A lone colon selects everything when indexing (A(:, 1))
+			let is_lone_colon = tokens.get(idx + consumed_len) == Some(&Token::Colon)
+				&& matches!(
+					tokens.get(idx + consumed_len + 1),
+					Some(Token::Comma) | Some(Token::CloseParen)
+				);
+
+			if is_lone_colon {
+				args.push(ASTNodeKind::Colon.into());
+				consumed_len += 1;
+			} else {
+				let (arg_len, arg) = Self::parse_expr(idx + consumed_len, tokens)?;
+				consumed_len += arg_len;
+
+				// `name = value` names the argument instead of assigning a variable
+				match arg.kind {
+					ASTNodeKind::Assignment(name, value) => {
+						args.push(ASTNodeKind::KeywordArg(name, value).into())
+					},
+					_ if args
+						.iter()
+						.any(|arg| matches!(arg.kind, ASTNodeKind::KeywordArg(_, _))) =>
+					{
+						return Err(ParsingError::PositionalAfterKeyword)
+					},
+					_ => args.push(arg),
+				}
+			}
+
+			match tokens.get(idx + consumed_len) {
+				Some(Token::Comma) => consumed_len += 1,
+
+				Some(Token::CloseParen) => {
+					consumed_len += 1;
+					break;
+				},
+
+				None => return Err(ParsingError::UnexpectedEndOfInput),
+				Some(token) => {
+					return Err(ParsingError::UnexpectedToken {
+						expected: Some(Token::CloseParen.stringify()),
+						found: Some(token.stringify()),
+					})
+				},
+			}
+		}
+
+		Ok((consumed_len, ASTNodeKind::Call(func_name, args).into()))
+	}
+
 	fn parse_matrix(idx: usize, tokens: &[Token]) -> Result<(usize, Self), ParsingError> {
 		// Consume the open bracket
 		let mut consumed_len = 1;
@@ -293,13 +903,6 @@ impl ASTNode {
 						consumed_len += 1;
 					}
 
-					if i >= 1 && mat[i - 1].len() != mat[i].len() {
-						return Err(ParsingError::DimensionsMismatch(
-							mat[i - 1].len(),
-							mat[i].len(),
-						));
-					}
-
 					mat.push(vec![]);
 					i += 1;
 				},
@@ -313,13 +916,7 @@ impl ASTNode {
 			}
 		}
 
-		if i >= 1 && mat[i - 1].len() != mat[i].len() {
-			return Err(ParsingError::DimensionsMismatch(
-				mat[i - 1].len(),
-				mat[i].len(),
-			));
-		}
-
+		// The rows are checked once evaluated, a cell can be a whole matrix like `[A; 1 2]`
 		let res = Self {
 			kind: ASTNodeKind::Matrix(mat),
 			span: None,
@@ -331,6 +928,45 @@ impl ASTNode {
 	}
 }
 
+// The name of an identifier, for the keywords which the lexer reads as identifiers
+fn keyword(token: Option<&Token>) -> Option<&str> {
+	match token {
+		Some(Token::Identifier(name)) => Some(name),
+		_ => None,
+	}
+}
+
+/// How many blocks like `if ... end` or `while ... end` are still open after the tokens,
+/// so the REPL reads more lines before evaluating them. Only the keywords starting a statement
+/// count, `end` in `A(end)` does not close anything.
+pub fn open_blocks(tokens: &[Token]) -> usize {
+	let mut depth: usize = 0;
+	let mut nesting: usize = 0;
+	let mut starts_stmt = true;
+
+	for token in tokens {
+		match token {
+			Token::OpenParen | Token::OpenBrace | Token::OpenCurly => nesting += 1,
+			Token::CloseParen | Token::CloseBrace | Token::CloseCurly => {
+				nesting = nesting.saturating_sub(1)
+			},
+			Token::Identifier(name) if starts_stmt && nesting == 0 => match name.as_str() {
+				"if" | "while" => depth += 1,
+				"end" => depth = depth.saturating_sub(1),
+				_ => {},
+			},
+			_ => {},
+		}
+
+		// A statement may follow `else` on the same line, `else if` opens a block of its own
+		let separates = matches!(token, Token::EndOfLine | Token::SemiColon | Token::Comma);
+		let is_else = starts_stmt && keyword(Some(token)) == Some("else");
+		starts_stmt = nesting == 0 && (separates || is_else);
+	}
+
+	depth
+}
+
 ////////////////////////////////
 //       Error Handling       //
 ////////////////////////////////
@@ -338,7 +974,12 @@ impl ASTNode {
 #[derive(Debug)]
 pub enum ParsingError {
 	EmptyMatrixElement,
-	DimensionsMismatch(usize, usize),
+	InvalidAssignmentTarget,
+	PositionalAfterKeyword,
+	/// `else` or `elseif` outside of an `if`.
+	UnmatchedKeyword(String),
+	/// A block like `if` or `while` without its `end`.
+	UnterminatedBlock(String),
 	UnexpectedEndOfInput,
 	UnexpectedToken {
 		expected: Option<String>,
@@ -350,18 +991,53 @@ impl std::error::Error for ParsingError {}
 impl std::fmt::Display for ParsingError {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		match self {
-			Self::EmptyMatrixElement => write!(f, "Empty matrix elements are not allowed"),
-			Self::DimensionsMismatch(i, j) => write!(f, "Dimensions mismatch ({i} vs {j})"),
+			Self::EmptyMatrixElement => {
+				write!(f, "{}", tr("Empty matrix elements are not allowed"))
+			},
+
+			Self::InvalidAssignmentTarget => {
+				write!(
+					f,
+					"{}",
+					tr("Only a variable, its elements, or a row of variable names can be assigned to")
+				)
+			},
+
+			Self::PositionalAfterKeyword => {
+				write!(
+					f,
+					"{}",
+					tr("Named arguments must come after the positional ones")
+				)
+			},
+
+			Self::UnmatchedKeyword(keyword) => {
+				write!(
+					f,
+					"{}",
+					tr_with("'{0}' without a matching 'if'", &[keyword])
+				)
+			},
+
+			Self::UnterminatedBlock(keyword) => {
+				write!(
+					f,
+					"{}",
+					tr_with("'{0}' without a matching 'end'", &[keyword])
+				)
+			},
 
-			Self::UnexpectedEndOfInput => write!(f, "Unexpected end of input tokens array"),
+			Self::UnexpectedEndOfInput => {
+				write!(f, "{}", tr("Unexpected end of input tokens array"))
+			},
 
 			Self::UnexpectedToken { expected, found } => {
-				let mut res = String::from("Unexpected token");
+				let mut res = tr("Unexpected token").to_string();
 				if let Some(expected) = expected {
-					res = format!("{res}, expected '{expected}'");
+					res = tr_with("{0}, expected '{1}'", &[&res, expected]);
 				}
 				if let Some(found) = found {
-					res = format!("{res}, found '{found}'");
+					res = tr_with("{0}, found '{1}'", &[&res, found]);
 				}
 				write!(f, "{res}")
 			},
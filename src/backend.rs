@@ -0,0 +1,118 @@
+use crate::{
+	ast::ASTNode,
+	bytecode::{Chunk, Compiled},
+	eval::{self, EvaluationError},
+	state::{RuntimeVal, State},
+};
+
+/// Runs parsed statements against a workspace. The REPL, engines, background jobs
+/// and `parrows` all go through the backend of their workspace, and so do the calls
+/// of aliases, so another way of evaluating only has to implement this trait.
+pub trait Evaluator: Send + Sync {
+	/// Evaluates a top level statement and reports the outcome to the observers.
+	fn evaluate_stmt(&self, ast: ASTNode, state: &mut State)
+		-> Result<RuntimeVal, EvaluationError>;
+
+	/// Evaluates an expression nested in another evaluation, like the argument of `async`.
+	fn evaluate(&self, ast: ASTNode, state: &mut State) -> Result<RuntimeVal, EvaluationError>;
+
+	/// Evaluates the body of an expression alias in the scope of a call, `compiled`
+	/// keeping what the evaluator made of the body for the next calls.
+	fn evaluate_alias(
+		&self,
+		body: &ASTNode,
+		compiled: &Compiled,
+		scope: &mut State,
+	) -> Result<RuntimeVal, EvaluationError>;
+}
+
+/// Walks the syntax tree, see the `eval` module.
+pub struct TreeWalker;
+
+impl Evaluator for TreeWalker {
+	fn evaluate_stmt(
+		&self,
+		ast: ASTNode,
+		state: &mut State,
+	) -> Result<RuntimeVal, EvaluationError> {
+		eval::evaluate_stmt(ast, state)
+	}
+
+	fn evaluate(&self, ast: ASTNode, state: &mut State) -> Result<RuntimeVal, EvaluationError> {
+		eval::evaluate(ast, state)
+	}
+
+	fn evaluate_alias(
+		&self,
+		body: &ASTNode,
+		_compiled: &Compiled,
+		scope: &mut State,
+	) -> Result<RuntimeVal, EvaluationError> {
+		eval::evaluate(body.clone(), scope)
+	}
+}
+
+/// Compiles statements to instructions run on a stack, see the `bytecode` module.
+pub struct Bytecode;
+
+impl Evaluator for Bytecode {
+	fn evaluate_stmt(
+		&self,
+		ast: ASTNode,
+		state: &mut State,
+	) -> Result<RuntimeVal, EvaluationError> {
+		eval::evaluate_stmt_with(ast, state, |ast, state| Chunk::compile(&ast).run(state))
+	}
+
+	fn evaluate(&self, ast: ASTNode, state: &mut State) -> Result<RuntimeVal, EvaluationError> {
+		Chunk::compile(&ast).run(state)
+	}
+
+	fn evaluate_alias(
+		&self,
+		body: &ASTNode,
+		compiled: &Compiled,
+		scope: &mut State,
+	) -> Result<RuntimeVal, EvaluationError> {
+		compiled.get_or_compile(body).run(scope)
+	}
+}
+
+/// The evaluators a workspace can use, chosen with `nam --backend <name>`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Backend {
+	#[default]
+	TreeWalker,
+	Bytecode,
+}
+
+impl Backend {
+	pub const ALL: &'static [Self] = &[Self::TreeWalker, Self::Bytecode];
+
+	pub fn evaluator(self) -> &'static dyn Evaluator {
+		match self {
+			Self::TreeWalker => &TreeWalker,
+			Self::Bytecode => &Bytecode,
+		}
+	}
+
+	pub fn name(self) -> &'static str {
+		match self {
+			Self::TreeWalker => "tree",
+			Self::Bytecode => "bytecode",
+		}
+	}
+
+	pub fn from_name(name: &str) -> Option<Self> {
+		Self::ALL
+			.iter()
+			.copied()
+			.find(|backend| backend.name() == name)
+	}
+}
+
+impl std::fmt::Display for Backend {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.name())
+	}
+}
@@ -0,0 +1,109 @@
+/// A non-negative integer of any size, used for exact combinatorics.
+/// Stored as base 10^9 limbs, least significant first, without leading zero limbs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigUint {
+	limbs: Vec<u32>,
+}
+
+const BASE: u64 = 1_000_000_000;
+
+// Above this, not every integer can be represented exactly by a f64
+pub const MAX_EXACT_F64: u64 = 1 << 53;
+
+impl BigUint {
+	/// Returns the value as a `u64` if it fits.
+	pub fn to_u64(&self) -> Option<u64> {
+		let mut res: u64 = 0;
+		for &limb in self.limbs.iter().rev() {
+			res = res.checked_mul(BASE)?.checked_add(limb as u64)?;
+		}
+		Some(res)
+	}
+
+	/// The nearest float, infinity if the value is too large.
+	pub fn to_f64(&self) -> f64 {
+		self.to_string().parse().unwrap_or(f64::INFINITY)
+	}
+
+	pub fn mul_small(&mut self, n: u64) {
+		if n == 0 {
+			self.limbs.clear();
+			return;
+		}
+
+		let mut carry: u128 = 0;
+		for limb in &mut self.limbs {
+			let prod = *limb as u128 * n as u128 + carry;
+			*limb = (prod % BASE as u128) as u32;
+			carry = prod / BASE as u128;
+		}
+		while carry > 0 {
+			self.limbs.push((carry % BASE as u128) as u32);
+			carry /= BASE as u128;
+		}
+	}
+
+	/// Divides in place and returns the remainder, `n` must not be zero.
+	pub fn div_small(&mut self, n: u64) -> u64 {
+		let mut rem: u128 = 0;
+		for limb in self.limbs.iter_mut().rev() {
+			let cur = rem * BASE as u128 + *limb as u128;
+			*limb = (cur / n as u128) as u32;
+			rem = cur % n as u128;
+		}
+		self.trim();
+		rem as u64
+	}
+
+	fn trim(&mut self) {
+		while self.limbs.last() == Some(&0) {
+			self.limbs.pop();
+		}
+	}
+}
+
+impl From<u64> for BigUint {
+	fn from(mut n: u64) -> Self {
+		let mut limbs = vec![];
+		while n > 0 {
+			limbs.push((n % BASE) as u32);
+			n /= BASE;
+		}
+		Self { limbs }
+	}
+}
+
+impl std::str::FromStr for BigUint {
+	type Err = ();
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+			return Err(());
+		}
+
+		let mut limbs: Vec<u32> = s
+			.as_bytes()
+			.rchunks(9)
+			.map(|chunk| std::str::from_utf8(chunk).unwrap().parse().unwrap())
+			.collect();
+		while limbs.last() == Some(&0) {
+			limbs.pop();
+		}
+
+		Ok(Self { limbs })
+	}
+}
+
+impl std::fmt::Display for BigUint {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let Some((last, rest)) = self.limbs.split_last() else {
+			return write!(f, "0");
+		};
+
+		write!(f, "{last}")?;
+		for limb in rest.iter().rev() {
+			write!(f, "{limb:09}")?;
+		}
+		Ok(())
+	}
+}
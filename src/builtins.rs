@@ -0,0 +1,576 @@
+mod asserts;
+mod audio;
+mod blocks;
+mod clipboard;
+mod combinatorics;
+mod control;
+mod csv;
+mod display;
+mod elementary;
+mod encoding;
+mod functional;
+mod graph;
+mod histogram;
+mod image;
+mod iterative;
+mod jobs;
+mod linear;
+mod logical;
+mod markov;
+mod missing;
+mod optim;
+mod parallel;
+mod poly;
+mod predicates;
+mod random;
+mod sets;
+mod special;
+mod table;
+mod timeseries;
+mod transforms;
+mod workspace;
+
+use crate::{
+	eval::EvaluationError,
+	i18n::{tr, tr_with},
+	matrix::Matrix,
+	state::{RuntimeVal, State},
+	table::Table,
+};
+
+/// A builtin function takes the evaluated arguments and returns its outputs,
+/// which may be empty for functions called only for their side effects.
+pub type Builtin = fn(&mut State, Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError>;
+
+/// The builtin of that name and the arguments it accepts, which are checked
+/// before it is called so builtins only validate their values.
+pub fn get(func_name: &str) -> Option<(Builtin, Signature)> {
+	use ArgType::*;
+
+	let res: (Builtin, Signature) = match func_name {
+		"imread" => (image::imread, Signature::between(1, &[String, Integer])),
+		"imwrite" => (image::imwrite, Signature::exactly(&[Matrix, String])),
+		"wavread" => (audio::wavread, Signature::exactly(&[String])),
+		"wavwrite" => (
+			audio::wavwrite,
+			Signature::exactly(&[Matrix, Number, String]),
+		),
+		"readcsv" => (
+			csv::readcsv,
+			Signature::between(1, &[String, String, Any, Any]),
+		),
+		"csvreduce" => (
+			csv::csvreduce,
+			Signature::between(2, &[String, Any, Integer, String, Any]),
+		),
+		"summary" => (table::summary, Signature::exactly(&[Table])),
+		"groupby" => (
+			table::groupby,
+			Signature::at_least(&[Table, String, String]),
+		),
+		"join" => (table::join, Signature::exactly(&[Table, Table, String])),
+		"setlabels" => (
+			table::setlabels,
+			Signature::between(2, &[Matrix, String, String]),
+		),
+		"parrows" => (parallel::parrows, Signature::exactly(&[String, Matrix])),
+		"wait" => (jobs::wait, Signature::exactly(&[Integer])),
+		"sin" => (elementary::sin, Signature::exactly(&[Matrix])),
+		"cos" => (elementary::cos, Signature::exactly(&[Matrix])),
+		"tan" => (elementary::tan, Signature::exactly(&[Matrix])),
+		"exp" => (elementary::exp, Signature::exactly(&[Matrix])),
+		"log" => (elementary::log, Signature::exactly(&[Matrix])),
+		"sqrt" => (elementary::sqrt, Signature::exactly(&[Matrix])),
+		"abs" => (elementary::abs, Signature::exactly(&[Matrix])),
+		"movsum" => (timeseries::movsum, Signature::exactly(&[Matrix, Integer])),
+		"movmean" => (timeseries::movmean, Signature::exactly(&[Matrix, Integer])),
+		"movmax" => (timeseries::movmax, Signature::exactly(&[Matrix, Integer])),
+		"movmin" => (timeseries::movmin, Signature::exactly(&[Matrix, Integer])),
+		"resample" => (
+			timeseries::resample,
+			Signature::exactly(&[Matrix, Integer, Integer]),
+		),
+		"nansum" => (missing::nansum, Signature::exactly(&[Matrix])),
+		"nanmean" => (missing::nanmean, Signature::exactly(&[Matrix])),
+		"fillmissing" => (
+			missing::fillmissing,
+			Signature::between(2, &[Matrix, String, Number]),
+		),
+		"rmoutliers" => (missing::rmoutliers, Signature::exactly(&[Matrix])),
+		"factorial" => (combinatorics::factorial, Signature::exactly(&[Matrix])),
+		"nchoosek" => (
+			combinatorics::nchoosek,
+			Signature::exactly(&[Vector, Integer]),
+		),
+		"perms" => (combinatorics::perms, Signature::exactly(&[Vector])),
+		"permmatrix" => (combinatorics::permmatrix, Signature::exactly(&[Vector])),
+		"linprog" => (
+			optim::linprog,
+			Signature::exactly(&[Vector, Matrix, Vector]),
+		),
+		"roots" => (poly::roots, Signature::exactly(&[Vector])),
+		"poly" => (poly::poly, Signature::exactly(&[Vector])),
+		"polyval" => (poly::polyval, Signature::exactly(&[Vector, Matrix])),
+		"polyder" => (poly::polyder, Signature::exactly(&[Vector])),
+		"polyint" => (poly::polyint, Signature::between(1, &[Vector, Number])),
+		"conv" => (poly::conv, Signature::exactly(&[Vector, Vector])),
+		"ss2tf" => (
+			control::ss2tf,
+			Signature::exactly(&[SquareMatrix, Matrix, Matrix, Matrix]),
+		),
+		"tf2ss" => (control::tf2ss, Signature::exactly(&[Vector, Vector])),
+		"ctrb" => (control::ctrb, Signature::exactly(&[SquareMatrix, Matrix])),
+		"obsv" => (control::obsv, Signature::exactly(&[SquareMatrix, Matrix])),
+		"lyap" => (
+			control::lyap,
+			Signature::exactly(&[SquareMatrix, SquareMatrix]),
+		),
+		"degree" => (graph::degree, Signature::exactly(&[SquareMatrix])),
+		"laplacian" => (graph::laplacian, Signature::exactly(&[SquareMatrix])),
+		"shortestpath" => (
+			graph::shortestpath,
+			Signature::between(1, &[SquareMatrix, Integer, Integer]),
+		),
+		"connectedcomponents" => (
+			graph::connectedcomponents,
+			Signature::exactly(&[SquareMatrix]),
+		),
+		"ispstochastic" => (markov::ispstochastic, Signature::exactly(&[Matrix])),
+		"stationary" => (markov::stationary, Signature::exactly(&[SquareMatrix])),
+		"mpower" => (markov::mpower, Signature::exactly(&[SquareMatrix, Integer])),
+		"union" => (sets::union, Signature::exactly(&[Matrix, Matrix])),
+		"intersect" => (sets::intersect, Signature::exactly(&[Matrix, Matrix])),
+		"setdiff" => (sets::setdiff, Signature::exactly(&[Matrix, Matrix])),
+		"ismember" => (sets::ismember, Signature::exactly(&[Matrix, Matrix])),
+		"histc" => (
+			histogram::histc,
+			Signature::between(2, &[Matrix, Vector, String]),
+		),
+		"histcounts" => (
+			histogram::histcounts,
+			Signature::between(1, &[Matrix, Matrix, String]),
+		),
+		"randperm" => (random::randperm, Signature::between(1, &[Integer, Integer])),
+		"shuffle" => (random::shuffle, Signature::exactly(&[Matrix])),
+		"randorth" => (random::randorth, Signature::exactly(&[Integer])),
+		"randspd" => (random::randspd, Signature::exactly(&[Integer, Number])),
+		"randsparse" => (random::randsparse, Signature::exactly(&[Integer, Number])),
+		"sample" => (
+			random::sample,
+			Signature::between(2, &[Matrix, Integer, Number]),
+		),
+		"issymmetric" => (
+			predicates::issymmetric,
+			Signature::between(1, &[Matrix, Number]),
+		),
+		"isdiag" => (predicates::isdiag, Signature::exactly(&[Matrix])),
+		"istriu" => (predicates::istriu, Signature::exactly(&[Matrix])),
+		"istril" => (predicates::istril, Signature::exactly(&[Matrix])),
+		"isposdef" => (
+			predicates::isposdef,
+			Signature::between(1, &[Matrix, Number]),
+		),
+		"linsolve" => (
+			linear::linsolve,
+			Signature::between(2, &[SquareMatrix, Matrix, Integer]),
+		),
+		"pcg" => (
+			iterative::pcg,
+			Signature::between(2, &[Any, Vector, Number, Integer, Any]),
+		),
+		"gmres" => (
+			iterative::gmres,
+			Signature::between(2, &[Any, Vector, Integer, Number, Integer]),
+		),
+		"sqrtm" => (linear::sqrtm, Signature::exactly(&[SquareMatrix])),
+		"householder" => (linear::householder, Signature::exactly(&[Vector])),
+		"givens" => (linear::givens, Signature::exactly(&[Number, Number])),
+		"rcond" => (linear::rcond, Signature::exactly(&[SquareMatrix])),
+		"det" => (linear::det, Signature::exactly(&[SquareMatrix])),
+		"inv" => (linear::inv, Signature::exactly(&[SquareMatrix])),
+		"rotx" => (transforms::rotx, Signature::exactly(&[Number])),
+		"roty" => (transforms::roty, Signature::exactly(&[Number])),
+		"rotz" => (transforms::rotz, Signature::exactly(&[Number])),
+		"trans" => (
+			transforms::trans,
+			Signature::exactly(&[Number, Number, Number]),
+		),
+		"quat2rotm" => (transforms::quat2rotm, Signature::exactly(&[Vector])),
+		"rotm2quat" => (transforms::rotm2quat, Signature::exactly(&[SquareMatrix])),
+		"getblock" => (
+			blocks::getblock,
+			Signature::exactly(&[Matrix, Integer, Integer, Integer, Integer]),
+		),
+		"setblock" => (
+			blocks::setblock,
+			Signature::exactly(&[Matrix, Integer, Integer, Matrix]),
+		),
+		"lu" => (linear::lu, Signature::exactly(&[SquareMatrix])),
+		"rref" => (linear::rref, Signature::exactly(&[Matrix])),
+		"tril" => (special::tril, Signature::between(1, &[Matrix, Integer])),
+		"triu" => (special::triu, Signature::between(1, &[Matrix, Integer])),
+		"toeplitz" => (special::toeplitz, Signature::between(1, &[Vector, Vector])),
+		"vander" => (special::vander, Signature::between(1, &[Vector, Integer])),
+		"hilb" => (special::hilb, Signature::exactly(&[Integer])),
+		"magic" => (special::magic, Signature::exactly(&[Integer])),
+		"full" => (display::full, Signature::exactly(&[Any])),
+		"report" => (display::report, Signature::exactly(&[String])),
+		"mat2str" => (encoding::mat2str, Signature::between(1, &[Matrix, String])),
+		"str2mat" => (encoding::str2mat, Signature::exactly(&[String])),
+		"assert_eq_matrix" => (
+			asserts::assert_eq_matrix,
+			Signature::between(2, &[Matrix, Matrix, Number]),
+		),
+		"assert_shape" => (
+			asserts::assert_shape,
+			Signature::exactly(&[Any, Integer, Integer]),
+		),
+		"all" => (logical::all, Signature::exactly(&[Matrix])),
+		"any" => (logical::any, Signature::exactly(&[Matrix])),
+		"isequal" => (logical::isequal, Signature::at_least(&[Any, Any])),
+		"copy" => (clipboard::copy, Signature::between(1, &[Any, String])),
+		"map" => (functional::map, Signature::exactly(&[Function, Matrix])),
+		"bind" => (functional::bind, Signature::at_least(&[Function, Any])),
+		"checkpoint" => (workspace::checkpoint, Signature::exactly(&[String])),
+		"resume" => (workspace::resume, Signature::exactly(&[String])),
+		"wsdiff" => (workspace::wsdiff, Signature::between(1, &[String, String])),
+
+		_ => return None,
+	};
+
+	Some(res)
+}
+
+/// An optional argument of a builtin that may also be passed by name,
+/// like `readcsv(path, header = false)`.
+pub struct Keyword {
+	pub name: &'static str,
+	/// Its position among the arguments, from 0.
+	pub position: usize,
+	/// Its value when a later argument is given by name but not this one.
+	pub default: fn(&State) -> RuntimeVal,
+}
+
+/// The arguments of a builtin that may be passed by name, by increasing position.
+pub fn keywords(func_name: &str) -> &'static [Keyword] {
+	match func_name {
+		"readcsv" => csv::READCSV_KEYWORDS,
+		"csvreduce" => csv::CSVREDUCE_KEYWORDS,
+		"linsolve" => linear::LINSOLVE_KEYWORDS,
+		"pcg" => iterative::PCG_KEYWORDS,
+		"gmres" => iterative::GMRES_KEYWORDS,
+		_ => &[],
+	}
+}
+
+/// Places the arguments passed by name at their position after the positional ones,
+/// the optional arguments in between take their default value.
+pub fn bind_keywords(
+	func_name: &str,
+	mut args: Vec<RuntimeVal>,
+	named: Vec<(String, RuntimeVal)>,
+	state: &State,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let keywords = keywords(func_name);
+	let invalid = |message| EvaluationError::InvalidArguments(func_name.to_string(), message);
+
+	let mut values: Vec<Option<RuntimeVal>> = vec![None; keywords.len()];
+	for (name, value) in named {
+		let Some(i) = keywords.iter().position(|keyword| keyword.name == name) else {
+			return Err(invalid(tr_with("unknown named argument '{0}'", &[&name])));
+		};
+		if keywords[i].position < args.len() {
+			return Err(invalid(tr_with(
+				"'{0}' is given both by position and by name",
+				&[&name],
+			)));
+		}
+		if values[i].replace(value).is_some() {
+			return Err(invalid(tr_with("'{0}' is given twice", &[&name])));
+		}
+	}
+
+	let Some(last) = values.iter().rposition(Option::is_some) else {
+		return Ok(args);
+	};
+	for (keyword, value) in keywords[..=last].iter().zip(values) {
+		if keyword.position < args.len() {
+			continue;
+		}
+		if keyword.position != args.len() {
+			return Err(invalid(tr_with(
+				"expected {0} positional argument(s) before the named ones",
+				&[&keyword.position],
+			)));
+		}
+		args.push(value.unwrap_or_else(|| (keyword.default)(state)));
+	}
+
+	Ok(args)
+}
+
+/// Whether a builtin always returns the same outputs for the same arguments
+/// and has no side effects, like printing, so its results may be cached.
+pub fn is_pure(func_name: &str) -> bool {
+	!matches!(
+		func_name,
+		"imread"
+			| "imwrite"
+			| "wavread"
+			| "wavwrite"
+			| "readcsv"
+			| "csvreduce"
+			| "parrows"
+			| "wait" | "randperm"
+			| "shuffle"
+			| "sample"
+			| "randorth"
+			| "randspd"
+			| "randsparse"
+			| "histc" | "histcounts"
+			| "full" | "summary"
+			| "copy" | "checkpoint"
+			| "resume"
+			| "wsdiff"
+			| "report"
+	) && get(func_name).is_some()
+}
+
+////////////////////////////////
+//     Argument Handling      //
+////////////////////////////////
+
+/// What a builtin expects of one of its arguments.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArgType {
+	Any,
+	/// A number or a 1x1 matrix.
+	Number,
+	/// A number without a fractional part.
+	Integer,
+	String,
+	/// A number or a matrix.
+	Matrix,
+	/// A row or a column, possibly empty.
+	Vector,
+	SquareMatrix,
+	Table,
+	/// A function handle, like `@polyval`.
+	Function,
+}
+
+impl ArgType {
+	fn accepts(&self, arg: &RuntimeVal) -> bool {
+		let shape = match arg {
+			RuntimeVal::Number(_) | RuntimeVal::BigInt(_) => Some((1, 1)),
+			RuntimeVal::Matrix(m) => Some((m.nrows(), m.ncols())),
+			RuntimeVal::String(_) | RuntimeVal::Table(_) | RuntimeVal::Function(_) => None,
+		};
+
+		match (self, arg, shape) {
+			(Self::Any, _, _) => true,
+			(Self::String, RuntimeVal::String(_), _) => true,
+			(Self::Table, RuntimeVal::Table(_), _) => true,
+			(Self::Function, RuntimeVal::Function(_), _) => true,
+			(Self::Number, _, Some((1, 1))) => true,
+			(Self::Integer, RuntimeVal::BigInt(_), _) => true,
+			(Self::Integer, RuntimeVal::Number(n), _) => n.fract() == 0.0,
+			(Self::Integer, RuntimeVal::Matrix(m), Some((1, 1))) => m.as_slice()[0].fract() == 0.0,
+			(Self::Matrix, _, Some(_)) => true,
+			(Self::Vector, _, Some((nrows, ncols))) => nrows <= 1 || ncols <= 1,
+			(Self::SquareMatrix, _, Some((nrows, ncols))) => nrows == ncols,
+			_ => false,
+		}
+	}
+
+	// The noun used when counting arguments, `None` for any value
+	fn noun(&self) -> Option<&'static str> {
+		match self {
+			Self::Any => None,
+			Self::Number => Some("number"),
+			Self::Integer => Some("integer"),
+			Self::String => Some("string"),
+			Self::Matrix => Some("matrix"),
+			Self::Vector => Some("vector"),
+			Self::SquareMatrix => Some("square matrix"),
+			Self::Table => Some("table"),
+			Self::Function => Some("function"),
+		}
+	}
+}
+
+impl std::fmt::Display for ArgType {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let text = match self {
+			Self::Any => "any value",
+			Self::Number => "a number",
+			Self::Integer => "an integer",
+			Self::String => "a string",
+			Self::Matrix => "a matrix",
+			Self::Vector => "a vector",
+			Self::SquareMatrix => "a square matrix",
+			Self::Table => "a table",
+			Self::Function => "a function",
+		};
+		write!(f, "{}", tr(text))
+	}
+}
+
+/// The arguments of a builtin: the required ones followed by the optional ones,
+/// the last one may be repeated when the builtin is variadic.
+#[derive(Debug, Clone, Copy)]
+pub struct Signature {
+	params: &'static [ArgType],
+	required: usize,
+	variadic: bool,
+}
+
+impl Signature {
+	pub const fn exactly(params: &'static [ArgType]) -> Self {
+		Self {
+			params,
+			required: params.len(),
+			variadic: false,
+		}
+	}
+
+	/// The first `required` parameters are required, the others optional.
+	pub const fn between(required: usize, params: &'static [ArgType]) -> Self {
+		Self {
+			params,
+			required,
+			variadic: false,
+		}
+	}
+
+	/// Every parameter is required, and the last one may be repeated.
+	pub const fn at_least(params: &'static [ArgType]) -> Self {
+		Self {
+			params,
+			required: params.len(),
+			variadic: true,
+		}
+	}
+
+	pub fn check(&self, func_name: &str, args: &[RuntimeVal]) -> Result<(), EvaluationError> {
+		let too_many = !self.variadic && args.len() > self.params.len();
+		if args.len() < self.required || too_many {
+			return Err(EvaluationError::WrongArgumentCount {
+				func_name: func_name.to_string(),
+				expected: self.describe_count(),
+				found: args.len(),
+			});
+		}
+
+		// The repeated arguments have the type of the last parameter, any type without one
+		for (i, arg) in args.iter().enumerate() {
+			let expected = self.params.get(i).or(self.params.last()).copied();
+			let expected = expected.unwrap_or(ArgType::Any);
+			if !expected.accepts(arg) {
+				return Err(EvaluationError::WrongArgumentType {
+					func_name: func_name.to_string(),
+					position: i + 1,
+					expected,
+					found: describe_value(arg),
+				});
+			}
+		}
+
+		Ok(())
+	}
+
+	// Like "1 matrix argument", "2 to 3 arguments" or "at least 2 arguments"
+	fn describe_count(&self) -> String {
+		let (min, max) = (self.required, self.params.len());
+		let one = min == 1;
+
+		if self.variadic {
+			let message = match one {
+				true => "at least {0} argument",
+				false => "at least {0} arguments",
+			};
+			return tr_with(message, &[&min]);
+		}
+		if min != max {
+			return tr_with("{0} to {1} arguments", &[&min, &max]);
+		}
+
+		let noun = self.params.first().and_then(ArgType::noun);
+		match noun.filter(|_| self.params.iter().all(|param| param.noun() == noun)) {
+			Some(noun) => {
+				let message = match one {
+					true => "{0} {1} argument",
+					false => "{0} {1} arguments",
+				};
+				tr_with(message, &[&min, &tr(noun)])
+			},
+			None => {
+				let message = match one {
+					true => "{0} argument",
+					false => "{0} arguments",
+				};
+				tr_with(message, &[&min])
+			},
+		}
+	}
+}
+
+// How a wrong argument is described in errors
+fn describe_value(value: &RuntimeVal) -> String {
+	match value {
+		RuntimeVal::Number(n) => tr_with("the number {0}", &[n]),
+		RuntimeVal::BigInt(n) => tr_with("the integer {0}", &[n]),
+		RuntimeVal::String(_) => tr("a string").to_string(),
+		RuntimeVal::Matrix(m) => tr_with("a {0}x{1} matrix", &[&m.nrows(), &m.ncols()]),
+		RuntimeVal::Table(_) => tr("a table").to_string(),
+		RuntimeVal::Function(func) => tr_with("the function {0}", &[func]),
+	}
+}
+
+pub fn expect_string(func_name: &str, arg: RuntimeVal) -> Result<String, EvaluationError> {
+	match arg {
+		RuntimeVal::String(s) => Ok(s),
+		_ => Err(EvaluationError::InvalidArguments(
+			func_name.to_string(),
+			tr("expected a string").to_string(),
+		)),
+	}
+}
+
+pub fn expect_number(func_name: &str, arg: RuntimeVal) -> Result<f64, EvaluationError> {
+	match arg {
+		RuntimeVal::Number(n) => Ok(n),
+		RuntimeVal::BigInt(n) => Ok(n.to_f64()),
+		RuntimeVal::Matrix(m) if m.len() == 1 => Ok(m.as_slice()[0]),
+		_ => Err(EvaluationError::InvalidArguments(
+			func_name.to_string(),
+			tr("expected a number").to_string(),
+		)),
+	}
+}
+
+pub fn expect_matrix(func_name: &str, arg: RuntimeVal) -> Result<Matrix, EvaluationError> {
+	match arg {
+		RuntimeVal::Number(n) => Ok(Matrix::from(n)),
+		RuntimeVal::BigInt(n) => Ok(Matrix::from(n.to_f64())),
+		RuntimeVal::Matrix(m) => Ok(m),
+		_ => Err(EvaluationError::InvalidArguments(
+			func_name.to_string(),
+			tr("expected a matrix").to_string(),
+		)),
+	}
+}
+
+pub fn expect_table(func_name: &str, arg: RuntimeVal) -> Result<Table, EvaluationError> {
+	match arg {
+		RuntimeVal::Table(t) => Ok(t),
+		_ => Err(EvaluationError::InvalidArguments(
+			func_name.to_string(),
+			tr("expected a table").to_string(),
+		)),
+	}
+}
+
+pub fn failure(func_name: &str, message: String) -> EvaluationError {
+	EvaluationError::BuiltinFailure(func_name.to_string(), message)
+}
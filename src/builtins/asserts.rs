@@ -0,0 +1,135 @@
+use {
+	super::{expect_matrix, expect_number, failure},
+	crate::{
+		eval::EvaluationError,
+		i18n::tr_with,
+		state::{RuntimeVal, State},
+	},
+};
+
+/*
+	Assertions for tests, run with `nam test`. They return nothing when they
+	hold and fail with what differs otherwise, so the failing statement tells
+	enough to fix the test without printing the values again.
+*/
+
+/// `assert_eq_matrix(A, B, tol)` fails unless A and B have the same shape and every
+/// element of A is within tol of the one of B, 0 by default. NaN equals NaN here.
+pub fn assert_eq_matrix(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let a = expect_matrix("assert_eq_matrix", args.next().unwrap())?;
+	let b = expect_matrix("assert_eq_matrix", args.next().unwrap())?;
+	let tolerance = match args.next() {
+		Some(arg) => expect_number("assert_eq_matrix", arg)?,
+		None => 0.0,
+	};
+	if tolerance < 0.0 || tolerance.is_nan() {
+		return Err(EvaluationError::InvalidArguments(
+			String::from("assert_eq_matrix"),
+			tr_with("expected a non-negative tolerance, got {0}", &[&tolerance]),
+		));
+	}
+
+	if a.nrows() != b.nrows() || a.ncols() != b.ncols() {
+		return Err(failure(
+			"assert_eq_matrix",
+			tr_with(
+				"expected a {0}x{1} matrix, got {2}x{3}",
+				&[&b.nrows(), &b.ncols(), &a.nrows(), &a.ncols()],
+			),
+		));
+	}
+
+	// The error of each element, Inf when only one of them is NaN
+	let errors =
+		a.as_slice()
+			.iter()
+			.zip(b.as_slice())
+			.map(|(&x, &y)| match (x.is_nan(), y.is_nan()) {
+				(true, true) => 0.0,
+				(true, false) | (false, true) => f64::INFINITY,
+				_ if x == y => 0.0,
+				_ => (x - y).abs(),
+			});
+
+	let mut first = None;
+	let mut count = 0;
+	let mut max_error: f64 = 0.0;
+	for (k, error) in errors.enumerate() {
+		if error <= tolerance {
+			continue;
+		}
+		first.get_or_insert(k);
+		count += 1;
+		max_error = max_error.max(error);
+	}
+	let Some(k) = first else {
+		return Ok(vec![]);
+	};
+
+	let (i, j) = (k / a.ncols() + 1, k % a.ncols() + 1);
+	Err(failure(
+		"assert_eq_matrix",
+		tr_with(
+			"{0} of {1} elements differ by more than {2}, first at ({3}, {4}): \
+			 {5} instead of {6}, the largest error is {7}",
+			&[
+				&count,
+				&a.len(),
+				&tolerance,
+				&i,
+				&j,
+				&(a.as_slice()[k]),
+				&(b.as_slice()[k]),
+				&max_error,
+			],
+		),
+	))
+}
+
+/// `assert_shape(A, r, c)` fails unless A has r rows and c columns.
+/// Numbers are 1x1, strings a row of characters and tables their data.
+pub fn assert_shape(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let value = args.next().unwrap();
+	let mut dimension = || {
+		let n = expect_number("assert_shape", args.next().unwrap())?;
+		if n < 0.0 {
+			return Err(EvaluationError::InvalidArguments(
+				String::from("assert_shape"),
+				tr_with(
+					"expected a non-negative number of rows and columns, got {0}",
+					&[&n],
+				),
+			));
+		}
+		Ok(n as usize)
+	};
+	let (nrows, ncols) = (dimension()?, dimension()?);
+
+	let shape = match &value {
+		RuntimeVal::Matrix(m) => (m.nrows(), m.ncols()),
+		RuntimeVal::Table(t) => (t.data().nrows(), t.data().ncols()),
+		RuntimeVal::String(s) => (1, s.chars().count()),
+		RuntimeVal::Number(_) | RuntimeVal::BigInt(_) | RuntimeVal::Function(_) => (1, 1),
+	};
+	if shape != (nrows, ncols) {
+		return Err(failure(
+			"assert_shape",
+			tr_with(
+				"expected a {0}x{1} value, got {2}x{3}",
+				&[&nrows, &ncols, &shape.0, &shape.1],
+			),
+		));
+	}
+
+	Ok(vec![])
+}
@@ -0,0 +1,183 @@
+use {
+	super::{expect_matrix, expect_number, expect_string, failure},
+	crate::{
+		eval::EvaluationError,
+		i18n::{tr, tr_with},
+		matrix::Matrix,
+		state::{RuntimeVal, State},
+	},
+};
+
+const FORMAT_PCM: u16 = 0x0001;
+const FORMAT_FLOAT: u16 = 0x0003;
+const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// `[y, fs] = wavread(path)` reads a WAV file as a matrix with one column per channel
+/// and samples scaled to [-1, 1], along with the sample rate.
+pub fn wavread(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let path = expect_string("wavread", args.into_iter().next().unwrap())?;
+
+	let bytes = std::fs::read(&path).map_err(|e| failure("wavread", format!("'{path}': {e}")))?;
+	let (samples, sample_rate) =
+		decode_wav(&bytes).map_err(|e| failure("wavread", format!("'{path}': {e}")))?;
+
+	Ok(vec![
+		RuntimeVal::Matrix(samples),
+		RuntimeVal::Number(sample_rate as f64),
+	])
+}
+
+/// `wavwrite(y, fs, path)` writes `y` as a 16-bit PCM WAV file,
+/// each column of `y` is a channel (a row vector is written as mono)
+/// and samples are clamped to [-1, 1].
+pub fn wavwrite(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let mut samples = expect_matrix("wavwrite", args.next().unwrap())?;
+	let sample_rate = expect_number("wavwrite", args.next().unwrap())?;
+	let path = expect_string("wavwrite", args.next().unwrap())?;
+
+	if samples.nrows() == 1 {
+		samples = Matrix::new(samples.ncols(), 1, samples.as_slice().to_vec()).unwrap();
+	}
+
+	if samples.is_empty() {
+		return Err(EvaluationError::InvalidArguments(
+			String::from("wavwrite"),
+			tr("expected at least one sample").to_string(),
+		));
+	}
+
+	// The sizes in the header are 32 bits, the data size and 36 bytes of header
+	if samples.len() > (u32::MAX as usize - 36) / 2 {
+		return Err(EvaluationError::InvalidArguments(
+			String::from("wavwrite"),
+			tr_with("too many samples for a WAV file ({0})", &[&samples.len()]),
+		));
+	}
+
+	if samples.ncols() > (u16::MAX / 2) as usize {
+		return Err(EvaluationError::InvalidArguments(
+			String::from("wavwrite"),
+			tr_with("too many channels ({0})", &[&samples.ncols()]),
+		));
+	}
+
+	if sample_rate.fract() != 0.0 || sample_rate < 1.0 || sample_rate > u32::MAX as f64 {
+		return Err(EvaluationError::InvalidArguments(
+			String::from("wavwrite"),
+			tr_with("invalid sample rate {0}", &[&sample_rate]),
+		));
+	}
+
+	let bytes = encode_wav(&samples, sample_rate as u32);
+	std::fs::write(&path, bytes).map_err(|e| failure("wavwrite", format!("'{path}': {e}")))?;
+
+	Ok(vec![])
+}
+
+fn decode_wav(bytes: &[u8]) -> Result<(Matrix, u32), String> {
+	if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+		return Err(String::from("not a WAV file"));
+	}
+
+	let mut format = None;
+	let mut data = None;
+
+	// Walk the RIFF chunks, each one is padded to an even size
+	let mut rest = &bytes[12..];
+	while rest.len() >= 8 {
+		let id = &rest[0..4];
+		let size = u32::from_le_bytes(rest[4..8].try_into().unwrap()) as usize;
+		let body = rest.get(8..8 + size).unwrap_or(&rest[8..]);
+
+		match id {
+			b"fmt " => format = Some(body),
+			b"data" => data = Some(body),
+			_ => {},
+		}
+
+		rest = rest.get(8 + size + size % 2..).unwrap_or(&[]);
+	}
+
+	let format = match format {
+		Some(f) if f.len() >= 16 => f,
+		_ => return Err(String::from("missing or invalid 'fmt ' chunk")),
+	};
+	let data = data.ok_or_else(|| String::from("missing 'data' chunk"))?;
+
+	let read_u16 = |i: usize| u16::from_le_bytes(format[i..i + 2].try_into().unwrap());
+	let mut tag = read_u16(0);
+	let channels = read_u16(2) as usize;
+	let sample_rate = u32::from_le_bytes(format[4..8].try_into().unwrap());
+	let bits = read_u16(14) as usize;
+
+	if tag == FORMAT_EXTENSIBLE && format.len() >= 26 {
+		tag = read_u16(24);
+	}
+
+	if channels == 0 || bits == 0 || !bits.is_multiple_of(8) {
+		return Err(format!(
+			"unsupported layout ({channels} channel(s), {bits} bits)"
+		));
+	}
+
+	let width = bits / 8;
+	let decode_sample: fn(&[u8]) -> f64 = match (tag, bits) {
+		(FORMAT_PCM, 8) => |s| (s[0] as f64 - 128.0) / 128.0,
+		(FORMAT_PCM, 16) => |s| i16::from_le_bytes([s[0], s[1]]) as f64 / 32768.0,
+		(FORMAT_PCM, 24) => |s| (i32::from_le_bytes([0, s[0], s[1], s[2]]) >> 8) as f64 / 8388608.0,
+		(FORMAT_PCM, 32) => |s| i32::from_le_bytes(s.try_into().unwrap()) as f64 / 2147483648.0,
+		(FORMAT_FLOAT, 32) => |s| f32::from_le_bytes(s.try_into().unwrap()) as f64,
+		(FORMAT_FLOAT, 64) => |s| f64::from_le_bytes(s.try_into().unwrap()),
+		_ => {
+			return Err(format!(
+				"unsupported sample format {tag:#06x} with {bits} bits"
+			))
+		},
+	};
+
+	let frame_len = width * channels;
+	let frames = data.len() / frame_len;
+	let samples = data[..frames * frame_len]
+		.chunks_exact(width)
+		.map(decode_sample)
+		.collect();
+
+	Ok((Matrix::new(frames, channels, samples).unwrap(), sample_rate))
+}
+
+fn encode_wav(samples: &Matrix, sample_rate: u32) -> Vec<u8> {
+	let channels = samples.ncols() as u16;
+	let block_align = channels * 2;
+	let data_len = samples.len() as u32 * 2;
+
+	let mut res = Vec::with_capacity(44 + data_len as usize);
+	res.extend_from_slice(b"RIFF");
+	res.extend_from_slice(&(36 + data_len).to_le_bytes());
+	res.extend_from_slice(b"WAVE");
+
+	res.extend_from_slice(b"fmt ");
+	res.extend_from_slice(&16u32.to_le_bytes());
+	res.extend_from_slice(&FORMAT_PCM.to_le_bytes());
+	res.extend_from_slice(&channels.to_le_bytes());
+	res.extend_from_slice(&sample_rate.to_le_bytes());
+	res.extend_from_slice(&sample_rate.saturating_mul(block_align as u32).to_le_bytes());
+	res.extend_from_slice(&block_align.to_le_bytes());
+	res.extend_from_slice(&16u16.to_le_bytes());
+
+	res.extend_from_slice(b"data");
+	res.extend_from_slice(&data_len.to_le_bytes());
+	for s in samples.as_slice() {
+		let s = (s.clamp(-1.0, 1.0) * 32767.0).round() as i16;
+		res.extend_from_slice(&s.to_le_bytes());
+	}
+
+	res
+}
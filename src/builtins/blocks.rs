@@ -0,0 +1,91 @@
+use {
+	super::{expect_matrix, expect_number},
+	crate::{
+		eval::EvaluationError,
+		i18n::tr_with,
+		state::{RuntimeVal, State},
+	},
+};
+
+/// `getblock(A, r0, c0, h, w)` returns the `h`x`w` block of `A`
+/// whose top left element is `A(r0, c0)`.
+pub fn getblock(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let a = expect_matrix("getblock", args.next().unwrap())?;
+	let r0 = expect_position("getblock", args.next().unwrap())?;
+	let c0 = expect_position("getblock", args.next().unwrap())?;
+	let h = expect_size("getblock", args.next().unwrap())?;
+	let w = expect_size("getblock", args.next().unwrap())?;
+
+	let block = a
+		.get_block(r0 - 1, c0 - 1, h, w)
+		.ok_or_else(|| out_of_bounds("getblock", (r0, c0), (h, w), (a.nrows(), a.ncols())))?;
+
+	Ok(vec![RuntimeVal::Matrix(block)])
+}
+
+/// `setblock(A, r0, c0, B)` returns a copy of `A` with `B` written
+/// so that its top left element is at `A(r0, c0)`.
+pub fn setblock(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let mut a = expect_matrix("setblock", args.next().unwrap())?;
+	let r0 = expect_position("setblock", args.next().unwrap())?;
+	let c0 = expect_position("setblock", args.next().unwrap())?;
+	let b = expect_matrix("setblock", args.next().unwrap())?;
+
+	a.set_block(r0 - 1, c0 - 1, &b).ok_or_else(|| {
+		out_of_bounds(
+			"setblock",
+			(r0, c0),
+			(b.nrows(), b.ncols()),
+			(a.nrows(), a.ncols()),
+		)
+	})?;
+
+	Ok(vec![RuntimeVal::Matrix(a)])
+}
+
+fn expect_position(func_name: &str, arg: RuntimeVal) -> Result<usize, EvaluationError> {
+	let n = expect_number(func_name, arg)?;
+	if n.fract() != 0.0 || n < 1.0 || n > u32::MAX as f64 {
+		return Err(EvaluationError::InvalidArguments(
+			func_name.to_string(),
+			tr_with("expected a positive integer index, got {0}", &[&n]),
+		));
+	}
+	Ok(n as usize)
+}
+
+fn expect_size(func_name: &str, arg: RuntimeVal) -> Result<usize, EvaluationError> {
+	let n = expect_number(func_name, arg)?;
+	if n.fract() != 0.0 || n < 0.0 || n > u32::MAX as f64 {
+		return Err(EvaluationError::InvalidArguments(
+			func_name.to_string(),
+			tr_with("expected a non-negative integer size, got {0}", &[&n]),
+		));
+	}
+	Ok(n as usize)
+}
+
+fn out_of_bounds(
+	func_name: &str,
+	(r0, c0): (usize, usize),
+	(h, w): (usize, usize),
+	(nrows, ncols): (usize, usize),
+) -> EvaluationError {
+	EvaluationError::InvalidArguments(
+		func_name.to_string(),
+		tr_with(
+			"a {0}x{1} block at ({2}, {3}) does not fit in a {4}x{5} matrix",
+			&[&h, &w, &r0, &c0, &nrows, &ncols],
+		),
+	)
+}
@@ -0,0 +1,35 @@
+use {
+	super::{expect_string, failure},
+	crate::{
+		clipboard::{self, ClipboardFormat},
+		eval::EvaluationError,
+		i18n::tr_with,
+		state::{RuntimeVal, State},
+	},
+};
+
+/// `copy(A)` places A on the system clipboard as tab separated text, to be pasted
+/// into a spreadsheet, `copy(A, "csv")` as comma separated text instead.
+pub fn copy(state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+	let value = args.next().unwrap();
+
+	let format = match args.next() {
+		Some(format) => {
+			let format = expect_string("copy", format)?;
+			ClipboardFormat::from_name(&format).ok_or_else(|| {
+				EvaluationError::InvalidArguments(
+					String::from("copy"),
+					tr_with(
+						"the format must be \"tsv\" or \"csv\", got \"{0}\"",
+						&[&format],
+					),
+				)
+			})?
+		},
+		None => ClipboardFormat::default(),
+	};
+
+	clipboard::copy(state, &value, format).map_err(|e| failure("copy", e.to_string()))?;
+	Ok(vec![])
+}
@@ -0,0 +1,236 @@
+use {
+	super::{expect_matrix, expect_number},
+	crate::{
+		bigint::{BigUint, MAX_EXACT_F64},
+		eval::EvaluationError,
+		i18n::tr_with,
+		matrix::Matrix,
+		state::{RuntimeVal, State},
+	},
+};
+
+// The exact path is quadratic in the number of digits, keep it interactive
+const MAX_EXACT_TERMS: u64 = 20_000;
+
+// Largest matrix built by perms and nchoosek
+const MAX_ELEMENTS: usize = 10_000_000;
+
+/// `factorial(n)` is exact for any size, results above 2^53 are big integers.
+/// For a matrix it is computed for each element as floats.
+pub fn factorial(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let arg = expect_matrix("factorial", args.into_iter().next().unwrap())?;
+	if arg.len() != 1 {
+		let mut data = vec![];
+		for &n in arg.as_slice() {
+			let n = expect_count("factorial", n)?;
+			data.push(if n > 170 {
+				f64::INFINITY
+			} else {
+				factorial_exact(n).to_f64()
+			});
+		}
+
+		let res = Matrix::new(arg.nrows(), arg.ncols(), data).unwrap();
+		return Ok(vec![RuntimeVal::Matrix(res)]);
+	}
+
+	let n = expect_count("factorial", arg.as_slice()[0])?;
+	if n > MAX_EXACT_TERMS {
+		return Err(too_large("factorial", n));
+	}
+
+	Ok(vec![integer(factorial_exact(n))])
+}
+
+/// `nchoosek(n, k)` is the exact binomial coefficient, results above 2^53 are big integers.
+/// `nchoosek(v, k)` returns every combination of `k` elements of the vector `v`, one per row.
+pub fn nchoosek(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let set = expect_matrix("nchoosek", args.next().unwrap())?;
+	let k = expect_number("nchoosek", args.next().unwrap())?;
+	let k = expect_count("nchoosek", k)?;
+
+	if set.len() != 1 {
+		return Ok(vec![RuntimeVal::Matrix(combinations(&set, k as usize)?)]);
+	}
+
+	let n = expect_count("nchoosek", set.as_slice()[0])?;
+	if k > n {
+		return Ok(vec![RuntimeVal::Number(0.0)]);
+	}
+
+	// C(n, k) = C(n, n - k), and each step stays an integer: res = C(n, i + 1)
+	let k = k.min(n - k);
+	if k > MAX_EXACT_TERMS {
+		return Err(too_large("nchoosek", k));
+	}
+
+	let mut res = BigUint::from(1);
+	for i in 0..k {
+		res.mul_small(n - i);
+		res.div_small(i + 1);
+	}
+
+	Ok(vec![integer(res)])
+}
+
+/// `perms(v)` returns every permutation of the elements of `v`, one per row,
+/// in reverse lexicographic order like Octave.
+pub fn perms(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let set = expect_matrix("perms", args.into_iter().next().unwrap())?;
+	let n = set.len();
+
+	let count = (1..=n).try_fold(1usize, |acc, i| acc.checked_mul(i));
+	let count = match count {
+		Some(count) if count.saturating_mul(n) <= MAX_ELEMENTS => count,
+		_ => return Err(too_many_rows("perms", n)),
+	};
+
+	let mut indices: Vec<usize> = (0..n).collect();
+	let mut rows = Vec::with_capacity(count);
+	loop {
+		rows.push(indices.clone());
+		if !next_permutation(&mut indices) {
+			break;
+		}
+	}
+
+	let elements = set.as_slice();
+	let data = rows
+		.iter()
+		.rev()
+		.flat_map(|row| row.iter().map(|&i| elements[i]))
+		.collect();
+
+	Ok(vec![RuntimeVal::Matrix(
+		Matrix::new(count, n, data).unwrap(),
+	)])
+}
+
+/// `permmatrix(p)` returns the permutation matrix `P` with `P * A == A(p, :)`,
+/// for a permutation `p` of `1:n` such as `randperm(n)`.
+pub fn permmatrix(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let p = expect_matrix("permmatrix", args.into_iter().next().unwrap())?;
+
+	let indices: Option<Vec<usize>> = p
+		.as_slice()
+		.iter()
+		.map(|&i| (i.fract() == 0.0 && i >= 1.0).then(|| i as usize - 1))
+		.collect();
+
+	let res = indices
+		.and_then(|indices| Matrix::from_permutations_vector(&indices))
+		.ok_or_else(|| {
+			EvaluationError::InvalidArguments(
+				String::from("permmatrix"),
+				tr_with("expected a permutation of 1 to {0}", &[&p.len()]),
+			)
+		})?;
+
+	Ok(vec![RuntimeVal::Matrix(res)])
+}
+
+fn factorial_exact(n: u64) -> BigUint {
+	let mut res = BigUint::from(1);
+	for i in 2..=n {
+		res.mul_small(i);
+	}
+	res
+}
+
+fn combinations(set: &Matrix, k: usize) -> Result<Matrix, EvaluationError> {
+	let n = set.len();
+	if k > n {
+		return Ok(Matrix::new(0, k, vec![]).unwrap());
+	}
+
+	let mut count = BigUint::from(1);
+	for i in 0..k as u64 {
+		count.mul_small(n as u64 - i);
+		count.div_small(i + 1);
+	}
+	let count = count.to_u64().unwrap_or(u64::MAX) as usize;
+	if count.saturating_mul(k) > MAX_ELEMENTS {
+		return Err(too_many_rows("nchoosek", n));
+	}
+
+	let elements = set.as_slice();
+	let mut data = Vec::with_capacity(count * k);
+	let mut indices: Vec<usize> = (0..k).collect();
+	loop {
+		data.extend(indices.iter().map(|&i| elements[i]));
+
+		// Advance the rightmost index that can still move
+		let Some(i) = (0..k).rev().find(|&i| indices[i] < n - k + i) else {
+			break;
+		};
+		indices[i] += 1;
+		for j in i + 1..k {
+			indices[j] = indices[j - 1] + 1;
+		}
+	}
+
+	Ok(Matrix::new(count, k, data).unwrap())
+}
+
+fn next_permutation(indices: &mut [usize]) -> bool {
+	let Some(i) = (1..indices.len())
+		.rev()
+		.find(|&i| indices[i - 1] < indices[i])
+	else {
+		return false;
+	};
+
+	let j = (i..indices.len())
+		.rev()
+		.find(|&j| indices[j] > indices[i - 1])
+		.unwrap();
+	indices.swap(i - 1, j);
+	indices[i..].reverse();
+	true
+}
+
+fn integer(n: BigUint) -> RuntimeVal {
+	match n.to_u64() {
+		Some(n) if n <= MAX_EXACT_F64 => RuntimeVal::Number(n as f64),
+		_ => RuntimeVal::BigInt(n),
+	}
+}
+
+fn expect_count(func_name: &str, n: f64) -> Result<u64, EvaluationError> {
+	if n.fract() != 0.0 || n < 0.0 || n > MAX_EXACT_F64 as f64 {
+		return Err(EvaluationError::InvalidArguments(
+			func_name.to_string(),
+			tr_with("expected a non-negative integer, got {0}", &[&n]),
+		));
+	}
+
+	Ok(n as u64)
+}
+
+fn too_large(func_name: &str, n: u64) -> EvaluationError {
+	EvaluationError::InvalidArguments(
+		func_name.to_string(),
+		tr_with("{0} is too large to compute exactly", &[&n]),
+	)
+}
+
+fn too_many_rows(func_name: &str, n: usize) -> EvaluationError {
+	EvaluationError::InvalidArguments(
+		func_name.to_string(),
+		tr_with("too many rows for a set of {0} elements", &[&n]),
+	)
+}
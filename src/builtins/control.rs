@@ -0,0 +1,232 @@
+use {
+	super::{expect_matrix, failure},
+	crate::{
+		eval::EvaluationError,
+		i18n::{tr, tr_with},
+		linalg,
+		matrix::Matrix,
+		state::{RuntimeVal, State},
+	},
+};
+
+/*
+	State-space models are given by the matrices A, B, C and D of
+	x' = A x + B u, y = C x + D u, transfer functions by the coefficients
+	of their numerator and denominator, highest power first.
+*/
+
+/// `[num, den] = ss2tf(A, B, C, D)` returns the transfer function of a
+/// single-input single-output state-space model.
+pub fn ss2tf(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let a = expect_matrix("ss2tf", args.next().unwrap())?;
+	let b = expect_matrix("ss2tf", args.next().unwrap())?;
+	let c = expect_matrix("ss2tf", args.next().unwrap())?;
+	let d = expect_matrix("ss2tf", args.next().unwrap())?;
+
+	let n = a.nrows();
+	if !a.is_square()
+		|| (b.nrows(), b.ncols()) != (n, 1)
+		|| (c.nrows(), c.ncols()) != (1, n)
+		|| d.len() != 1
+	{
+		return Err(invalid(
+			"ss2tf",
+			tr_with(
+				"expected a nxn A, a nx1 B, a 1xn C and a scalar D, with n = {0}",
+				&[&n],
+			),
+		));
+	}
+	let d = d.as_slice()[0];
+
+	// The zeros of the system are the eigenvalues of A - B C: num = poly(A - B C) + (D - 1) den
+	let den = linalg::characteristic_polynomial(&a).ok_or_else(|| not_converged("ss2tf"))?;
+
+	let mut closed_loop = a.clone();
+	let bc = linalg::matmul(&b, &c);
+	for i in 0..n {
+		for j in 0..n {
+			closed_loop[(i, j)] -= bc[(i, j)];
+		}
+	}
+	let zeros =
+		linalg::characteristic_polynomial(&closed_loop).ok_or_else(|| not_converged("ss2tf"))?;
+
+	let num: Vec<f64> = zeros
+		.iter()
+		.zip(&den)
+		.map(|(z, p)| z + (d - 1.0) * p)
+		.collect();
+
+	Ok(vec![row(num), row(den)])
+}
+
+/// `[A, B, C, D] = tf2ss(num, den)` returns a state-space model of a transfer function,
+/// in controllable canonical form.
+pub fn tf2ss(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let num = expect_matrix("tf2ss", args.next().unwrap())?;
+	let den = expect_matrix("tf2ss", args.next().unwrap())?;
+
+	let den = match den.as_slice().iter().position(|&c| c != 0.0) {
+		Some(first) => &den.as_slice()[first..],
+		None => return Err(invalid("tf2ss", tr("the denominator is zero").to_string())),
+	};
+	let first = num
+		.as_slice()
+		.iter()
+		.position(|&c| c != 0.0)
+		.unwrap_or(num.len());
+	let num = &num.as_slice()[first..];
+
+	let n = den.len() - 1;
+	if num.len() > den.len() {
+		return Err(invalid(
+			"tf2ss",
+			tr("the numerator has a higher degree than the denominator").to_string(),
+		));
+	}
+
+	// Make the denominator monic and pad the numerator to the same length
+	let lead = den[0];
+	let den: Vec<f64> = den.iter().map(|c| c / lead).collect();
+	let mut padded = vec![0.0; den.len() - num.len()];
+	padded.extend(num.iter().map(|c| c / lead));
+
+	let d = padded[0];
+
+	let mut a = Matrix::zeros(n, n);
+	for j in 0..n {
+		a[(0, j)] = -den[j + 1];
+	}
+	for i in 1..n {
+		a[(i, i - 1)] = 1.0;
+	}
+
+	let mut b = Matrix::zeros(n, 1);
+	if n > 0 {
+		b[(0, 0)] = 1.0;
+	}
+
+	let c = (1..=n).map(|j| padded[j] - d * den[j]).collect();
+
+	Ok(vec![
+		RuntimeVal::Matrix(a),
+		RuntimeVal::Matrix(b),
+		row(c),
+		RuntimeVal::Number(d),
+	])
+}
+
+/// `ctrb(A, B)` returns the controllability matrix `[B, A B, ..., A^(n-1) B]`.
+pub fn ctrb(_state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let a = expect_matrix("ctrb", args.next().unwrap())?;
+	let b = expect_matrix("ctrb", args.next().unwrap())?;
+
+	if !a.is_square() || b.nrows() != a.nrows() {
+		return Err(invalid(
+			"ctrb",
+			tr("expected a nxn A and a nxm B").to_string(),
+		));
+	}
+
+	let mut res = Matrix::default();
+	let mut block = b;
+	for _ in 0..a.nrows() {
+		let next = linalg::matmul(&a, &block);
+		res = res.hcat(block).unwrap();
+		block = next;
+	}
+
+	Ok(vec![RuntimeVal::Matrix(res)])
+}
+
+/// `obsv(A, C)` returns the observability matrix `[C; C A; ...; C A^(n-1)]`.
+pub fn obsv(_state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let a = expect_matrix("obsv", args.next().unwrap())?;
+	let c = expect_matrix("obsv", args.next().unwrap())?;
+
+	if !a.is_square() || c.ncols() != a.nrows() {
+		return Err(invalid(
+			"obsv",
+			tr("expected a nxn A and a pxn C").to_string(),
+		));
+	}
+
+	let mut res = Matrix::default();
+	let mut block = c;
+	for _ in 0..a.nrows() {
+		let next = linalg::matmul(&block, &a);
+		res = res.vcat(block).unwrap();
+		block = next;
+	}
+
+	Ok(vec![RuntimeVal::Matrix(res)])
+}
+
+/// `lyap(A, Q)` solves the Lyapunov equation `A X + X A' + Q = 0`.
+pub fn lyap(_state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let a = expect_matrix("lyap", args.next().unwrap())?;
+	let q = expect_matrix("lyap", args.next().unwrap())?;
+
+	let n = a.nrows();
+	if !a.is_square() || (q.nrows(), q.ncols()) != (n, n) {
+		return Err(invalid(
+			"lyap",
+			tr("expected two square matrices of the same size").to_string(),
+		));
+	}
+
+	// With x the rows of X stacked, (A ⊗ I + I ⊗ A) x = -q
+	let mut system = Matrix::zeros(n * n, n * n);
+	for i in 0..n {
+		for j in 0..n {
+			for k in 0..n {
+				system[(i * n + j, k * n + j)] += a[(i, k)];
+				system[(i * n + j, i * n + k)] += a[(j, k)];
+			}
+		}
+	}
+
+	let rhs = Matrix::new(n * n, 1, q.as_slice().iter().map(|v| -v).collect()).unwrap();
+	let x = linalg::solve(&system, &rhs).ok_or_else(|| {
+		failure(
+			"lyap",
+			tr("no unique solution, A and -A' share an eigenvalue").to_string(),
+		)
+	})?;
+
+	Ok(vec![RuntimeVal::Matrix(
+		Matrix::new(n, n, x.into_vec()).unwrap(),
+	)])
+}
+
+fn row(data: Vec<f64>) -> RuntimeVal {
+	RuntimeVal::Matrix(Matrix::new(1, data.len(), data).unwrap())
+}
+
+fn invalid(func_name: &str, message: String) -> EvaluationError {
+	EvaluationError::InvalidArguments(func_name.to_string(), message)
+}
+
+fn not_converged(func_name: &str) -> EvaluationError {
+	failure(
+		func_name,
+		tr("the eigenvalues did not converge").to_string(),
+	)
+}
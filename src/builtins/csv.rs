@@ -0,0 +1,437 @@
+use {
+	super::{expect_number, expect_string, failure, Keyword},
+	crate::{
+		eval::{self, EvaluationError},
+		function::Function,
+		i18n::{tr, tr_with},
+		matrix::{Labels, Matrix},
+		state::{RuntimeVal, State},
+		table::Table,
+	},
+	std::{
+		fs::File,
+		io::{BufRead, BufReader, Lines},
+	},
+};
+
+const DEFAULT_CHUNK_ROWS: usize = 65536;
+
+const DELIMITER: Keyword = Keyword {
+	name: "delimiter",
+	position: 1,
+	default: |state| RuntimeVal::String(CsvFormat::new(state).delimiter.to_string()),
+};
+
+const HEADER: Keyword = Keyword {
+	name: "header",
+	position: 2,
+	default: |_| RuntimeVal::String(String::from("auto")),
+};
+
+pub const READCSV_KEYWORDS: &[Keyword] = &[
+	DELIMITER,
+	HEADER,
+	Keyword {
+		name: "labels",
+		position: 3,
+		default: |_| RuntimeVal::Number(0.0),
+	},
+];
+
+pub const CSVREDUCE_KEYWORDS: &[Keyword] = &[
+	Keyword {
+		name: "chunk",
+		position: 2,
+		default: |_| RuntimeVal::Number(DEFAULT_CHUNK_ROWS as f64),
+	},
+	Keyword {
+		position: 3,
+		..DELIMITER
+	},
+	Keyword {
+		position: 4,
+		..HEADER
+	},
+];
+
+/// `readcsv(path)` reads a comma separated file of numbers as a matrix,
+/// if the first line is not numeric it is used as column names and a table is returned.
+/// Empty or non-numeric cells are read as NaN.
+/// With the `decimal comma` setting cells are separated by semicolons instead.
+/// `readcsv(path, delimiter = ";", header = true)` sets the separator of the cells,
+/// and whether the first line is a header instead of guessing it ("auto").
+/// `readcsv(path, labels = true)` returns a matrix with the header as column labels
+/// instead of a table.
+pub fn readcsv(
+	state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+	let path = expect_string("readcsv", args.next().unwrap())?;
+	let format = CsvFormat::from_args("readcsv", state, args.next(), args.next())?;
+	let labels = match args.next() {
+		Some(labels) => expect_number("readcsv", labels)? != 0.0,
+		None => false,
+	};
+
+	let mut chunks = CsvChunks::open(&path, DEFAULT_CHUNK_ROWS, format)
+		.map_err(|e| failure("readcsv", format!("'{path}': {e}")))?;
+
+	let mut data = Matrix::default();
+	for chunk in &mut chunks {
+		let chunk = chunk.map_err(|e| failure("readcsv", format!("'{path}': {e}")))?;
+		data = data.vcat(chunk).unwrap();
+	}
+
+	let res = match chunks.header {
+		Some(names) => {
+			if data.is_empty() {
+				data = Matrix::new(0, names.len(), vec![]).unwrap();
+			}
+			match labels {
+				true => RuntimeVal::Matrix(
+					data.with_labels(Labels {
+						rows: None,
+						cols: Some(names),
+					})
+					.unwrap(),
+				),
+				false => RuntimeVal::Table(Table::new(names, data).unwrap()),
+			}
+		},
+		None => RuntimeVal::Matrix(data),
+	};
+
+	Ok(vec![res])
+}
+
+/// `csvreduce(path, f)` reduces every column of a CSV file with the function f, reading
+/// the file in chunks so it may be larger than the memory. f is called on each chunk and
+/// then on the results of two chunks stacked, so it has to reduce the columns of a matrix
+/// to a row, like `@nansum`, and a single row to itself. `csvreduce(path, "sum")` does
+/// the same with sum, mean, min, max or count, mean and count not being such functions.
+/// `csvreduce(path, f, n)` reads `n` rows per chunk, also named `chunk`,
+/// `delimiter` and `header` are the same as for `readcsv`.
+pub fn csvreduce(
+	state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let path = expect_string("csvreduce", args.next().unwrap())?;
+	let reduction = args.next().unwrap();
+	let chunk_rows = match args.next() {
+		Some(n) => {
+			let n = expect_number("csvreduce", n)?;
+			if n.fract() != 0.0 || n < 1.0 {
+				return Err(EvaluationError::InvalidArguments(
+					String::from("csvreduce"),
+					tr_with("invalid chunk size {0}", &[&n]),
+				));
+			}
+			n as usize
+		},
+		None => DEFAULT_CHUNK_ROWS,
+	};
+
+	match &reduction {
+		RuntimeVal::String(name)
+			if ["sum", "mean", "min", "max", "count"].contains(&name.as_str()) => {},
+		RuntimeVal::String(name) => {
+			return Err(EvaluationError::InvalidArguments(
+				String::from("csvreduce"),
+				tr_with("unknown reduction '{0}'", &[&name]),
+			))
+		},
+		RuntimeVal::Function(_) => {},
+		_ => {
+			return Err(EvaluationError::InvalidArguments(
+				String::from("csvreduce"),
+				tr("expected a function or the name of a reduction").to_string(),
+			))
+		},
+	}
+
+	let format = CsvFormat::from_args("csvreduce", state, args.next(), args.next())?;
+	let mut chunks = CsvChunks::open(&path, chunk_rows, format)
+		.map_err(|e| failure("csvreduce", format!("'{path}': {e}")))?;
+
+	let res = match reduction {
+		RuntimeVal::Function(function) => reduce_with(&function, &mut chunks, &path, state)?,
+		RuntimeVal::String(name) => reduce_named(&name, &mut chunks, &path)?,
+		_ => unreachable!(),
+	};
+
+	let res = match chunks.header {
+		Some(names) => RuntimeVal::Table(Table::new(names, res).unwrap()),
+		None => RuntimeVal::Matrix(res),
+	};
+
+	Ok(vec![res])
+}
+
+fn reduce_named(name: &str, chunks: &mut CsvChunks, path: &str) -> Result<Matrix, EvaluationError> {
+	let ncols = chunks.ncols().unwrap_or(0);
+	let mut count = 0;
+	let mut sum = vec![0.0; ncols];
+	let mut min = vec![f64::INFINITY; ncols];
+	let mut max = vec![f64::NEG_INFINITY; ncols];
+
+	for chunk in chunks {
+		let chunk = chunk.map_err(|e| failure("csvreduce", format!("'{path}': {e}")))?;
+
+		for row in chunk.as_slice().chunks_exact(ncols) {
+			for (j, &n) in row.iter().enumerate() {
+				sum[j] += n;
+				min[j] = min[j].min(n);
+				max[j] = max[j].max(n);
+			}
+		}
+		count += chunk.nrows();
+	}
+
+	let res = match name {
+		"sum" => sum,
+		"mean" => sum.iter().map(|s| s / count as f64).collect(),
+		"min" => min,
+		"max" => max,
+		"count" => vec![count as f64; ncols],
+		_ => unreachable!(),
+	};
+
+	Ok(Matrix::new(1, ncols, res).unwrap())
+}
+
+// Keeps a single row, the reduction of the chunks so far, a file without rows reducing
+// to NaN like the mean of nothing
+fn reduce_with(
+	function: &Function,
+	chunks: &mut CsvChunks,
+	path: &str,
+	state: &mut State,
+) -> Result<Matrix, EvaluationError> {
+	let ncols = chunks.ncols().unwrap_or(0);
+	let mut reduce = |a: Matrix| {
+		let outputs = eval::call_function(function, vec![RuntimeVal::Matrix(a)], state)?;
+		let res = match outputs.into_iter().next() {
+			Some(RuntimeVal::Number(n)) => Matrix::from(n),
+			Some(RuntimeVal::Matrix(m)) => m,
+			_ => {
+				return Err(failure(
+					"csvreduce",
+					tr_with("{0} returned no matrix", &[&function]),
+				))
+			},
+		};
+		if (res.nrows(), res.ncols()) != (1, ncols) {
+			return Err(failure(
+				"csvreduce",
+				tr_with(
+					"{0} returned a {1}x{2} matrix for {3} columns, expected a row",
+					&[&function, &res.nrows(), &res.ncols(), &ncols],
+				),
+			));
+		}
+		Ok(res)
+	};
+
+	let mut res: Option<Matrix> = None;
+	for chunk in chunks {
+		let chunk = chunk.map_err(|e| failure("csvreduce", format!("'{path}': {e}")))?;
+		let row = match chunk.nrows() {
+			1 => chunk,
+			_ => reduce(chunk)?,
+		};
+		res = Some(match res {
+			Some(res) => reduce(res.vcat(row).unwrap())?,
+			None => row,
+		});
+	}
+
+	Ok(res.unwrap_or_else(|| Matrix::new(1, ncols, vec![f64::NAN; ncols]).unwrap()))
+}
+
+/// How the cells of a CSV file are written.
+#[derive(Debug, Clone, Copy)]
+pub struct CsvFormat {
+	/// Locally formatted files like `1,5;2,25`.
+	pub decimal_comma: bool,
+	pub delimiter: char,
+	/// Whether the first line holds the column names, guessed if `None`.
+	pub header: Option<bool>,
+}
+
+impl CsvFormat {
+	/// Follows the decimal setting of the workspace, cells are separated
+	/// by semicolons when the decimal separator is a comma.
+	pub fn new(state: &State) -> Self {
+		let decimal_comma = state.settings().decimal_comma;
+		Self {
+			decimal_comma,
+			delimiter: if decimal_comma { ';' } else { ',' },
+			header: None,
+		}
+	}
+
+	fn from_args(
+		func_name: &str,
+		state: &State,
+		delimiter: Option<RuntimeVal>,
+		header: Option<RuntimeVal>,
+	) -> Result<Self, EvaluationError> {
+		let mut res = Self::new(state);
+
+		if let Some(delimiter) = delimiter {
+			let delimiter = expect_string(func_name, delimiter)?;
+			let mut chars = delimiter.chars();
+			res.delimiter = match (chars.next(), chars.next()) {
+				(Some(c), None) if c != '"' => c,
+				_ => {
+					return Err(EvaluationError::InvalidArguments(
+						func_name.to_string(),
+						tr_with(
+							"the delimiter must be a single character, got \"{0}\"",
+							&[&delimiter],
+						),
+					))
+				},
+			};
+		}
+
+		res.header = match header {
+			Some(RuntimeVal::String(s)) if s == "auto" => None,
+			Some(header) => Some(expect_number(func_name, header)? != 0.0),
+			None => None,
+		};
+
+		Ok(res)
+	}
+}
+
+/// Reads a CSV file of numbers a chunk of rows at a time.
+pub struct CsvChunks {
+	lines: Lines<BufReader<File>>,
+	line_number: usize,
+	chunk_rows: usize,
+	format: CsvFormat,
+
+	header: Option<Vec<String>>,
+	ncols: Option<usize>,
+
+	// The first data line is read early while looking for a header
+	first_line: Option<(usize, Vec<String>)>,
+}
+
+impl CsvChunks {
+	pub fn open(path: &str, chunk_rows: usize, format: CsvFormat) -> Result<Self, String> {
+		let file = File::open(path).map_err(|e| e.to_string())?;
+
+		let mut res = Self {
+			lines: BufReader::new(file).lines(),
+			line_number: 0,
+			chunk_rows,
+			format,
+			header: None,
+			ncols: None,
+			first_line: None,
+		};
+
+		if let Some((line_number, cells)) = res.next_line()? {
+			res.ncols = Some(cells.len());
+
+			let is_header = res.format.header.unwrap_or_else(|| {
+				cells
+					.iter()
+					.any(|c| !c.is_empty() && res.parse_cell(c).is_none())
+			});
+			if is_header {
+				res.header = Some(cells);
+			} else {
+				res.first_line = Some((line_number, cells));
+			}
+		}
+
+		Ok(res)
+	}
+
+	/// The column names, if the first line of the file is a header.
+	#[allow(unused)]
+	pub fn header(&self) -> Option<&[String]> {
+		self.header.as_deref()
+	}
+
+	/// The number of columns, `None` if the file is empty.
+	pub fn ncols(&self) -> Option<usize> {
+		self.ncols
+	}
+
+	fn next_line(&mut self) -> Result<Option<(usize, Vec<String>)>, String> {
+		for line in &mut self.lines {
+			self.line_number += 1;
+
+			let line = line.map_err(|e| e.to_string())?;
+			if !line.trim().is_empty() {
+				return Ok(Some((
+					self.line_number,
+					split_line(&line, self.format.delimiter),
+				)));
+			}
+		}
+
+		Ok(None)
+	}
+
+	fn parse_cell(&self, cell: &str) -> Option<f64> {
+		if self.format.decimal_comma {
+			cell.replace(',', ".").parse().ok()
+		} else {
+			cell.parse().ok()
+		}
+	}
+
+	fn next_chunk(&mut self) -> Result<Option<Matrix>, String> {
+		let ncols = self.ncols.unwrap_or(0);
+		let mut nrows = 0;
+		let mut data = vec![];
+
+		while nrows < self.chunk_rows {
+			let (line_number, cells) = match self.first_line.take() {
+				Some(line) => line,
+				None => match self.next_line()? {
+					Some(line) => line,
+					None => break,
+				},
+			};
+
+			if cells.len() != ncols {
+				return Err(format!(
+					"line {line_number} has {} cell(s), expected {ncols}",
+					cells.len()
+				));
+			}
+
+			data.extend(cells.iter().map(|c| self.parse_cell(c).unwrap_or(f64::NAN)));
+			nrows += 1;
+		}
+
+		if nrows == 0 {
+			return Ok(None);
+		}
+
+		Ok(Some(Matrix::new(nrows, ncols, data).unwrap()))
+	}
+}
+
+impl Iterator for CsvChunks {
+	type Item = Result<Matrix, String>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		self.next_chunk().transpose()
+	}
+}
+
+fn split_line(line: &str, separator: char) -> Vec<String> {
+	line.split(separator)
+		.map(|cell| cell.trim().trim_matches('"').to_string())
+		.collect()
+}
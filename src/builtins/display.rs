@@ -0,0 +1,50 @@
+use {
+	super::{expect_string, failure},
+	crate::{
+		eval::EvaluationError,
+		format,
+		report::Report,
+		state::{RuntimeVal, State},
+	},
+};
+
+/// `full(A)` prints every element of A, even past the summary limit.
+pub fn full(state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let value = args.into_iter().next().unwrap();
+
+	let format = state.settings().number_format;
+	let text = match &value {
+		RuntimeVal::Matrix(m) => format::matrix(m, format),
+		_ => state.display(&value).to_string(),
+	};
+	state.print(&text);
+
+	Ok(vec![])
+}
+
+/// `report(path)` writes the following statements and what they print to a Markdown
+/// file until `report("off")`, replacing a report being written.
+pub fn report(
+	state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let path = expect_string("report", args.into_iter().next().unwrap())?;
+
+	let finished = match path.as_str() {
+		"off" => state.take_report(),
+		_ => {
+			let report = Report::new(path.clone());
+			report
+				.write()
+				.map_err(|e| failure("report", format!("'{path}': {e}")))?;
+			state.start_report(report)
+		},
+	};
+	if let Some(report) = finished {
+		report
+			.write()
+			.map_err(|e| failure("report", format!("'{}': {e}", report.path())))?;
+	}
+
+	Ok(vec![])
+}
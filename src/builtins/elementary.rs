@@ -0,0 +1,67 @@
+use {
+	super::expect_matrix,
+	crate::{
+		eval::EvaluationError,
+		matrix::Matrix,
+		state::{RuntimeVal, State},
+	},
+};
+
+/*
+	Functions of a real number, applied to each element of a matrix. Angles are in
+	radians. Results are real only, so where the function is not, like the square
+	root or the logarithm of a negative number, the result is NaN.
+*/
+
+/// `sin(x)` is the sine of x.
+pub fn sin(_state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	map("sin", args, f64::sin)
+}
+
+/// `cos(x)` is the cosine of x.
+pub fn cos(_state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	map("cos", args, f64::cos)
+}
+
+/// `tan(x)` is the tangent of x.
+pub fn tan(_state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	map("tan", args, f64::tan)
+}
+
+/// `exp(x)` is e to the power x.
+pub fn exp(_state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	map("exp", args, f64::exp)
+}
+
+/// `log(x)` is the natural logarithm of x, -Inf for 0.
+pub fn log(_state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	map("log", args, f64::ln)
+}
+
+/// `sqrt(x)` is the square root of x.
+pub fn sqrt(_state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	map("sqrt", args, f64::sqrt)
+}
+
+/// `abs(x)` is the absolute value of x.
+pub fn abs(_state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	map("abs", args, f64::abs)
+}
+
+// Numbers give numbers and matrices matrices of the same shape, without labels
+fn map(
+	func_name: &str,
+	args: Vec<RuntimeVal>,
+	f: fn(f64) -> f64,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let res = match args.into_iter().next().unwrap() {
+		RuntimeVal::Number(x) => RuntimeVal::Number(f(x)),
+		arg => {
+			let m = expect_matrix(func_name, arg)?;
+			let data = m.as_slice().iter().map(|&x| f(x)).collect();
+			RuntimeVal::Matrix(Matrix::new(m.nrows(), m.ncols(), data).unwrap())
+		},
+	};
+
+	Ok(vec![res])
+}
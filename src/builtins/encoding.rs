@@ -0,0 +1,213 @@
+use {
+	super::{expect_matrix, expect_string, failure},
+	crate::{
+		ast::{ASTNode, ASTNodeKind},
+		eval::EvaluationError,
+		i18n::{tr, tr_with},
+		lexer,
+		matrix::Matrix,
+		state::{RuntimeVal, State},
+		workspace,
+	},
+};
+
+/*
+	Matrices as strings, to paste into a script or a message or to send to a host,
+	without a file. The text form is a matrix literal like `[1 2;3 4]`, with every
+	element written with the digits that read back to the same number. The base64
+	form holds the matrix as a workspace file (see `workspace`) would, labels and
+	all, and is shorter for matrices of arbitrary numbers. It starts with "TkFNV",
+	the encoding of the magic number of workspace files, which tells them apart.
+*/
+
+const BASE64_ALPHABET: &[u8; 64] =
+	b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// `mat2str(A)` is A as a matrix literal, `mat2str(A, "base64")` in the compact form
+/// that also keeps its labels. `str2mat` reads both back.
+pub fn mat2str(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let a = expect_matrix("mat2str", args.next().unwrap())?;
+	let format = match args.next() {
+		Some(format) => expect_string("mat2str", format)?,
+		None => String::from("text"),
+	};
+
+	let res = match format.as_str() {
+		"text" => literal(&a),
+		"base64" => {
+			let name = String::new();
+			let value = RuntimeVal::Matrix(a);
+			let bytes = workspace::encode([(&name, &value)])
+				.map_err(|e| failure("mat2str", e.to_string()))?;
+			encode_base64(&bytes)
+		},
+		_ => {
+			return Err(EvaluationError::InvalidArguments(
+				String::from("mat2str"),
+				tr_with(
+					"unknown format \"{0}\", expected \"text\" or \"base64\"",
+					&[&format],
+				),
+			))
+		},
+	};
+
+	Ok(vec![RuntimeVal::String(res)])
+}
+
+/// `str2mat(s)` is the matrix written by `mat2str`, in either form. The text form
+/// may be any matrix literal of numbers, `NaN` and `Inf`.
+pub fn str2mat(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let s = expect_string("str2mat", args.into_iter().next().unwrap())?;
+	let s = s.trim();
+
+	let res = match s.starts_with("TkFNV") {
+		true => {
+			let bytes = decode_base64(s)
+				.ok_or_else(|| failure("str2mat", tr("invalid base64").to_string()))?;
+			let vars = workspace::decode(&bytes).map_err(|e| failure("str2mat", e.to_string()))?;
+			match vars.into_iter().next() {
+				Some((_, value @ (RuntimeVal::Matrix(_) | RuntimeVal::Number(_)))) => value,
+				_ => {
+					return Err(failure(
+						"str2mat",
+						tr("the string holds no matrix").to_string(),
+					))
+				},
+			}
+		},
+		false => parse_literal(s)?,
+	};
+
+	Ok(vec![res])
+}
+
+// Numbers are written without brackets, and empty matrices of any shape as `[]`
+fn literal(a: &Matrix) -> String {
+	if a.len() == 1 {
+		return element(a.as_slice()[0]);
+	}
+	if a.is_empty() {
+		return String::from("[]");
+	}
+
+	let mut res = String::from("[");
+	for i in 0..a.nrows() {
+		if i > 0 {
+			res.push(';');
+		}
+		for j in 0..a.ncols() {
+			if j > 0 {
+				res.push(' ');
+			}
+			res.push_str(&element(a[(i, j)]));
+		}
+	}
+	res.push(']');
+	res
+}
+
+// The shortest digits reading back to x, integers without a fraction and very large
+// or small numbers with an exponent
+fn element(x: f64) -> String {
+	match x {
+		_ if x.is_nan() => String::from("NaN"),
+		f64::INFINITY => String::from("Inf"),
+		f64::NEG_INFINITY => String::from("-Inf"),
+		_ if x.fract() == 0.0 && x.abs() < 1e15 => format!("{x}"),
+		_ => format!("{x:?}"),
+	}
+}
+
+// Reads a literal without evaluating it, so the string cannot run any code
+fn parse_literal(s: &str) -> Result<RuntimeVal, EvaluationError> {
+	let invalid = || failure("str2mat", tr("expected a matrix of numbers").to_string());
+
+	let tokens = lexer::try_tokenize(0, s).map_err(|e| failure("str2mat", e.to_string()))?;
+	let ast = ASTNode::try_from(&tokens).map_err(|e| failure("str2mat", e.to_string()))?;
+
+	let rows = match ast.kind {
+		ASTNodeKind::Matrix(rows) => rows,
+		kind => return Ok(RuntimeVal::Number(number(kind).ok_or_else(invalid)?)),
+	};
+
+	// Trailing `;` leave empty rows
+	let rows: Vec<Vec<ASTNode>> = rows.into_iter().filter(|row| !row.is_empty()).collect();
+	let ncols = rows.first().map_or(0, Vec::len);
+	let mut data = Vec::with_capacity(rows.len() * ncols);
+	for row in &rows {
+		if row.len() != ncols {
+			return Err(failure(
+				"str2mat",
+				tr("the rows do not have the same length").to_string(),
+			));
+		}
+		for cell in row {
+			data.push(number(cell.kind.clone()).ok_or_else(invalid)?);
+		}
+	}
+
+	Ok(RuntimeVal::Matrix(
+		Matrix::new(rows.len(), ncols, data).unwrap(),
+	))
+}
+
+fn number(kind: ASTNodeKind) -> Option<f64> {
+	match kind {
+		ASTNodeKind::Number(x) => Some(x),
+		ASTNodeKind::Variable(name) if name == "NaN" => Some(f64::NAN),
+		ASTNodeKind::Variable(name) if name == "Inf" => Some(f64::INFINITY),
+		ASTNodeKind::Negate(operand) => number(operand.kind).map(|x| -x),
+		ASTNodeKind::Plus(operand) => number(operand.kind),
+		_ => None,
+	}
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+	let mut res = String::with_capacity(bytes.len().div_ceil(3) * 4);
+	for chunk in bytes.chunks(3) {
+		let group = chunk.iter().enumerate().fold(0u32, |acc, (i, &byte)| {
+			acc | ((byte as u32) << (16 - 8 * i))
+		});
+		for i in 0..4 {
+			match i <= chunk.len() {
+				true => res.push(BASE64_ALPHABET[((group >> (18 - 6 * i)) & 63) as usize] as char),
+				false => res.push('='),
+			}
+		}
+	}
+	res
+}
+
+// `None` for characters out of the alphabet or a wrong length, line breaks are skipped
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+	let digits: Vec<u8> = text.bytes().filter(|c| !c.is_ascii_whitespace()).collect();
+	if !digits.len().is_multiple_of(4) {
+		return None;
+	}
+
+	let mut res = Vec::with_capacity(digits.len() / 4 * 3);
+	for chunk in digits.chunks(4) {
+		let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+		if padding > 2 {
+			return None;
+		}
+
+		let mut group = 0u32;
+		for &c in &chunk[..4 - padding] {
+			let value = BASE64_ALPHABET.iter().position(|&d| d == c)?;
+			group = (group << 6) | value as u32;
+		}
+		group <<= 6 * padding;
+		res.extend_from_slice(&group.to_be_bytes()[1..4 - padding]);
+	}
+	Some(res)
+}
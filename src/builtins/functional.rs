@@ -0,0 +1,70 @@
+use {
+	super::{expect_matrix, expect_number, failure},
+	crate::{
+		eval::{self, EvaluationError},
+		function::Function,
+		i18n::tr_with,
+		matrix::Matrix,
+		state::{RuntimeVal, State},
+	},
+};
+
+/// `map(f, A)` calls f on every element of A, the results form a matrix of the same shape.
+pub fn map(state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let RuntimeVal::Function(function) = args.next().unwrap() else {
+		unreachable!()
+	};
+	let a = expect_matrix("map", args.next().unwrap())?;
+
+	let mut res = Vec::with_capacity(a.len());
+	for (i, &x) in a.as_slice().iter().enumerate() {
+		let outputs = eval::call_function(&function, vec![RuntimeVal::Number(x)], state)?;
+		let output = match outputs.into_iter().next() {
+			Some(output) => output,
+			None => {
+				return Err(failure(
+					"map",
+					tr_with("{0} returned no value", &[&function]),
+				))
+			},
+		};
+
+		let value = expect_number("map", output).map_err(|_| {
+			failure(
+				"map",
+				tr_with(
+					"{0} returned a non scalar for element {1}",
+					&[&function, &(i + 1)],
+				),
+			)
+		})?;
+		res.push(value);
+	}
+
+	Ok(vec![RuntimeVal::Matrix(
+		Matrix::new(a.nrows(), a.ncols(), res).unwrap(),
+	)])
+}
+
+/// `bind(f, a, b, ...)` is f with its first arguments fixed to a, b, ...,
+/// so `bind(@polyval, p)` is the polynomial p as a function of x.
+pub fn bind(_state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let RuntimeVal::Function(function) = args.next().unwrap() else {
+		unreachable!()
+	};
+
+	// Binding a bound function again adds to its arguments
+	let (function, bound) = match function {
+		Function::Bound { function, args } => (function, args),
+		function => (Box::new(function), vec![]),
+	};
+
+	Ok(vec![RuntimeVal::Function(Function::Bound {
+		function,
+		args: bound.into_iter().chain(args).collect(),
+	})])
+}
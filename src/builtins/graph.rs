@@ -0,0 +1,253 @@
+use {
+	super::{expect_matrix, expect_number},
+	crate::{
+		eval::EvaluationError,
+		i18n::{tr, tr_with},
+		matrix::Matrix,
+		state::{RuntimeVal, State},
+	},
+	std::{cmp::Ordering, collections::BinaryHeap},
+};
+
+/*
+	Graphs are square adjacency matrices, A(i, j) is the weight of the edge
+	from node i to node j and 0 means there is no edge.
+*/
+
+/// `degree(A)` returns the weighted out-degree of every node as a column,
+/// the number of neighbours for a 0/1 matrix.
+pub fn degree(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let a = expect_adjacency("degree", args.into_iter().next().unwrap())?;
+
+	let n = a.nrows();
+	let res = a.rows().map(|row| row.iter().sum()).collect();
+
+	Ok(vec![RuntimeVal::Matrix(Matrix::new(n, 1, res).unwrap())])
+}
+
+/// `laplacian(A)` returns the Laplacian matrix `diag(degree(A)) - A`.
+pub fn laplacian(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let a = expect_adjacency("laplacian", args.into_iter().next().unwrap())?;
+
+	let n = a.nrows();
+	let mut res = Matrix::zeros(n, n);
+	for i in 0..n {
+		for j in 0..n {
+			res[(i, j)] -= a[(i, j)];
+			res[(i, i)] += a[(i, j)];
+		}
+	}
+
+	Ok(vec![RuntimeVal::Matrix(res)])
+}
+
+/// `[d, path] = shortestpath(A, i, j)` returns the length of the shortest path from
+/// node `i` to node `j` and its nodes as a row (Dijkstra), `Inf` and `[]` if there is none.
+/// `shortestpath(A)` returns the matrix of the distances between all nodes (Floyd-Warshall).
+pub fn shortestpath(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	if args.len() == 2 {
+		return Err(invalid(
+			"shortestpath",
+			tr("expected a target node after the source node").to_string(),
+		));
+	}
+	let mut args = args.into_iter();
+
+	let a = expect_adjacency("shortestpath", args.next().unwrap())?;
+	let n = a.nrows();
+
+	if a.as_slice().iter().any(|&w| w < 0.0) {
+		return Err(invalid(
+			"shortestpath",
+			tr("edge weights must not be negative").to_string(),
+		));
+	}
+
+	let (Some(source), Some(target)) = (args.next(), args.next()) else {
+		return Ok(vec![RuntimeVal::Matrix(floyd_warshall(&a))]);
+	};
+	let source = expect_node("shortestpath", source, n)?;
+	let target = expect_node("shortestpath", target, n)?;
+
+	let (dist, previous) = dijkstra(&a, source);
+	if dist[target].is_infinite() {
+		return Ok(vec![
+			RuntimeVal::Number(f64::INFINITY),
+			RuntimeVal::Matrix(Matrix::default()),
+		]);
+	}
+
+	let mut path = vec![target];
+	while let Some(node) = previous[*path.last().unwrap()] {
+		path.push(node);
+	}
+	let path: Vec<f64> = path.iter().rev().map(|&node| (node + 1) as f64).collect();
+
+	Ok(vec![
+		RuntimeVal::Number(dist[target]),
+		RuntimeVal::Matrix(Matrix::new(1, path.len(), path).unwrap()),
+	])
+}
+
+/// `[labels, count] = connectedcomponents(A)` numbers the connected components
+/// from 1, ignoring the direction of the edges, and returns the label of every node as a column.
+pub fn connectedcomponents(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let a = expect_adjacency("connectedcomponents", args.into_iter().next().unwrap())?;
+
+	let n = a.nrows();
+	let mut labels = vec![0.0; n];
+	let mut count = 0;
+
+	for start in 0..n {
+		if labels[start] != 0.0 {
+			continue;
+		}
+
+		count += 1;
+		labels[start] = count as f64;
+		let mut stack = vec![start];
+		while let Some(node) = stack.pop() {
+			for next in 0..n {
+				if labels[next] == 0.0 && (is_edge(a[(node, next)]) || is_edge(a[(next, node)])) {
+					labels[next] = count as f64;
+					stack.push(next);
+				}
+			}
+		}
+	}
+
+	Ok(vec![
+		RuntimeVal::Matrix(Matrix::new(n, 1, labels).unwrap()),
+		RuntimeVal::Number(count as f64),
+	])
+}
+
+// Distances from `source` and the previous node on the shortest path to every node
+fn dijkstra(a: &Matrix, source: usize) -> (Vec<f64>, Vec<Option<usize>>) {
+	let n = a.nrows();
+	let mut dist = vec![f64::INFINITY; n];
+	let mut previous = vec![None; n];
+	let mut queue = BinaryHeap::new();
+
+	dist[source] = 0.0;
+	queue.push(Visit(0.0, source));
+
+	while let Some(Visit(d, node)) = queue.pop() {
+		if d > dist[node] {
+			continue;
+		}
+
+		for next in 0..n {
+			let weight = a[(node, next)];
+			if !is_edge(weight) {
+				continue;
+			}
+
+			let candidate = d + weight;
+			if candidate < dist[next] {
+				dist[next] = candidate;
+				previous[next] = Some(node);
+				queue.push(Visit(candidate, next));
+			}
+		}
+	}
+
+	(dist, previous)
+}
+
+fn floyd_warshall(a: &Matrix) -> Matrix {
+	let n = a.nrows();
+	let mut dist = Matrix::zeros(n, n);
+	for i in 0..n {
+		for j in 0..n {
+			if i != j {
+				dist[(i, j)] = if is_edge(a[(i, j)]) {
+					a[(i, j)]
+				} else {
+					f64::INFINITY
+				};
+			}
+		}
+	}
+
+	for k in 0..n {
+		for i in 0..n {
+			for j in 0..n {
+				let through = dist[(i, k)] + dist[(k, j)];
+				if through < dist[(i, j)] {
+					dist[(i, j)] = through;
+				}
+			}
+		}
+	}
+
+	dist
+}
+
+// A node to visit at some distance, ordered so the binary heap pops the closest first
+struct Visit(f64, usize);
+
+impl PartialEq for Visit {
+	fn eq(&self, other: &Self) -> bool {
+		self.cmp(other) == Ordering::Equal
+	}
+}
+
+impl Eq for Visit {}
+
+impl PartialOrd for Visit {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for Visit {
+	fn cmp(&self, other: &Self) -> Ordering {
+		other.0.total_cmp(&self.0).then(other.1.cmp(&self.1))
+	}
+}
+
+fn is_edge(weight: f64) -> bool {
+	weight != 0.0 && !weight.is_nan()
+}
+
+fn expect_adjacency(func_name: &str, arg: RuntimeVal) -> Result<Matrix, EvaluationError> {
+	let a = expect_matrix(func_name, arg)?;
+	if !a.is_square() {
+		return Err(invalid(
+			func_name,
+			tr_with(
+				"expected a square adjacency matrix, got {0}x{1}",
+				&[&a.nrows(), &a.ncols()],
+			),
+		));
+	}
+	Ok(a)
+}
+
+fn expect_node(func_name: &str, arg: RuntimeVal, n: usize) -> Result<usize, EvaluationError> {
+	let node = expect_number(func_name, arg)?;
+	if node.fract() != 0.0 || node < 1.0 || node > n as f64 {
+		return Err(invalid(
+			func_name,
+			tr_with("{0} is not a node of a graph with {1} nodes", &[&node, &n]),
+		));
+	}
+	Ok(node as usize - 1)
+}
+
+fn invalid(func_name: &str, message: String) -> EvaluationError {
+	EvaluationError::InvalidArguments(func_name.to_string(), message)
+}
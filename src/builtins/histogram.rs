@@ -0,0 +1,188 @@
+use {
+	super::{expect_matrix, expect_number, expect_string},
+	crate::{
+		eval::EvaluationError,
+		i18n::{tr, tr_with},
+		matrix::Matrix,
+		state::{RuntimeVal, State},
+	},
+	std::fmt::Write,
+};
+
+const DEFAULT_BINS: usize = 10;
+
+// Width in characters of the longest bar drawn with the "plot" option
+const BAR_WIDTH: usize = 50;
+
+/// `histc(v, edges)` counts the elements with `edges(k) <= v < edges(k + 1)`,
+/// the last count is the number of elements equal to `edges(end)`.
+/// `histc(v, edges, "plot")` also draws the counts in the terminal.
+pub fn histc(state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let v = expect_matrix("histc", args.next().unwrap())?;
+	let edges = expect_matrix("histc", args.next().unwrap())?;
+	let plot = expect_plot_option("histc", args.next())?;
+
+	// The counts keep the orientation of the edges
+	let column = edges.ncols() == 1 && edges.nrows() > 1;
+	let edges = expect_edges("histc", edges.as_slice())?;
+
+	let mut counts = vec![0.0; edges.len()];
+	for &x in v.as_slice() {
+		if let Some(k) = bin(&edges, x, false) {
+			counts[k] += 1.0;
+		}
+	}
+
+	if plot {
+		state.print(&draw(&counts, &edges));
+	}
+
+	let len = counts.len();
+	let res = if column {
+		Matrix::new(len, 1, counts)
+	} else {
+		Matrix::new(1, len, counts)
+	};
+	Ok(vec![RuntimeVal::Matrix(res.unwrap())])
+}
+
+/// `[counts, edges] = histcounts(v)` splits the range of `v` in 10 bins of the same width,
+/// `histcounts(v, nbins)` in `nbins` bins and `histcounts(v, edges)` uses the given edges.
+/// Every bin includes its left edge and the last one also its right edge.
+/// `histcounts(v, bins, "plot")` also draws the counts in the terminal.
+pub fn histcounts(
+	state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let v = expect_matrix("histcounts", args.next().unwrap())?;
+	let bins = args.next();
+	let plot = expect_plot_option("histcounts", args.next())?;
+
+	let edges = match bins {
+		Some(RuntimeVal::Matrix(edges)) if edges.len() != 1 => {
+			expect_edges("histcounts", edges.as_slice())?
+		},
+		Some(nbins) => uniform_edges(v.as_slice(), expect_bins(nbins)?),
+		None => uniform_edges(v.as_slice(), DEFAULT_BINS),
+	};
+
+	let mut counts = vec![0.0; edges.len() - 1];
+	for &x in v.as_slice() {
+		if let Some(k) = bin(&edges, x, true) {
+			counts[k] += 1.0;
+		}
+	}
+
+	if plot {
+		state.print(&draw(&counts, &edges));
+	}
+
+	let (ncounts, nedges) = (counts.len(), edges.len());
+	Ok(vec![
+		RuntimeVal::Matrix(Matrix::new(1, ncounts, counts).unwrap()),
+		RuntimeVal::Matrix(Matrix::new(1, nedges, edges).unwrap()),
+	])
+}
+
+/// The bin of `x` between sorted edges. With `closed_last`, the last bin is
+/// `[edges(end - 1), edges(end)]`, otherwise it only holds `edges(end)` (histc).
+fn bin(edges: &[f64], x: f64, closed_last: bool) -> Option<usize> {
+	let last = *edges.last()?;
+	if x.is_nan() || x < edges[0] || x > last {
+		return None;
+	}
+
+	if x == last {
+		return match closed_last {
+			true => Some(edges.len() - 2),
+			false => Some(edges.len() - 1),
+		};
+	}
+
+	Some(edges.partition_point(|&e| e <= x) - 1)
+}
+
+fn uniform_edges(values: &[f64], nbins: usize) -> Vec<f64> {
+	let finite = values.iter().copied().filter(|x| x.is_finite());
+	let (min, max) = finite.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), x| {
+		(min.min(x), max.max(x))
+	});
+
+	// No range to split, center unit-width bins on the value
+	let (min, max) = if min > max {
+		(0.0, 1.0)
+	} else if min == max {
+		(min - 0.5, max + 0.5)
+	} else {
+		(min, max)
+	};
+
+	let width = (max - min) / nbins as f64;
+	let mut edges: Vec<f64> = (0..nbins).map(|k| min + k as f64 * width).collect();
+	edges.push(max);
+	edges
+}
+
+fn expect_bins(arg: RuntimeVal) -> Result<usize, EvaluationError> {
+	let nbins = expect_number("histcounts", arg)?;
+	if nbins.fract() != 0.0 || nbins < 1.0 {
+		return Err(EvaluationError::InvalidArguments(
+			String::from("histcounts"),
+			tr_with("expected a positive number of bins, got {0}", &[&nbins]),
+		));
+	}
+	Ok(nbins as usize)
+}
+
+fn expect_edges(func_name: &str, edges: &[f64]) -> Result<Vec<f64>, EvaluationError> {
+	if edges.is_empty()
+		|| edges.iter().any(|e| e.is_nan())
+		|| edges.windows(2).any(|w| w[0] >= w[1])
+	{
+		return Err(EvaluationError::InvalidArguments(
+			func_name.to_string(),
+			tr("expected increasing bin edges").to_string(),
+		));
+	}
+	Ok(edges.to_vec())
+}
+
+fn expect_plot_option(func_name: &str, arg: Option<RuntimeVal>) -> Result<bool, EvaluationError> {
+	let Some(arg) = arg else {
+		return Ok(false);
+	};
+
+	match expect_string(func_name, arg)?.as_str() {
+		"plot" => Ok(true),
+		option => Err(EvaluationError::InvalidArguments(
+			func_name.to_string(),
+			tr_with("unknown option \"{0}\", expected \"plot\"", &[&option]),
+		)),
+	}
+}
+
+// One horizontal bar per bin, labelled with its left edge
+fn draw(counts: &[f64], edges: &[f64]) -> String {
+	let max = counts.iter().copied().fold(0.0, f64::max);
+	let labels: Vec<String> = edges
+		.iter()
+		.take(counts.len())
+		.map(|e| format!("{e}"))
+		.collect();
+	let label_width = labels.iter().map(|l| l.len()).max().unwrap_or(0);
+
+	let mut res = String::new();
+	for (label, &count) in labels.iter().zip(counts) {
+		let len = if max > 0.0 {
+			(count / max * BAR_WIDTH as f64).round() as usize
+		} else {
+			0
+		};
+		let _ = writeln!(res, "{label:>label_width$} | {} {count}", "#".repeat(len));
+	}
+	res
+}
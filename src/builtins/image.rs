@@ -0,0 +1,115 @@
+use {
+	super::{expect_matrix, expect_number, expect_string, failure},
+	crate::{
+		eval::EvaluationError,
+		i18n::{tr, tr_with},
+		matrix::Matrix,
+		state::{RuntimeVal, State},
+	},
+	std::{fs::File, io::BufWriter},
+};
+
+/// `imread(path)` reads a PNG image as a grayscale matrix with values in [0, 1],
+/// `imread(path, c)` returns only the channel `c` (1-based) instead.
+pub fn imread(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let path = expect_string("imread", args.next().unwrap())?;
+	let channel = match args.next() {
+		Some(c) => Some(expect_number("imread", c)?),
+		None => None,
+	};
+
+	let file = File::open(&path).map_err(|e| failure("imread", format!("'{path}': {e}")))?;
+
+	let mut decoder = png::Decoder::new(file);
+	decoder.set_transformations(png::Transformations::normalize_to_color8());
+
+	let mut reader = decoder
+		.read_info()
+		.map_err(|e| failure("imread", format!("'{path}': {e}")))?;
+
+	let mut buffer = vec![0; reader.output_buffer_size()];
+	let info = reader
+		.next_frame(&mut buffer)
+		.map_err(|e| failure("imread", format!("'{path}': {e}")))?;
+
+	let samples = info.color_type.samples();
+	let pixels = buffer[..info.buffer_size()].chunks_exact(samples);
+
+	let data = match channel {
+		None => pixels.map(luminance).collect(),
+
+		Some(c) => {
+			if c.fract() != 0.0 || c < 1.0 || c > samples as f64 {
+				return Err(EvaluationError::InvalidArguments(
+					String::from("imread"),
+					tr_with(
+						"the image has {0} channel(s), got channel {1}",
+						&[&samples, &c],
+					),
+				));
+			}
+			pixels.map(|px| px[c as usize - 1] as f64 / 255.0).collect()
+		},
+	};
+
+	let res = Matrix::new(info.height as usize, info.width as usize, data).unwrap();
+	Ok(vec![RuntimeVal::Matrix(res)])
+}
+
+/// `imwrite(A, path)` writes `A` as a grayscale PNG image,
+/// values are clamped to [0, 1] and scaled to 0-255.
+pub fn imwrite(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let image = expect_matrix("imwrite", args.next().unwrap())?;
+	let path = expect_string("imwrite", args.next().unwrap())?;
+
+	if image.is_empty() {
+		return Err(EvaluationError::InvalidArguments(
+			String::from("imwrite"),
+			tr("cannot write an empty image").to_string(),
+		));
+	}
+
+	let file = File::create(&path).map_err(|e| failure("imwrite", format!("'{path}': {e}")))?;
+
+	let mut encoder = png::Encoder::new(
+		BufWriter::new(file),
+		image.ncols() as u32,
+		image.nrows() as u32,
+	);
+	encoder.set_color(png::ColorType::Grayscale);
+	encoder.set_depth(png::BitDepth::Eight);
+
+	let data: Vec<u8> = image
+		.as_slice()
+		.iter()
+		.map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8)
+		.collect();
+
+	encoder
+		.write_header()
+		.and_then(|mut writer| writer.write_image_data(&data))
+		.map_err(|e| failure("imwrite", format!("'{path}': {e}")))?;
+
+	Ok(vec![])
+}
+
+// Same weights as Octave's rgb2gray
+fn luminance(px: &[u8]) -> f64 {
+	match px {
+		[gray] | [gray, _] => *gray as f64 / 255.0,
+		[r, g, b, ..] => {
+			(0.298936 * *r as f64 + 0.587043 * *g as f64 + 0.114021 * *b as f64) / 255.0
+		},
+		[] => unreachable!(),
+	}
+}
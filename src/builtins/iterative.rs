@@ -0,0 +1,371 @@
+use {
+	super::{expect_matrix, expect_number, failure, Keyword},
+	crate::{
+		eval::{self, EvaluationError},
+		function::Function,
+		i18n::{tr, tr_with},
+		linalg,
+		matrix::Matrix,
+		state::{RuntimeVal, State},
+	},
+};
+
+/*
+	Krylov solvers for systems too large to factorize. They only multiply by A,
+	so A may also be a function returning `A * x` for a column x.
+	Both return `[x, flag, relres, iter]`: the solution, 0 if it converged,
+	1 if it ran out of iterations and 2 if it broke down, the relative
+	residual `norm(b - A*x) / norm(b)` and the number of iterations done.
+*/
+
+const DEFAULT_TOLERANCE: f64 = 1e-6;
+const DEFAULT_MAX_ITERATIONS: f64 = 1000.0;
+const DEFAULT_RESTART: f64 = 30.0;
+
+const CONVERGED: f64 = 0.0;
+const NOT_CONVERGED: f64 = 1.0;
+const BREAKDOWN: f64 = 2.0;
+
+const TOLERANCE: Keyword = Keyword {
+	name: "tol",
+	position: 2,
+	default: |_| RuntimeVal::Number(DEFAULT_TOLERANCE),
+};
+
+const MAX_ITERATIONS: Keyword = Keyword {
+	name: "maxit",
+	position: 3,
+	default: |_| RuntimeVal::Number(DEFAULT_MAX_ITERATIONS),
+};
+
+pub const PCG_KEYWORDS: &[Keyword] = &[
+	TOLERANCE,
+	MAX_ITERATIONS,
+	Keyword {
+		name: "precond",
+		position: 4,
+		default: |_| RuntimeVal::Number(1.0),
+	},
+];
+
+pub const GMRES_KEYWORDS: &[Keyword] = &[
+	Keyword {
+		name: "restart",
+		position: 2,
+		default: |_| RuntimeVal::Number(DEFAULT_RESTART),
+	},
+	Keyword {
+		position: 3,
+		..TOLERANCE
+	},
+	Keyword {
+		position: 4,
+		..MAX_ITERATIONS
+	},
+];
+
+/// `pcg(A, b, tol, maxit, M)` solves `A * x = b` for a symmetric positive definite A by
+/// preconditioned conjugate gradients, until the relative residual is below `tol`.
+/// The preconditioner M approximates A, as a matrix or a function returning `M \ r`.
+pub fn pcg(state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let a = args.next().unwrap();
+	let b = expect_matrix("pcg", args.next().unwrap())?;
+	let a = Operator::new("pcg", "A", a, b.len())?;
+	let tolerance = expect_tolerance("pcg", args.next())?;
+	let max_iterations = expect_iterations("pcg", "maxit", args.next(), DEFAULT_MAX_ITERATIONS)?;
+	let precond = match args.next() {
+		Some(arg) => Operator::new("pcg", "M", arg, b.len())?.inverted("pcg")?,
+		None => Operator::Scalar(1.0),
+	};
+
+	let b = b.into_vec();
+	let b_norm = norm(&b);
+	let mut x = vec![0.0; b.len()];
+	let mut r = b.clone();
+	if b_norm == 0.0 {
+		return Ok(outputs(x, CONVERGED, 0.0, 0));
+	}
+
+	let mut z = precond.apply("pcg", &r, state)?;
+	let mut p = z.clone();
+	let mut rz = dot(&r, &z);
+	let mut flag = NOT_CONVERGED;
+	let mut iterations = 0;
+
+	while iterations < max_iterations {
+		if state.is_interrupted() {
+			return Err(EvaluationError::Interrupted);
+		}
+		iterations += 1;
+
+		let q = a.apply("pcg", &p, state)?;
+		let pq = dot(&p, &q);
+		if pq <= 0.0 || !pq.is_finite() {
+			// A is not positive definite along p
+			flag = BREAKDOWN;
+			break;
+		}
+
+		let alpha = rz / pq;
+		axpy(alpha, &p, &mut x);
+		axpy(-alpha, &q, &mut r);
+		if norm(&r) <= tolerance * b_norm {
+			flag = CONVERGED;
+			break;
+		}
+
+		z = precond.apply("pcg", &r, state)?;
+		let rz_next = dot(&r, &z);
+		let beta = rz_next / rz;
+		rz = rz_next;
+		for (pi, zi) in p.iter_mut().zip(&z) {
+			*pi = zi + beta * *pi;
+		}
+	}
+
+	let relres = norm(&residual("pcg", &a, &b, &x, state)?) / b_norm;
+	Ok(outputs(x, flag, relres, iterations))
+}
+
+/// `gmres(A, b, restart, tol, maxit)` solves `A * x = b` for any square A by the
+/// generalized minimal residual method, restarted every `restart` iterations to bound
+/// the memory used, until the relative residual is below `tol`.
+pub fn gmres(state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let a = args.next().unwrap();
+	let b = expect_matrix("gmres", args.next().unwrap())?;
+	let a = Operator::new("gmres", "A", a, b.len())?;
+	let restart = expect_iterations("gmres", "restart", args.next(), DEFAULT_RESTART)?.max(1);
+	let tolerance = expect_tolerance("gmres", args.next())?;
+	let max_iterations = expect_iterations("gmres", "maxit", args.next(), DEFAULT_MAX_ITERATIONS)?;
+
+	let b = b.into_vec();
+	let n = b.len();
+	let b_norm = norm(&b);
+	let mut x = vec![0.0; n];
+	if b_norm == 0.0 {
+		return Ok(outputs(x, CONVERGED, 0.0, 0));
+	}
+
+	let mut iterations = 0;
+	loop {
+		let r = residual("gmres", &a, &b, &x, state)?;
+		let beta = norm(&r);
+		if beta <= tolerance * b_norm {
+			return Ok(outputs(x, CONVERGED, beta / b_norm, iterations));
+		}
+		if iterations >= max_iterations {
+			return Ok(outputs(x, NOT_CONVERGED, beta / b_norm, iterations));
+		}
+
+		// The Arnoldi basis, and the Hessenberg matrix reduced to triangular by the
+		// Givens rotations (cs, sn) so the least squares residual is |g(k)|
+		let m = restart.min(max_iterations - iterations);
+		let mut basis = vec![r.iter().map(|ri| ri / beta).collect::<Vec<f64>>()];
+		let mut h = vec![vec![0.0; m]; m + 1];
+		let (mut cs, mut sn) = (vec![0.0; m], vec![0.0; m]);
+		let mut g = vec![0.0; m + 1];
+		g[0] = beta;
+
+		let mut k = 0;
+		while k < m {
+			if state.is_interrupted() {
+				return Err(EvaluationError::Interrupted);
+			}
+			iterations += 1;
+
+			let mut w = a.apply("gmres", &basis[k], state)?;
+			for (i, v) in basis.iter().enumerate() {
+				h[i][k] = dot(&w, v);
+				axpy(-h[i][k], v, &mut w);
+			}
+			let w_norm = norm(&w);
+			h[k + 1][k] = w_norm;
+
+			for i in 0..k {
+				let (hi, hj) = (h[i][k], h[i + 1][k]);
+				h[i][k] = cs[i] * hi + sn[i] * hj;
+				h[i + 1][k] = -sn[i] * hi + cs[i] * hj;
+			}
+			let radius = h[k][k].hypot(h[k + 1][k]);
+			if radius == 0.0 {
+				return Ok(outputs(x, BREAKDOWN, beta / b_norm, iterations));
+			}
+			cs[k] = h[k][k] / radius;
+			sn[k] = h[k + 1][k] / radius;
+			h[k][k] = radius;
+			h[k + 1][k] = 0.0;
+			g[k + 1] = -sn[k] * g[k];
+			g[k] *= cs[k];
+
+			k += 1;
+			// An exact solution lies in the basis when w vanishes
+			if g[k].abs() <= tolerance * b_norm || w_norm == 0.0 {
+				break;
+			}
+			basis.push(w.iter().map(|wi| wi / w_norm).collect());
+		}
+
+		// x += basis * y with h(1:k, 1:k) * y = g(1:k)
+		let mut y = g[..k].to_vec();
+		for i in (0..k).rev() {
+			for j in i + 1..k {
+				y[i] -= h[i][j] * y[j];
+			}
+			y[i] /= h[i][i];
+		}
+		for (yi, v) in y.iter().zip(&basis) {
+			axpy(*yi, v, &mut x);
+		}
+	}
+}
+
+// A linear operator on columns of length n
+enum Operator {
+	Matrix(Matrix),
+	Function(Function, usize),
+	/// A multiple of the identity.
+	Scalar(f64),
+}
+
+impl Operator {
+	fn new(
+		func_name: &str,
+		name: &str,
+		arg: RuntimeVal,
+		n: usize,
+	) -> Result<Self, EvaluationError> {
+		let res = match arg {
+			RuntimeVal::Function(function) => Self::Function(function, n),
+			arg => match expect_matrix(func_name, arg)? {
+				m if m.len() == 1 => Self::Scalar(m.as_slice()[0]),
+				m if m.nrows() == n && m.ncols() == n => Self::Matrix(m),
+				m => {
+					return Err(EvaluationError::InvalidArguments(
+						func_name.to_string(),
+						tr_with(
+							"{0} is {1}x{2}, expected {3}x{3} for b of length {3}",
+							&[&name, &m.nrows(), &m.ncols(), &n],
+						),
+					))
+				},
+			},
+		};
+		Ok(res)
+	}
+
+	// The operator applying the inverse, a function preconditioner already returns `M \ r`
+	fn inverted(self, func_name: &str) -> Result<Self, EvaluationError> {
+		let singular = || failure(func_name, tr("the preconditioner is singular").to_string());
+		match self {
+			Self::Scalar(0.0) => Err(singular()),
+			Self::Scalar(s) => Ok(Self::Scalar(1.0 / s)),
+			Self::Matrix(m) => linalg::solve(&m, &Matrix::identity(m.nrows()))
+				.map(Self::Matrix)
+				.ok_or_else(singular),
+			function => Ok(function),
+		}
+	}
+
+	fn apply(
+		&self,
+		func_name: &str,
+		x: &[f64],
+		state: &mut State,
+	) -> Result<Vec<f64>, EvaluationError> {
+		match self {
+			Self::Scalar(s) => Ok(x.iter().map(|xi| s * xi).collect()),
+			Self::Matrix(m) => Ok(m.rows().map(|row| dot(row, x)).collect()),
+			Self::Function(function, n) => {
+				let column = Matrix::new(x.len(), 1, x.to_vec()).unwrap();
+				let outputs =
+					eval::call_function(function, vec![RuntimeVal::Matrix(column)], state)?;
+				let output = outputs.into_iter().next().ok_or_else(|| {
+					failure(func_name, tr_with("{0} returned no value", &[&function]))
+				})?;
+				let res = expect_matrix(func_name, output)?;
+				if res.len() != *n {
+					return Err(failure(
+						func_name,
+						tr_with(
+							"{0} returned {1} elements, expected {2}",
+							&[&function, &res.len(), &n],
+						),
+					));
+				}
+				Ok(res.into_vec())
+			},
+		}
+	}
+}
+
+fn residual(
+	func_name: &str,
+	a: &Operator,
+	b: &[f64],
+	x: &[f64],
+	state: &mut State,
+) -> Result<Vec<f64>, EvaluationError> {
+	let ax = a.apply(func_name, x, state)?;
+	Ok(b.iter().zip(ax).map(|(bi, axi)| bi - axi).collect())
+}
+
+fn outputs(x: Vec<f64>, flag: f64, relres: f64, iterations: usize) -> Vec<RuntimeVal> {
+	vec![
+		RuntimeVal::Matrix(Matrix::new(x.len(), 1, x).unwrap()),
+		RuntimeVal::Number(flag),
+		RuntimeVal::Number(relres),
+		RuntimeVal::Number(iterations as f64),
+	]
+}
+
+fn dot(x: &[f64], y: &[f64]) -> f64 {
+	x.iter().zip(y).map(|(xi, yi)| xi * yi).sum()
+}
+
+fn norm(x: &[f64]) -> f64 {
+	dot(x, x).sqrt()
+}
+
+// y += alpha * x
+fn axpy(alpha: f64, x: &[f64], y: &mut [f64]) {
+	for (yi, xi) in y.iter_mut().zip(x) {
+		*yi += alpha * xi;
+	}
+}
+
+fn expect_tolerance(func_name: &str, arg: Option<RuntimeVal>) -> Result<f64, EvaluationError> {
+	let tolerance = match arg {
+		Some(arg) => expect_number(func_name, arg)?,
+		None => DEFAULT_TOLERANCE,
+	};
+	if tolerance.is_nan() || tolerance <= 0.0 {
+		return Err(EvaluationError::InvalidArguments(
+			func_name.to_string(),
+			tr_with("expected a positive tolerance, got {0}", &[&tolerance]),
+		));
+	}
+	Ok(tolerance)
+}
+
+fn expect_iterations(
+	func_name: &str,
+	name: &str,
+	arg: Option<RuntimeVal>,
+	default: f64,
+) -> Result<usize, EvaluationError> {
+	let n = match arg {
+		Some(arg) => expect_number(func_name, arg)?,
+		None => default,
+	};
+	if n < 0.0 || n > u32::MAX as f64 {
+		return Err(EvaluationError::InvalidArguments(
+			func_name.to_string(),
+			tr_with("expected a non-negative {0}, got {1}", &[&name, &n]),
+		));
+	}
+	Ok(n as usize)
+}
@@ -0,0 +1,46 @@
+use {
+	super::{expect_number, failure},
+	crate::{
+		eval::EvaluationError,
+		i18n::tr_with,
+		state::{RuntimeVal, State},
+	},
+	std::{thread, time::Duration},
+};
+
+/// `wait(job)` blocks until the job started by `async(expr)` is finished and returns its value.
+/// Ctrl-C stops waiting, but the job keeps running.
+pub fn wait(state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let id = expect_number("wait", args.into_iter().next().unwrap())?;
+
+	let no_such_job = || {
+		EvaluationError::InvalidArguments(
+			String::from("wait"),
+			tr_with("there is no job {0}", &[&id]),
+		)
+	};
+
+	if id.fract() != 0.0 || id < 1.0 {
+		return Err(no_such_job());
+	}
+	let id = id as usize;
+
+	// Poll instead of joining right away, so Ctrl-C is noticed
+	loop {
+		match state.jobs().is_finished(id) {
+			None => return Err(no_such_job()),
+			Some(true) => break,
+			Some(false) => {},
+		}
+
+		if state.is_interrupted() {
+			return Err(EvaluationError::Interrupted);
+		}
+		thread::sleep(Duration::from_millis(10));
+	}
+
+	match state.jobs_mut().take_result(id).unwrap() {
+		Ok(res) => Ok(vec![res]),
+		Err(e) => Err(failure("wait", tr_with("job {0} failed: {1}", &[&id, &e]))),
+	}
+}
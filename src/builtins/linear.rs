@@ -0,0 +1,173 @@
+use {
+	super::{expect_matrix, expect_number, failure, Keyword},
+	crate::{
+		arith,
+		eval::EvaluationError,
+		i18n::{tr, tr_with},
+		linalg,
+		matrix::Matrix,
+		state::{RuntimeVal, State},
+		warning::Warning,
+	},
+};
+
+// More passes of iterative refinement do not improve the solution further
+const MAX_REFINEMENTS: f64 = 2.0;
+
+pub const LINSOLVE_KEYWORDS: &[Keyword] = &[Keyword {
+	name: "refine",
+	position: 2,
+	default: |_| RuntimeVal::Number(0.0),
+}];
+
+/// `linsolve(A, b)` solves `A * x = b` for a square `A`, every column of `b` is a
+/// right-hand side. Triangular and banded systems are solved without a full LU.
+/// `linsolve(A, b, refine = n)` improves x with n passes of iterative refinement, and
+/// `[x, r] = linsolve(A, b)` also returns the relative residual of x in the infinity norm.
+pub fn linsolve(
+	state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let a = expect_matrix("linsolve", args.next().unwrap())?;
+	let b = expect_matrix("linsolve", args.next().unwrap())?;
+	let passes = match args.next() {
+		Some(arg) => expect_number("linsolve", arg)?,
+		None => 0.0,
+	};
+	if !(0.0..=MAX_REFINEMENTS).contains(&passes) {
+		return Err(EvaluationError::InvalidArguments(
+			String::from("linsolve"),
+			tr_with(
+				"expected 0 to {0} refinement passes, got {1}",
+				&[&MAX_REFINEMENTS, &passes],
+			),
+		));
+	}
+
+	if !a.is_square() || a.nrows() != b.nrows() {
+		return Err(EvaluationError::InvalidArguments(
+			String::from("linsolve"),
+			tr_with(
+				"expected a square A and b with as many rows, got {0}x{1} and {2}x{3}",
+				&[&a.nrows(), &a.ncols(), &b.nrows(), &b.ncols()],
+			),
+		));
+	}
+
+	let (x, rcond) = linalg::solve_refined(&a, &b, passes as usize)
+		.ok_or_else(|| failure("linsolve", tr("the matrix is singular").to_string()))?;
+	if let Some(warning) = Warning::check_rcond(rcond) {
+		state.warn(warning);
+	}
+
+	let residual = relative_residual(&a, &b, &x);
+	Ok(vec![RuntimeVal::Matrix(x), RuntimeVal::Number(residual)])
+}
+
+/// `rcond(A)` estimates the reciprocal condition number of a square A in the 1-norm:
+/// about 1 for a well-conditioned A, 0 for a singular one. Solving with A loses
+/// about `-log10(rcond(A))` digits.
+pub fn rcond(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let a = expect_matrix("rcond", args.into_iter().next().unwrap())?;
+
+	Ok(vec![RuntimeVal::Number(linalg::rcond(&a))])
+}
+
+// The backward error of x in the infinity norm, 0 for an exact solution
+fn relative_residual(a: &Matrix, b: &Matrix, x: &Matrix) -> f64 {
+	let norm = |m: &Matrix| {
+		m.rows()
+			.map(|row| row.iter().map(|v| v.abs()).sum::<f64>())
+			.fold(0.0, f64::max)
+	};
+
+	let residual = norm(&linalg::residual(a, b, x));
+	match norm(a) * norm(x) {
+		scale if scale > 0.0 => residual / scale,
+		_ => residual,
+	}
+}
+
+/// `sqrtm(A)` is the principal square root of a square A, the X with `X * X = A`,
+/// the same as `A^0.5`. It is not real when A has an eigenvalue that is negative or 0.
+pub fn sqrtm(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let a = expect_matrix("sqrtm", args.into_iter().next().unwrap())?;
+
+	Ok(vec![RuntimeVal::Matrix(arith::matrix_power(&a, 0.5)?)])
+}
+
+/// `det(A)` is the determinant of a square A.
+pub fn det(_state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let a = expect_matrix("det", args.into_iter().next().unwrap())?;
+
+	Ok(vec![RuntimeVal::Number(linalg::det(&a))])
+}
+
+/// `inv(A)` is the inverse of a square A, which must not be singular.
+pub fn inv(_state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let a = expect_matrix("inv", args.into_iter().next().unwrap())?;
+
+	let res = linalg::inverse(&a).ok_or(EvaluationError::SingularMatrix)?;
+	Ok(vec![RuntimeVal::Matrix(res)])
+}
+
+/// `[L, U, P] = lu(A)` decomposes a square A as `P * A = L * U` with partial pivoting,
+/// the elimination is shown step by step with `:steps on`.
+pub fn lu(state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let a = expect_matrix("lu", args.into_iter().next().unwrap())?;
+
+	let (l, u, p) = linalg::lu(&a, state);
+
+	Ok(vec![
+		RuntimeVal::Matrix(l),
+		RuntimeVal::Matrix(u),
+		RuntimeVal::Matrix(p),
+	])
+}
+
+/// `rref(A)` is the reduced row echelon form of A, shown step by step with `:steps on`.
+pub fn rref(state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let a: Matrix = expect_matrix("rref", args.into_iter().next().unwrap())?;
+
+	Ok(vec![RuntimeVal::Matrix(linalg::rref(&a, state))])
+}
+
+/// `[H, u] = householder(v)` is the Householder reflection `H = I - 2 * u * u'` with
+/// `H * v` zero but for its first element, and the unit vector u defining it.
+pub fn householder(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let v = expect_matrix("householder", args.into_iter().next().unwrap())?;
+
+	let (h, u) = linalg::householder(v.as_slice());
+
+	let u = Matrix::new(u.len(), 1, u).unwrap();
+	Ok(vec![RuntimeVal::Matrix(h), RuntimeVal::Matrix(u)])
+}
+
+/// `givens(a, b)` is the rotation `G = [c s; -s c]` with `G * [a; b] = [r; 0]`.
+pub fn givens(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let a = expect_number("givens", args.next().unwrap())?;
+	let b = expect_number("givens", args.next().unwrap())?;
+
+	let (c, s) = linalg::givens(a, b);
+	// Subtracted rather than negated, so no -0 is printed for b = 0
+	let minus_s = 0.0 - s;
+	Ok(vec![RuntimeVal::Matrix(
+		Matrix::new(2, 2, vec![c, s, minus_s, c]).unwrap(),
+	)])
+}
@@ -0,0 +1,99 @@
+use {
+	super::expect_matrix,
+	crate::{
+		eval::EvaluationError,
+		function::Function,
+		matrix::Matrix,
+		state::{RuntimeVal, State},
+	},
+};
+
+/*
+	Logical values are numbers, 0 is false and anything else is true, NaN included.
+	Like in Octave, the reductions work on vectors as a whole and on the columns of
+	other matrices, so `all(all(A == B))` checks every element.
+*/
+
+/// `all(A)` is 1 if no element of A is 0, per column for a matrix.
+pub fn all(_state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let a = expect_matrix("all", args.into_iter().next().unwrap())?;
+
+	Ok(vec![reduce(&a, |values| values.iter().all(|&x| x != 0.0))])
+}
+
+/// `any(A)` is 1 if some element of A is not 0, per column for a matrix.
+pub fn any(_state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let a = expect_matrix("any", args.into_iter().next().unwrap())?;
+
+	Ok(vec![reduce(&a, |values| values.iter().any(|&x| x != 0.0))])
+}
+
+/// `isequal(A, B, ...)` is 1 if all the values have the same type, shape and elements.
+pub fn isequal(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let first = &args[0];
+	let res = args[1..].iter().all(|other| equal(first, other));
+
+	Ok(vec![RuntimeVal::Number(res as u8 as f64)])
+}
+
+fn reduce(a: &Matrix, f: impl Fn(&[f64]) -> bool) -> RuntimeVal {
+	let truth = |values: &[f64]| f(values) as u8 as f64;
+
+	if a.nrows() == 1 || a.ncols() == 1 || a.is_empty() {
+		return RuntimeVal::Number(truth(a.as_slice()));
+	}
+
+	let res = (0..a.ncols())
+		.map(|j| truth(&a.col(j).copied().collect::<Vec<_>>()))
+		.collect();
+	RuntimeVal::Matrix(Matrix::new(1, a.ncols(), res).unwrap())
+}
+
+fn equal(a: &RuntimeVal, b: &RuntimeVal) -> bool {
+	match (a, b) {
+		(RuntimeVal::String(a), RuntimeVal::String(b)) => a == b,
+		(RuntimeVal::Table(a), RuntimeVal::Table(b)) => {
+			a.names() == b.names() && same_matrix(a.data(), b.data())
+		},
+		(RuntimeVal::Function(a), RuntimeVal::Function(b)) => same_function(a, b),
+		(RuntimeVal::String(_) | RuntimeVal::Table(_) | RuntimeVal::Function(_), _)
+		| (_, RuntimeVal::String(_) | RuntimeVal::Table(_) | RuntimeVal::Function(_)) => false,
+		(a, b) => match (as_matrix(a), as_matrix(b)) {
+			(Some(a), Some(b)) => same_matrix(&a, &b),
+			_ => false,
+		},
+	}
+}
+
+fn same_function(a: &Function, b: &Function) -> bool {
+	match (a, b) {
+		(Function::Named(a), Function::Named(b)) => a == b,
+		(
+			Function::Bound {
+				function: f,
+				args: a,
+			},
+			Function::Bound {
+				function: g,
+				args: b,
+			},
+		) => same_function(f, g) && a.len() == b.len() && a.iter().zip(b).all(|(a, b)| equal(a, b)),
+		_ => false,
+	}
+}
+
+fn same_matrix(a: &Matrix, b: &Matrix) -> bool {
+	(a.nrows(), a.ncols()) == (b.nrows(), b.ncols()) && a.as_slice() == b.as_slice()
+}
+
+fn as_matrix(value: &RuntimeVal) -> Option<Matrix> {
+	match value {
+		RuntimeVal::Number(n) => Some(Matrix::from(*n)),
+		RuntimeVal::BigInt(n) => Some(Matrix::from(n.to_f64())),
+		RuntimeVal::Matrix(m) => Some(m.clone()),
+		RuntimeVal::String(_) | RuntimeVal::Table(_) | RuntimeVal::Function(_) => None,
+	}
+}
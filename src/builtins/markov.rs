@@ -0,0 +1,115 @@
+use {
+	super::{expect_matrix, expect_number, failure},
+	crate::{
+		eval::EvaluationError,
+		i18n::{tr, tr_with},
+		linalg,
+		matrix::Matrix,
+		state::{RuntimeVal, State},
+	},
+};
+
+/*
+	Markov chains are given by their transition matrix P, where P(i, j) is the
+	probability to go from state i to state j, so every row sums to 1.
+*/
+
+/// `ispstochastic(P)` returns 1 if `P` is a square matrix of probabilities
+/// whose rows sum to 1, 0 otherwise.
+pub fn ispstochastic(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let p = expect_matrix("ispstochastic", args.into_iter().next().unwrap())?;
+
+	let res = is_stochastic(&p);
+	Ok(vec![RuntimeVal::Number(if res { 1.0 } else { 0.0 })])
+}
+
+/// `stationary(P)` returns the stationary distribution of a Markov chain as a row,
+/// the probabilities `pi` with `pi * P = pi` that sum to 1.
+pub fn stationary(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let p = expect_matrix("stationary", args.into_iter().next().unwrap())?;
+
+	if !is_stochastic(&p) {
+		return Err(EvaluationError::InvalidArguments(
+			String::from("stationary"),
+			tr("expected a square matrix whose rows are probabilities summing to 1").to_string(),
+		));
+	}
+
+	// (P' - I) pi' = 0 has one redundant equation, replaced by sum(pi) = 1
+	let n = p.nrows();
+	let mut system = Matrix::zeros(n, n);
+	for (i, col) in p.cols().enumerate() {
+		for (j, &x) in col.enumerate() {
+			system[(i, j)] = x - if i == j { 1.0 } else { 0.0 };
+		}
+	}
+
+	let mut rhs = Matrix::zeros(n, 1);
+	if n > 0 {
+		for j in 0..n {
+			system[(n - 1, j)] = 1.0;
+		}
+		rhs[(n - 1, 0)] = 1.0;
+	}
+
+	let pi = linalg::solve(&system, &rhs).ok_or_else(|| {
+		failure(
+			"stationary",
+			tr("the chain has more than one stationary distribution").to_string(),
+		)
+	})?;
+
+	Ok(vec![RuntimeVal::Matrix(
+		Matrix::new(1, n, pi.into_vec()).unwrap(),
+	)])
+}
+
+/// `mpower(A, n)` raises a square matrix to an integer power,
+/// `mpower(P, n)` gives the n-step transition probabilities of a Markov chain.
+pub fn mpower(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let a = expect_matrix("mpower", args.next().unwrap())?;
+	let n = expect_number("mpower", args.next().unwrap())?;
+
+	if !a.is_square() {
+		return Err(EvaluationError::InvalidArguments(
+			String::from("mpower"),
+			tr_with(
+				"expected a square matrix, got {0}x{1}",
+				&[&a.nrows(), &a.ncols()],
+			),
+		));
+	}
+	if n.fract() != 0.0 || n.abs() > i64::MAX as f64 {
+		return Err(EvaluationError::InvalidArguments(
+			String::from("mpower"),
+			tr_with("expected an integer power, got {0}", &[&n]),
+		));
+	}
+
+	let res = linalg::matpow(&a, n as i64)
+		.ok_or_else(|| failure("mpower", tr("the matrix is singular").to_string()))?;
+
+	Ok(vec![RuntimeVal::Matrix(res)])
+}
+
+fn is_stochastic(p: &Matrix) -> bool {
+	const TOLERANCE: f64 = 1e-10;
+
+	p.is_square()
+		&& p.as_slice().iter().all(|&x| (0.0..=1.0).contains(&x))
+		&& p.rows().all(|row| {
+			let sum: f64 = row.iter().sum();
+			(sum - 1.0).abs() <= TOLERANCE * p.ncols() as f64
+		})
+}
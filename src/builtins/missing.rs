@@ -0,0 +1,255 @@
+use {
+	super::{expect_matrix, expect_number, expect_string, timeseries::along},
+	crate::{
+		eval::EvaluationError,
+		i18n::{tr, tr_with},
+		matrix::Matrix,
+		state::{RuntimeVal, State},
+	},
+};
+
+/*
+	Cleaning of imported data, where a missing value is a NaN, like the empty cells
+	`readcsv` reads. Like the other reductions, `nansum` and `nanmean` work on vectors
+	as a whole and on the columns of other matrices, and so does `fillmissing`.
+	`rmoutliers` removes the elements of a vector, or the rows of a matrix, where
+	a value is an outlier of its column.
+*/
+
+// How many scaled median absolute deviations away from the median an outlier is
+const OUTLIER_THRESHOLD: f64 = 3.0;
+// Scales the median absolute deviation to the standard deviation of normal data
+const MAD_SCALE: f64 = 1.482602218505602;
+
+/// `nansum(v)` is the sum of the elements of v that are not NaN, per column for a matrix.
+pub fn nansum(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let a = expect_matrix("nansum", args.into_iter().next().unwrap())?;
+
+	Ok(vec![reduce(&a, |values| present(values).sum())])
+}
+
+/// `nanmean(v)` is the mean of the elements of v that are not NaN, per column for
+/// a matrix, NaN when they all are.
+pub fn nanmean(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let a = expect_matrix("nanmean", args.into_iter().next().unwrap())?;
+
+	Ok(vec![reduce(&a, |values| {
+		let count = present(values).count();
+		present(values).sum::<f64>() / count as f64
+	})])
+}
+
+/// `fillmissing(v, method)` replaces the NaN of v, per column for a matrix, with
+/// the previous value that is not NaN ("previous"), the next one ("next"), the
+/// closest one ("nearest"), or by linear interpolation between them ("linear"),
+/// extrapolating at the ends. `fillmissing(v, "constant", c)` replaces them with c.
+pub fn fillmissing(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let v = expect_matrix("fillmissing", args.next().unwrap())?;
+	let method = expect_string("fillmissing", args.next().unwrap())?;
+	let constant = args
+		.next()
+		.map(|arg| expect_number("fillmissing", arg))
+		.transpose()?;
+
+	let fill: fn(&[f64]) -> Vec<f64> = match (method.as_str(), constant) {
+		("constant", Some(c)) => {
+			let data = v
+				.as_slice()
+				.iter()
+				.map(|&x| if x.is_nan() { c } else { x })
+				.collect();
+			let res = Matrix::new(v.nrows(), v.ncols(), data).unwrap();
+			return Ok(vec![RuntimeVal::Matrix(res)]);
+		},
+		("constant", None) => {
+			return Err(EvaluationError::InvalidArguments(
+				String::from("fillmissing"),
+				tr("the method \"constant\" expects the value to fill with").to_string(),
+			))
+		},
+		(_, Some(_)) => {
+			return Err(EvaluationError::InvalidArguments(
+				String::from("fillmissing"),
+				tr_with("the method \"{0}\" takes no value", &[&method]),
+			))
+		},
+		("previous", None) => fill_previous,
+		("next", None) => fill_next,
+		("nearest", None) => fill_nearest,
+		("linear", None) => fill_linear,
+		_ => {
+			return Err(EvaluationError::InvalidArguments(
+				String::from("fillmissing"),
+				tr_with(
+					"unknown method \"{0}\", expected \"previous\", \"next\", \"nearest\", \
+					 \"linear\" or \"constant\"",
+					&[&method],
+				),
+			))
+		},
+	};
+
+	Ok(vec![RuntimeVal::Matrix(along(&v, fill))])
+}
+
+/// `rmoutliers(v)` removes the outliers of a vector, or the rows of a matrix with an
+/// outlier in some column. An outlier is more than three scaled median absolute
+/// deviations away from the median of its column, NaN being left out of both.
+pub fn rmoutliers(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let v = expect_matrix("rmoutliers", args.into_iter().next().unwrap())?;
+
+	if v.nrows() == 1 {
+		let data: Vec<f64> = outliers(v.as_slice())
+			.zip(v.as_slice())
+			.filter(|(outlier, _)| !outlier)
+			.map(|(_, &x)| x)
+			.collect();
+		return Ok(vec![RuntimeVal::Matrix(
+			Matrix::new(1, data.len(), data).unwrap(),
+		)]);
+	}
+
+	let mut kept = vec![true; v.nrows()];
+	for j in 0..v.ncols() {
+		let column: Vec<f64> = v.col(j).copied().collect();
+		for (keep, outlier) in kept.iter_mut().zip(outliers(&column)) {
+			*keep &= !outlier;
+		}
+	}
+
+	let data: Vec<f64> = v
+		.as_slice()
+		.chunks_exact(v.ncols().max(1))
+		.zip(&kept)
+		.filter(|(_, &keep)| keep)
+		.flat_map(|(row, _)| row.iter().copied())
+		.collect();
+	let nrows = kept.iter().filter(|&&keep| keep).count();
+	Ok(vec![RuntimeVal::Matrix(
+		Matrix::new(nrows, v.ncols(), data).unwrap(),
+	)])
+}
+
+fn present(values: &[f64]) -> impl Iterator<Item = f64> + '_ {
+	values.iter().copied().filter(|x| !x.is_nan())
+}
+
+// Vectors give a number, other matrices a row with a value per column
+fn reduce(a: &Matrix, f: impl Fn(&[f64]) -> f64) -> RuntimeVal {
+	if a.nrows() == 1 || a.ncols() == 1 || a.is_empty() {
+		return RuntimeVal::Number(f(a.as_slice()));
+	}
+
+	let res = (0..a.ncols())
+		.map(|j| f(&a.col(j).copied().collect::<Vec<_>>()))
+		.collect();
+	RuntimeVal::Matrix(Matrix::new(1, a.ncols(), res).unwrap())
+}
+
+fn fill_previous(v: &[f64]) -> Vec<f64> {
+	let mut last = f64::NAN;
+	v.iter()
+		.map(|&x| {
+			if !x.is_nan() {
+				last = x;
+			}
+			last
+		})
+		.collect()
+}
+
+fn fill_next(v: &[f64]) -> Vec<f64> {
+	let mut res: Vec<f64> = v.iter().rev().copied().collect();
+	res = fill_previous(&res);
+	res.reverse();
+	res
+}
+
+// The closer of the previous and next values, the next one when both are as close
+fn fill_nearest(v: &[f64]) -> Vec<f64> {
+	let mut next: Vec<Option<usize>> = vec![None; v.len()];
+	for k in (0..v.len()).rev() {
+		next[k] = match v[k].is_nan() {
+			true => next.get(k + 1).copied().flatten(),
+			false => Some(k),
+		};
+	}
+
+	let mut previous = None;
+	(0..v.len())
+		.map(|k| match (previous, next[k]) {
+			_ if !v[k].is_nan() => {
+				previous = Some(k);
+				v[k]
+			},
+			(Some(i), Some(j)) if k - i < j - k => v[i],
+			(_, Some(j)) => v[j],
+			(Some(i), None) => v[i],
+			(None, None) => f64::NAN,
+		})
+		.collect()
+}
+
+// Interpolates between the previous and next values, and extrapolates from the two
+// closest values past the first and the last
+fn fill_linear(v: &[f64]) -> Vec<f64> {
+	let known: Vec<usize> = (0..v.len()).filter(|&i| !v[i].is_nan()).collect();
+	if known.len() < 2 {
+		return v.to_vec();
+	}
+
+	let line = |i: usize, j: usize, k: usize| {
+		let slope = (v[j] - v[i]) / (j - i) as f64;
+		v[i] + slope * (k as f64 - i as f64)
+	};
+	let (first, last) = (known[0], known[known.len() - 1]);
+	let mut next = 0;
+	(0..v.len())
+		.map(|k| {
+			if !v[k].is_nan() {
+				next += 1;
+				return v[k];
+			}
+			match next {
+				0 => line(first, known[1], k),
+				n if n == known.len() => line(known[n - 2], last, k),
+				n => line(known[n - 1], known[n], k),
+			}
+		})
+		.collect()
+}
+
+// Whether each value is an outlier of the values
+fn outliers(values: &[f64]) -> impl Iterator<Item = bool> + '_ {
+	let center = median(present(values).collect());
+	let deviation = MAD_SCALE * median(present(values).map(|x| (x - center).abs()).collect());
+	values
+		.iter()
+		.map(move |x| (x - center).abs() > OUTLIER_THRESHOLD * deviation)
+}
+
+fn median(mut values: Vec<f64>) -> f64 {
+	if values.is_empty() {
+		return f64::NAN;
+	}
+	values.sort_by(f64::total_cmp);
+	let mid = values.len() / 2;
+	match values.len() % 2 {
+		0 => (values[mid - 1] + values[mid]) / 2.0,
+		_ => values[mid],
+	}
+}
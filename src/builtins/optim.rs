@@ -0,0 +1,220 @@
+use {
+	super::{expect_matrix, failure},
+	crate::{
+		eval::EvaluationError,
+		i18n::tr_with,
+		matrix::Matrix,
+		state::{RuntimeVal, State},
+	},
+};
+
+const EPSILON: f64 = 1e-9;
+
+// Bland's rule cannot cycle, this only guards against numerical trouble
+const MAX_PIVOTS: usize = 100_000;
+
+/// `[x, fval] = linprog(c, A, b)` minimizes `c' * x` subject to `A * x <= b`,
+/// returns the optimal point as a column and the objective value at it.
+/// The variables are free, add rows like `-x(i) <= 0` to make them non-negative.
+pub fn linprog(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let c = expect_matrix("linprog", args.next().unwrap())?;
+	let a = expect_matrix("linprog", args.next().unwrap())?;
+	let b = expect_matrix("linprog", args.next().unwrap())?;
+
+	let (m, n) = (a.nrows(), a.ncols());
+	if !(a.is_empty() && b.is_empty()) && (n != c.len() || m != b.len()) {
+		return Err(EvaluationError::InvalidArguments(
+			String::from("linprog"),
+			tr_with(
+				"A is {0}x{1}, expected {2}x{3} for {4} costs and {5} bounds",
+				&[&m, &n, &b.len(), &c.len(), &c.len(), &b.len()],
+			),
+		));
+	}
+
+	let a = if a.is_empty() {
+		Matrix::new(0, c.len(), vec![]).unwrap()
+	} else {
+		a
+	};
+
+	let x =
+		simplex(c.as_slice(), &a, b.as_slice()).map_err(|e| failure("linprog", e.to_string()))?;
+	let fval = x.iter().zip(c.as_slice()).map(|(x, c)| x * c).sum();
+
+	Ok(vec![
+		RuntimeVal::Matrix(Matrix::new(x.len(), 1, x).unwrap()),
+		RuntimeVal::Number(fval),
+	])
+}
+
+#[derive(Debug)]
+enum LpError {
+	Infeasible,
+	Unbounded,
+	TooManyPivots,
+}
+
+impl std::fmt::Display for LpError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Infeasible => write!(f, "the constraints cannot be satisfied"),
+			Self::Unbounded => write!(f, "the objective is unbounded"),
+			Self::TooManyPivots => write!(f, "no solution found after {MAX_PIVOTS} pivots"),
+		}
+	}
+}
+
+/*
+	Two phase simplex on a dense tableau. The free variables are split as
+	x = u - v with u, v >= 0, every row gets a slack variable and the rows
+	with a negative bound are negated and get an artificial variable, so
+	the columns are [u, v, slack, artificial, rhs].
+*/
+fn simplex(c: &[f64], a: &Matrix, b: &[f64]) -> Result<Vec<f64>, LpError> {
+	let (m, n) = (a.nrows(), a.ncols());
+	let artificial_rows: Vec<usize> = (0..m).filter(|&i| b[i] < 0.0).collect();
+
+	let first_artificial = 2 * n + m;
+	let rhs = first_artificial + artificial_rows.len();
+
+	let mut tableau = Matrix::zeros(m, rhs + 1);
+	let mut basis = vec![0; m];
+
+	for i in 0..m {
+		let sign = if b[i] < 0.0 { -1.0 } else { 1.0 };
+		for j in 0..n {
+			tableau[(i, j)] = sign * a[(i, j)];
+			tableau[(i, n + j)] = -sign * a[(i, j)];
+		}
+		tableau[(i, 2 * n + i)] = sign;
+		tableau[(i, rhs)] = sign * b[i];
+		basis[i] = 2 * n + i;
+	}
+
+	for (k, &i) in artificial_rows.iter().enumerate() {
+		tableau[(i, first_artificial + k)] = 1.0;
+		basis[i] = first_artificial + k;
+	}
+
+	// Phase 1: find a feasible basis by driving the artificial variables to zero
+	if !artificial_rows.is_empty() {
+		let mut cost = vec![0.0; rhs];
+		cost[first_artificial..].fill(1.0);
+
+		optimize(&mut tableau, &mut basis, &cost, rhs)?;
+
+		let infeasibility: f64 = (0..m)
+			.filter(|&i| basis[i] >= first_artificial)
+			.map(|i| tableau[(i, rhs)])
+			.sum();
+		if infeasibility > EPSILON * (1.0 + b.iter().map(|b| b.abs()).sum::<f64>()) {
+			return Err(LpError::Infeasible);
+		}
+
+		// Artificial variables left in the basis are zero, swap them out when possible
+		for i in 0..m {
+			if basis[i] >= first_artificial {
+				if let Some(j) = (0..first_artificial).find(|&j| tableau[(i, j)].abs() > EPSILON) {
+					pivot(&mut tableau, &mut basis, i, j);
+				}
+			}
+		}
+	}
+
+	// Phase 2: the artificial columns may no longer enter the basis
+	let mut cost = vec![0.0; rhs];
+	cost[..n].copy_from_slice(c);
+	for j in 0..n {
+		cost[n + j] = -c[j];
+	}
+
+	optimize(&mut tableau, &mut basis, &cost, first_artificial)?;
+
+	let mut values = vec![0.0; rhs];
+	for i in 0..m {
+		values[basis[i]] = tableau[(i, rhs)];
+	}
+
+	Ok((0..n).map(|j| values[j] - values[n + j]).collect())
+}
+
+/// Pivots until no column below `entering_limit` improves the objective.
+fn optimize(
+	tableau: &mut Matrix,
+	basis: &mut [usize],
+	cost: &[f64],
+	entering_limit: usize,
+) -> Result<(), LpError> {
+	let m = tableau.nrows();
+	let rhs = tableau.ncols() - 1;
+
+	for _ in 0..MAX_PIVOTS {
+		// Bland's rule: the first column with a negative reduced cost enters
+		let entering = (0..entering_limit).find(|&j| {
+			let reduced = cost[j]
+				- (0..m)
+					.map(|i| cost[basis[i]] * tableau[(i, j)])
+					.sum::<f64>();
+			reduced < -EPSILON
+		});
+
+		let Some(j) = entering else {
+			return Ok(());
+		};
+
+		// Ratio test, ties go to the smallest basic variable
+		let mut leaving: Option<(usize, f64)> = None;
+		for i in 0..m {
+			if tableau[(i, j)] <= EPSILON {
+				continue;
+			}
+
+			let ratio = tableau[(i, rhs)] / tableau[(i, j)];
+			let better = match leaving {
+				None => true,
+				Some((l, best)) => {
+					ratio < best - EPSILON || (ratio <= best + EPSILON && basis[i] < basis[l])
+				},
+			};
+			if better {
+				leaving = Some((i, ratio));
+			}
+		}
+
+		let Some((i, _)) = leaving else {
+			return Err(LpError::Unbounded);
+		};
+
+		pivot(tableau, basis, i, j);
+	}
+
+	Err(LpError::TooManyPivots)
+}
+
+fn pivot(tableau: &mut Matrix, basis: &mut [usize], row: usize, col: usize) {
+	let (m, ncols) = (tableau.nrows(), tableau.ncols());
+
+	let p = tableau[(row, col)];
+	for j in 0..ncols {
+		tableau[(row, j)] /= p;
+	}
+
+	for i in 0..m {
+		let factor = tableau[(i, col)];
+		if i == row || factor == 0.0 {
+			continue;
+		}
+		for j in 0..ncols {
+			let delta = factor * tableau[(row, j)];
+			tableau[(i, j)] -= delta;
+		}
+	}
+
+	basis[row] = col;
+}
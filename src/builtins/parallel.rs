@@ -0,0 +1,132 @@
+use {
+	super::{expect_matrix, expect_string, failure},
+	crate::{
+		ast::ASTNode,
+		eval::EvaluationError,
+		i18n::tr_with,
+		jobs::WORKER_STACK_SIZE,
+		lexer::{self, LexerConfig},
+		matrix::Matrix,
+		observer::CapturedOutput,
+		state::{RuntimeVal, State},
+	},
+	rand::Rng,
+	std::thread,
+};
+
+/// `parrows("expr", A)` evaluates `expr` once per row of `A` with the row bound to `x`,
+/// spreading the rows over a thread pool. Every worker gets its own copy of the workspace.
+/// Each result must be a number or a row vector of the same length, they are stacked
+/// into a matrix with one row per row of `A`. What the rows print is printed after
+/// the workers are done, in the order of the rows. In deterministic mode the random
+/// numbers of a row are the same whatever the number of workers.
+pub fn parrows(
+	state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let code = expect_string("parrows", args.next().unwrap())?;
+	let mat = expect_matrix("parrows", args.next().unwrap())?;
+
+	// Parsed once, so syntax errors are reported before starting the workers
+	let ast = parse(&code, &state.settings().lexer_config())?;
+
+	let nrows = mat.nrows();
+	let workers = thread::available_parallelism()
+		.map(|n| n.get())
+		.unwrap_or(1)
+		.clamp(1, nrows.max(1));
+	let rows_per_worker = nrows.div_ceil(workers);
+
+	// Ctrl-C stops the workers along with the foreground evaluation
+	let interrupt_flag = state.interrupt_flag();
+
+	// In deterministic mode each row has its own seed, so what it draws does not
+	// depend on how the rows are spread over the workers
+	let seeds: Option<Vec<u64>> = state
+		.is_deterministic()
+		.then(|| (0..nrows).map(|_| state.rng().gen()).collect());
+
+	let outputs: Vec<CapturedOutput> = (0..workers).map(|_| CapturedOutput::default()).collect();
+	let results: Vec<Result<Vec<Matrix>, EvaluationError>> = thread::scope(|s| {
+		let handles: Vec<_> = (0..workers)
+			.map(|w| {
+				let mut state = state.snapshot();
+				state.set_interrupt_flag(interrupt_flag.clone());
+				state.add_observer(Box::new(outputs[w].clone()));
+				let (ast, mat, seeds) = (&ast, &mat, &seeds);
+				let rows = w * rows_per_worker..((w + 1) * rows_per_worker).min(nrows);
+
+				let worker = thread::Builder::new().stack_size(WORKER_STACK_SIZE);
+				worker
+					.spawn_scoped(s, move || {
+						let mut res = vec![];
+						for i in rows {
+							if let Some(seeds) = seeds {
+								state.make_deterministic(seeds[i]);
+							}
+							let row = mat.select(&[i], &(0..mat.ncols()).collect::<Vec<_>>());
+							state.assign_var(String::from("x"), RuntimeVal::Matrix(row));
+
+							let value = state
+								.evaluator()
+								.evaluate(ast.clone(), &mut state)
+								.map_err(|e| {
+									failure("parrows", tr_with("row {0}: {1}", &[&(i + 1), &e]))
+								})?;
+
+							res.push(match value {
+								RuntimeVal::Number(n) => Matrix::from(n),
+								RuntimeVal::BigInt(n) => Matrix::from(n.to_f64()),
+								RuntimeVal::Matrix(m) if m.nrows() == 1 => m,
+								_ => {
+									return Err(failure(
+										"parrows",
+										tr_with(
+											"row {0}: the result is not a number or a row",
+											&[&(i + 1)],
+										),
+									))
+								},
+							});
+						}
+						Ok(res)
+					})
+					.expect("failed to spawn a worker thread")
+			})
+			.collect();
+
+		handles.into_iter().map(|h| h.join().unwrap()).collect()
+	});
+
+	for text in outputs.iter().flat_map(CapturedOutput::take) {
+		state.print(&text);
+	}
+
+	let mut res = Matrix::default();
+	for rows in results {
+		for row in rows? {
+			res = res.vcat(row).map_err(|(i, j)| {
+				failure(
+					"parrows",
+					tr_with("the results have different lengths ({0} vs {1})", &[&i, &j]),
+				)
+			})?;
+		}
+	}
+
+	Ok(vec![RuntimeVal::Matrix(res)])
+}
+
+fn parse(code: &str, config: &LexerConfig) -> Result<ASTNode, EvaluationError> {
+	let tokens =
+		lexer::try_tokenize_with(0, code, config).map_err(|e| failure("parrows", e.to_string()))?;
+	let mut ast = ASTNode::try_from(&tokens).map_err(|e| failure("parrows", e.to_string()))?;
+
+	// Only the value is needed, not printing it nor storing it in ans
+	ast.silence();
+	ast.store_in_ans = false;
+
+	Ok(ast)
+}
@@ -0,0 +1,159 @@
+use {
+	super::{expect_matrix, expect_number, failure},
+	crate::{
+		eval::{self, EvaluationError},
+		i18n::tr,
+		linalg,
+		matrix::Matrix,
+		state::{RuntimeVal, State},
+	},
+};
+
+/*
+	Polynomials are row vectors of coefficients, highest power first like Octave,
+	so [1, 0, -4] is x^2 - 4. Every function returns row vectors.
+*/
+
+/// `[re, im] = roots(p)` returns the roots of `p` as columns of real and imaginary parts,
+/// computed as the eigenvalues of the companion matrix.
+pub fn roots(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let p = expect_matrix("roots", args.into_iter().next().unwrap())?;
+
+	// Leading zeros do not change the polynomial, trailing zeros are roots at 0
+	let p = p.as_slice();
+	let first = p.iter().position(|&c| c != 0.0).unwrap_or(p.len());
+	let last = p.iter().rposition(|&c| c != 0.0).map_or(first, |i| i + 1);
+	let coefs = &p[first..last];
+	let zero_roots = p.len() - last;
+
+	let degree = coefs.len().saturating_sub(1);
+	let mut companion = Matrix::zeros(degree, degree);
+	for j in 0..degree {
+		companion[(0, j)] = -coefs[j + 1] / coefs[0];
+	}
+	for i in 1..degree {
+		companion[(i, i - 1)] = 1.0;
+	}
+
+	let mut res = linalg::hessenberg_eigenvalues(companion)
+		.ok_or_else(|| failure("roots", tr("the eigenvalues did not converge").to_string()))?;
+	res.extend(std::iter::repeat_n((0.0, 0.0), zero_roots));
+
+	let (re, im): (Vec<f64>, Vec<f64>) = res.into_iter().unzip();
+	Ok(vec![
+		RuntimeVal::Matrix(Matrix::new(re.len(), 1, re).unwrap()),
+		RuntimeVal::Matrix(Matrix::new(im.len(), 1, im).unwrap()),
+	])
+}
+
+/// `poly(r)` returns the monic polynomial whose roots are the elements of `r`.
+pub fn poly(_state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let r = expect_matrix("poly", args.into_iter().next().unwrap())?;
+
+	let mut res = vec![1.0];
+	for &root in r.as_slice() {
+		res = convolve(&res, &[1.0, -root]);
+	}
+
+	Ok(vec![row(res)])
+}
+
+/// `polyval(p, x)` evaluates `p` at every element of `x`.
+pub fn polyval(
+	state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let p = expect_matrix("polyval", args.next().unwrap())?;
+	let x = expect_matrix("polyval", args.next().unwrap())?;
+
+	// Horner's scheme
+	let data = x
+		.as_slice()
+		.iter()
+		.map(|&x| p.as_slice().iter().fold(0.0, |acc, &c| acc * x + c))
+		.collect();
+
+	let res = Matrix::new(x.nrows(), x.ncols(), data).unwrap();
+	Ok(vec![eval::collapse(res, state)])
+}
+
+/// `polyder(p)` returns the derivative of `p`.
+pub fn polyder(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let p = expect_matrix("polyder", args.into_iter().next().unwrap())?;
+	let p = p.as_slice();
+
+	let degree = p.len().saturating_sub(1);
+	let res: Vec<f64> = p[..degree]
+		.iter()
+		.enumerate()
+		.map(|(i, c)| c * (degree - i) as f64)
+		.collect();
+
+	// The derivative of a constant is 0, not an empty polynomial
+	Ok(vec![row(if res.is_empty() { vec![0.0] } else { res })])
+}
+
+/// `polyint(p)` returns the antiderivative of `p` that is 0 at 0,
+/// `polyint(p, k)` the one that is `k` at 0.
+pub fn polyint(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let p = expect_matrix("polyint", args.next().unwrap())?;
+	let k = match args.next() {
+		Some(k) => expect_number("polyint", k)?,
+		None => 0.0,
+	};
+
+	let degree = p.len();
+	let mut res: Vec<f64> = p
+		.as_slice()
+		.iter()
+		.enumerate()
+		.map(|(i, c)| c / (degree - i) as f64)
+		.collect();
+	res.push(k);
+
+	Ok(vec![row(res)])
+}
+
+/// `conv(a, b)` is the convolution of two vectors, the product of the polynomials.
+pub fn conv(_state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let a = expect_matrix("conv", args.next().unwrap())?;
+	let b = expect_matrix("conv", args.next().unwrap())?;
+
+	if a.is_empty() || b.is_empty() {
+		return Err(EvaluationError::InvalidArguments(
+			String::from("conv"),
+			tr("expected non-empty vectors").to_string(),
+		));
+	}
+
+	Ok(vec![row(convolve(a.as_slice(), b.as_slice()))])
+}
+
+fn convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+	let mut res = vec![0.0; a.len() + b.len() - 1];
+	for (i, x) in a.iter().enumerate() {
+		for (j, y) in b.iter().enumerate() {
+			res[i + j] += x * y;
+		}
+	}
+	res
+}
+
+fn row(data: Vec<f64>) -> RuntimeVal {
+	RuntimeVal::Matrix(Matrix::new(1, data.len(), data).unwrap())
+}
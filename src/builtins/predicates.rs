@@ -0,0 +1,88 @@
+use {
+	super::{expect_matrix, expect_number},
+	crate::{
+		eval::EvaluationError,
+		i18n::tr_with,
+		state::{RuntimeVal, State},
+	},
+};
+
+// Relative tolerance of issymmetric and isposdef when none is given
+const DEFAULT_TOLERANCE: f64 = 0.0;
+
+/// `issymmetric(A)` returns 1 if `A` is square and equal to its transpose,
+/// `issymmetric(A, tol)` allows differences up to `tol` times the largest element.
+pub fn issymmetric(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let a = expect_matrix("issymmetric", args.next().unwrap())?;
+	let tolerance = expect_tolerance("issymmetric", args.next())?;
+
+	Ok(vec![boolean(a.is_symmetric(tolerance))])
+}
+
+/// `isdiag(A)` returns 1 if every element of `A` off the main diagonal is 0.
+pub fn isdiag(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let a = expect_matrix("isdiag", args.into_iter().next().unwrap())?;
+
+	Ok(vec![boolean(a.is_diag())])
+}
+
+/// `istriu(A)` returns 1 if every element of `A` below the main diagonal is 0.
+pub fn istriu(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let a = expect_matrix("istriu", args.into_iter().next().unwrap())?;
+
+	Ok(vec![boolean(a.is_triu())])
+}
+
+/// `istril(A)` returns 1 if every element of `A` above the main diagonal is 0.
+pub fn istril(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let a = expect_matrix("istril", args.into_iter().next().unwrap())?;
+
+	Ok(vec![boolean(a.is_tril())])
+}
+
+/// `isposdef(A)` returns 1 if `A` is symmetric positive definite,
+/// `isposdef(A, tol)` uses `tol` for the symmetry check like `issymmetric`.
+pub fn isposdef(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let a = expect_matrix("isposdef", args.next().unwrap())?;
+	let tolerance = expect_tolerance("isposdef", args.next())?;
+
+	Ok(vec![boolean(a.is_posdef(tolerance))])
+}
+
+fn expect_tolerance(func_name: &str, arg: Option<RuntimeVal>) -> Result<f64, EvaluationError> {
+	let Some(arg) = arg else {
+		return Ok(DEFAULT_TOLERANCE);
+	};
+
+	let tolerance = expect_number(func_name, arg)?;
+	if tolerance < 0.0 || tolerance.is_nan() {
+		return Err(EvaluationError::InvalidArguments(
+			func_name.to_string(),
+			tr_with("expected a non-negative tolerance, got {0}", &[&tolerance]),
+		));
+	}
+	Ok(tolerance)
+}
+
+fn boolean(value: bool) -> RuntimeVal {
+	RuntimeVal::Number(if value { 1.0 } else { 0.0 })
+}
@@ -0,0 +1,231 @@
+use {
+	super::{expect_matrix, expect_number},
+	crate::{
+		eval::EvaluationError,
+		i18n::{tr, tr_with},
+		linalg,
+		matrix::Matrix,
+		state::{RuntimeVal, State},
+	},
+	rand::{
+		seq::{index, SliceRandom},
+		Rng,
+	},
+	std::f64::consts::PI,
+};
+
+/// `randperm(n)` returns a random permutation of `1:n` as a row,
+/// `randperm(n, k)` only its first `k` elements.
+pub fn randperm(
+	state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let n = expect_count("randperm", args.next().unwrap())?;
+	let k = match args.next() {
+		Some(k) => expect_count("randperm", k)?,
+		None => n,
+	};
+
+	if k > n {
+		return Err(EvaluationError::InvalidArguments(
+			String::from("randperm"),
+			tr_with(
+				"cannot take {0} elements from a permutation of {1}",
+				&[&k, &n],
+			),
+		));
+	}
+
+	let mut res: Vec<f64> = (1..=n).map(|i| i as f64).collect();
+	let (res, _) = res.partial_shuffle(state.rng(), k);
+
+	Ok(vec![row(res.to_vec())])
+}
+
+/// `shuffle(v)` returns the elements of `v` in a random order, with the shape of `v`.
+pub fn shuffle(
+	state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let v = expect_matrix("shuffle", args.into_iter().next().unwrap())?;
+
+	let (nrows, ncols) = (v.nrows(), v.ncols());
+	let mut data = v.into_vec();
+	data.shuffle(state.rng());
+
+	Ok(vec![RuntimeVal::Matrix(
+		Matrix::new(nrows, ncols, data).unwrap(),
+	)])
+}
+
+/// `sample(v, k)` draws `k` elements of `v` without replacement,
+/// `sample(v, k, replace)` with replacement when `replace` is not 0.
+/// Returns a column for a column vector and a row otherwise.
+pub fn sample(
+	state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let v = expect_matrix("sample", args.next().unwrap())?;
+	let k = expect_count("sample", args.next().unwrap())?;
+	let replace = match args.next() {
+		Some(replace) => expect_number("sample", replace)? != 0.0,
+		None => false,
+	};
+
+	let elements = v.as_slice();
+	let res: Vec<f64> = if replace {
+		if elements.is_empty() && k > 0 {
+			return Err(EvaluationError::InvalidArguments(
+				String::from("sample"),
+				tr("cannot sample from an empty set").to_string(),
+			));
+		}
+		let rng = state.rng();
+		(0..k)
+			.map(|_| elements[rng.gen_range(0..elements.len())])
+			.collect()
+	} else {
+		if k > elements.len() {
+			return Err(EvaluationError::InvalidArguments(
+				String::from("sample"),
+				tr_with(
+					"cannot draw {0} elements from {1} without replacement",
+					&[&k, &elements.len()],
+				),
+			));
+		}
+		elements.choose_multiple(state.rng(), k).copied().collect()
+	};
+
+	Ok(vec![if v.ncols() == 1 && v.nrows() > 1 {
+		RuntimeVal::Matrix(Matrix::new(k, 1, res).unwrap())
+	} else {
+		row(res)
+	}])
+}
+
+/// `randorth(n)` returns a random orthogonal n x n matrix, uniformly distributed
+/// (by the Haar measure): the Q of the QR decomposition of a Gaussian matrix, with
+/// the signs of its columns fixed by the diagonal of R.
+pub fn randorth(
+	state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let n = expect_count("randorth", args.into_iter().next().unwrap())?;
+
+	Ok(vec![RuntimeVal::Matrix(orthogonal(state, n))])
+}
+
+/// `randspd(n, c)` returns a random symmetric positive definite n x n matrix whose
+/// condition number is c, with eigenvalues spaced geometrically from 1 down to 1/c
+/// and random eigenvectors.
+pub fn randspd(
+	state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let n = expect_count("randspd", args.next().unwrap())?;
+	let cond = expect_number("randspd", args.next().unwrap())?;
+	if !(1.0..f64::INFINITY).contains(&cond) {
+		return Err(EvaluationError::InvalidArguments(
+			String::from("randspd"),
+			tr_with(
+				"expected a finite condition number of at least 1, got {0}",
+				&[&cond],
+			),
+		));
+	}
+
+	// q * diag(eigenvalues) * q', made exactly symmetric
+	let q = orthogonal(state, n);
+	let eigenvalues: Vec<f64> = (0..n)
+		.map(|i| cond.powf(-(i as f64) / (n - 1).max(1) as f64))
+		.collect();
+	let mut scaled = q.clone();
+	for i in 0..n {
+		for (j, eigenvalue) in eigenvalues.iter().enumerate() {
+			scaled[(i, j)] *= eigenvalue;
+		}
+	}
+	let mut res = linalg::matmul(&scaled, &q.transpose());
+	for i in 0..n {
+		for j in 0..i {
+			let mean = (res[(i, j)] + res[(j, i)]) / 2.0;
+			res[(i, j)] = mean;
+			res[(j, i)] = mean;
+		}
+	}
+
+	Ok(vec![RuntimeVal::Matrix(res)])
+}
+
+/// `randsparse(n, density)` returns an n x n matrix with `round(density * n^2)`
+/// elements drawn from the standard normal distribution at random positions,
+/// the others being 0.
+pub fn randsparse(
+	state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let n = expect_count("randsparse", args.next().unwrap())?;
+	let density = expect_number("randsparse", args.next().unwrap())?;
+	if !(0.0..=1.0).contains(&density) {
+		return Err(EvaluationError::InvalidArguments(
+			String::from("randsparse"),
+			tr_with("expected a density between 0 and 1, got {0}", &[&density]),
+		));
+	}
+
+	let len = n * n;
+	let count = (density * len as f64).round() as usize;
+	let mut data = vec![0.0; len];
+	for i in index::sample(state.rng(), len, count) {
+		data[i] = gaussian(state);
+	}
+
+	Ok(vec![RuntimeVal::Matrix(Matrix::new(n, n, data).unwrap())])
+}
+
+fn orthogonal(state: &mut State, n: usize) -> Matrix {
+	let data = (0..n * n).map(|_| gaussian(state)).collect();
+	let (mut q, r) = linalg::qr(&Matrix::new(n, n, data).unwrap());
+
+	for j in 0..n {
+		if r[(j, j)] < 0.0 {
+			for i in 0..n {
+				q[(i, j)] = -q[(i, j)];
+			}
+		}
+	}
+	q
+}
+
+// A sample of the standard normal distribution, by the Box-Muller transform
+fn gaussian(state: &mut State) -> f64 {
+	let rng = state.rng();
+	// In (0, 1] so the logarithm is finite
+	let u = 1.0 - rng.gen::<f64>();
+	let v: f64 = rng.gen();
+	(-2.0 * u.ln()).sqrt() * (2.0 * PI * v).cos()
+}
+
+fn expect_count(func_name: &str, arg: RuntimeVal) -> Result<usize, EvaluationError> {
+	let n = expect_number(func_name, arg)?;
+	if n.fract() != 0.0 || n < 0.0 || n > u32::MAX as f64 {
+		return Err(EvaluationError::InvalidArguments(
+			func_name.to_string(),
+			tr_with("expected a non-negative integer, got {0}", &[&n]),
+		));
+	}
+	Ok(n as usize)
+}
+
+fn row(data: Vec<f64>) -> RuntimeVal {
+	RuntimeVal::Matrix(Matrix::new(1, data.len(), data).unwrap())
+}
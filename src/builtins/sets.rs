@@ -0,0 +1,136 @@
+use {
+	super::expect_matrix,
+	crate::{
+		eval::EvaluationError,
+		matrix::Matrix,
+		state::{RuntimeVal, State},
+	},
+};
+
+/*
+	Sets are matrices whose elements are taken in column-major order.
+	The results are sorted without duplicates, as columns if every input
+	is a column vector and as rows otherwise, like Octave.
+*/
+
+/// `union(a, b)` returns the elements that are in `a` or in `b`.
+pub fn union(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let (a, b) = expect_sets("union", args)?;
+
+	let mut res = a.as_slice().to_vec();
+	res.extend_from_slice(b.as_slice());
+
+	Ok(vec![set(unique(res), is_column(&a) && is_column(&b))])
+}
+
+/// `intersect(a, b)` returns the elements that are both in `a` and in `b`.
+pub fn intersect(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let (a, b) = expect_sets("intersect", args)?;
+
+	let others = unique(b.as_slice().to_vec());
+	let res = unique(a.as_slice().to_vec())
+		.into_iter()
+		.filter(|x| contains(&others, *x))
+		.collect();
+
+	Ok(vec![set(res, is_column(&a) && is_column(&b))])
+}
+
+/// `setdiff(a, b)` returns the elements of `a` that are not in `b`.
+pub fn setdiff(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let (a, b) = expect_sets("setdiff", args)?;
+
+	let others = unique(b.as_slice().to_vec());
+	let res = unique(a.as_slice().to_vec())
+		.into_iter()
+		.filter(|x| !contains(&others, *x))
+		.collect();
+
+	Ok(vec![set(res, is_column(&a))])
+}
+
+/// `[tf, loc] = ismember(x, v)` returns a mask of the shape of `x` that is 1 where
+/// the element is in `v`, and the index of its first occurrence in `v` or 0.
+pub fn ismember(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let (x, v) = expect_sets("ismember", args)?;
+
+	// Sorted by value then index, so the first match is the first occurrence
+	let mut lookup: Vec<(f64, usize)> = v
+		.as_slice()
+		.iter()
+		.enumerate()
+		.map(|(i, &value)| (value, i + 1))
+		.collect();
+	lookup.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+
+	let loc: Vec<f64> = x
+		.as_slice()
+		.iter()
+		.map(|&value| {
+			let i = lookup.partition_point(|&(other, _)| other < value);
+			match lookup.get(i) {
+				Some(&(other, index)) if other == value => index as f64,
+				_ => 0.0,
+			}
+		})
+		.collect();
+	let tf = loc
+		.iter()
+		.map(|&i| if i > 0.0 { 1.0 } else { 0.0 })
+		.collect();
+
+	let (nrows, ncols) = (x.nrows(), x.ncols());
+	Ok(vec![
+		RuntimeVal::Matrix(Matrix::new(nrows, ncols, tf).unwrap()),
+		RuntimeVal::Matrix(Matrix::new(nrows, ncols, loc).unwrap()),
+	])
+}
+
+fn expect_sets(
+	func_name: &str,
+	args: Vec<RuntimeVal>,
+) -> Result<(Matrix, Matrix), EvaluationError> {
+	let mut args = args.into_iter();
+
+	let a = expect_matrix(func_name, args.next().unwrap())?;
+	let b = expect_matrix(func_name, args.next().unwrap())?;
+	Ok((a, b))
+}
+
+// NaN is never equal to itself, so every NaN is kept and sorted last
+fn unique(mut elements: Vec<f64>) -> Vec<f64> {
+	elements.sort_by(|a, b| a.total_cmp(b));
+	elements.dedup_by(|a, b| a == b);
+	elements
+}
+
+fn contains(sorted: &[f64], x: f64) -> bool {
+	let i = sorted.partition_point(|&other| other < x);
+	sorted.get(i) == Some(&x)
+}
+
+fn is_column(m: &Matrix) -> bool {
+	m.ncols() == 1 && m.nrows() > 1
+}
+
+fn set(elements: Vec<f64>, column: bool) -> RuntimeVal {
+	let len = elements.len();
+	let res = if column {
+		Matrix::new(len, 1, elements)
+	} else {
+		Matrix::new(1, len, elements)
+	};
+	RuntimeVal::Matrix(res.unwrap())
+}
@@ -0,0 +1,213 @@
+use {
+	super::{expect_matrix, expect_number},
+	crate::{
+		eval::EvaluationError,
+		i18n::tr_with,
+		matrix::Matrix,
+		state::{RuntimeVal, State},
+	},
+};
+
+// Largest matrix built by the generators
+const MAX_ELEMENTS: usize = 10_000_000;
+
+/// `tril(A)` keeps the elements of `A` on and below the main diagonal and sets the
+/// others to 0, `tril(A, k)` keeps those on and below the `k`-th diagonal above it.
+pub fn tril(_state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let a = expect_matrix("tril", args.next().unwrap())?;
+	let k = expect_diagonal("tril", args.next())?;
+
+	Ok(vec![RuntimeVal::Matrix(keep(&a, |i, j| j - i <= k))])
+}
+
+/// `triu(A)` keeps the elements of `A` on and above the main diagonal and sets the
+/// others to 0, `triu(A, k)` keeps those on and above the `k`-th diagonal above it.
+pub fn triu(_state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let a = expect_matrix("triu", args.next().unwrap())?;
+	let k = expect_diagonal("triu", args.next())?;
+
+	Ok(vec![RuntimeVal::Matrix(keep(&a, |i, j| j - i >= k))])
+}
+
+/// `toeplitz(c, r)` is the matrix constant along its diagonals with the first column `c`
+/// and the first row `r`, the diagonal is `c(1)`. `toeplitz(c)` is symmetric with `r = c`.
+pub fn toeplitz(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let col = expect_matrix("toeplitz", args.next().unwrap())?;
+	let row = match args.next() {
+		Some(arg) => expect_matrix("toeplitz", arg)?,
+		None => col.clone(),
+	};
+	let (col, row) = (col.as_slice(), row.as_slice());
+	check_size("toeplitz", col.len(), row.len())?;
+
+	let mut data = Vec::with_capacity(col.len() * row.len());
+	for i in 0..col.len() {
+		for j in 0..row.len() {
+			data.push(if i >= j { col[i - j] } else { row[j - i] });
+		}
+	}
+
+	let res = Matrix::new(col.len(), row.len(), data).unwrap();
+	Ok(vec![RuntimeVal::Matrix(res)])
+}
+
+/// `vander(v)` is the Vandermonde matrix whose columns are the powers of the vector `v`,
+/// from `v.^(n-1)` down to `v.^0`. `vander(v, n)` has `n` columns instead of `length(v)`.
+pub fn vander(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let v = expect_matrix("vander", args.next().unwrap())?;
+	let v = v.as_slice();
+	let ncols = match args.next() {
+		Some(arg) => expect_order("vander", arg)?,
+		None => v.len(),
+	};
+	check_size("vander", v.len(), ncols)?;
+
+	let mut data = Vec::with_capacity(v.len() * ncols);
+	for &x in v {
+		data.extend((0..ncols).rev().map(|power| x.powi(power as i32)));
+	}
+
+	let res = Matrix::new(v.len(), ncols, data).unwrap();
+	Ok(vec![RuntimeVal::Matrix(res)])
+}
+
+/// `hilb(n)` is the `n`x`n` Hilbert matrix `H(i, j) = 1 / (i + j - 1)`,
+/// a classic example of an ill-conditioned matrix.
+pub fn hilb(_state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let n = expect_order("hilb", args.into_iter().next().unwrap())?;
+	check_size("hilb", n, n)?;
+
+	let data = (0..n * n)
+		.map(|k| 1.0 / ((k / n + k % n + 1) as f64))
+		.collect();
+
+	Ok(vec![RuntimeVal::Matrix(Matrix::new(n, n, data).unwrap())])
+}
+
+/// `magic(n)` is an `n`x`n` magic square of the numbers 1 to n^2, whose rows, columns
+/// and diagonals all have the same sum. There is none of order 2, `magic(2)` is not magic.
+pub fn magic(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let n = expect_order("magic", args.into_iter().next().unwrap())?;
+	check_size("magic", n, n)?;
+
+	Ok(vec![RuntimeVal::Matrix(magic_square(n))])
+}
+
+fn magic_square(n: usize) -> Matrix {
+	let mut res = Matrix::zeros(n, n);
+
+	if n % 2 == 1 {
+		// The Siamese method, moving diagonally from the middle of the first row
+		for i in 0..n {
+			for j in 0..n {
+				let a = (i + j + n + 2 - (n + 3) / 2) % n;
+				let b = (i + 2 * j + 1) % n;
+				res[(i, j)] = (n * a + b + 1) as f64;
+			}
+		}
+	} else if n.is_multiple_of(4) {
+		// Counting in order, with the cells of the diagonals of each 4x4 block reversed
+		for i in 0..n {
+			for j in 0..n {
+				let k = (i * n + j + 1) as f64;
+				let reversed = ((i + 1) % 4 / 2) == ((j + 1) % 4 / 2);
+				res[(i, j)] = if reversed { (n * n + 1) as f64 - k } else { k };
+			}
+		}
+	} else {
+		// Strachey's method: four odd squares, with cells swapped between the top and bottom ones
+		let p = n / 2;
+		let quarter = magic_square(p);
+		let offsets = [(0, 0, 0.0), (0, p, 2.0), (p, 0, 3.0), (p, p, 1.0)];
+		for (r0, c0, offset) in offsets {
+			for i in 0..p {
+				for j in 0..p {
+					res[(r0 + i, c0 + j)] = quarter[(i, j)] + offset * (p * p) as f64;
+				}
+			}
+		}
+
+		let k = (n - 2) / 4;
+		let cols: Vec<usize> = (0..k).chain(n + 1 - k..n).collect();
+		for i in 0..p {
+			for &j in &cols {
+				swap_halves(&mut res, i, j);
+			}
+		}
+
+		// The middle row swaps the next column instead of the first, to keep the diagonals
+		let mut cols = vec![0, k];
+		cols.dedup();
+		for j in cols {
+			swap_halves(&mut res, k, j);
+		}
+	}
+
+	res
+}
+
+// Swaps the element (i, j) of the top half of a square matrix with the one below it
+fn swap_halves(a: &mut Matrix, i: usize, j: usize) {
+	let p = a.nrows() / 2;
+	let tmp = a[(i, j)];
+	a[(i, j)] = a[(i + p, j)];
+	a[(i + p, j)] = tmp;
+}
+
+// The elements (i, j) of `a` for which `predicate(i, j)` holds, 0 elsewhere
+fn keep(a: &Matrix, predicate: impl Fn(i64, i64) -> bool) -> Matrix {
+	let mut res = a.clone();
+	for i in 0..a.nrows() {
+		for j in 0..a.ncols() {
+			if !predicate(i as i64, j as i64) {
+				res[(i, j)] = 0.0;
+			}
+		}
+	}
+	res
+}
+
+fn expect_diagonal(func_name: &str, arg: Option<RuntimeVal>) -> Result<i64, EvaluationError> {
+	match arg {
+		Some(arg) => Ok(expect_number(func_name, arg)? as i64),
+		None => Ok(0),
+	}
+}
+
+fn expect_order(func_name: &str, arg: RuntimeVal) -> Result<usize, EvaluationError> {
+	let n = expect_number(func_name, arg)?;
+	if n < 0.0 || n > u32::MAX as f64 {
+		return Err(EvaluationError::InvalidArguments(
+			func_name.to_string(),
+			tr_with("expected a non-negative size, got {0}", &[&n]),
+		));
+	}
+	Ok(n as usize)
+}
+
+fn check_size(func_name: &str, nrows: usize, ncols: usize) -> Result<(), EvaluationError> {
+	if nrows.saturating_mul(ncols) > MAX_ELEMENTS {
+		return Err(EvaluationError::InvalidArguments(
+			func_name.to_string(),
+			tr_with("a {0}x{1} matrix is too large", &[&nrows, &ncols]),
+		));
+	}
+	Ok(())
+}
@@ -0,0 +1,242 @@
+use {
+	super::{expect_matrix, expect_string, expect_table},
+	crate::{
+		eval::EvaluationError,
+		format::escape_controls,
+		i18n::{tr, tr_with},
+		matrix::{Labels, Matrix},
+		state::{RuntimeVal, State},
+		table::Table,
+	},
+	std::{collections::HashMap, fmt::Write},
+};
+
+/// `summary(t)` prints the minimum, maximum, mean and NaN count of every column
+/// of a table (or a matrix).
+pub fn summary(
+	state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let (names, data): (Vec<String>, Matrix) = match args.into_iter().next().unwrap() {
+		RuntimeVal::Table(t) => (
+			t.names()
+				.iter()
+				.map(|name| escape_controls(name).into_owned())
+				.collect(),
+			t.data().clone(),
+		),
+		RuntimeVal::Matrix(m) => ((1..=m.ncols()).map(|j| j.to_string()).collect(), m),
+		RuntimeVal::Number(n) => (vec![String::from("1")], Matrix::from(n)),
+		RuntimeVal::BigInt(n) => (vec![String::from("1")], Matrix::from(n.to_f64())),
+		RuntimeVal::String(_) | RuntimeVal::Function(_) => {
+			return Err(EvaluationError::InvalidArguments(
+				String::from("summary"),
+				tr("expected a table or a matrix").to_string(),
+			))
+		},
+	};
+
+	let width = names.iter().map(|n| n.len()).max().unwrap_or(0).max(6);
+	let mut res = format!("{} rows, {} columns\n\n", data.nrows(), data.ncols());
+	let _ = writeln!(
+		res,
+		"  {:<width$}  {:>12}  {:>12}  {:>12}  {:>6}",
+		"column", "min", "max", "mean", "NaNs"
+	);
+
+	for (j, name) in names.iter().enumerate() {
+		let column = (0..data.nrows()).map(|i| data[(i, j)]);
+		let nan_count = column.clone().filter(|n| n.is_nan()).count();
+		let values: Vec<f64> = column.filter(|n| !n.is_nan()).collect();
+
+		let min = values.iter().copied().fold(f64::NAN, f64::min);
+		let max = values.iter().copied().fold(f64::NAN, f64::max);
+		let mean = values.iter().sum::<f64>() / values.len() as f64;
+
+		let _ = writeln!(
+			res,
+			"  {name:<width$}  {min:>12.4}  {max:>12.4}  {mean:>12.4}  {nan_count:>6}"
+		);
+	}
+	state.print(&res);
+
+	Ok(vec![])
+}
+
+/// `groupby(t, "key", "mean", "sum", ...)` groups the rows of `t` by the values of the
+/// key column and aggregates every other column with each of the given functions
+/// (count, sum, mean, min or max), the result has one row per key in ascending order.
+pub fn groupby(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let table = expect_table("groupby", args.next().unwrap())?;
+	let key_name = expect_string("groupby", args.next().unwrap())?;
+	let key = key_column("groupby", &table, &key_name)?;
+
+	let mut aggregations = vec![];
+	for arg in args {
+		let agg_name = expect_string("groupby", arg)?;
+		let agg: fn(&[f64]) -> f64 = match agg_name.as_str() {
+			"count" => |v| v.len() as f64,
+			"sum" => |v| v.iter().sum(),
+			"mean" => |v| v.iter().sum::<f64>() / v.len() as f64,
+			"min" => |v| v.iter().copied().fold(f64::INFINITY, f64::min),
+			"max" => |v| v.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+			_ => {
+				return Err(EvaluationError::InvalidArguments(
+					String::from("groupby"),
+					tr_with("unknown aggregation '{0}'", &[&agg_name]),
+				))
+			},
+		};
+		aggregations.push((agg_name, agg));
+	}
+
+	let data = table.data();
+	let mut rows: Vec<usize> = (0..data.nrows()).collect();
+	rows.sort_by(|&a, &b| data[(a, key)].total_cmp(&data[(b, key)]));
+
+	let groups: Vec<&[usize]> = rows
+		.chunk_by(|&a, &b| data[(a, key)].total_cmp(&data[(b, key)]).is_eq())
+		.collect();
+
+	let value_cols: Vec<usize> = (0..data.ncols()).filter(|&j| j != key).collect();
+
+	let mut names = vec![key_name];
+	for (agg_name, _) in &aggregations {
+		for &j in &value_cols {
+			names.push(format!("{agg_name}_{}", table.names()[j]));
+		}
+	}
+
+	let mut res = Vec::with_capacity(groups.len() * names.len());
+	for group in &groups {
+		res.push(data[(group[0], key)]);
+
+		for (_, agg) in &aggregations {
+			for &j in &value_cols {
+				let values: Vec<f64> = group.iter().map(|&i| data[(i, j)]).collect();
+				res.push(agg(&values));
+			}
+		}
+	}
+
+	let res = Matrix::new(groups.len(), names.len(), res).unwrap();
+	Ok(vec![RuntimeVal::Table(Table::new(names, res).unwrap())])
+}
+
+/// `join(t1, t2, "key")` is the inner join of two tables on their key columns,
+/// columns of `t2` whose names are already used get a "_2" suffix.
+pub fn join(_state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let lhs = expect_table("join", args.next().unwrap())?;
+	let rhs = expect_table("join", args.next().unwrap())?;
+	let key_name = expect_string("join", args.next().unwrap())?;
+
+	let lhs_key = key_column("join", &lhs, &key_name)?;
+	let rhs_key = key_column("join", &rhs, &key_name)?;
+	let (lhs_data, rhs_data) = (lhs.data(), rhs.data());
+
+	let mut rhs_rows: HashMap<u64, Vec<usize>> = HashMap::new();
+	for i in 0..rhs_data.nrows() {
+		let key = rhs_data[(i, rhs_key)];
+		// NaN keys never match, like NULL in SQL
+		if !key.is_nan() {
+			rhs_rows.entry(key_bits(key)).or_default().push(i);
+		}
+	}
+
+	let rhs_cols: Vec<usize> = (0..rhs_data.ncols()).filter(|&j| j != rhs_key).collect();
+
+	let mut names = lhs.names().to_vec();
+	for &j in &rhs_cols {
+		let name = &rhs.names()[j];
+		if names.contains(name) {
+			names.push(format!("{name}_2"));
+		} else {
+			names.push(name.clone());
+		}
+	}
+
+	let mut nrows = 0;
+	let mut res = vec![];
+	for i in 0..lhs_data.nrows() {
+		let key = lhs_data[(i, lhs_key)];
+		let Some(matches) = rhs_rows.get(&key_bits(key)).filter(|_| !key.is_nan()) else {
+			continue;
+		};
+
+		for &k in matches {
+			res.extend((0..lhs_data.ncols()).map(|j| lhs_data[(i, j)]));
+			res.extend(rhs_cols.iter().map(|&j| rhs_data[(k, j)]));
+			nrows += 1;
+		}
+	}
+
+	let res = Matrix::new(nrows, names.len(), res).unwrap();
+	Ok(vec![RuntimeVal::Table(Table::new(names, res).unwrap())])
+}
+
+fn key_column(func_name: &str, table: &Table, key_name: &str) -> Result<usize, EvaluationError> {
+	match table.names().iter().position(|n| n == key_name) {
+		Some(j) => Ok(j),
+		None => Err(EvaluationError::InvalidArguments(
+			func_name.to_string(),
+			tr_with("the table has no column named '{0}'", &[&key_name]),
+		)),
+	}
+}
+
+// 0.0 and -0.0 are the same key
+fn key_bits(key: f64) -> u64 {
+	(key + 0.0).to_bits()
+}
+
+/// `setlabels(A, "r1, r2", "c1, c2, c3")` names the rows and columns of A, which are
+/// printed as headers. An empty string leaves the rows or the columns without names.
+pub fn setlabels(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+	let mat = expect_matrix("setlabels", args.next().unwrap())?;
+
+	let mut names = || -> Result<Option<Vec<String>>, EvaluationError> {
+		let Some(names) = args.next() else {
+			return Ok(None);
+		};
+		let names = expect_string("setlabels", names)?;
+		Ok(match names.trim().is_empty() {
+			true => None,
+			false => Some(
+				names
+					.split(',')
+					.map(|name| name.trim().to_string())
+					.collect(),
+			),
+		})
+	};
+	let labels = Labels {
+		rows: names()?,
+		cols: names()?,
+	};
+
+	let (nrows, ncols) = (mat.nrows(), mat.ncols());
+	let count = |names: &Option<Vec<String>>| names.as_ref().map_or(0, Vec::len);
+	let (row_count, col_count) = (count(&labels.rows), count(&labels.cols));
+
+	match mat.with_labels(labels) {
+		Some(mat) => Ok(vec![RuntimeVal::Matrix(mat)]),
+		None => Err(EvaluationError::InvalidArguments(
+			String::from("setlabels"),
+			tr_with(
+				"{0} row and {1} column names for a {2}x{3} matrix",
+				&[&row_count, &col_count, &nrows, &ncols],
+			),
+		)),
+	}
+}
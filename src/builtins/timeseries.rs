@@ -0,0 +1,308 @@
+use {
+	super::{expect_matrix, expect_number},
+	crate::{
+		eval::EvaluationError,
+		i18n::{tr, tr_with},
+		matrix::Matrix,
+		state::{RuntimeVal, State},
+	},
+	std::{collections::VecDeque, f64::consts::PI},
+};
+
+/*
+	Functions of sampled signals. Like in MATLAB they work along a vector, or along
+	each column of a matrix. The moving statistics take windows of k samples centered
+	on each one, (k - 1) / 2 on each side for an odd k and one more before for an even
+	k, which shrink at the ends. A NaN makes every window containing it NaN.
+
+	Each runs in one pass over the samples whatever k is: sums are kept up to date as
+	the window slides, and extrema with a queue of the samples that may still become
+	the extremum of a later window.
+*/
+
+// Half the number of taps per phase of the anti-aliasing filter of resample
+const RESAMPLE_HALF_TAPS: usize = 10;
+// The shape of the Kaiser window of that filter
+const KAISER_BETA: f64 = 5.0;
+
+/// `movsum(v, k)` is the sum of each window of k samples.
+pub fn movsum(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	moving("movsum", args, |v, k| sliding_sums(v, k, false))
+}
+
+/// `movmean(v, k)` is the mean of each window of k samples.
+pub fn movmean(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	moving("movmean", args, |v, k| sliding_sums(v, k, true))
+}
+
+/// `movmax(v, k)` is the largest sample of each window of k samples.
+pub fn movmax(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	moving("movmax", args, |v, k| sliding_extrema(v, k, |x, y| x >= y))
+}
+
+/// `movmin(v, k)` is the smallest sample of each window of k samples.
+pub fn movmin(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	moving("movmin", args, |v, k| sliding_extrema(v, k, |x, y| x <= y))
+}
+
+/// `resample(v, p, q)` changes the sample rate of v by p/q, giving `ceil(n * p / q)`
+/// samples for n. Frequencies the new rate cannot represent are filtered out first
+/// with a lowpass FIR filter, windowed with a Kaiser window like in MATLAB.
+pub fn resample(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let v = expect_matrix("resample", args.next().unwrap())?;
+	let p = expect_positive("resample", args.next().unwrap(), "factor")?;
+	let q = expect_positive("resample", args.next().unwrap(), "factor")?;
+
+	let divisor = gcd(p, q);
+	let (p, q) = (p / divisor, q / divisor);
+	if p == 1 && q == 1 {
+		return Ok(vec![RuntimeVal::Matrix(v)]);
+	}
+
+	let filter = lowpass(p, q);
+	Ok(vec![RuntimeVal::Matrix(along(&v, |x| {
+		polyphase(x, &filter, p, q)
+	}))])
+}
+
+fn moving(
+	func_name: &str,
+	args: Vec<RuntimeVal>,
+	f: impl Fn(&[f64], usize) -> Vec<f64>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+
+	let v = expect_matrix(func_name, args.next().unwrap())?;
+	let k = expect_positive(func_name, args.next().unwrap(), "window length")?;
+
+	Ok(vec![RuntimeVal::Matrix(along(&v, |x| f(x, k)))])
+}
+
+fn expect_positive(
+	func_name: &str,
+	arg: RuntimeVal,
+	what: &'static str,
+) -> Result<usize, EvaluationError> {
+	let n = expect_number(func_name, arg)?;
+	if n < 1.0 {
+		return Err(EvaluationError::InvalidArguments(
+			func_name.to_string(),
+			tr_with("expected a positive {0}, got {1}", &[&tr(what), &n]),
+		));
+	}
+	Ok(n as usize)
+}
+
+// Applies `f` along a vector, or along each column of a matrix
+pub(super) fn along(v: &Matrix, f: impl Fn(&[f64]) -> Vec<f64>) -> Matrix {
+	if v.nrows() == 1 {
+		let res = f(v.as_slice());
+		return Matrix::new(1, res.len(), res).unwrap();
+	}
+
+	let cols: Vec<Vec<f64>> = (0..v.ncols())
+		.map(|j| f(&v.col(j).copied().collect::<Vec<_>>()))
+		.collect();
+	let nrows = cols.first().map_or(v.nrows(), Vec::len);
+	let data = (0..nrows)
+		.flat_map(|i| cols.iter().map(move |col| col[i]))
+		.collect();
+	Matrix::new(nrows, v.ncols(), data).unwrap()
+}
+
+// The first and last index of the window of each sample
+fn window(i: usize, k: usize, len: usize) -> (usize, usize) {
+	(i.saturating_sub(k / 2), (i + (k - 1) / 2).min(len - 1))
+}
+
+// The sums, or means, of the windows. Infinities and NaN are counted apart so they
+// leave the window without turning the sum into NaN, and the sum is compensated
+// (Neumaier) so rounding errors do not pile up as samples come and go
+fn sliding_sums(v: &[f64], k: usize, mean: bool) -> Vec<f64> {
+	#[derive(Default)]
+	struct Window {
+		sum: f64,
+		compensation: f64,
+		nan: usize,
+		pos_inf: usize,
+		neg_inf: usize,
+	}
+
+	impl Window {
+		fn add(&mut self, x: f64, sign: f64) {
+			let count = match x {
+				_ if x.is_nan() => &mut self.nan,
+				f64::INFINITY => &mut self.pos_inf,
+				f64::NEG_INFINITY => &mut self.neg_inf,
+				_ => {
+					let x = sign * x;
+					let total = self.sum + x;
+					self.compensation += match self.sum.abs() >= x.abs() {
+						true => (self.sum - total) + x,
+						false => (x - total) + self.sum,
+					};
+					self.sum = total;
+					return;
+				},
+			};
+			match sign > 0.0 {
+				true => *count += 1,
+				false => *count -= 1,
+			}
+		}
+
+		fn value(&self) -> f64 {
+			match (self.nan, self.pos_inf, self.neg_inf) {
+				(0, 0, 0) => self.sum + self.compensation,
+				(0, _, 0) => f64::INFINITY,
+				(0, 0, _) => f64::NEG_INFINITY,
+				_ => f64::NAN,
+			}
+		}
+	}
+
+	let mut window_sum = Window::default();
+	let (mut start, mut end) = (0, 0);
+	let mut res = Vec::with_capacity(v.len());
+	for i in 0..v.len() {
+		let (first, last) = window(i, k, v.len());
+		while end <= last {
+			window_sum.add(v[end], 1.0);
+			end += 1;
+		}
+		while start < first {
+			window_sum.add(v[start], -1.0);
+			start += 1;
+		}
+
+		let sum = window_sum.value();
+		res.push(match mean {
+			true => sum / (last - first + 1) as f64,
+			false => sum,
+		});
+	}
+	res
+}
+
+// The extrema of the windows, `keeps(x, y)` telling whether x is at least as extreme
+// as y. The queue holds the samples of the window that no later sample exceeds yet,
+// so its front is the extremum and each sample enters and leaves it once
+fn sliding_extrema(v: &[f64], k: usize, keeps: fn(f64, f64) -> bool) -> Vec<f64> {
+	let mut queue: VecDeque<usize> = VecDeque::new();
+	let mut nan = 0;
+	let (mut start, mut end) = (0, 0);
+	let mut res = Vec::with_capacity(v.len());
+	for i in 0..v.len() {
+		let (first, last) = window(i, k, v.len());
+		while end <= last {
+			if v[end].is_nan() {
+				nan += 1;
+			} else {
+				while queue.back().is_some_and(|&j| keeps(v[end], v[j])) {
+					queue.pop_back();
+				}
+				queue.push_back(end);
+			}
+			end += 1;
+		}
+		while start < first {
+			if v[start].is_nan() {
+				nan -= 1;
+			}
+			if queue.front() == Some(&start) {
+				queue.pop_front();
+			}
+			start += 1;
+		}
+
+		res.push(match (nan, queue.front()) {
+			(0, Some(&j)) => v[j],
+			_ => f64::NAN,
+		});
+	}
+	res
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+	match b {
+		0 => a,
+		_ => gcd(b, a % b),
+	}
+}
+
+// The lowpass filter of resample at p times the original rate, cutting at the
+// Nyquist frequency of the lower of the two rates. It is a sinc windowed by a Kaiser
+// window, with a gain of p to make up for the zeros inserted between the samples
+fn lowpass(p: usize, q: usize) -> Vec<f64> {
+	let factor = p.max(q);
+	let len = 2 * RESAMPLE_HALF_TAPS * factor + 1;
+	let center = (len / 2) as f64;
+	let cutoff = 0.5 / factor as f64;
+
+	let mut res: Vec<f64> = (0..len)
+		.map(|i| {
+			let t = i as f64 - center;
+			let sinc = match t == 0.0 {
+				true => 2.0 * cutoff,
+				false => (2.0 * PI * cutoff * t).sin() / (PI * t),
+			};
+			let r = t / center;
+			sinc * bessel_i0(KAISER_BETA * (1.0 - r * r).sqrt()) / bessel_i0(KAISER_BETA)
+		})
+		.collect();
+
+	let gain = p as f64 / res.iter().sum::<f64>();
+	res.iter_mut().for_each(|h| *h *= gain);
+	res
+}
+
+// Upsamples by p, filters and keeps one sample out of q, computing only the kept
+// samples and only the products with the samples that are not inserted zeros.
+// The filter is centered so the output sample m is at the time m * q / p of the input
+fn polyphase(x: &[f64], filter: &[f64], p: usize, q: usize) -> Vec<f64> {
+	let half = filter.len() / 2;
+	let len = (x.len() * p).div_ceil(q);
+
+	(0..len)
+		.map(|m| {
+			let t = m * q;
+			// The input samples at j * p within the filter around t
+			let first = t.saturating_sub(half).div_ceil(p);
+			let last = ((t + half) / p).min(x.len() - 1);
+			(first..=last)
+				.map(|j| x[j] * filter[t + half - j * p])
+				.sum()
+		})
+		.collect()
+}
+
+// The modified Bessel function of the first kind of order 0, by its series
+fn bessel_i0(x: f64) -> f64 {
+	let mut term = 1.0;
+	let mut res = 1.0;
+	for k in 1..50 {
+		term *= (x / (2.0 * k as f64)).powi(2);
+		res += term;
+		if term < res * f64::EPSILON {
+			break;
+		}
+	}
+	res
+}
@@ -0,0 +1,157 @@
+use {
+	super::{expect_matrix, expect_number, failure},
+	crate::{
+		eval::EvaluationError,
+		i18n::{tr, tr_with},
+		matrix::Matrix,
+		state::{RuntimeVal, State},
+	},
+};
+
+/*
+	Rotations and homogeneous transforms of 3D space, as 3x3 and 4x4 matrices
+	whose products, determinants and inverses take the fixed-size kernels of
+	`linalg`. Angles are in radians and rotations are counterclockwise looking
+	down the axis, so `rotz(t) * v` turns v by t from x towards y. Quaternions
+	are rows `[w x y z]` with the scalar part first, like in MATLAB.
+*/
+
+/// `rotx(t)` is the rotation by t about the x axis.
+pub fn rotx(_state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let (s, c) = expect_number("rotx", args.into_iter().next().unwrap())?.sin_cos();
+
+	Ok(vec![matrix3([[1.0, 0.0, 0.0], [0.0, c, -s], [0.0, s, c]])])
+}
+
+/// `roty(t)` is the rotation by t about the y axis.
+pub fn roty(_state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let (s, c) = expect_number("roty", args.into_iter().next().unwrap())?.sin_cos();
+
+	Ok(vec![matrix3([[c, 0.0, s], [0.0, 1.0, 0.0], [-s, 0.0, c]])])
+}
+
+/// `rotz(t)` is the rotation by t about the z axis.
+pub fn rotz(_state: &mut State, args: Vec<RuntimeVal>) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let (s, c) = expect_number("rotz", args.into_iter().next().unwrap())?.sin_cos();
+
+	Ok(vec![matrix3([[c, -s, 0.0], [s, c, 0.0], [0.0, 0.0, 1.0]])])
+}
+
+/// `trans(x, y, z)` is the 4x4 homogeneous transform translating by (x, y, z),
+/// `trans(x, y, z) * [p; 1]` moves the point p.
+pub fn trans(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut res = Matrix::identity(4);
+	for (i, arg) in args.into_iter().enumerate() {
+		res[(i, 3)] = expect_number("trans", arg)?;
+	}
+
+	Ok(vec![RuntimeVal::Matrix(res)])
+}
+
+/// `quat2rotm(q)` is the rotation of the quaternion `q = [w x y z]`, normalized first.
+pub fn quat2rotm(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let q = expect_matrix("quat2rotm", args.into_iter().next().unwrap())?;
+	let &[w, x, y, z] = q.as_slice() else {
+		return Err(failure(
+			"quat2rotm",
+			tr_with("expected a quaternion of 4 elements, got {0}", &[&q.len()]),
+		));
+	};
+
+	let norm = (w * w + x * x + y * y + z * z).sqrt();
+	if norm == 0.0 || !norm.is_finite() {
+		return Err(failure(
+			"quat2rotm",
+			tr("the quaternion has no direction").to_string(),
+		));
+	}
+	let (w, x, y, z) = (w / norm, x / norm, y / norm, z / norm);
+
+	Ok(vec![matrix3([
+		[
+			1.0 - 2.0 * (y * y + z * z),
+			2.0 * (x * y - w * z),
+			2.0 * (x * z + w * y),
+		],
+		[
+			2.0 * (x * y + w * z),
+			1.0 - 2.0 * (x * x + z * z),
+			2.0 * (y * z - w * x),
+		],
+		[
+			2.0 * (x * z - w * y),
+			2.0 * (y * z + w * x),
+			1.0 - 2.0 * (x * x + y * y),
+		],
+	])])
+}
+
+/// `rotm2quat(R)` is the unit quaternion `[w x y z]` of the 3x3 rotation R, with w >= 0.
+pub fn rotm2quat(
+	_state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let r = expect_matrix("rotm2quat", args.into_iter().next().unwrap())?;
+	if r.nrows() != 3 || r.ncols() != 3 {
+		return Err(failure(
+			"rotm2quat",
+			tr_with(
+				"expected a 3x3 rotation, got {0}x{1}",
+				&[&r.nrows(), &r.ncols()],
+			),
+		));
+	}
+
+	// Shepperd's method: computed from the largest of the four components,
+	// the others are not divided by a number close to 0
+	let r = |i: usize, j: usize| r[(i, j)];
+	let trace = r(0, 0) + r(1, 1) + r(2, 2);
+	let q = if trace > 0.0 {
+		let s = 2.0 * (trace + 1.0).sqrt();
+		[
+			s / 4.0,
+			(r(2, 1) - r(1, 2)) / s,
+			(r(0, 2) - r(2, 0)) / s,
+			(r(1, 0) - r(0, 1)) / s,
+		]
+	} else if r(0, 0) > r(1, 1) && r(0, 0) > r(2, 2) {
+		let s = 2.0 * (1.0 + r(0, 0) - r(1, 1) - r(2, 2)).sqrt();
+		[
+			(r(2, 1) - r(1, 2)) / s,
+			s / 4.0,
+			(r(0, 1) + r(1, 0)) / s,
+			(r(0, 2) + r(2, 0)) / s,
+		]
+	} else if r(1, 1) > r(2, 2) {
+		let s = 2.0 * (1.0 + r(1, 1) - r(0, 0) - r(2, 2)).sqrt();
+		[
+			(r(0, 2) - r(2, 0)) / s,
+			(r(0, 1) + r(1, 0)) / s,
+			s / 4.0,
+			(r(1, 2) + r(2, 1)) / s,
+		]
+	} else {
+		let s = 2.0 * (1.0 + r(2, 2) - r(0, 0) - r(1, 1)).sqrt();
+		[
+			(r(1, 0) - r(0, 1)) / s,
+			(r(0, 2) + r(2, 0)) / s,
+			(r(1, 2) + r(2, 1)) / s,
+			s / 4.0,
+		]
+	};
+
+	// q and -q are the same rotation, adding 0 turns the -0 of negated zeros into 0
+	let sign = if q[0] < 0.0 { -1.0 } else { 1.0 };
+	let q = q.iter().map(|x| sign * x + 0.0).collect();
+	Ok(vec![RuntimeVal::Matrix(Matrix::new(1, 4, q).unwrap())])
+}
+
+fn matrix3(rows: [[f64; 3]; 3]) -> RuntimeVal {
+	RuntimeVal::Matrix(Matrix::new(3, 3, rows.as_flattened().to_vec()).unwrap())
+}
@@ -0,0 +1,73 @@
+use {
+	super::{expect_string, failure},
+	crate::{
+		eval::EvaluationError,
+		i18n::tr,
+		state::{RuntimeVal, State},
+		workspace,
+	},
+};
+
+/// `checkpoint(path)` saves the variables, settings and aliases, so a long computation
+/// interrupted later can go on from there with `resume(path)`.
+pub fn checkpoint(
+	state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let path = expect_string("checkpoint", args.into_iter().next().unwrap())?;
+
+	workspace::checkpoint(state, &path)
+		.map_err(|e| failure("checkpoint", format!("'{path}': {e}")))?;
+
+	Ok(vec![])
+}
+
+/// `resume(path)` replaces the variables, settings and aliases with the ones saved by
+/// `checkpoint(path)`.
+pub fn resume(
+	state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let path = expect_string("resume", args.into_iter().next().unwrap())?;
+
+	workspace::resume(state, &path).map_err(|e| failure("resume", format!("'{path}': {e}")))?;
+
+	Ok(vec![])
+}
+
+/// `wsdiff(a, b)` prints the variables added, removed or changed from the workspace
+/// file `a` to `b`, with their shapes and the largest difference of their elements.
+/// `wsdiff(a)` compares `a` with the current variables.
+pub fn wsdiff(
+	state: &mut State,
+	args: Vec<RuntimeVal>,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let mut args = args.into_iter();
+	let old_path = expect_string("wsdiff", args.next().unwrap())?;
+	let new_path = args
+		.next()
+		.map(|arg| expect_string("wsdiff", arg))
+		.transpose()?;
+
+	let read =
+		|path: &str| workspace::read(path).map_err(|e| failure("wsdiff", format!("'{path}': {e}")));
+	let old = read(&old_path)?;
+	let new = match &new_path {
+		Some(path) => read(path)?,
+		None => state
+			.variables()
+			.map(|(name, value)| (name.clone(), value.clone()))
+			.collect(),
+	};
+
+	let diff = workspace::diff(old, new);
+	match diff.is_empty() {
+		true => state.print(tr("The workspaces hold the same variables")),
+		false => {
+			let report = diff.report(state.settings().number_format);
+			state.print(report.trim_end());
+		},
+	}
+
+	Ok(vec![])
+}
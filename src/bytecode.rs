@@ -0,0 +1,538 @@
+use {
+	crate::{
+		arith,
+		ast::{ASTNode, ASTNodeKind, BinaryOpKind},
+		eval::{self, EvaluationError},
+		matrix::Matrix,
+		state::{RuntimeVal, State},
+	},
+	std::{
+		collections::BTreeSet,
+		sync::{Arc, OnceLock},
+	},
+};
+
+/*
+	The bytecode backend, chosen with `nam --backend bytecode`. A statement is
+	compiled once into a flat list of instructions run on a stack of values, so
+	the body of a loop is not cloned and walked again on every iteration and the
+	texts describing operations for the profiler are written once. The jumps of
+	`if`, `while`, `&&` and `||` become instructions too. The operations themselves
+	are the ones of the tree walker (see `eval::binary` and `eval::linear`), so both
+	backends give the same results and errors. Nodes without instructions of their
+	own, like calls, matrices and ranges, are handed to the tree walker whole, with
+	their arguments. Aliases keep their compiled body, see `Compiled`.
+*/
+
+/// A compiled statement or expression, leaving its value on the stack.
+#[derive(Debug)]
+pub struct Chunk {
+	code: Vec<Instr>,
+	// The expressions assigned to variables, kept for `:recompute`
+	definitions: Vec<ASTNode>,
+	// How many `while` loops count their iterations
+	loops: usize,
+}
+
+/// The compiled body of an alias, compiled on the first call with the bytecode
+/// backend. Clones share it, and redefining the alias starts over.
+#[derive(Debug, Clone, Default)]
+pub struct Compiled(Arc<OnceLock<Chunk>>);
+
+impl Compiled {
+	pub fn get_or_compile(&self, body: &ASTNode) -> &Chunk {
+		self.0.get_or_init(|| Chunk::compile(body))
+	}
+}
+
+#[derive(Debug)]
+enum Instr {
+	/// Pushes a number or a string.
+	Push(RuntimeVal),
+	/// Pushes the value of a variable.
+	Load(String),
+	/// Pushes the value of a node left to the tree walker.
+	Eval(Box<ASTNode>),
+	/// Pops the right and the left operands and pushes the result.
+	Binary(Box<BinaryOp>),
+	/// Pops the terms of a sum, the first one deepest, and pushes the result.
+	Linear(Box<Linear>),
+	/// Transposes the top of the stack, described by the text for the profiler.
+	Transpose(String),
+	Not,
+	/// Pops a value and jumps to `target` when its truth is `when`.
+	Branch {
+		test: Test,
+		when: bool,
+		target: usize,
+	},
+	Jump(usize),
+	Pop,
+	/// Collects the variables read by a definition before evaluating it.
+	Inputs(usize),
+	/// Assigns the top of the stack to a variable, with its definition.
+	Store {
+		name: String,
+		definition: usize,
+	},
+	/// Assigns the top of the stack to `ans`, and prints it.
+	StoreAns {
+		print: bool,
+	},
+	/// Prints the top of the stack as `name = value`.
+	Print(String),
+	/// Starts counting the iterations of a loop.
+	LoopStart(usize),
+	/// Counts an iteration, failing past the limit of the settings or on Ctrl-C.
+	Iterate(usize),
+}
+
+#[derive(Debug)]
+struct BinaryOp {
+	op: BinaryOpKind,
+	names: [Option<String>; 2],
+	operation: String,
+}
+
+#[derive(Debug)]
+struct Linear {
+	names: Vec<Option<String>>,
+	texts: Vec<(f64, String)>,
+}
+
+/// How a value is taken for true, by `&&` and `||` or by the condition of a statement.
+#[derive(Debug, Clone, Copy)]
+enum Test {
+	Truth(&'static str),
+	Condition(&'static str),
+}
+
+impl Chunk {
+	pub fn compile(ast: &ASTNode) -> Self {
+		let mut compiler = Compiler::default();
+		compiler.node(ast);
+		Self {
+			code: compiler.code,
+			definitions: compiler.definitions,
+			loops: compiler.loops,
+		}
+	}
+
+	pub fn run(&self, state: &mut State) -> Result<RuntimeVal, EvaluationError> {
+		if state.is_interrupted() {
+			return Err(EvaluationError::Interrupted);
+		}
+
+		let mut stack = vec![];
+		let mut inputs = vec![];
+		let mut iterations = vec![0; self.loops];
+		let mut pc = 0;
+		while let Some(instr) = self.code.get(pc) {
+			pc += 1;
+			match instr {
+				Instr::Push(value) => stack.push(value.clone()),
+				Instr::Load(var_name) => match state.lookup_var(var_name) {
+					Some(value) => stack.push(value.clone()),
+					None => return Err(EvaluationError::NonexistantVar(var_name.clone())),
+				},
+				Instr::Eval(ast) => stack.push(eval::evaluate((**ast).clone(), state)?),
+				Instr::Binary(binary) => {
+					let rhs = stack.pop().unwrap();
+					let lhs = stack.pop().unwrap();
+					let res = eval::binary(
+						&binary.op,
+						lhs,
+						rhs,
+						&binary.names,
+						&binary.operation,
+						state,
+					)?;
+					stack.push(res);
+				},
+				Instr::Linear(linear) => {
+					let values = stack.split_off(stack.len() - linear.names.len());
+					let values = linear
+						.texts
+						.iter()
+						.zip(values)
+						.zip(&linear.names)
+						.map(|((&(c, _), value), name)| (c, value, name.clone()))
+						.collect();
+					stack.push(eval::linear(values, &linear.texts, state)?);
+				},
+				Instr::Transpose(operation) => {
+					let value = stack.pop().unwrap();
+					stack.push(eval::transpose(value, operation, state)?);
+				},
+				Instr::Not => {
+					let value = stack.pop().unwrap();
+					stack.push(arith::not(value)?);
+				},
+				Instr::Branch { test, when, target } => {
+					let value = stack.pop().unwrap();
+					let truth = match test {
+						Test::Truth(op) => arith::truth(value, op)?,
+						Test::Condition(keyword) => arith::condition(value, keyword)?,
+					};
+					if truth == *when {
+						pc = *target;
+					}
+				},
+				Instr::Jump(target) => pc = *target,
+				Instr::Pop => {
+					stack.pop();
+				},
+				Instr::Inputs(definition) => {
+					let mut names = BTreeSet::new();
+					eval::read_variables(&self.definitions[*definition], state, &mut names);
+					inputs.push(names);
+				},
+				Instr::Store { name, definition } => {
+					let value = stack.last().unwrap().clone();
+					state.assign_var(name.clone(), value);
+					let definition = self.definitions[*definition].clone();
+					state.define_var(name.clone(), definition, inputs.pop().unwrap());
+				},
+				Instr::StoreAns { print } => {
+					let value = stack.last().unwrap();
+					state.assign_var(String::from("ans"), value.clone());
+					if *print {
+						state.print_value("ans", value);
+					}
+				},
+				Instr::Print(name) => state.print_value(name, stack.last().unwrap()),
+				Instr::LoopStart(slot) => iterations[*slot] = 0,
+				Instr::Iterate(slot) => {
+					if state.is_interrupted() {
+						return Err(EvaluationError::Interrupted);
+					}
+					if let Some(limit) = state.settings().max_iterations {
+						if iterations[*slot] == limit {
+							return Err(EvaluationError::TooManyIterations(limit));
+						}
+					}
+					iterations[*slot] += 1;
+				},
+			}
+		}
+
+		Ok(stack.pop().unwrap())
+	}
+}
+
+#[derive(Default)]
+struct Compiler {
+	code: Vec<Instr>,
+	definitions: Vec<ASTNode>,
+	loops: usize,
+}
+
+impl Compiler {
+	// Emits the instructions pushing the value of the node, and storing or printing
+	// it as the tree walker would
+	fn node(&mut self, ast: &ASTNode) {
+		match &ast.kind {
+			ASTNodeKind::Number(n) => {
+				self.code.push(Instr::Push(RuntimeVal::Number(*n)));
+				self.store_in_ans(ast);
+			},
+			ASTNodeKind::String(s) => {
+				self.code.push(Instr::Push(RuntimeVal::String(s.clone())));
+				self.store_in_ans(ast);
+			},
+
+			ASTNodeKind::Variable(var_name) => {
+				self.code.push(Instr::Load(var_name.clone()));
+				if ast.print_result {
+					self.code.push(Instr::Print(var_name.clone()));
+				}
+			},
+
+			ASTNodeKind::Assignment(var_name, value) => {
+				let definition = self.definitions.len();
+				self.definitions.push((**value).clone());
+				self.code.push(Instr::Inputs(definition));
+				self.node(value);
+				self.code.push(Instr::Store {
+					name: var_name.clone(),
+					definition,
+				});
+				if ast.print_result {
+					self.code.push(Instr::Print(var_name.clone()));
+				}
+			},
+
+			ASTNodeKind::BinaryExpr(BinaryOpKind::Add | BinaryOpKind::Subtract, _, _)
+			| ASTNodeKind::Negate(_)
+			| ASTNodeKind::Plus(_) => {
+				let mut terms = vec![];
+				eval::linear_terms(ast.kind.clone().into(), 1.0, &mut terms);
+
+				let mut linear = Linear {
+					names: vec![],
+					texts: vec![],
+				};
+				for (c, term) in &terms {
+					self.node(term);
+					linear.names.push(eval::variable_name(term));
+					linear.texts.push((*c, eval::operand_text(term)));
+				}
+				self.code.push(Instr::Linear(Box::new(linear)));
+				self.store_in_ans(ast);
+			},
+
+			ASTNodeKind::BinaryExpr(op, lhs, rhs) => {
+				self.node(lhs);
+				self.node(rhs);
+				self.code.push(Instr::Binary(Box::new(BinaryOp {
+					op: op.clone(),
+					names: [eval::variable_name(lhs), eval::variable_name(rhs)],
+					operation: format!(
+						"{} {op} {}",
+						eval::operand_text(lhs),
+						eval::operand_text(rhs)
+					),
+				})));
+				self.store_in_ans(ast);
+			},
+
+			ASTNodeKind::Transpose(operand) => {
+				self.node(operand);
+				let operation = format!("{}'", eval::operand_text(operand));
+				self.code.push(Instr::Transpose(operation));
+				self.store_in_ans(ast);
+			},
+
+			ASTNodeKind::Not(operand) => {
+				self.node(operand);
+				self.code.push(Instr::Not);
+				self.store_in_ans(ast);
+			},
+
+			// Both operands jump to the same result when they decide it
+			ASTNodeKind::ShortAnd(lhs, rhs) | ASTNodeKind::ShortOr(lhs, rhs) => {
+				let (op, decides) = match ast.kind {
+					ASTNodeKind::ShortAnd(..) => ("&&", false),
+					_ => ("||", true),
+				};
+				let mut decided = vec![];
+				for operand in [lhs, rhs] {
+					self.node(operand);
+					decided.push(self.branch(Test::Truth(op), decides));
+				}
+				self.code
+					.push(Instr::Push(RuntimeVal::Number(!decides as u8 as f64)));
+				let end = self.jump();
+				for at in decided {
+					self.patch(at);
+				}
+				self.code
+					.push(Instr::Push(RuntimeVal::Number(decides as u8 as f64)));
+				self.patch(end);
+				self.store_in_ans(ast);
+			},
+
+			// The value of the last statement evaluated stays on the stack
+			ASTNodeKind::If(branches, otherwise) => {
+				self.code
+					.push(Instr::Push(RuntimeVal::Matrix(Matrix::default())));
+				let mut ends = vec![];
+				for (condition, body) in branches {
+					self.node(condition);
+					let next = self.branch(Test::Condition("if"), false);
+					self.block(body);
+					ends.push(self.jump());
+					self.patch(next);
+				}
+				self.block(otherwise);
+				for at in ends {
+					self.patch(at);
+				}
+			},
+
+			ASTNodeKind::While(condition, body) => {
+				let slot = self.loops;
+				self.loops += 1;
+
+				self.code
+					.push(Instr::Push(RuntimeVal::Matrix(Matrix::default())));
+				self.code.push(Instr::LoopStart(slot));
+				let start = self.code.len();
+				self.node(condition);
+				let end = self.branch(Test::Condition("while"), false);
+				self.code.push(Instr::Iterate(slot));
+				self.block(body);
+				self.code.push(Instr::Jump(start));
+				self.patch(end);
+			},
+
+			_ => self.code.push(Instr::Eval(Box::new(ast.clone()))),
+		}
+	}
+
+	// Each statement replaces the value of the previous one
+	fn block(&mut self, stmts: &[ASTNode]) {
+		for stmt in stmts {
+			self.code.push(Instr::Pop);
+			self.node(stmt);
+		}
+	}
+
+	fn store_in_ans(&mut self, ast: &ASTNode) {
+		if ast.store_in_ans {
+			self.code.push(Instr::StoreAns {
+				print: ast.print_result,
+			});
+		}
+	}
+
+	// Jumps are emitted before their target is known, and patched once it is
+	fn branch(&mut self, test: Test, when: bool) -> usize {
+		self.code.push(Instr::Branch {
+			test,
+			when,
+			target: usize::MAX,
+		});
+		self.code.len() - 1
+	}
+
+	fn jump(&mut self) -> usize {
+		self.code.push(Instr::Jump(usize::MAX));
+		self.code.len() - 1
+	}
+
+	fn patch(&mut self, at: usize) {
+		let here = self.code.len();
+		match &mut self.code[at] {
+			Instr::Branch { target, .. } | Instr::Jump(target) => *target = here,
+			_ => unreachable!("only jumps are patched"),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use {
+		super::*,
+		crate::{
+			alias::Alias, backend::Backend, jobs::WORKER_STACK_SIZE, lexer,
+			observer::CapturedOutput,
+		},
+	};
+
+	// What a program prints, and the error stopping it
+	type Run = (Vec<String>, Option<String>);
+
+	fn run(backend: Backend, aliases: &[&str], code: &str) -> Run {
+		let mut state = State::new();
+		state.set_backend(backend);
+		state.settings_mut().max_iterations = Some(100);
+		let output = CapturedOutput::default();
+		state.add_observer(Box::new(output.clone()));
+
+		let config = state.settings().lexer_config();
+		for definition in aliases {
+			let (name, alias) = Alias::parse(definition, &config).unwrap();
+			state.define_alias(name, alias).unwrap();
+		}
+
+		let tokens = lexer::try_tokenize_with(0, code, &config).unwrap();
+		let mut error = None;
+		for stmt in ASTNode::parse_program(&tokens).unwrap() {
+			if let Err(e) = state.evaluator().evaluate_stmt(stmt, &mut state) {
+				error = Some(e.to_string());
+				break;
+			}
+		}
+		(output.take(), error)
+	}
+
+	// Runs the program with both backends, which must print the same and fail the same.
+	// On the stack of a worker, as deep as aliases go
+	fn same(aliases: &[&str], code: &str) -> Run {
+		let [tree, bytecode] = [Backend::TreeWalker, Backend::Bytecode].map(|backend| {
+			std::thread::scope(|s| {
+				std::thread::Builder::new()
+					.stack_size(WORKER_STACK_SIZE)
+					.spawn_scoped(s, || run(backend, aliases, code))
+					.unwrap()
+					.join()
+					.unwrap()
+			})
+		});
+		assert_eq!(bytecode, tree, "with the program\n{code}");
+		tree
+	}
+
+	#[test]
+	fn short_circuit() {
+		let (output, error) = same(
+			&[],
+			"a = 0 && nope\nb = 1 || nope\nc = 1 && 2\nd = 0 || 0\n1 && nope",
+		);
+		assert_eq!(output, ["a = 0", "b = 1", "c = 1", "d = 0"]);
+		assert!(error.is_some_and(|e| e.contains("nope")));
+
+		// The operands must have a truth value
+		let (_, error) = same(&[], "x = 0/0 && 1");
+		assert!(error.is_some());
+		let (_, error) = same(&[], "x = 0 || [1 2]");
+		assert!(error.is_some());
+	}
+
+	#[test]
+	fn while_limit() {
+		// Each loop counts its own iterations, from 0 every time it starts
+		let (output, error) = same(
+			&[],
+			"n = 0;\ni = 0;\nwhile i < 60\n  i = i + 1;\n  j = 0;\n  while j < 60\n    j = j + 1;\n    n = n + 1;\n  end\nend\nn",
+		);
+		assert_eq!(output, ["n = 3600"]);
+		assert_eq!(error, None);
+
+		let (output, error) = same(&[], "i = 0;\nwhile 1\n  i = i + 1;\nend");
+		assert!(output.is_empty());
+		assert!(error.is_some_and(|e| e.contains("100")));
+		let (output, _) = same(&[], "i = 0;\nwhile 1\n  i = i + 1;\nend\ni");
+		assert!(output.is_empty());
+
+		// The compiled body of an alias is called on every iteration
+		let (output, _) = same(
+			&["one(n) = n / n"],
+			"i = 0;\nwhile i < 90\n  i = i + one(i + 1);\nend\ni",
+		);
+		assert_eq!(output, ["i = 90"]);
+	}
+
+	#[test]
+	fn ans_and_printing() {
+		let (output, error) = same(
+			&[],
+			"1 + 2\nans * 2\nx = 3\nx\nx;\n-x\n[1 2]'\nx' + 1;\nans\nif x > 2\n  y = 1\n  x + 1\nelse\n  y = 2\nend\nans\nwhile x > 1\n  x = x - 1\nend",
+		);
+		assert_eq!(error, None);
+		let column = "ans = [\n     1\n     2\n]";
+		assert_eq!(
+			output,
+			[
+				"ans = 3", "ans = 6", "x = 3", "x = 3", "ans = -3", column, "ans = 4", "y = 1",
+				"ans = 4", "ans = 4", "x = 2", "x = 1",
+			]
+		);
+	}
+
+	#[test]
+	fn eval_fallbacks() {
+		let (output, error) = same(
+			&["sq(x) = x * x"],
+			"v = [1 -2 3]\nv(2) = 5\nv(end)\nw = abs(-v)\n1:2:5\n[L, U] = lu(magic(3));\nf = @abs\nmap(f, [-1 2])\nsq(v(1) + 2)\nsq(v)",
+		);
+		assert!(output.contains(&String::from("f = @abs")));
+		assert_eq!(output.last().map(String::as_str), Some("ans = 9"));
+		assert!(error.is_some_and(|e| e.contains("inner dimensions")));
+
+		// Errors inside calls are the same, including the depth limit of aliases
+		let (_, error) = same(&["fact(n) = n * fact(n - 1)"], "fact(3)");
+		assert!(error.is_some_and(|e| e.contains("64")));
+	}
+}
@@ -0,0 +1,155 @@
+use {
+	crate::{
+		i18n::{tr, tr_with},
+		matrix::Matrix,
+		state::{RuntimeVal, State},
+	},
+	std::fmt::Write,
+};
+
+/*
+	Values are copied as delimited text so they can be pasted into spreadsheets,
+	tab separated by default. With the `decimal comma` setting numbers use a
+	decimal comma and CSV cells are separated by semicolons, like `readcsv` expects.
+	The system clipboard is only reachable when built with the `clipboard` feature.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ClipboardFormat {
+	#[default]
+	Tsv,
+	Csv,
+}
+
+impl ClipboardFormat {
+	pub fn from_name(name: &str) -> Option<Self> {
+		match name {
+			"tsv" => Some(Self::Tsv),
+			"csv" => Some(Self::Csv),
+			_ => None,
+		}
+	}
+}
+
+/// Places a value on the system clipboard, strings as they are
+/// and numbers, matrices and tables as delimited text.
+pub fn copy(
+	state: &State,
+	value: &RuntimeVal,
+	format: ClipboardFormat,
+) -> Result<(), ClipboardError> {
+	let decimal_comma = state.settings().decimal_comma;
+	let delimiter = match format {
+		ClipboardFormat::Tsv => '\t',
+		ClipboardFormat::Csv if decimal_comma => ';',
+		ClipboardFormat::Csv => ',',
+	};
+
+	let text = match value {
+		RuntimeVal::String(s) => s.clone(),
+		RuntimeVal::Number(n) => cell(*n, decimal_comma),
+		RuntimeVal::BigInt(n) => n.to_string(),
+		RuntimeVal::Matrix(m) => rows(m, delimiter, decimal_comma),
+		RuntimeVal::Table(t) => {
+			let names: Vec<String> = t
+				.names()
+				.iter()
+				.map(|name| quote(name, delimiter))
+				.collect();
+			format!(
+				"{}\n{}",
+				names.join(&delimiter.to_string()),
+				rows(t.data(), delimiter, decimal_comma)
+			)
+		},
+		RuntimeVal::Function(_) => return Err(ClipboardError::NotCopyable),
+	};
+
+	set_text(text)
+}
+
+fn rows(mat: &Matrix, delimiter: char, decimal_comma: bool) -> String {
+	let mut res = String::new();
+	for row in mat.rows() {
+		let cells: Vec<String> = row.iter().map(|&x| cell(x, decimal_comma)).collect();
+		let _ = writeln!(res, "{}", cells.join(&delimiter.to_string()));
+	}
+	res
+}
+
+// Every digit is kept, NaN cells are left empty like `readcsv` reads them
+fn cell(x: f64, decimal_comma: bool) -> String {
+	match (x.is_nan(), decimal_comma) {
+		(true, _) => String::new(),
+		(false, true) => x.to_string().replace('.', ","),
+		(false, false) => x.to_string(),
+	}
+}
+
+fn quote(name: &str, delimiter: char) -> String {
+	match name.contains([delimiter, '"', '\n']) {
+		true => format!("\"{}\"", name.replace('"', "\"\"")),
+		false => name.to_string(),
+	}
+}
+
+#[cfg(feature = "clipboard")]
+pub fn set_text(text: String) -> Result<(), ClipboardError> {
+	use std::sync::{Mutex, OnceLock};
+
+	// On X11 the text is served by the process that copied it, so the
+	// clipboard is kept open until exiting instead of closed after copying
+	static CLIPBOARD: OnceLock<Mutex<Option<arboard::Clipboard>>> = OnceLock::new();
+
+	let mut clipboard = CLIPBOARD
+		.get_or_init(|| Mutex::new(None))
+		.lock()
+		.unwrap_or_else(std::sync::PoisonError::into_inner);
+	if clipboard.is_none() {
+		*clipboard =
+			Some(arboard::Clipboard::new().map_err(|e| ClipboardError::Failed(e.to_string()))?);
+	}
+
+	clipboard
+		.as_mut()
+		.unwrap()
+		.set_text(text)
+		.map_err(|e| ClipboardError::Failed(e.to_string()))
+}
+
+#[cfg(not(feature = "clipboard"))]
+pub fn set_text(_text: String) -> Result<(), ClipboardError> {
+	Err(ClipboardError::Unsupported)
+}
+
+////////////////////////////////
+//       Error Handling       //
+////////////////////////////////
+
+#[derive(Debug)]
+pub enum ClipboardError {
+	#[cfg(not(feature = "clipboard"))]
+	Unsupported,
+	NotCopyable,
+	Failed(String),
+}
+
+impl std::error::Error for ClipboardError {}
+impl std::fmt::Display for ClipboardError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			#[cfg(not(feature = "clipboard"))]
+			Self::Unsupported => write!(
+				f,
+				"{}",
+				tr("The clipboard needs nam built with the 'clipboard' feature")
+			),
+			Self::NotCopyable => write!(f, "{}", tr("Functions cannot be copied to the clipboard")),
+			Self::Failed(message) => write!(
+				f,
+				"{}",
+				tr_with("Could not use the clipboard: {0}", &[message])
+			),
+		}
+	}
+}
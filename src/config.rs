@@ -0,0 +1,73 @@
+use {
+	crate::dirs,
+	std::{fs, io, path::PathBuf},
+};
+
+/*
+	The configuration file is a script of REPL input run at startup,
+	one statement or command per line, empty lines and lines starting
+	with '#' are skipped. Settings changed at the prompt that should last
+	(like aliases) are written back to it. It is `namrc` in the configuration
+	directory of the platform, or `~/.namrc` where one was made before.
+*/
+
+const FILE_NAME: &str = "namrc";
+const LEGACY_FILE_NAME: &str = ".namrc";
+
+pub fn path() -> Option<PathBuf> {
+	let legacy = dirs::home_dir().map(|home| home.join(LEGACY_FILE_NAME));
+	match legacy {
+		Some(legacy) if legacy.is_file() => Some(legacy),
+		_ => dirs::config_dir().map(|dir| dir.join(FILE_NAME)),
+	}
+}
+
+/// The lines to run at startup with their line numbers, empty if there is no configuration file.
+pub fn read() -> io::Result<Vec<(usize, String)>> {
+	let Some(path) = path() else {
+		return Ok(vec![]);
+	};
+
+	let content = match fs::read_to_string(path) {
+		Ok(content) => content,
+		Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+		Err(e) => return Err(e),
+	};
+
+	let lines = content
+		.lines()
+		.enumerate()
+		.map(|(i, line)| (i + 1, line.trim().to_string()))
+		.filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+		.collect();
+
+	Ok(lines)
+}
+
+/// Removes the lines for which `replaces` is true and appends `line` if there is one.
+pub fn update(replaces: impl Fn(&str) -> bool, line: Option<&str>) -> io::Result<()> {
+	let Some(path) = path() else {
+		return Err(io::Error::new(
+			io::ErrorKind::NotFound,
+			"no home directory to write the configuration file to",
+		));
+	};
+	if let Some(dir) = path.parent() {
+		fs::create_dir_all(dir)?;
+	}
+
+	let content = match fs::read_to_string(&path) {
+		Ok(content) => content,
+		Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+		Err(e) => return Err(e),
+	};
+
+	let mut res: Vec<&str> = content.lines().filter(|l| !replaces(l.trim())).collect();
+	if let Some(line) = line {
+		res.push(line);
+	}
+
+	let mut res = res.join("\n");
+	res.push('\n');
+	fs::write(path, res)
+}
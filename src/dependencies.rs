@@ -0,0 +1,96 @@
+use {
+	crate::ast::ASTNode,
+	std::collections::{BTreeMap, BTreeSet},
+};
+
+/*
+	How each variable was last assigned by a statement like `y = 2*x + 1`: the
+	expression and the variables it read. Assigning a variable any other way, like
+	`A(2) = 5`, `load` or a multiple assignment, forgets its definition.
+	A variable is stale when one of the variables it was computed from changed
+	since, `:recompute` evaluates the definitions again in dependency order.
+*/
+
+#[derive(Debug, Clone)]
+pub struct Definition {
+	pub expr: ASTNode,
+	pub inputs: BTreeSet<String>,
+	/// The generation of the workspace right after the assignment, see `State::generation`.
+	pub generation: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Definitions {
+	by_name: BTreeMap<String, Definition>,
+}
+
+impl Definitions {
+	pub fn insert(&mut self, name: String, definition: Definition) {
+		self.by_name.insert(name, definition);
+	}
+
+	pub fn remove(&mut self, name: &str) {
+		self.by_name.remove(name);
+	}
+
+	pub fn clear(&mut self) {
+		self.by_name.clear();
+	}
+
+	pub fn get(&self, name: &str) -> Option<&Definition> {
+		self.by_name.get(name)
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = (&String, &Definition)> {
+		self.by_name.iter()
+	}
+
+	/// The variables computed from `name`, directly or through other variables.
+	pub fn dependents(&self, name: &str) -> BTreeSet<String> {
+		let mut res = BTreeSet::new();
+		let mut queue = vec![name];
+		while let Some(input) = queue.pop() {
+			for (dependent, definition) in &self.by_name {
+				if definition.inputs.contains(input)
+					&& dependent != name
+					&& res.insert(dependent.clone())
+				{
+					queue.push(dependent);
+				}
+			}
+		}
+		res
+	}
+
+	/// The given variables ordered so each one comes after the ones among them
+	/// it is computed from. Variables defined from each other keep their names' order.
+	pub fn sorted(&self, names: &BTreeSet<String>) -> Vec<String> {
+		let mut res = vec![];
+		let mut visited = BTreeSet::new();
+		for name in names {
+			self.visit(name, names, &mut visited, &mut res);
+		}
+		res
+	}
+
+	// Depth first, the inputs of a variable are placed before it
+	fn visit<'a>(
+		&'a self,
+		name: &'a str,
+		names: &BTreeSet<String>,
+		visited: &mut BTreeSet<&'a str>,
+		res: &mut Vec<String>,
+	) {
+		if !visited.insert(name) {
+			return;
+		}
+		if let Some(definition) = self.by_name.get(name) {
+			for input in &definition.inputs {
+				if names.contains(input) {
+					self.visit(input, names, visited, res);
+				}
+			}
+		}
+		res.push(name.to_string());
+	}
+}
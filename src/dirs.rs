@@ -0,0 +1,68 @@
+use std::path::PathBuf;
+
+/*
+	Where nam keeps its files, following the conventions of each platform:
+	the XDG base directories on Linux and other Unix systems,
+	`~/Library/Application Support` on macOS, and `%APPDATA%` for the
+	configuration and `%LOCALAPPDATA%` for the rest on Windows.
+*/
+
+const APP_NAME: &str = "nam";
+
+/// The directory of the configuration file.
+pub fn config_dir() -> Option<PathBuf> {
+	if cfg!(windows) {
+		return env_dir("APPDATA").map(|dir| dir.join(APP_NAME));
+	}
+	platform_dir("XDG_CONFIG_HOME", ".config")
+}
+
+/// The directory of the files kept between sessions, like the prompt history.
+pub fn data_dir() -> Option<PathBuf> {
+	if cfg!(windows) {
+		return env_dir("LOCALAPPDATA").map(|dir| dir.join(APP_NAME));
+	}
+	platform_dir("XDG_DATA_HOME", ".local/share")
+}
+
+/// The directory of the files only useful to the next session, like the autosaved workspace.
+pub fn state_dir() -> Option<PathBuf> {
+	if cfg!(windows) {
+		return env_dir("LOCALAPPDATA").map(|dir| dir.join(APP_NAME));
+	}
+	platform_dir("XDG_STATE_HOME", ".local/state")
+}
+
+pub fn home_dir() -> Option<PathBuf> {
+	match cfg!(windows) {
+		true => env_dir("USERPROFILE"),
+		false => env_dir("HOME"),
+	}
+}
+
+pub fn history_file() -> Option<PathBuf> {
+	data_dir().map(|dir| dir.join("history"))
+}
+
+/// The workspace saved while the REPL runs, left behind when it does not exit normally.
+pub fn autosave_file() -> Option<PathBuf> {
+	state_dir().map(|dir| dir.join("autosave"))
+}
+
+// macOS keeps everything in Application Support, other systems follow XDG
+fn platform_dir(xdg_var: &str, xdg_default: &str) -> Option<PathBuf> {
+	if cfg!(target_os = "macos") {
+		return home_dir().map(|home| home.join("Library/Application Support").join(APP_NAME));
+	}
+
+	env_dir(xdg_var)
+		.or_else(|| home_dir().map(|home| home.join(xdg_default)))
+		.map(|dir| dir.join(APP_NAME))
+}
+
+// Relative paths are ignored like the XDG specification asks
+fn env_dir(var: &str) -> Option<PathBuf> {
+	std::env::var_os(var)
+		.map(PathBuf::from)
+		.filter(|path| path.is_absolute())
+}
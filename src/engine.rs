@@ -0,0 +1,182 @@
+use {
+	crate::{
+		ast::{ASTNode, ParsingError},
+		eval::EvaluationError,
+		lexer::{self, TokenizationError},
+		matrix::Matrix,
+		session::Stdlib,
+		state::{RuntimeVal, State},
+	},
+	std::{
+		sync::{
+			atomic::{AtomicBool, Ordering},
+			Arc, Mutex, MutexGuard, PoisonError,
+		},
+		time::Instant,
+	},
+};
+
+/// A workspace that hosts can share between threads, e.g. a server handling several
+/// requests of the same session. Clones share the same workspace, and statements are
+/// evaluated one at a time. Nothing is printed, the results are returned instead, and
+/// builtins printing a text like `summary` pass it to `EngineObserver::on_output`.
+#[derive(Clone)]
+pub struct Engine {
+	state: Arc<Mutex<State>>,
+	interrupted: Arc<AtomicBool>,
+}
+
+// Hosts rely on sharing engines between threads
+const _: fn() = || {
+	fn assert_send_sync<T: Send + Sync>() {}
+	assert_send_sync::<Engine>();
+};
+
+impl Engine {
+	pub fn new() -> Self {
+		Self::from_state(State::new())
+	}
+
+	/// An engine whose workspace sees the constants, aliases and functions of `stdlib`,
+	/// e.g. with `Stdlib::standard().with_function("fetch", fetch, signature)` the host
+	/// lets statements call `fetch(...)`, dispatched by name and checked like builtins.
+	pub fn with_stdlib(stdlib: Stdlib) -> Self {
+		Self::from_state(State::with_stdlib(Arc::new(stdlib)))
+	}
+
+	/// Wraps an existing workspace, its interrupt flag is kept.
+	pub fn from_state(state: State) -> Self {
+		Self {
+			interrupted: state.interrupt_flag(),
+			state: Arc::new(Mutex::new(state)),
+		}
+	}
+
+	/// Evaluates the statements of `code` and returns the value of the last one, an empty
+	/// matrix for no statement. It stops at the first error and waits for the statements
+	/// of other threads to finish.
+	pub fn eval(&self, code: &str) -> Result<RuntimeVal, EngineError> {
+		let mut state = self.lock();
+		self.interrupted.store(false, Ordering::Relaxed);
+
+		let tokens = lexer::try_tokenize_with(0, code, &state.settings().lexer_config())?;
+		let stmts = ASTNode::parse_program(&tokens)?;
+
+		let start = Instant::now();
+		let mut res = Ok(RuntimeVal::Matrix(Matrix::default()));
+		for mut ast in stmts {
+			ast.silence();
+			res = state.evaluator().evaluate_stmt(ast, &mut state);
+			if res.is_err() {
+				break;
+			}
+		}
+		state.profiler_mut().record_statement(code, start);
+
+		Ok(res?)
+	}
+
+	/// Parses a whole source into its statements with the lexer settings of the
+	/// workspace, to be evaluated by `eval_program`.
+	pub fn parse(&self, code: &str) -> Result<Vec<ASTNode>, EngineError> {
+		let config = self.lock().settings().lexer_config();
+		let tokens = lexer::try_tokenize_with(0, code, &config)?;
+		Ok(ASTNode::parse_program(&tokens)?)
+	}
+
+	/// Evaluates statements in order and returns their values, stopping at the first
+	/// error. The statements of other threads wait for the whole program.
+	pub fn eval_program(&mut self, stmts: Vec<ASTNode>) -> Result<Vec<RuntimeVal>, EngineError> {
+		let mut state = self.lock();
+		self.interrupted.store(false, Ordering::Relaxed);
+
+		let mut res = Vec::with_capacity(stmts.len());
+		for (i, mut ast) in stmts.into_iter().enumerate() {
+			ast.silence();
+
+			let start = Instant::now();
+			let value = state.evaluator().evaluate_stmt(ast, &mut state);
+			state
+				.profiler_mut()
+				.record_statement(&format!("statement {} of a program", i + 1), start);
+
+			res.push(value?);
+		}
+
+		Ok(res)
+	}
+
+	pub fn get_var(&self, var_name: &str) -> Option<RuntimeVal> {
+		self.lock().lookup_var(var_name).cloned()
+	}
+
+	pub fn set_var(&self, var_name: &str, value: RuntimeVal) {
+		self.lock().assign_var(var_name.to_string(), value);
+	}
+
+	/// Empties the workspace, see `State::reset`. Clones of the engine share the reset.
+	pub fn reset(&mut self, keep_builtins: bool) {
+		self.lock().reset(keep_builtins);
+	}
+
+	/// Stops the statement being evaluated, without waiting for it.
+	pub fn interrupt(&self) {
+		self.interrupted.store(true, Ordering::Relaxed);
+	}
+
+	/// Gives exclusive access to the workspace, e.g. to register observers or change settings.
+	pub fn with_state<R>(&self, f: impl FnOnce(&mut State) -> R) -> R {
+		f(&mut self.lock())
+	}
+
+	// A statement that panicked leaves the workspace usable, only that statement is lost
+	fn lock(&self) -> MutexGuard<'_, State> {
+		self.state.lock().unwrap_or_else(PoisonError::into_inner)
+	}
+}
+
+impl Default for Engine {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+////////////////////////////////
+//       Error Handling       //
+////////////////////////////////
+
+#[derive(Debug)]
+pub enum EngineError {
+	Tokenization(TokenizationError),
+	Parsing(ParsingError),
+	Evaluation(EvaluationError),
+}
+
+impl From<TokenizationError> for EngineError {
+	fn from(value: TokenizationError) -> Self {
+		Self::Tokenization(value)
+	}
+}
+
+impl From<ParsingError> for EngineError {
+	fn from(value: ParsingError) -> Self {
+		Self::Parsing(value)
+	}
+}
+
+impl From<EvaluationError> for EngineError {
+	fn from(value: EvaluationError) -> Self {
+		Self::Evaluation(value)
+	}
+}
+
+impl std::error::Error for EngineError {}
+impl std::fmt::Display for EngineError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Tokenization(e) => write!(f, "{e}"),
+			Self::Parsing(e) => write!(f, "{e}"),
+			Self::Evaluation(e) => write!(f, "{e}"),
+		}
+	}
+}
@@ -1,7 +1,51 @@
+use crate::alias::Alias;
+use crate::arith;
 use crate::ast::{ASTNode, ASTNodeKind, BinaryOpKind};
+use crate::builtins::{self, ArgType};
+use crate::bytecode::Compiled;
+use crate::function::Function;
+use crate::i18n::{tr, tr_with};
+use crate::matrix::{Labels, Matrix};
+use crate::observer::CapturedOutput;
+use crate::optimizer;
+use crate::settings::Coercion;
 use crate::state::{RuntimeVal, State};
+use std::{collections::BTreeSet, time::Instant};
+
+/// Evaluates a top level statement and reports the outcome to the observers of the state.
+pub fn evaluate_stmt(ast: ASTNode, state: &mut State) -> Result<RuntimeVal, EvaluationError> {
+	evaluate_stmt_with(ast, state, evaluate)
+}
+
+/// Like `evaluate_stmt`, with another way of evaluating the optimized statement,
+/// for the backends.
+pub fn evaluate_stmt_with(
+	ast: ASTNode,
+	state: &mut State,
+	evaluate: impl FnOnce(ASTNode, &mut State) -> Result<RuntimeVal, EvaluationError>,
+) -> Result<RuntimeVal, EvaluationError> {
+	let ast = match state.settings().optimize {
+		true => optimizer::optimize(ast),
+		false => ast,
+	};
+	let res = evaluate(ast, state);
+
+	state.stats_mut().record_statement(res.as_ref().err());
+	for observer in state.observers_mut() {
+		match &res {
+			Ok(value) => observer.on_statement(value),
+			Err(e) => observer.on_error(e),
+		}
+	}
+
+	res
+}
 
 pub fn evaluate(ast: ASTNode, state: &mut State) -> Result<RuntimeVal, EvaluationError> {
+	if state.is_interrupted() {
+		return Err(EvaluationError::Interrupted);
+	}
+
 	match ast.kind {
 		ASTNodeKind::Number(n) => {
 			let res = RuntimeVal::Number(n);
@@ -9,7 +53,20 @@ pub fn evaluate(ast: ASTNode, state: &mut State) -> Result<RuntimeVal, Evaluatio
 			if ast.store_in_ans {
 				state.assign_var("ans".to_string(), res.clone());
 				if ast.print_result {
-					println!("\nans = {n}");
+					state.print_value("ans", &res);
+				}
+			}
+
+			Ok(res)
+		},
+
+		ASTNodeKind::String(s) => {
+			let res = RuntimeVal::String(s);
+
+			if ast.store_in_ans {
+				state.assign_var("ans".to_string(), res.clone());
+				if ast.print_result {
+					state.print_value("ans", &res);
 				}
 			}
 
@@ -17,75 +74,1294 @@ pub fn evaluate(ast: ASTNode, state: &mut State) -> Result<RuntimeVal, Evaluatio
 		},
 
 		ASTNodeKind::Matrix(m) => {
-			let mut res_mat = vec![];
+			let mut res_mat = Matrix::default();
 			for i in m {
-				let mut row = vec![];
-				for j in i {
-					row.push(evaluate(j, state)?);
+				let mut row = Matrix::default();
+				// The name of the row while it is a single variable, for errors
+				let mut row_name = None;
+				for (k, j) in i.into_iter().enumerate() {
+					let cell_name = variable_name(&j);
+					let cell = match evaluate(j, state)? {
+						RuntimeVal::Number(n) => Matrix::from(n),
+						RuntimeVal::BigInt(n) => Matrix::from(n.to_f64()),
+						RuntimeVal::Matrix(m) => m,
+						RuntimeVal::String(_) | RuntimeVal::Table(_) | RuntimeVal::Function(_) => {
+							return Err(EvaluationError::NotANumber)
+						},
+					};
+
+					if row.nrows() != cell.nrows() && !row.is_empty() && !cell.is_empty() {
+						return Err(EvaluationError::mismatch(
+							Operation::HorizontalConcat,
+							&row,
+							&cell,
+						)
+						.with_operand_names(row_name, cell_name));
+					}
+					row_name = if k == 0 { cell_name } else { None };
+					row = row.hcat(cell).unwrap();
 				}
-				res_mat.push(row);
+
+				if res_mat.ncols() != row.ncols() && !res_mat.is_empty() && !row.is_empty() {
+					return Err(EvaluationError::mismatch(
+						Operation::VerticalConcat,
+						&res_mat,
+						&row,
+					));
+				}
+				res_mat = res_mat.vcat(row).unwrap();
 			}
 
 			let res = RuntimeVal::Matrix(res_mat);
 			if ast.store_in_ans {
 				state.assign_var("ans".to_string(), res.clone());
 				if ast.print_result {
-					println!("\nans = {res:?}");
+					state.print_value("ans", &res);
+				}
+			}
+
+			Ok(res)
+		},
+
+		ASTNodeKind::Range(start, step, end) => {
+			let start = expect_scalar(evaluate(*start, state)?)?;
+			let end = expect_scalar(evaluate(*end, state)?)?;
+			let step = match step {
+				Some(step) => expect_scalar(evaluate(*step, state)?)?,
+				None => 1.0,
+			};
+
+			// A small tolerance so that 0:0.1:1 includes 1
+			let count = ((end - start) / step + 1e-10).floor() + 1.0;
+			let data: Vec<f64> = if count.is_finite() && count >= 1.0 {
+				(0..count as usize)
+					.map(|i| start + i as f64 * step)
+					.collect()
+			} else {
+				vec![]
+			};
+
+			let res = RuntimeVal::Matrix(Matrix::new(1, data.len(), data).unwrap());
+			if ast.store_in_ans {
+				state.assign_var("ans".to_string(), res.clone());
+				if ast.print_result {
+					state.print_value("ans", &res);
+				}
+			}
+
+			Ok(res)
+		},
+
+		ASTNodeKind::Colon => Err(EvaluationError::UnexpectedColon),
+
+		ASTNodeKind::End => match state.index_end() {
+			Some(end) => Ok(RuntimeVal::Number(end as f64)),
+			None => Err(EvaluationError::UnexpectedEnd),
+		},
+
+		ASTNodeKind::FunctionRef(func_name) => {
+			if state.get_alias(&func_name).is_none() && state.get_function(&func_name).is_none() {
+				return Err(EvaluationError::NonexistantFunction(func_name));
+			}
+
+			let res = RuntimeVal::Function(Function::Named(func_name));
+			if ast.store_in_ans {
+				state.assign_var("ans".to_string(), res.clone());
+				if ast.print_result {
+					state.print_value("ans", &res);
 				}
 			}
 
 			Ok(res)
 		},
 
-		ASTNodeKind::Variable(var_name) => match state.get_var(&var_name) {
+		// Named arguments are taken apart by `call`
+		ASTNodeKind::KeywordArg(name, _) => Err(EvaluationError::UnexpectedKeyword(name)),
+
+		ASTNodeKind::Variable(var_name) => match state.lookup_var(&var_name).cloned() {
 			Some(var_value) => {
 				if ast.print_result {
-					println!("\n{var_name} = {var_value}");
+					state.print_value(&var_name, &var_value);
 				}
 
-				Ok(var_value.clone())
+				Ok(var_value)
 			},
 			None => Err(EvaluationError::NonexistantVar(var_name)),
 		},
 
 		ASTNodeKind::Assignment(var_name, var_value) => {
+			let definition = (*var_value).clone();
+			let mut inputs = BTreeSet::new();
+			read_variables(&definition, state, &mut inputs);
+
 			let res = evaluate(*var_value, state)?;
 			state.assign_var(var_name.clone(), res.clone());
+			state.define_var(var_name.clone(), definition, inputs);
 
 			if ast.print_result {
-				println!("\n{var_name} = {res}");
+				state.print_value(&var_name, &res);
 			}
 
 			Ok(res)
 		},
 
-		ASTNodeKind::BinaryExpr(op, lhs, rhs) => {
-			let res_lhs: f64 = match evaluate(*lhs, state)? {
-				RuntimeVal::Number(var_value) => var_value,
-				_ => return Err(EvaluationError::NotANumber),
+		ASTNodeKind::IndexedAssignment(var_name, args, var_value) => {
+			let value = evaluate(*var_value, state)?;
+			let res = assign_indexed(&var_name, args, value, state)?;
+			state.assign_var(var_name.clone(), res.clone());
+
+			if ast.print_result {
+				state.print_value(&var_name, &res);
+			}
+
+			Ok(res)
+		},
+
+		ASTNodeKind::MultiAssignment(var_names, var_value) => {
+			let outputs = match var_value.kind {
+				ASTNodeKind::Call(func_name, args) => call(func_name, args, state)?,
+				_ => vec![evaluate(*var_value, state)?],
 			};
 
-			let res_rhs: f64 = match evaluate(*rhs, state)? {
-				RuntimeVal::Number(var_value) => var_value,
-				_ => return Err(EvaluationError::NotANumber),
+			if outputs.len() < var_names.len() {
+				return Err(EvaluationError::NotEnoughOutputs(
+					var_names.len(),
+					outputs.len(),
+				));
+			}
+
+			let mut res = None;
+			for (var_name, value) in var_names.into_iter().zip(outputs) {
+				if ast.print_result {
+					state.print_value(&var_name, &value);
+				}
+
+				state.assign_var(var_name, value.clone());
+				res.get_or_insert(value);
+			}
+
+			Ok(res.unwrap_or(RuntimeVal::Matrix(Matrix::default())))
+		},
+
+		ASTNodeKind::Call(func_name, args) => {
+			let mut outputs = call(func_name.clone(), args, state)?;
+			if outputs.is_empty() {
+				// Statements like `imwrite(A, "a.png")` are fine without a result
+				if ast.store_in_ans {
+					return Ok(RuntimeVal::Matrix(Matrix::default()));
+				}
+				return Err(EvaluationError::NoValue(func_name));
+			}
+
+			let res = outputs.swap_remove(0);
+			if ast.store_in_ans {
+				state.assign_var("ans".to_string(), res.clone());
+				if ast.print_result {
+					state.print_value("ans", &res);
+				}
+			}
+
+			Ok(res)
+		},
+
+		ASTNodeKind::Field(base, field_name) => {
+			let res = match evaluate(*base, state)? {
+				RuntimeVal::Table(t) => match t.column(&field_name) {
+					Some(column) => RuntimeVal::Matrix(column),
+					None => return Err(EvaluationError::NonexistantField(field_name)),
+				},
+				_ => return Err(EvaluationError::NotATable),
 			};
 
+			if ast.store_in_ans {
+				state.assign_var("ans".to_string(), res.clone());
+				if ast.print_result {
+					state.print_value("ans", &res);
+				}
+			}
+
+			Ok(res)
+		},
+
+		// Evaluated as sums, so they accept the same operands
+		ASTNodeKind::Negate(_) | ASTNodeKind::Plus(_) => {
+			let res = evaluate_linear(ast.kind.into(), state)?;
+
+			if ast.store_in_ans {
+				state.assign_var("ans".to_string(), res.clone());
+				if ast.print_result {
+					state.print_value("ans", &res);
+				}
+			}
+
+			Ok(res)
+		},
+
+		ASTNodeKind::Transpose(operand) => {
+			let operation = format!("{}'", operand_text(&operand));
+			let res = evaluate(*operand, state)?;
+			let res = transpose(res, &operation, state)?;
+
+			if ast.store_in_ans {
+				state.assign_var("ans".to_string(), res.clone());
+				if ast.print_result {
+					state.print_value("ans", &res);
+				}
+			}
+
+			Ok(res)
+		},
+
+		ASTNodeKind::Not(operand) => {
+			let res = arith::not(evaluate(*operand, state)?)?;
+
+			if ast.store_in_ans {
+				state.assign_var("ans".to_string(), res.clone());
+				if ast.print_result {
+					state.print_value("ans", &res);
+				}
+			}
+
+			Ok(res)
+		},
+
+		// The right operand is only evaluated when the left one does not decide,
+		// so `exist && x > 0` does not need x when exist is false
+		ASTNodeKind::ShortAnd(lhs, rhs) => {
+			let res = arith::truth(evaluate(*lhs, state)?, "&&")?
+				&& arith::truth(evaluate(*rhs, state)?, "&&")?;
+			let res = RuntimeVal::Number(res as u8 as f64);
+
+			if ast.store_in_ans {
+				state.assign_var("ans".to_string(), res.clone());
+				if ast.print_result {
+					state.print_value("ans", &res);
+				}
+			}
+
+			Ok(res)
+		},
+		ASTNodeKind::ShortOr(lhs, rhs) => {
+			let res = arith::truth(evaluate(*lhs, state)?, "||")?
+				|| arith::truth(evaluate(*rhs, state)?, "||")?;
+			let res = RuntimeVal::Number(res as u8 as f64);
+
+			if ast.store_in_ans {
+				state.assign_var("ans".to_string(), res.clone());
+				if ast.print_result {
+					state.print_value("ans", &res);
+				}
+			}
+
+			Ok(res)
+		},
+
+		// The value is the one of the last statement evaluated, empty if there is none
+		ASTNodeKind::If(branches, otherwise) => {
+			let mut body = otherwise;
+			for (condition, stmts) in branches {
+				if arith::condition(evaluate(condition, state)?, "if")? {
+					body = stmts;
+					break;
+				}
+			}
+
+			let mut res = RuntimeVal::Matrix(Matrix::default());
+			for stmt in body {
+				res = evaluate(stmt, state)?;
+			}
+			Ok(res)
+		},
+
+		// Like for `if`, the value is the one of the last statement evaluated.
+		// Ctrl-C stops the loop, and so does the iteration limit of the settings
+		ASTNodeKind::While(condition, body) => {
+			let mut res = RuntimeVal::Matrix(Matrix::default());
+			let mut iterations = 0;
+			while arith::condition(evaluate((*condition).clone(), state)?, "while")? {
+				if let Some(limit) = state.settings().max_iterations {
+					if iterations == limit {
+						return Err(EvaluationError::TooManyIterations(limit));
+					}
+				}
+				iterations += 1;
+
+				for stmt in &body {
+					res = evaluate(stmt.clone(), state)?;
+				}
+			}
+			Ok(res)
+		},
+
+		ASTNodeKind::BinaryExpr(op, lhs, rhs) => {
 			let res = match op {
-				BinaryOpKind::Add => RuntimeVal::Number(res_lhs + res_rhs),
-				BinaryOpKind::Subtract => RuntimeVal::Number(res_lhs - res_rhs),
-				BinaryOpKind::Multiply => RuntimeVal::Number(res_lhs * res_rhs),
-				BinaryOpKind::Divide => RuntimeVal::Number(res_lhs / res_rhs),
+				BinaryOpKind::Add | BinaryOpKind::Subtract => {
+					evaluate_linear(ASTNodeKind::BinaryExpr(op, lhs, rhs).into(), state)?
+				},
+				_ => {
+					let names = [variable_name(&lhs), variable_name(&rhs)];
+					let operation = format!("{} {op} {}", operand_text(&lhs), operand_text(&rhs));
+					let (lhs, rhs) = (evaluate(*lhs, state)?, evaluate(*rhs, state)?);
+					binary(&op, lhs, rhs, &names, &operation, state)?
+				},
 			};
 
 			if ast.store_in_ans {
 				state.assign_var("ans".to_string(), res.clone());
 				if ast.print_result {
-					println!("\nans = {res}");
+					state.print_value("ans", &res);
+				}
+			}
+
+			Ok(res)
+		},
+	}
+}
+
+// Chains like `A + B - 2*C` are computed in one pass, see `linear_terms`
+fn evaluate_linear(ast: ASTNode, state: &mut State) -> Result<RuntimeVal, EvaluationError> {
+	let mut terms = vec![];
+	linear_terms(ast, 1.0, &mut terms);
+
+	let mut values = vec![];
+	let mut texts = vec![];
+	for (c, term) in terms {
+		texts.push((c, operand_text(&term)));
+		let name = variable_name(&term);
+		values.push((c, evaluate(term, state)?, name));
+	}
+
+	linear(values, &texts, state)
+}
+
+/// Adds evaluated terms `(coefficient, value, name)`, `texts` writing the terms back
+/// for the profiler and the strict mode, see `operand_text`.
+pub(crate) fn linear(
+	values: Vec<(f64, RuntimeVal, Option<String>)>,
+	texts: &[(f64, String)],
+	state: &mut State,
+) -> Result<RuntimeVal, EvaluationError> {
+	let strict = state.settings().strict
+		&& values
+			.iter()
+			.all(|(_, value, _)| non_finite(value).is_none());
+	let profiled = state.profiler().is_enabled();
+	let operation = (strict || profiled).then(|| describe_sum(texts));
+	let matrix_op = values.iter().any(|(_, value, _)| is_matrix(value));
+
+	let start = Instant::now();
+	let res = arith::linear_combination(values, state);
+	if let Some(operation) = operation.as_ref().filter(|_| profiled) {
+		state.profiler_mut().record_operation(operation, start);
+	}
+	if matrix_op {
+		state.stats_mut().record_matrix_op(start);
+	}
+	let res = res?;
+	if let Some(operation) = operation.filter(|_| strict) {
+		check_finite(operation, &res)?;
+	}
+	Ok(res)
+}
+
+/// Applies an operator other than `+` and `-` to evaluated operands, `names` naming
+/// them in errors and `operation` describing it for the profiler and the strict mode.
+pub(crate) fn binary(
+	op: &BinaryOpKind,
+	lhs: RuntimeVal,
+	rhs: RuntimeVal,
+	names: &[Option<String>; 2],
+	operation: &str,
+	state: &mut State,
+) -> Result<RuntimeVal, EvaluationError> {
+	let strict =
+		state.settings().strict && non_finite(&lhs).is_none() && non_finite(&rhs).is_none();
+	let matrix_op = is_matrix(&lhs) || is_matrix(&rhs);
+
+	let start = Instant::now();
+	let res = arith::binary(op, lhs, rhs, state);
+	state.profiler_mut().record_operation(operation, start);
+	if matrix_op {
+		state.stats_mut().record_matrix_op(start);
+	}
+	let [lhs_name, rhs_name] = names;
+	let res = res.map_err(|e| e.with_operand_names(lhs_name.clone(), rhs_name.clone()))?;
+	if strict {
+		check_finite(operation.to_string(), &res)?;
+	}
+	Ok(res)
+}
+
+/// Transposes an evaluated operand, `operation` describing it for the profiler.
+pub(crate) fn transpose(
+	value: RuntimeVal,
+	operation: &str,
+	state: &mut State,
+) -> Result<RuntimeVal, EvaluationError> {
+	let start = Instant::now();
+	let res = match value {
+		RuntimeVal::Matrix(m) => RuntimeVal::Matrix(m.transpose()),
+		// The column names of a table name the rows of its transpose
+		RuntimeVal::Table(t) => {
+			let labels = Labels {
+				rows: Some(t.names().to_vec()),
+				cols: None,
+			};
+			RuntimeVal::Matrix(t.data().transpose().with_labels(labels).unwrap())
+		},
+		value @ (RuntimeVal::Number(_) | RuntimeVal::BigInt(_)) => value,
+		RuntimeVal::String(_) | RuntimeVal::Function(_) => return Err(EvaluationError::NotANumber),
+	};
+	if let RuntimeVal::Matrix(_) = res {
+		state.profiler_mut().record_operation(operation, start);
+		state.stats_mut().record_matrix_op(start);
+	}
+	Ok(res)
+}
+
+// The name to show in errors about an operand
+pub(crate) fn variable_name(ast: &ASTNode) -> Option<String> {
+	match &ast.kind {
+		ASTNodeKind::Variable(name) => Some(name.clone()),
+		_ => None,
+	}
+}
+
+/// Splits a chain of additions and subtractions into `(coefficient, term)` pairs,
+/// where a term multiplied by a literal number has it as its coefficient.
+/// Only the left operands are followed so the terms are added in the same order
+/// as they would be one by one, `A + (B + C)` has the two terms `A` and `B + C`.
+/// A negation flips the sign of the terms of its operand.
+pub(crate) fn linear_terms(ast: ASTNode, sign: f64, terms: &mut Vec<(f64, ASTNode)>) {
+	let scaled = |ast: ASTNode, sign: f64| match ast.kind {
+		ASTNodeKind::BinaryExpr(BinaryOpKind::Multiply, lhs, rhs) => match (lhs.kind, rhs.kind) {
+			(ASTNodeKind::Number(c), kind) | (kind, ASTNodeKind::Number(c)) => {
+				(sign * c, ASTNode::from(kind))
+			},
+			(lhs, rhs) => (
+				sign,
+				ASTNodeKind::BinaryExpr(
+					BinaryOpKind::Multiply,
+					Box::new(lhs.into()),
+					Box::new(rhs.into()),
+				)
+				.into(),
+			),
+		},
+		kind => (sign, kind.into()),
+	};
+
+	match ast.kind {
+		ASTNodeKind::BinaryExpr(BinaryOpKind::Add, lhs, rhs) => {
+			linear_terms(*lhs, sign, terms);
+			terms.push(scaled(*rhs, sign));
+		},
+		ASTNodeKind::BinaryExpr(BinaryOpKind::Subtract, lhs, rhs) => {
+			linear_terms(*lhs, sign, terms);
+			terms.push(scaled(*rhs, -sign));
+		},
+		ASTNodeKind::Negate(operand) => linear_terms(*operand, -sign, terms),
+		ASTNodeKind::Plus(operand) => linear_terms(*operand, sign, terms),
+		kind => terms.push(scaled(kind.into(), sign)),
+	}
+}
+
+/// Collects the variables an expression reads, including the ones indexed like `A(2)`.
+pub(crate) fn read_variables(ast: &ASTNode, state: &State, names: &mut BTreeSet<String>) {
+	match &ast.kind {
+		ASTNodeKind::Variable(var_name) => {
+			names.insert(var_name.clone());
+		},
+		ASTNodeKind::Call(name, args) => {
+			if state.has_var(name) {
+				names.insert(name.clone());
+			}
+			for arg in args {
+				read_variables(arg, state, names);
+			}
+		},
+		ASTNodeKind::IndexedAssignment(var_name, args, value) => {
+			names.insert(var_name.clone());
+			for arg in args {
+				read_variables(arg, state, names);
+			}
+			read_variables(value, state, names);
+		},
+		ASTNodeKind::Matrix(rows) => {
+			for cell in rows.iter().flatten() {
+				read_variables(cell, state, names);
+			}
+		},
+		ASTNodeKind::Range(start, step, end) => {
+			read_variables(start, state, names);
+			if let Some(step) = step {
+				read_variables(step, state, names);
+			}
+			read_variables(end, state, names);
+		},
+		ASTNodeKind::BinaryExpr(_, lhs, rhs)
+		| ASTNodeKind::ShortAnd(lhs, rhs)
+		| ASTNodeKind::ShortOr(lhs, rhs) => {
+			read_variables(lhs, state, names);
+			read_variables(rhs, state, names);
+		},
+		ASTNodeKind::Assignment(_, value)
+		| ASTNodeKind::MultiAssignment(_, value)
+		| ASTNodeKind::KeywordArg(_, value)
+		| ASTNodeKind::Field(value, _)
+		| ASTNodeKind::Negate(value)
+		| ASTNodeKind::Plus(value)
+		| ASTNodeKind::Transpose(value)
+		| ASTNodeKind::Not(value) => read_variables(value, state, names),
+		ASTNodeKind::If(branches, otherwise) => {
+			for (condition, body) in branches {
+				read_variables(condition, state, names);
+				for stmt in body {
+					read_variables(stmt, state, names);
+				}
+			}
+			for stmt in otherwise {
+				read_variables(stmt, state, names);
+			}
+		},
+		ASTNodeKind::While(condition, body) => {
+			read_variables(condition, state, names);
+			for stmt in body {
+				read_variables(stmt, state, names);
+			}
+		},
+		ASTNodeKind::Number(_)
+		| ASTNodeKind::String(_)
+		| ASTNodeKind::Colon
+		| ASTNodeKind::End
+		| ASTNodeKind::FunctionRef(_) => {},
+	}
+}
+
+// Aliases calling aliases deeper than this are not cached
+const MAX_PURITY_DEPTH: usize = 32;
+
+/// Calls a function, reusing the outputs of the same call when the cache setting is on,
+/// the call is pure, and none of the variables and functions it uses changed since,
+/// other than variables assigned the same values again.
+fn call(
+	func_name: String,
+	args: Vec<ASTNode>,
+	state: &mut State,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	if !state.settings().cache {
+		return call_uncached(func_name, args, state);
+	}
+
+	let mut dependencies = vec![];
+	if !is_pure_call(&func_name, &args, state, &mut dependencies, 0) {
+		return call_uncached(func_name, args, state);
+	}
+
+	let key = format!("{func_name}{args:?}");
+	if let Some(outputs) = state.cached(&key, &dependencies) {
+		return Ok(outputs.to_vec());
+	}
+
+	let generation = state.generation();
+	let outputs = call_uncached(func_name, args, state)?;
+	state.cache(key, generation, &dependencies, outputs.clone());
+	Ok(outputs)
+}
+
+/// Whether an expression has no side effects and only calls pure functions,
+/// collecting the names of the variables and functions it depends on.
+fn is_pure(ast: &ASTNode, state: &State, dependencies: &mut Vec<String>, depth: usize) -> bool {
+	match &ast.kind {
+		ASTNodeKind::Variable(var_name) => {
+			dependencies.push(var_name.clone());
+			match state.lookup_var(var_name) {
+				// Passing a function to a builtin like `map` calls it
+				Some(RuntimeVal::Function(function)) => {
+					is_pure_call(function.name(), &[], state, dependencies, depth + 1)
+				},
+				_ => true,
+			}
+		},
+		ASTNodeKind::FunctionRef(func_name) => {
+			is_pure_call(func_name, &[], state, dependencies, depth + 1)
+		},
+		ASTNodeKind::Number(_) | ASTNodeKind::String(_) | ASTNodeKind::Colon | ASTNodeKind::End => {
+			true
+		},
+		ASTNodeKind::Matrix(rows) => rows
+			.iter()
+			.flatten()
+			.all(|cell| is_pure(cell, state, dependencies, depth)),
+		ASTNodeKind::Range(start, step, end) => {
+			is_pure(start, state, dependencies, depth)
+				&& step
+					.as_ref()
+					.is_none_or(|step| is_pure(step, state, dependencies, depth))
+				&& is_pure(end, state, dependencies, depth)
+		},
+		ASTNodeKind::Field(base, _) => is_pure(base, state, dependencies, depth),
+		ASTNodeKind::KeywordArg(_, value)
+		| ASTNodeKind::Negate(value)
+		| ASTNodeKind::Plus(value)
+		| ASTNodeKind::Transpose(value)
+		| ASTNodeKind::Not(value) => is_pure(value, state, dependencies, depth),
+		ASTNodeKind::BinaryExpr(_, lhs, rhs)
+		| ASTNodeKind::ShortAnd(lhs, rhs)
+		| ASTNodeKind::ShortOr(lhs, rhs) => {
+			is_pure(lhs, state, dependencies, depth) && is_pure(rhs, state, dependencies, depth)
+		},
+		ASTNodeKind::Call(func_name, args) => {
+			is_pure_call(func_name, args, state, dependencies, depth)
+		},
+		ASTNodeKind::Assignment(_, _)
+		| ASTNodeKind::IndexedAssignment(_, _, _)
+		| ASTNodeKind::MultiAssignment(_, _)
+		| ASTNodeKind::If(_, _)
+		| ASTNodeKind::While(_, _) => false,
+	}
+}
+
+fn is_pure_call(
+	func_name: &str,
+	args: &[ASTNode],
+	state: &State,
+	dependencies: &mut Vec<String>,
+	depth: usize,
+) -> bool {
+	if depth > MAX_PURITY_DEPTH {
+		return false;
+	}
+
+	// Defining a variable or an alias with this name changes what the call does
+	dependencies.push(func_name.to_string());
+	if !args
+		.iter()
+		.all(|arg| is_pure(arg, state, dependencies, depth))
+	{
+		return false;
+	}
+
+	match state.lookup_var(func_name) {
+		Some(RuntimeVal::Function(function)) => {
+			return is_pure_call(function.name(), &[], state, dependencies, depth + 1)
+		},
+		Some(_) => return true,
+		None => {},
+	}
+
+	match state.get_alias(func_name) {
+		Some(Alias::Function(target)) => is_pure_call(target, &[], state, dependencies, depth + 1),
+		Some(Alias::Expr { body, .. }) => is_pure(body, state, dependencies, depth + 1),
+		None => builtins::is_pure(func_name),
+	}
+}
+
+fn call_uncached(
+	func_name: String,
+	args: Vec<ASTNode>,
+	state: &mut State,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	// A variable holding a function handle calls it, `f = @polyval; f(p, 2)`
+	if let Some(RuntimeVal::Function(function)) = state.lookup_var(&func_name) {
+		let function = function.clone();
+		let (args, keywords) = evaluate_args(args, state)?;
+		return apply(&function, args, keywords, state);
+	}
+
+	// Other variables shadow builtins, so `A(1, 2)` indexes into A
+	if state.has_var(&func_name) {
+		return Ok(vec![index(func_name, args, state)?]);
+	}
+
+	// Aliases shadow builtins too
+	if let Some(alias) = state.get_alias(&func_name) {
+		return match alias.clone() {
+			Alias::Function(target) => call(target, args, state),
+			Alias::Expr {
+				params,
+				body,
+				compiled,
+				..
+			} => {
+				let args = args
+					.into_iter()
+					.map(|arg| evaluate(arg, state))
+					.collect::<Result<_, _>>()?;
+				call_alias(func_name, params, (&body, &compiled), args, state)
+			},
+		};
+	}
+
+	// async(expr) evaluates its argument in the background instead of now
+	if func_name == "async" {
+		if args.len() != 1 {
+			return Err(EvaluationError::WrongArgumentCount {
+				func_name,
+				expected: String::from("1 argument"),
+				found: args.len(),
+			});
+		}
+
+		let workspace = state.snapshot();
+		let id = state
+			.jobs_mut()
+			.spawn(workspace, args.into_iter().next().unwrap());
+		return Ok(vec![RuntimeVal::Number(id as f64)]);
+	}
+
+	let (args, keywords) = evaluate_args(args, state)?;
+	call_builtin(func_name, args, keywords, state)
+}
+
+/// Calls a function handle with evaluated arguments, for builtins taking functions.
+pub fn call_function(
+	function: &Function,
+	args: Vec<RuntimeVal>,
+	state: &mut State,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	apply(function, args, vec![], state)
+}
+
+fn apply(
+	function: &Function,
+	args: Vec<RuntimeVal>,
+	keywords: NamedArgs,
+	state: &mut State,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	match function {
+		Function::Named(func_name) => call_named(func_name.clone(), args, keywords, state),
+		Function::Bound {
+			function,
+			args: bound,
+		} => {
+			let args = bound.iter().cloned().chain(args).collect();
+			apply(function, args, keywords, state)
+		},
+	}
+}
+
+// Calls an alias or a builtin, variables of the same name are not looked at
+fn call_named(
+	func_name: String,
+	args: Vec<RuntimeVal>,
+	keywords: NamedArgs,
+	state: &mut State,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	match state.get_alias(&func_name).cloned() {
+		Some(Alias::Function(target)) => call_named(target, args, keywords, state),
+		Some(Alias::Expr {
+			params,
+			body,
+			compiled,
+			..
+		}) => match keywords.into_iter().next() {
+			Some((name, _)) => Err(EvaluationError::UnexpectedKeyword(name)),
+			None => call_alias(func_name, params, (&body, &compiled), args, state),
+		},
+		None => call_builtin(func_name, args, keywords, state),
+	}
+}
+
+// The arguments passed by name, like `header = false`
+type NamedArgs = Vec<(String, RuntimeVal)>;
+
+// Evaluates the arguments of a call, setting apart the ones passed by name
+fn evaluate_args(
+	args: Vec<ASTNode>,
+	state: &mut State,
+) -> Result<(Vec<RuntimeVal>, NamedArgs), EvaluationError> {
+	let mut values = vec![];
+	let mut keywords = vec![];
+	for arg in args {
+		match arg.kind {
+			ASTNodeKind::KeywordArg(name, value) => keywords.push((name, evaluate(*value, state)?)),
+			_ => values.push(evaluate(arg, state)?),
+		}
+	}
+	Ok((values, keywords))
+}
+
+fn call_builtin(
+	func_name: String,
+	mut args: Vec<RuntimeVal>,
+	keywords: NamedArgs,
+	state: &mut State,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	let (func, signature) = match state.get_function(&func_name) {
+		Some(builtin) => builtin,
+		None => return Err(EvaluationError::NonexistantFunction(func_name)),
+	};
+
+	if !keywords.is_empty() {
+		args = builtins::bind_keywords(&func_name, args, keywords, state)?;
+	}
+	signature.check(&func_name, &args)?;
+
+	let strict = state.settings().strict && args.iter().all(|arg| non_finite(arg).is_none());
+	let start = state.profiler().is_enabled().then(Instant::now);
+	let outputs = func(state, args);
+	if let Some(start) = start {
+		state.profiler_mut().record_builtin(&func_name, start);
+	}
+	let outputs = outputs?;
+	if strict {
+		for output in &outputs {
+			check_finite(func_name.clone(), output)?;
+		}
+	}
+
+	Ok(outputs)
+}
+
+// The first NaN or infinite element of a value
+fn non_finite(value: &RuntimeVal) -> Option<f64> {
+	match value {
+		RuntimeVal::Number(n) => Some(*n).filter(|n| !n.is_finite()),
+		RuntimeVal::Matrix(m) => m.as_slice().iter().copied().find(|n| !n.is_finite()),
+		RuntimeVal::Table(t) => t.data().as_slice().iter().copied().find(|n| !n.is_finite()),
+		RuntimeVal::BigInt(_) | RuntimeVal::String(_) | RuntimeVal::Function(_) => None,
+	}
+}
+
+/// In strict mode, fails if an operation on finite values produced NaN or Inf.
+fn check_finite(operation: String, result: &RuntimeVal) -> Result<(), EvaluationError> {
+	match non_finite(result) {
+		Some(value) => Err(EvaluationError::NonFinite(operation, value)),
+		None => Ok(()),
+	}
+}
+
+// Operations on these are counted as matrix operations in the session statistics
+fn is_matrix(value: &RuntimeVal) -> bool {
+	matches!(value, RuntimeVal::Matrix(_) | RuntimeVal::Table(_))
+}
+
+// Names an operand in strict mode errors, nested expressions are elided
+pub(crate) fn operand_text(ast: &ASTNode) -> String {
+	match &ast.kind {
+		ASTNodeKind::Variable(name) => name.clone(),
+		ASTNodeKind::Number(n) if *n != 0.0 && (n.abs() < 1e-4 || n.abs() >= 1e15) => {
+			format!("{n:e}")
+		},
+		ASTNodeKind::Number(n) => n.to_string(),
+		_ => String::from("(...)"),
+	}
+}
+
+// Writes a chain of additions back, like `A - 2*B + (...)`
+fn describe_sum(terms: &[(f64, String)]) -> String {
+	let mut res = String::new();
+	for (i, (c, text)) in terms.iter().enumerate() {
+		match (i, *c < 0.0) {
+			(0, true) => res.push('-'),
+			(0, false) => {},
+			(_, true) => res.push_str(" - "),
+			(_, false) => res.push_str(" + "),
+		}
+		if c.abs() != 1.0 {
+			res.push_str(&format!("{}*", c.abs()));
+		}
+		res.push_str(text);
+	}
+	res
+}
+
+// Aliases may call themselves, deeper calls fail instead of overflowing the stack
+const MAX_ALIAS_DEPTH: usize = 64;
+
+/// Evaluates the body of an expression alias with its parameters bound to the arguments,
+/// in a copy of the workspace so the assignments do not leak out, by the backend
+/// of the workspace.
+fn call_alias(
+	func_name: String,
+	params: Vec<String>,
+	(body, compiled): (&ASTNode, &Compiled),
+	args: Vec<RuntimeVal>,
+	state: &mut State,
+) -> Result<Vec<RuntimeVal>, EvaluationError> {
+	if args.len() != params.len() {
+		return Err(EvaluationError::InvalidArguments(
+			func_name,
+			tr_with(
+				"expected {0} argument(s), got {1}",
+				&[&params.len(), &args.len()],
+			),
+		));
+	}
+
+	let depth = state.alias_depth() + 1;
+	if depth > MAX_ALIAS_DEPTH {
+		return Err(EvaluationError::TooDeep(MAX_ALIAS_DEPTH));
+	}
+
+	let mut scope = state.snapshot();
+	scope.set_interrupt_flag(state.interrupt_flag());
+	scope.set_alias_depth(depth);
+	let output = CapturedOutput::default();
+	scope.add_observer(Box::new(output.clone()));
+
+	for (param, arg) in params.into_iter().zip(args) {
+		scope.assign_var(param, arg);
+	}
+
+	let res = scope.evaluator().evaluate_alias(body, compiled, &mut scope);
+	for text in output.take() {
+		state.print(&text);
+	}
+	Ok(vec![res?])
+}
+
+enum Subscript {
+	All,
+	Values(RuntimeVal),
+}
+
+fn index(
+	var_name: String,
+	args: Vec<ASTNode>,
+	state: &mut State,
+) -> Result<RuntimeVal, EvaluationError> {
+	let (nrows, ncols) = match state.lookup_var(&var_name) {
+		Some(RuntimeVal::Number(_) | RuntimeVal::BigInt(_)) => (1, 1),
+		Some(RuntimeVal::Matrix(m)) => (m.nrows(), m.ncols()),
+		Some(RuntimeVal::Table(t)) => (t.data().nrows(), t.data().ncols()),
+		Some(RuntimeVal::String(_) | RuntimeVal::Function(_)) => {
+			return Err(EvaluationError::NotANumber)
+		},
+		None => return Err(EvaluationError::NonexistantVar(var_name)),
+	};
+
+	let subscripts = evaluate_subscripts(args, nrows, ncols, state)?;
+
+	let value = match state.lookup_var(&var_name) {
+		Some(value) => value,
+		None => return Err(EvaluationError::NonexistantVar(var_name)),
+	};
+
+	match value {
+		RuntimeVal::Number(n) => Ok(collapse(
+			index_matrix(&Matrix::from(*n), subscripts)?,
+			state,
+		)),
+		RuntimeVal::BigInt(n) => Ok(collapse(
+			index_matrix(&Matrix::from(n.to_f64()), subscripts)?,
+			state,
+		)),
+		RuntimeVal::Matrix(m) => Ok(collapse(index_matrix(m, subscripts)?, state)),
+		RuntimeVal::String(_) | RuntimeVal::Function(_) => Err(EvaluationError::NotANumber),
+
+		RuntimeVal::Table(t) => {
+			if subscripts.len() != 2 {
+				return Err(EvaluationError::WrongSubscriptCount(subscripts.len()));
+			}
+
+			let mut subscripts = subscripts.into_iter();
+			let rows = to_indices(subscripts.next().unwrap(), t.data().nrows())?;
+			let cols = to_indices(subscripts.next().unwrap(), t.data().ncols())?;
+
+			Ok(RuntimeVal::Table(t.select(&rows, &cols)))
+		},
+	}
+}
+
+fn evaluate_subscripts(
+	args: Vec<ASTNode>,
+	nrows: usize,
+	ncols: usize,
+	state: &mut State,
+) -> Result<Vec<Subscript>, EvaluationError> {
+	let count = args.len();
+	let mut subscripts = vec![];
+	for (k, arg) in args.into_iter().enumerate() {
+		// `end` is the last index of the dimension the subscript is for
+		let end = match (count, k) {
+			(1, _) => nrows * ncols,
+			(_, 0) => nrows,
+			(_, 1) => ncols,
+			_ => 1,
+		};
+
+		subscripts.push(match arg.kind {
+			ASTNodeKind::Colon => Subscript::All,
+			_ => {
+				state.push_index_end(end);
+				let value = evaluate(arg, state);
+				state.pop_index_end();
+				Subscript::Values(value?)
+			},
+		});
+	}
+
+	Ok(subscripts)
+}
+
+/// `A(i, j) = B` overwrites the selected elements of A with the elements of B,
+/// which must have the shape of the selection, or be a scalar that fills it.
+/// `A(i, :) = []` deletes rows instead,
+/// and `A(:, j) = []` columns.
+fn assign_indexed(
+	var_name: &str,
+	args: Vec<ASTNode>,
+	value: RuntimeVal,
+	state: &mut State,
+) -> Result<RuntimeVal, EvaluationError> {
+	let grow = state.settings().grow;
+	let mut mat = match state.lookup_var(var_name) {
+		Some(RuntimeVal::Number(n)) => Matrix::from(*n),
+		Some(RuntimeVal::BigInt(n)) => Matrix::from(n.to_f64()),
+		Some(RuntimeVal::Matrix(m)) => m.clone(),
+		Some(_) => return Err(EvaluationError::NotANumber),
+		// Growing an empty matrix creates the variable, like in Octave
+		None if grow => Matrix::default(),
+		None => return Err(EvaluationError::NonexistantVar(var_name.to_string())),
+	};
+
+	let subscripts = evaluate_subscripts(args, mat.nrows(), mat.ncols(), state)?;
+	let value = match value {
+		RuntimeVal::Number(n) => Matrix::from(n),
+		RuntimeVal::BigInt(n) => Matrix::from(n.to_f64()),
+		RuntimeVal::Matrix(m) => m,
+		_ => return Err(EvaluationError::NotANumber),
+	};
+
+	// Only `[]` itself deletes, not every empty matrix
+	if value.nrows() == 0 && value.ncols() == 0 {
+		return Ok(collapse(delete(&mat, subscripts)?, state));
+	}
+
+	if grow {
+		mat = grown(mat, &subscripts)?;
+	}
+
+	match subscripts.len() {
+		// The selected elements are taken column by column, like when indexing
+		1 => {
+			let indices = to_indices(subscripts.into_iter().next().unwrap(), mat.len())?;
+			if indices.len() != value.len() && value.len() != 1 {
+				return Err(assign_mismatch(1, indices.len(), &value));
+			}
+
+			// The elements of the value are taken column by column too,
+			// a scalar is repeated over the selection
+			let nrows = mat.nrows();
+			for (n, k) in indices.into_iter().enumerate() {
+				let n = n % value.len();
+				mat[(k % nrows, k / nrows)] = value[(n % value.nrows(), n / value.nrows())];
+			}
+		},
+
+		2 => {
+			let mut subscripts = subscripts.into_iter();
+			let rows = to_indices(subscripts.next().unwrap(), mat.nrows())?;
+			let cols = to_indices(subscripts.next().unwrap(), mat.ncols())?;
+			// A row can be assigned to a column and the other way around, like in Octave
+			let is_vector = |nrows: usize, ncols: usize| nrows == 1 || ncols == 1;
+			let value = match (rows.len(), cols.len()) {
+				shape if shape == (value.nrows(), value.ncols()) => value,
+				// `A(:, 2) = 0` fills the selection
+				(nrows, ncols) if value.len() == 1 => {
+					Matrix::new(nrows, ncols, vec![value.as_slice()[0]; nrows * ncols]).unwrap()
+				},
+				(nrows, ncols)
+					if is_vector(nrows, ncols)
+						&& is_vector(value.nrows(), value.ncols())
+						&& nrows * ncols == value.len() =>
+				{
+					Matrix::new(nrows, ncols, value.into_vec()).unwrap()
+				},
+				(nrows, ncols) => return Err(assign_mismatch(nrows, ncols, &value)),
+			};
+
+			for (i, &row) in rows.iter().enumerate() {
+				for (j, &col) in cols.iter().enumerate() {
+					mat[(row, col)] = value[(i, j)];
 				}
 			}
+		},
+
+		n => return Err(EvaluationError::WrongSubscriptCount(n)),
+	}
+
+	Ok(collapse(mat, state))
+}
+
+// Matrices do not grow past this many elements, 800 MB, so a mistyped index
+// fails instead of exhausting the memory
+const MAX_GROWN_ELEMENTS: usize = 100_000_000;
+
+// The matrix padded with zeros so the subscripts fit in it. A vector grows along
+// its length, only a matrix with both dimensions above 1 cannot grow from a linear index.
+fn grown(mat: Matrix, subscripts: &[Subscript]) -> Result<Matrix, EvaluationError> {
+	let max_index = |subscript: &Subscript| match subscript {
+		Subscript::Values(RuntimeVal::Number(n)) => *n as usize,
+		Subscript::Values(RuntimeVal::Matrix(m)) => {
+			m.as_slice().iter().fold(0, |max, &n| max.max(n as usize))
+		},
+		Subscript::Values(_) | Subscript::All => 0,
+	};
+
+	let (nrows, ncols) = match subscripts {
+		[k] => match max_index(k) {
+			k if k <= mat.len() => return Ok(mat),
+			k if mat.nrows() <= 1 => (1, k),
+			k if mat.ncols() == 1 => (k, 1),
+			k => return Err(EvaluationError::IndexOutOfBounds(k, mat.len())),
+		},
+		[i, j] => (max_index(i), max_index(j)),
+		_ => return Ok(mat),
+	};
+
+	let (nrows, ncols) = (nrows.max(mat.nrows()), ncols.max(mat.ncols()));
+	if nrows
+		.checked_mul(ncols)
+		.is_none_or(|len| len > MAX_GROWN_ELEMENTS)
+	{
+		return Err(EvaluationError::TooLargeToGrow(
+			nrows,
+			ncols,
+			MAX_GROWN_ELEMENTS,
+		));
+	}
+	Ok(mat.grow_to(nrows, ncols))
+}
+
+fn assign_mismatch(nrows: usize, ncols: usize, value: &Matrix) -> EvaluationError {
+	EvaluationError::DimensionsMismatch(Box::new(ShapeMismatch {
+		operation: Operation::Assign,
+		lhs: Shape {
+			name: None,
+			nrows,
+			ncols,
+		},
+		rhs: Shape::of(value),
+	}))
+}
+
+// `A(i, :) = []`, `A(:, j) = []`, or `v(k) = []` which keeps v a row or a column
+fn delete(mat: &Matrix, subscripts: Vec<Subscript>) -> Result<Matrix, EvaluationError> {
+	let all = |bound: usize| (0..bound).collect::<Vec<usize>>();
 
+	match subscripts.len() {
+		1 => {
+			let indices = to_indices(subscripts.into_iter().next().unwrap(), mat.len())?;
+			let res = match (mat.nrows(), mat.ncols()) {
+				(_, 1) => mat.remove_rows(&indices),
+				(1, _) => mat.remove_cols(&indices),
+				_ if indices.len() == mat.len() => Matrix::default(),
+				_ => return Err(EvaluationError::PartialDeletion),
+			};
 			Ok(res)
 		},
+
+		2 => {
+			let mut subscripts = subscripts.into_iter();
+			let rows = to_indices(subscripts.next().unwrap(), mat.nrows())?;
+			let cols = to_indices(subscripts.next().unwrap(), mat.ncols())?;
+
+			// Whole rows are selected when every column is, whatever the order
+			let covers = |indices: &[usize], bound: usize| {
+				let mut indices = indices.to_vec();
+				indices.sort_unstable();
+				indices.dedup();
+				indices == all(bound)
+			};
+
+			if covers(&cols, mat.ncols()) {
+				Ok(mat.remove_rows(&rows))
+			} else if covers(&rows, mat.nrows()) {
+				Ok(mat.remove_cols(&cols))
+			} else {
+				Err(EvaluationError::PartialDeletion)
+			}
+		},
+
+		n => Err(EvaluationError::WrongSubscriptCount(n)),
+	}
+}
+
+fn index_matrix(mat: &Matrix, subscripts: Vec<Subscript>) -> Result<Matrix, EvaluationError> {
+	let res = match subscripts.len() {
+		0 => mat.clone(),
+
+		// Linear indexing goes through the elements column by column, like in Octave
+		1 => {
+			let subscript = subscripts.into_iter().next().unwrap();
+			let shape = match &subscript {
+				Subscript::All => None,
+				Subscript::Values(RuntimeVal::Matrix(m)) => Some((m.nrows(), m.ncols())),
+				Subscript::Values(_) => Some((1, 1)),
+			};
+
+			let indices = to_indices(subscript, mat.len())?;
+			let data: Vec<f64> = indices
+				.iter()
+				.map(|k| mat[(k % mat.nrows(), k / mat.nrows())])
+				.collect();
+
+			let (nrows, ncols) = match shape {
+				_ if mat.nrows() == 1 && shape.is_some() => (1, data.len()),
+				Some(_) if mat.ncols() == 1 => (data.len(), 1),
+				Some(shape) => shape,
+				None => (data.len(), 1),
+			};
+
+			Matrix::new(nrows, ncols, data).unwrap()
+		},
+
+		2 => {
+			let mut subscripts = subscripts.into_iter();
+			let rows = to_indices(subscripts.next().unwrap(), mat.nrows())?;
+			let cols = to_indices(subscripts.next().unwrap(), mat.ncols())?;
+
+			mat.select(&rows, &cols)
+		},
+
+		n => return Err(EvaluationError::WrongSubscriptCount(n)),
+	};
+
+	Ok(res)
+}
+
+/// A 1x1 matrix as a number, unless the shapes are strict (see `Coercion`).
+pub fn collapse(mat: Matrix, state: &State) -> RuntimeVal {
+	match state.settings().coercion {
+		Coercion::Scalars | Coercion::Permissive if mat.len() == 1 => {
+			RuntimeVal::Number(mat.as_slice()[0])
+		},
+		_ => RuntimeVal::Matrix(mat),
+	}
+}
+
+// Converts a 1-based subscript to 0-based indices below `bound`
+fn to_indices(subscript: Subscript, bound: usize) -> Result<Vec<usize>, EvaluationError> {
+	let values = match subscript {
+		Subscript::All => return Ok((0..bound).collect()),
+		Subscript::Values(RuntimeVal::Number(n)) => vec![n],
+		Subscript::Values(RuntimeVal::Matrix(m)) => m.into_vec(),
+		Subscript::Values(_) => return Err(EvaluationError::NotANumber),
+	};
+
+	let mut res = Vec::with_capacity(values.len());
+	for i in values {
+		if i.fract() != 0.0 || i < 1.0 {
+			return Err(EvaluationError::InvalidIndex(i));
+		}
+		if i > bound as f64 {
+			return Err(EvaluationError::IndexOutOfBounds(i as usize, bound));
+		}
+		res.push(i as usize - 1);
+	}
+
+	Ok(res)
+}
+
+fn expect_scalar(value: RuntimeVal) -> Result<f64, EvaluationError> {
+	match value {
+		RuntimeVal::Number(n) => Ok(n),
+		RuntimeVal::BigInt(n) => Ok(n.to_f64()),
+		RuntimeVal::Matrix(m) if m.len() == 1 => Ok(m.as_slice()[0]),
+		_ => Err(EvaluationError::NotANumber),
 	}
 }
 
@@ -96,15 +1372,325 @@ pub fn evaluate(ast: ASTNode, state: &mut State) -> Result<RuntimeVal, Evaluatio
 #[derive(Debug)]
 pub enum EvaluationError {
 	NonexistantVar(String),
+	NonexistantFunction(String),
 	NotANumber,
+	DimensionsMismatch(Box<ShapeMismatch>),
+	NoValue(String),
+	NotEnoughOutputs(usize, usize),
+	NonexistantField(String),
+	NotATable,
+	UnexpectedColon,
+	UnexpectedEnd,
+	UnexpectedKeyword(String),
+	InvalidIndex(f64),
+	IndexOutOfBounds(usize, usize),
+	/// An assignment that would grow a matrix to more elements than the limit.
+	TooLargeToGrow(usize, usize, usize),
+	SingularMatrix,
+	/// A fractional power of a matrix with an eigenvalue on the closed negative
+	/// real axis, which has no real principal value.
+	NoRealPower(f64),
+	/// An operation on finite values produced NaN or Inf, in strict mode.
+	NonFinite(String, f64),
+	WrongSubscriptCount(usize),
+	/// `A(i, j) = []` where the selection is not made of whole rows or columns.
+	PartialDeletion,
+	/// NaN used as a condition, it is neither true nor false.
+	LogicalNaN,
+	/// A matrix operand of `&&` or `||`.
+	NotALogicalScalar(&'static str),
+	/// A matrix as the condition of a statement like `if`.
+	NonScalarCondition(&'static str),
+	Interrupted,
+	/// A `while` loop reached the iteration limit of the settings.
+	TooManyIterations(usize),
+	/// Aliases calling each other past the depth limit, like a recursion that does not end.
+	TooDeep(usize),
+	WrongArgumentCount {
+		func_name: String,
+		expected: String,
+		found: usize,
+	},
+	WrongArgumentType {
+		func_name: String,
+		position: usize,
+		expected: ArgType,
+		found: String,
+	},
+	InvalidArguments(String, String),
+	BuiltinFailure(String, String),
+}
+
+impl EvaluationError {
+	pub fn mismatch(operation: Operation, lhs: &Matrix, rhs: &Matrix) -> Self {
+		Self::DimensionsMismatch(Box::new(ShapeMismatch {
+			operation,
+			lhs: Shape::of(lhs),
+			rhs: Shape::of(rhs),
+		}))
+	}
+
+	/// A short name of the kind of error, to count them in `:stats`.
+	pub fn kind(&self) -> &'static str {
+		match self {
+			Self::NonexistantVar(_) | Self::NonexistantFunction(_) | Self::NonexistantField(_) => {
+				"undefined name"
+			},
+			Self::DimensionsMismatch(_) => "dimensions",
+			Self::NotANumber
+			| Self::NoValue(_)
+			| Self::NotEnoughOutputs(..)
+			| Self::NotATable
+			| Self::UnexpectedKeyword(_)
+			| Self::NotALogicalScalar(_)
+			| Self::NonScalarCondition(_) => "type",
+			Self::UnexpectedColon
+			| Self::UnexpectedEnd
+			| Self::InvalidIndex(_)
+			| Self::IndexOutOfBounds(..)
+			| Self::TooLargeToGrow(..)
+			| Self::WrongSubscriptCount(_)
+			| Self::PartialDeletion => "indexing",
+			Self::SingularMatrix
+			| Self::NoRealPower(_)
+			| Self::NonFinite(..)
+			| Self::LogicalNaN => "numerical",
+			Self::WrongArgumentCount { .. }
+			| Self::WrongArgumentType { .. }
+			| Self::InvalidArguments(..) => "arguments",
+			Self::BuiltinFailure(..) => "builtin failure",
+			Self::Interrupted | Self::TooManyIterations(_) | Self::TooDeep(_) => "interrupted",
+		}
+	}
+
+	/// Names the operands of a dimensions mismatch, when they are variables.
+	pub fn with_operand_names(self, lhs: Option<String>, rhs: Option<String>) -> Self {
+		match self {
+			Self::DimensionsMismatch(mut mismatch) => {
+				mismatch.lhs.name = mismatch.lhs.name.or(lhs);
+				mismatch.rhs.name = mismatch.rhs.name.or(rhs);
+				Self::DimensionsMismatch(mismatch)
+			},
+			e => e,
+		}
+	}
 }
 
 impl std::error::Error for EvaluationError {}
 impl std::fmt::Display for EvaluationError {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		match self {
-			Self::NonexistantVar(var_name) => write!(f, "Variable {var_name} does not exist"),
-			Self::NotANumber => write!(f, "Some value was used as a number while it is not"),
+		let message = match self {
+			Self::NonexistantVar(var_name) => tr_with("Variable {0} does not exist", &[var_name]),
+			Self::NonexistantFunction(func_name) => {
+				tr_with("Function {0} does not exist", &[func_name])
+			},
+			Self::NotANumber => tr("Some value was used as a number while it is not").to_string(),
+			Self::DimensionsMismatch(mismatch) => mismatch.to_string(),
+			Self::NoValue(func_name) => {
+				tr_with("Function {0} does not return a value", &[func_name])
+			},
+			Self::NotEnoughOutputs(expected, found) => tr_with(
+				"Expected {0} value(s) on the right hand side, found {1}",
+				&[expected, found],
+			),
+			Self::NonexistantField(field_name) => {
+				tr_with("Field {0} does not exist", &[field_name])
+			},
+			Self::NotATable => tr("Only tables have fields").to_string(),
+			Self::UnexpectedColon => tr("A lone ':' can only be used as an index").to_string(),
+			Self::UnexpectedEnd => {
+				tr("'end' can only be used in the index of a variable").to_string()
+			},
+			Self::UnexpectedKeyword(name) => tr_with(
+				"The named argument {0} can only be passed to a builtin",
+				&[name],
+			),
+			Self::InvalidIndex(i) => tr_with("Index {0} is not a positive integer", &[i]),
+			Self::IndexOutOfBounds(i, bound) => tr_with(
+				"Index {0} out of bounds, the dimension is {1}",
+				&[i, bound],
+			),
+			Self::TooLargeToGrow(nrows, ncols, limit) => tr_with(
+				"The assignment would grow the matrix to {0}x{1}, more than {2} elements",
+				&[nrows, ncols, limit],
+			),
+			Self::SingularMatrix => tr("Matrix is singular to machine precision").to_string(),
+			Self::NoRealPower(p) => tr_with(
+				"The power {0} of the matrix is not real: it has an eigenvalue that is negative or 0",
+				&[p],
+			),
+			Self::NonFinite(operation, value) => {
+				let value = if value.is_nan() { "NaN" } else { "Inf" };
+				tr_with("{0} produced {1} (strict mode)", &[operation, &value])
+			},
+			Self::WrongSubscriptCount(n) => tr_with("Wrong number of subscripts ({0})", &[n]),
+			Self::PartialDeletion => {
+				tr("Only whole rows or columns can be deleted, like A(2, :) = []").to_string()
+			},
+			Self::LogicalNaN => tr("NaN is neither true nor false").to_string(),
+			Self::NotALogicalScalar(op) => tr_with(
+				"The operands of {0} have to be scalars, {1} works element by element",
+				&[op, &&op[1..]],
+			),
+			Self::NonScalarCondition(keyword) => tr_with(
+				"The condition of {0} has to be a scalar, like all(x) or any(x) for a matrix",
+				&[keyword],
+			),
+			Self::Interrupted => tr("Interrupted").to_string(),
+			Self::TooManyIterations(limit) => tr_with(
+				"The loop was stopped after {0} iterations, the limit is changed with :set iterations",
+				&[limit],
+			),
+			Self::TooDeep(limit) => tr_with(
+				"The aliases were stopped after {0} nested calls, a recursive alias needs a condition to end",
+				&[limit],
+			),
+			Self::WrongArgumentCount {
+				func_name,
+				expected,
+				found,
+			} => {
+				let message = match found {
+					1 => "{0} expects {1}, got {2} argument",
+					_ => "{0} expects {1}, got {2} arguments",
+				};
+				tr_with(message, &[func_name, expected, found])
+			},
+			Self::WrongArgumentType {
+				func_name,
+				position,
+				expected,
+				found,
+			} => tr_with(
+				"{0} expects {1} as argument {2}, got {3}",
+				&[func_name, expected, position, found],
+			),
+			Self::InvalidArguments(func_name, message) => {
+				tr_with("Invalid arguments to {0}: {1}", &[func_name, message])
+			},
+			Self::BuiltinFailure(func_name, message) => format!("{func_name}: {message}"),
+		};
+		write!(f, "{message}")
+	}
+}
+
+/// An operation between two matrices whose shapes do not fit.
+#[derive(Debug)]
+pub struct ShapeMismatch {
+	pub operation: Operation,
+	pub lhs: Shape,
+	pub rhs: Shape,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Operation {
+	Add,
+	Subtract,
+	Multiply,
+	Divide,
+	Compare,
+	/// The element by element `&` and `|`.
+	Combine,
+	/// Putting matrices side by side in a literal, `[A, B]`.
+	HorizontalConcat,
+	/// Stacking matrices in a literal, `[A; B]`.
+	VerticalConcat,
+	/// Overwriting the elements selected by subscripts, `A(1, :) = B`.
+	Assign,
+	/// The matrix power `A^p`, for a square A and a scalar p.
+	Power,
+}
+
+/// The shape of an operand, and the variable it comes from when known.
+#[derive(Debug)]
+pub struct Shape {
+	pub name: Option<String>,
+	pub nrows: usize,
+	pub ncols: usize,
+}
+
+impl Shape {
+	fn of(m: &Matrix) -> Self {
+		Self {
+			name: None,
+			nrows: m.nrows(),
+			ncols: m.ncols(),
 		}
 	}
 }
+
+impl std::fmt::Display for Shape {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match &self.name {
+			Some(name) => write!(f, "{name} ({}x{})", self.nrows, self.ncols),
+			None => write!(
+				f,
+				"{}",
+				tr_with("a {0}x{1} matrix", &[&self.nrows, &self.ncols])
+			),
+		}
+	}
+}
+
+impl std::fmt::Display for ShapeMismatch {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let (lhs, rhs) = (&self.lhs, &self.rhs);
+
+		// Element by element operations broadcast dimensions of 1
+		let broadcast = || {
+			if lhs.nrows != rhs.nrows && lhs.nrows != 1 && rhs.nrows != 1 {
+				tr_with("they have {0} and {1} rows", &[&lhs.nrows, &rhs.nrows])
+			} else {
+				tr_with("they have {0} and {1} columns", &[&lhs.ncols, &rhs.ncols])
+			}
+		};
+
+		let message = match self.operation {
+			Operation::Add => tr_with("Cannot add {0} and {1}: {2}", &[lhs, rhs, &broadcast()]),
+			Operation::Subtract => tr_with(
+				"Cannot subtract {1} from {0}: {2}",
+				&[lhs, rhs, &broadcast()],
+			),
+			Operation::Compare => tr_with(
+				"Cannot compare {0} with {1}: {2}",
+				&[lhs, rhs, &broadcast()],
+			),
+			Operation::Combine => tr_with(
+				"Cannot combine {0} with {1}: {2}",
+				&[lhs, rhs, &broadcast()],
+			),
+			Operation::Multiply => tr_with(
+				"Cannot multiply {0} by {1}: inner dimensions {2} and {3} differ",
+				&[lhs, rhs, &lhs.ncols, &rhs.nrows],
+			),
+			Operation::Divide if rhs.nrows != rhs.ncols => tr_with(
+				"Cannot divide {0} by {1}: the divisor is not square",
+				&[lhs, rhs],
+			),
+			Operation::Divide => tr_with(
+				"Cannot divide {0} by {1}: they have {2} and {3} columns",
+				&[lhs, rhs, &lhs.ncols, &rhs.ncols],
+			),
+			Operation::HorizontalConcat => tr_with(
+				"Cannot put {0} next to {1}: they have {2} and {3} rows",
+				&[lhs, rhs, &lhs.nrows, &rhs.nrows],
+			),
+			Operation::VerticalConcat => tr_with(
+				"Cannot put {0} above {1}: they have {2} and {3} columns",
+				&[lhs, rhs, &lhs.ncols, &rhs.ncols],
+			),
+			Operation::Assign => tr_with(
+				"Cannot assign {0} to {1}x{2} selected elements",
+				&[rhs, &lhs.nrows, &lhs.ncols],
+			),
+			Operation::Power if lhs.nrows != lhs.ncols => {
+				tr_with("Cannot raise {0} to a power: it is not square", &[lhs])
+			},
+			Operation::Power => tr_with(
+				"Cannot raise {0} to {1}: the exponent is not a scalar",
+				&[lhs, rhs],
+			),
+		};
+		write!(f, "{message}")
+	}
+}
@@ -0,0 +1,128 @@
+use {
+	crate::{engine::Engine, state::RuntimeVal},
+	std::{
+		ffi::{c_char, CStr, CString},
+		fmt::Write,
+	},
+};
+
+/*
+	C interface to embed the interpreter, see include/nam.h. An engine may be
+	used from several threads, the statements are evaluated one at a time.
+
+	`nam_eval` evaluates one statement and returns a JSON object, either
+	{"ok": true, "value": ...} or {"ok": false, "error": "..."}.
+	Numbers are JSON numbers (null for NaN and infinities), big integers are
+	JSON numbers with all their digits, matrices are arrays of rows and tables
+	are {"columns": [...], "data": [...]} with the data as rows.
+	Function handles are strings like "@polyval".
+*/
+
+/// Creates an engine, to be released with `nam_engine_free`.
+#[no_mangle]
+pub extern "C" fn nam_engine_new() -> *mut Engine {
+	Box::into_raw(Box::new(Engine::new()))
+}
+
+/// Releases an engine created by `nam_engine_new`, does nothing for a null pointer.
+///
+/// # Safety
+/// `engine` must be null or come from `nam_engine_new` and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn nam_engine_free(engine: *mut Engine) {
+	if !engine.is_null() {
+		drop(Box::from_raw(engine));
+	}
+}
+
+/// Evaluates the statements of `code` and returns the value of the last one as a JSON string,
+/// to be released with `nam_string_free`. Nothing is printed to the standard output.
+///
+/// # Safety
+/// `engine` must come from `nam_engine_new` and `code` must be a null-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn nam_eval(engine: *const Engine, code: *const c_char) -> *mut c_char {
+	let json = match (engine.as_ref(), code.is_null()) {
+		(Some(engine), false) => match CStr::from_ptr(code).to_str() {
+			Ok(code) => match engine.eval(code) {
+				Ok(value) => format!("{{\"ok\": true, \"value\": {}}}", to_json(&value)),
+				Err(e) => error_json(&e.to_string()),
+			},
+			Err(_) => error_json("the code is not valid UTF-8"),
+		},
+		_ => error_json("null engine or code"),
+	};
+
+	// JSON strings escape every control character, so there is no interior nul
+	CString::new(json).unwrap().into_raw()
+}
+
+/// Releases a string returned by `nam_eval`, does nothing for a null pointer.
+///
+/// # Safety
+/// `s` must be null or come from `nam_eval` and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn nam_string_free(s: *mut c_char) {
+	if !s.is_null() {
+		drop(CString::from_raw(s));
+	}
+}
+
+fn error_json(message: &str) -> String {
+	format!("{{\"ok\": false, \"error\": {}}}", json_string(message))
+}
+
+fn to_json(value: &RuntimeVal) -> String {
+	match value {
+		RuntimeVal::Number(n) => json_number(*n),
+		RuntimeVal::BigInt(n) => n.to_string(),
+		RuntimeVal::String(s) => json_string(s),
+		RuntimeVal::Matrix(m) => json_rows(m.rows()),
+		RuntimeVal::Table(t) => {
+			let names: Vec<String> = t.names().iter().map(|name| json_string(name)).collect();
+			format!(
+				"{{\"columns\": [{}], \"data\": {}}}",
+				names.join(", "),
+				json_rows(t.data().rows())
+			)
+		},
+		RuntimeVal::Function(func) => json_string(&func.to_string()),
+	}
+}
+
+fn json_rows<'a>(rows: impl Iterator<Item = &'a [f64]>) -> String {
+	let rows: Vec<String> = rows
+		.map(|row| {
+			let row: Vec<String> = row.iter().map(|&x| json_number(x)).collect();
+			format!("[{}]", row.join(", "))
+		})
+		.collect();
+	format!("[{}]", rows.join(", "))
+}
+
+fn json_number(n: f64) -> String {
+	match n.is_finite() {
+		true => format!("{n}"),
+		false => String::from("null"),
+	}
+}
+
+pub(crate) fn json_string(s: &str) -> String {
+	let mut res = String::with_capacity(s.len() + 2);
+	res.push('"');
+	for c in s.chars() {
+		match c {
+			'"' => res.push_str("\\\""),
+			'\\' => res.push_str("\\\\"),
+			'\n' => res.push_str("\\n"),
+			'\r' => res.push_str("\\r"),
+			'\t' => res.push_str("\\t"),
+			c if c.is_control() => {
+				let _ = write!(res, "\\u{:04x}", c as u32);
+			},
+			c => res.push(c),
+		}
+	}
+	res.push('"');
+	res
+}
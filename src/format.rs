@@ -0,0 +1,391 @@
+use {
+	crate::{
+		matrix::{Labels, Matrix},
+		state::RuntimeVal,
+		table::Table,
+	},
+	std::borrow::Cow,
+};
+
+/// How numbers are written when printing results, chosen with `format`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NumberFormat {
+	pub notation: Notation,
+	/// In the short notation, numbers whose decimal exponent is at least this far
+	/// from 0 are written in scientific notation, like `1.5e+20` or `2e-09`.
+	pub exponent_threshold: Option<u32>,
+	/// In the short notation, separates the thousands of the integer part
+	/// with commas, like `1,234,567.5`.
+	pub thousands: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Notation {
+	/// The shortest representation that reads back as the same number.
+	#[default]
+	Short,
+	/// Exponents in multiples of 3, like `12.5e+03`.
+	Eng,
+	/// Like `Eng` with SI prefixes instead of exponents, like `12.5k`.
+	EngSi,
+	/// The nearest fraction with a small denominator, like `1/3`, when it is
+	/// that close to the number. Other numbers are written as in `Short`.
+	Frac,
+}
+
+impl Notation {
+	pub fn name(&self) -> &'static str {
+		match self {
+			Self::Short => "short",
+			Self::Eng => "eng",
+			Self::EngSi => "eng-si",
+			Self::Frac => "frac",
+		}
+	}
+
+	pub fn from_name(name: &str) -> Option<Self> {
+		match name {
+			"short" => Some(Self::Short),
+			"eng" => Some(Self::Eng),
+			"eng-si" => Some(Self::EngSi),
+			"frac" => Some(Self::Frac),
+			_ => None,
+		}
+	}
+}
+
+// Fractions are only printed with denominators up to this
+const MAX_DENOMINATOR: f64 = 10_000.0;
+
+// How close, relative to the number, a fraction must be to be printed instead
+const FRACTION_TOLERANCE: f64 = 1e-12;
+
+const SI_PREFIXES: [&str; 17] = [
+	"y", "z", "a", "f", "p", "n", "µ", "m", "", "k", "M", "G", "T", "P", "E", "Z", "Y",
+];
+
+pub fn number(n: f64, format: NumberFormat) -> String {
+	if n == 0.0 || !n.is_finite() {
+		return n.to_string();
+	}
+	if format.notation == Notation::Frac {
+		return match fraction(n) {
+			Some((p, 1.0)) => p.to_string(),
+			Some((p, q)) => format!("{p}/{q}"),
+			None => short(n, format),
+		};
+	}
+	if format.notation == Notation::Short {
+		return short(n, format);
+	}
+
+	// Five significant digits, the mantissa is in [1, 1000)
+	let mut exponent = (n.abs().log10() / 3.0).floor() as i32 * 3;
+	let mantissa = n / 10f64.powi(exponent);
+	let mut mantissa = round_to(mantissa, 4 - mantissa.abs().log10().floor() as i32);
+	if mantissa.abs() >= 1000.0 {
+		exponent += 3;
+		mantissa /= 1000.0;
+	}
+
+	let prefix = usize::try_from(exponent / 3 + 8)
+		.ok()
+		.and_then(|i| SI_PREFIXES.get(i));
+
+	match (format.notation, prefix) {
+		(Notation::EngSi, Some(prefix)) => format!("{mantissa}{prefix}"),
+		_ => {
+			let sign = if exponent < 0 { '-' } else { '+' };
+			format!("{mantissa}e{sign}{:02}", exponent.abs())
+		},
+	}
+}
+
+// The shortest representation of a finite number that is not 0
+fn short(n: f64, format: NumberFormat) -> String {
+	let exponent = n.abs().log10().floor() as i32;
+	if format
+		.exponent_threshold
+		.is_some_and(|threshold| exponent.unsigned_abs() >= threshold)
+	{
+		// Rust writes `1.5e20`, the sign and two digits are added as in the other notations
+		let res = format!("{n:e}");
+		let (mantissa, exponent) = res.split_once('e').unwrap();
+		let exponent: i32 = exponent.parse().unwrap();
+		let sign = if exponent < 0 { '-' } else { '+' };
+		return format!("{mantissa}e{sign}{:02}", exponent.abs());
+	}
+
+	let res = n.to_string();
+	match format.thousands {
+		true => group_thousands(&res),
+		false => res,
+	}
+}
+
+// The fraction p/q nearest to n from its continued fraction, if one with a small
+// enough denominator is within the tolerance
+fn fraction(n: f64) -> Option<(f64, f64)> {
+	let tolerance = FRACTION_TOLERANCE * n.abs();
+
+	// The last two convergents, starting from 1/0 and 0/1
+	let (mut p, mut q) = (1.0, 0.0);
+	let (mut prev_p, mut prev_q) = (0.0, 1.0);
+	let mut x = n;
+	loop {
+		let a = x.floor();
+		(p, prev_p) = (a * p + prev_p, p);
+		(q, prev_q) = (a * q + prev_q, q);
+
+		if q > MAX_DENOMINATOR || !p.is_finite() {
+			return None;
+		}
+		if (n - p / q).abs() <= tolerance {
+			return Some((p, q));
+		}
+
+		let rest = x - a;
+		if rest == 0.0 {
+			return None;
+		}
+		x = 1.0 / rest;
+	}
+}
+
+fn group_thousands(n: &str) -> String {
+	let (sign, n) = match n.strip_prefix('-') {
+		Some(n) => ("-", n),
+		None => ("", n),
+	};
+	let (integer, fraction) = match n.find('.') {
+		Some(i) => n.split_at(i),
+		None => (n, ""),
+	};
+
+	let mut res = String::from(sign);
+	for (i, digit) in integer.chars().enumerate() {
+		if i > 0 && (integer.len() - i) % 3 == 0 {
+			res.push(',');
+		}
+		res.push(digit);
+	}
+	res.push_str(fraction);
+	res
+}
+
+fn round_to(n: f64, decimals: i32) -> f64 {
+	let scale = 10f64.powi(decimals);
+	(n * scale).round() / scale
+}
+
+pub fn matrix(mat: &Matrix, format: NumberFormat) -> String {
+	if let Some(labels) = mat.labels() {
+		return labeled(mat, labels, format);
+	}
+
+	let mut buffer = String::new();
+	buffer.push('[');
+	for i in 0..mat.nrows() {
+		buffer.push_str("\n   ");
+		for j in 0..mat.ncols() {
+			buffer.push_str("  ");
+			buffer.push_str(&number(mat[(i, j)], format));
+		}
+	}
+	buffer.push_str("\n]");
+	buffer
+}
+
+// A labeled matrix is aligned like a table, with its row names in a first column
+fn labeled(mat: &Matrix, labels: &Labels, format: NumberFormat) -> String {
+	let (nrows, ncols) = (mat.nrows(), mat.ncols());
+	let labels = Labels {
+		rows: escape_names(&labels.rows),
+		cols: escape_names(&labels.cols),
+	};
+
+	let cells: Vec<String> = mat.as_slice().iter().map(|&n| number(n, format)).collect();
+	let widths: Vec<usize> = (0..ncols)
+		.map(|j| {
+			(0..nrows)
+				.map(|i| cells[i * ncols + j].chars().count())
+				.chain(labels.cols.iter().map(|names| names[j].chars().count()))
+				.max()
+				.unwrap_or(0)
+		})
+		.collect();
+	let row_width = labels
+		.rows
+		.iter()
+		.flatten()
+		.map(|name| name.chars().count())
+		.max();
+
+	let mut buffer = String::new();
+	buffer.push('[');
+
+	if let Some(names) = &labels.cols {
+		buffer.push_str("\n   ");
+		if let Some(row_width) = row_width {
+			buffer.push_str(&format!("  {:row_width$}", ""));
+		}
+		for (name, width) in names.iter().zip(&widths) {
+			buffer.push_str(&format!("  {name:>width$}"));
+		}
+	}
+
+	for i in 0..nrows {
+		buffer.push_str("\n   ");
+		if let (Some(names), Some(row_width)) = (&labels.rows, row_width) {
+			buffer.push_str(&format!("  {:<row_width$}", names[i]));
+		}
+		for (j, width) in widths.iter().enumerate() {
+			buffer.push_str(&format!("  {:>width$}", cells[i * ncols + j]));
+		}
+	}
+
+	buffer.push_str("\n]");
+	buffer
+}
+
+pub fn table(table: &Table, format: NumberFormat) -> String {
+	let (names, data) = (table.names(), table.data());
+	let (nrows, ncols) = (data.nrows(), data.ncols());
+	let names: Vec<Cow<str>> = names.iter().map(|name| escape_controls(name)).collect();
+
+	let cells: Vec<String> = data.as_slice().iter().map(|&n| number(n, format)).collect();
+	let widths: Vec<usize> = (0..ncols)
+		.map(|j| {
+			(0..nrows)
+				.map(|i| cells[i * ncols + j].chars().count())
+				.chain([names[j].chars().count()])
+				.max()
+				.unwrap()
+		})
+		.collect();
+
+	let mut buffer = String::new();
+	buffer.push('[');
+
+	buffer.push_str("\n   ");
+	for (name, width) in names.iter().zip(&widths) {
+		buffer.push_str(&format!("  {name:>width$}"));
+	}
+
+	for i in 0..nrows {
+		buffer.push_str("\n   ");
+		for (j, width) in widths.iter().enumerate() {
+			buffer.push_str(&format!("  {:>width$}", cells[i * ncols + j]));
+		}
+	}
+
+	buffer.push_str("\n]");
+	buffer
+}
+
+// Rows and columns shown at each edge of a summarised matrix
+const SUMMARY_EDGE: usize = 3;
+
+/// The shape and range of a matrix, with only the elements near its corners.
+pub fn summary(mat: &Matrix, format: NumberFormat) -> String {
+	let (nrows, ncols) = (mat.nrows(), mat.ncols());
+	let values = mat.as_slice();
+
+	let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+	let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+	let mean = values.iter().sum::<f64>() / values.len() as f64;
+
+	let mut buffer = format!(
+		"{nrows}x{ncols} matrix, min {}, max {}, mean {}, full() prints every element",
+		number(min, format),
+		number(max, format),
+		number(mean, format),
+	);
+
+	let rows = edges(nrows);
+	let cols = edges(ncols);
+	buffer.push_str("\n[");
+	for i in rows {
+		buffer.push_str("\n   ");
+		let Some(i) = i else {
+			buffer.push_str("  ...");
+			continue;
+		};
+		for j in &cols {
+			buffer.push_str("  ");
+			match j {
+				Some(j) => buffer.push_str(&number(mat[(i, *j)], format)),
+				None => buffer.push_str("..."),
+			}
+		}
+	}
+	buffer.push_str("\n]");
+	buffer
+}
+
+// The indices shown along a dimension, `None` stands for the ones left out
+fn edges(len: usize) -> Vec<Option<usize>> {
+	if len <= 2 * SUMMARY_EDGE {
+		return (0..len).map(Some).collect();
+	}
+
+	(0..SUMMARY_EDGE)
+		.map(Some)
+		.chain([None])
+		.chain((len - SUMMARY_EDGE..len).map(Some))
+		.collect()
+}
+
+/// Writes the control characters of a string, like the escape starting an ANSI
+/// sequence, as escapes such as `\u{1b}`, so printing text given by the user
+/// cannot change the colors or the cursor of the terminal. Keeps newlines and tabs.
+pub fn escape_controls(text: &str) -> Cow<'_, str> {
+	if !text.chars().any(is_escaped) {
+		return Cow::Borrowed(text);
+	}
+
+	let mut res = String::with_capacity(text.len());
+	for c in text.chars() {
+		match is_escaped(c) {
+			true => res.extend(c.escape_default()),
+			false => res.push(c),
+		}
+	}
+	Cow::Owned(res)
+}
+
+fn is_escaped(c: char) -> bool {
+	c.is_control() && c != '\n' && c != '\t'
+}
+
+fn escape_names(names: &Option<Vec<String>>) -> Option<Vec<String>> {
+	names.as_ref().map(|names| {
+		names
+			.iter()
+			.map(|name| escape_controls(name).into_owned())
+			.collect()
+	})
+}
+
+/// A value printed with the display settings of a workspace, see `State::display`.
+pub struct Displayed<'a> {
+	pub value: &'a RuntimeVal,
+	pub format: NumberFormat,
+	pub summary_limit: Option<usize>,
+}
+
+impl std::fmt::Display for Displayed<'_> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self.value {
+			RuntimeVal::Number(n) => write!(f, "{}", number(*n, self.format)),
+			RuntimeVal::BigInt(n) => write!(f, "{n}"),
+			RuntimeVal::String(s) => write!(f, "{}", escape_controls(s)),
+			RuntimeVal::Matrix(m) if self.summary_limit.is_some_and(|limit| m.len() > limit) => {
+				write!(f, "{}", summary(m, self.format))
+			},
+			RuntimeVal::Matrix(m) => write!(f, "{}", matrix(m, self.format)),
+			RuntimeVal::Table(t) => write!(f, "{}", table(t, self.format)),
+			RuntimeVal::Function(func) => write!(f, "{func}"),
+		}
+	}
+}
@@ -0,0 +1,37 @@
+use crate::state::RuntimeVal;
+
+/// A function used as a value, like `@polyval` in `map(@polyval, A)`.
+/// Builtins and aliases are called the same way, see `eval::call_function`.
+#[derive(Debug, Clone)]
+pub enum Function {
+	/// A builtin or an alias, looked up when called like a call by name,
+	/// except that variables do not shadow it.
+	Named(String),
+	/// A function with its first arguments fixed, made by `bind`.
+	Bound {
+		function: Box<Function>,
+		args: Vec<RuntimeVal>,
+	},
+}
+
+impl Function {
+	/// The name of the builtin or alias eventually called.
+	pub fn name(&self) -> &str {
+		match self {
+			Self::Named(name) => name,
+			Self::Bound { function, .. } => function.name(),
+		}
+	}
+}
+
+impl std::fmt::Display for Function {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Named(name) => write!(f, "@{name}"),
+			Self::Bound { function, args } => {
+				let plural = if args.len() == 1 { "" } else { "s" };
+				write!(f, "{function} with {} bound argument{plural}", args.len())
+			},
+		}
+	}
+}
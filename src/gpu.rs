@@ -0,0 +1,533 @@
+#[cfg(feature = "gpu")]
+use crate::i18n::tr_with;
+use crate::{ast::BinaryOpKind, i18n::tr, matrix::Matrix};
+
+/*
+	An experimental backend running large matrix products and element by element
+	operations on the GPU through wgpu, used when built with the `gpu` feature and
+	turned on with `:gpu on`. The device needs 64-bit floats, so results are the
+	same as on the CPU up to the rounding of fused multiply-adds.
+
+	Operands are uploaded once and kept on the device while the memory allows,
+	keyed by their content, so a matrix used in several statements is not copied
+	again. Only results are read back, the partial sums of `2*A + 3*B - C` stay
+	on the device. Small operands, values that are not finite (shaders may assume
+	they are not) and any failure of the device fall back to the CPU.
+*/
+
+// Below these sizes copying to the device and back costs more than it saves
+const MATMUL_THRESHOLD: usize = 1 << 21;
+const ELEMENTWISE_THRESHOLD: usize = 1 << 18;
+
+/// Initializes the device if it is not yet, and describes it.
+pub fn init() -> Result<String, GpuError> {
+	device::init()
+}
+
+/// The matrix product on the device, None when it is not worth it or failed.
+pub fn matmul(a: &Matrix, b: &Matrix) -> Option<Matrix> {
+	if a.nrows() * a.ncols() * b.ncols() < MATMUL_THRESHOLD || !all_finite(&[a, b]) {
+		return None;
+	}
+	device::matmul(a, b)
+}
+
+/// `a op b` element by element on the device, broadcasting like `Matrix::broadcast`.
+/// Divisions, powers and shapes that do not broadcast are left to the CPU.
+pub fn elementwise(op: &BinaryOpKind, a: &Matrix, b: &Matrix) -> Option<Matrix> {
+	let kernel = Kernel::from_op(op)?;
+	let broadcasts = |x: usize, y: usize| x == y || x == 1 || y == 1;
+	if !broadcasts(a.nrows(), b.nrows())
+		|| !broadcasts(a.ncols(), b.ncols())
+		|| a.nrows().max(b.nrows()) * a.ncols().max(b.ncols()) < ELEMENTWISE_THRESHOLD
+		|| !all_finite(&[a, b])
+	{
+		return None;
+	}
+	device::elementwise(kernel, a, b)
+}
+
+/// `c1 * x1 + c2 * x2 + ...` from left to right on the device, the terms
+/// being matrices of the same shape or 1x1 matrices.
+pub fn linear_combination(terms: &[(f64, &Matrix)]) -> Option<Matrix> {
+	let len = terms.iter().map(|(_, x)| x.len()).max()?;
+	let matrices: Vec<&Matrix> = terms.iter().map(|&(_, x)| x).collect();
+	if len < ELEMENTWISE_THRESHOLD
+		|| !all_finite(&matrices)
+		|| terms.iter().any(|(c, _)| !c.is_finite())
+	{
+		return None;
+	}
+	device::linear_combination(terms)
+}
+
+fn all_finite(matrices: &[&Matrix]) -> bool {
+	matrices
+		.iter()
+		.all(|m| m.as_slice().iter().all(|x| x.is_finite()))
+}
+
+/// The operations of the element by element shader, numbered like its `op` parameter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(not(feature = "gpu"), allow(dead_code))]
+enum Kernel {
+	Add,
+	Subtract,
+	Multiply,
+	Equal,
+	NotEqual,
+	Less,
+	Greater,
+	LessEqual,
+	GreaterEqual,
+	And,
+	Or,
+	/// `coef_a * a`
+	Scale,
+	/// `a + coef_b * b`
+	Accumulate,
+}
+
+impl Kernel {
+	fn from_op(op: &BinaryOpKind) -> Option<Self> {
+		let res = match op {
+			BinaryOpKind::Add => Self::Add,
+			BinaryOpKind::Subtract => Self::Subtract,
+			BinaryOpKind::Multiply => Self::Multiply,
+			BinaryOpKind::Equal => Self::Equal,
+			BinaryOpKind::NotEqual => Self::NotEqual,
+			BinaryOpKind::Less => Self::Less,
+			BinaryOpKind::Greater => Self::Greater,
+			BinaryOpKind::LessEqual => Self::LessEqual,
+			BinaryOpKind::GreaterEqual => Self::GreaterEqual,
+			BinaryOpKind::And => Self::And,
+			BinaryOpKind::Or => Self::Or,
+			BinaryOpKind::Divide | BinaryOpKind::Power => return None,
+		};
+		Some(res)
+	}
+}
+
+#[cfg(feature = "gpu")]
+mod device {
+	use {
+		super::{GpuError, Kernel},
+		crate::{hash::ContentHash, matrix::Matrix},
+		std::sync::{mpsc, Mutex, OnceLock, PoisonError},
+		wgpu::util::DeviceExt,
+	};
+
+	// Uploaded operands are dropped, least recently used first, past this size
+	const UPLOADS_CAPACITY: u64 = 256 << 20;
+
+	const TILE: usize = 16;
+	const WORKGROUP_SIZE: usize = 256;
+
+	struct Gpu {
+		name: String,
+		device: wgpu::Device,
+		queue: wgpu::Queue,
+		matmul: wgpu::ComputePipeline,
+		elementwise: wgpu::ComputePipeline,
+
+		// Also makes the operations run one at a time, error scopes being per device
+		uploads: Mutex<Uploads>,
+	}
+
+	/// A matrix in the memory of the device.
+	struct DeviceMatrix {
+		buffer: wgpu::Buffer,
+		nrows: usize,
+		ncols: usize,
+	}
+
+	#[derive(Default)]
+	struct Uploads {
+		// The most recently used last, with the content hash of the matrix and
+		// the matrix itself, as the hash of another one could be the same
+		buffers: Vec<(u64, Matrix, wgpu::Buffer)>,
+		bytes: u64,
+	}
+
+	// The device, initialized by the first call
+	fn get() -> Result<&'static Gpu, GpuError> {
+		static GPU: OnceLock<Result<Gpu, GpuError>> = OnceLock::new();
+		GPU.get_or_init(|| pollster::block_on(Gpu::new()))
+			.as_ref()
+			.map_err(Clone::clone)
+	}
+
+	pub fn init() -> Result<String, GpuError> {
+		get().map(|gpu| gpu.name.clone())
+	}
+
+	pub fn matmul(a: &Matrix, b: &Matrix) -> Option<Matrix> {
+		get().ok()?.matmul(a, b)
+	}
+
+	pub fn elementwise(kernel: Kernel, a: &Matrix, b: &Matrix) -> Option<Matrix> {
+		get().ok()?.elementwise(kernel, a, b)
+	}
+
+	pub fn linear_combination(terms: &[(f64, &Matrix)]) -> Option<Matrix> {
+		get().ok()?.linear_combination(terms)
+	}
+
+	impl Gpu {
+		async fn new() -> Result<Self, GpuError> {
+			let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+			let adapter = instance
+				.request_adapter(&wgpu::RequestAdapterOptions {
+					power_preference: wgpu::PowerPreference::HighPerformance,
+					..Default::default()
+				})
+				.await
+				.ok_or(GpuError::NoAdapter)?;
+
+			let info = adapter.get_info();
+			let name = format!("{} ({:?})", info.name, info.backend);
+			if !adapter.features().contains(wgpu::Features::SHADER_F64) {
+				return Err(GpuError::NoF64(name));
+			}
+
+			let (device, queue) = adapter
+				.request_device(
+					&wgpu::DeviceDescriptor {
+						label: Some("nam"),
+						required_features: wgpu::Features::SHADER_F64,
+						required_limits: adapter.limits(),
+						memory_hints: wgpu::MemoryHints::Performance,
+					},
+					None,
+				)
+				.await
+				.map_err(|e| GpuError::Device(e.to_string()))?;
+
+			let pipeline = |source: &str| {
+				let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+					label: None,
+					source: wgpu::ShaderSource::Wgsl(source.into()),
+				});
+				device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+					label: None,
+					layout: None,
+					module: &module,
+					entry_point: Some("main"),
+					compilation_options: Default::default(),
+					cache: None,
+				})
+			};
+
+			device.push_error_scope(wgpu::ErrorFilter::Validation);
+			let matmul = pipeline(include_str!("gpu/matmul.wgsl"));
+			let elementwise = pipeline(include_str!("gpu/elementwise.wgsl"));
+			if let Some(e) = device.pop_error_scope().await {
+				return Err(GpuError::Device(e.to_string()));
+			}
+
+			Ok(Self {
+				name,
+				device,
+				queue,
+				matmul,
+				elementwise,
+				uploads: Mutex::default(),
+			})
+		}
+
+		fn matmul(&self, a: &Matrix, b: &Matrix) -> Option<Matrix> {
+			let (m, k, n) = (a.nrows(), a.ncols(), b.ncols());
+			let groups = (n.div_ceil(TILE), m.div_ceil(TILE));
+			self.run(|uploads| {
+				let (a, b) = (self.upload(uploads, a)?, self.upload(uploads, b)?);
+				let res = self.output(m, n)?;
+				let dims: Vec<u8> = [m, k, n, 0]
+					.iter()
+					.flat_map(|&x| (x as u32).to_le_bytes())
+					.collect();
+				self.dispatch(&self.matmul, [&a, &b, &res], &dims, groups)?;
+				self.download(&res)
+			})
+		}
+
+		fn elementwise(&self, kernel: Kernel, a: &Matrix, b: &Matrix) -> Option<Matrix> {
+			self.run(|uploads| {
+				let (a, b) = (self.upload(uploads, a)?, self.upload(uploads, b)?);
+				let res = self.apply(kernel, &a, &b, (1.0, 1.0))?;
+				self.download(&res)
+			})
+		}
+
+		fn linear_combination(&self, terms: &[(f64, &Matrix)]) -> Option<Matrix> {
+			self.run(|uploads| {
+				let ((c, x), rest) = terms.split_first()?;
+				let x = self.upload(uploads, x)?;
+				let mut acc = self.apply(Kernel::Scale, &x, &x, (*c, 0.0))?;
+				for (c, x) in rest {
+					let x = self.upload(uploads, x)?;
+					acc = self.apply(Kernel::Accumulate, &acc, &x, (0.0, *c))?;
+				}
+				self.download(&acc)
+			})
+		}
+
+		// Runs an operation, failing if the device reported any error meanwhile
+		fn run(&self, f: impl FnOnce(&mut Uploads) -> Option<Matrix>) -> Option<Matrix> {
+			let mut uploads = self.uploads.lock().unwrap_or_else(PoisonError::into_inner);
+			self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+			self.device.push_error_scope(wgpu::ErrorFilter::OutOfMemory);
+			let res = f(&mut uploads);
+			let out_of_memory = pollster::block_on(self.device.pop_error_scope());
+			let invalid = pollster::block_on(self.device.pop_error_scope());
+
+			if out_of_memory.is_some() || invalid.is_some() {
+				// The buffers uploaded meanwhile may be the ones that failed
+				*uploads = Uploads::default();
+				return None;
+			}
+			res
+		}
+
+		fn apply(
+			&self,
+			kernel: Kernel,
+			a: &DeviceMatrix,
+			b: &DeviceMatrix,
+			(coef_a, coef_b): (f64, f64),
+		) -> Option<DeviceMatrix> {
+			let (nrows, ncols) = (a.nrows.max(b.nrows), a.ncols.max(b.ncols));
+			let res = self.output(nrows, ncols)?;
+
+			let mut params: Vec<u8> = [
+				nrows,
+				ncols,
+				a.nrows,
+				a.ncols,
+				b.nrows,
+				b.ncols,
+				kernel as usize,
+				0,
+			]
+			.iter()
+			.flat_map(|&x| (x as u32).to_le_bytes())
+			.collect();
+			params.extend(coef_a.to_le_bytes());
+			params.extend(coef_b.to_le_bytes());
+
+			// Past the limit of workgroups in a dimension, the rest go in more rows
+			let groups = (nrows * ncols).div_ceil(WORKGROUP_SIZE);
+			let max = self.device.limits().max_compute_workgroups_per_dimension as usize;
+			let groups = (groups.min(max), groups.div_ceil(max));
+			self.dispatch(&self.elementwise, [a, b, &res], &params, groups)?;
+			Some(res)
+		}
+
+		fn dispatch(
+			&self,
+			pipeline: &wgpu::ComputePipeline,
+			[a, b, res]: [&DeviceMatrix; 3],
+			params: &[u8],
+			(x, y): (usize, usize),
+		) -> Option<()> {
+			let max = self.device.limits().max_compute_workgroups_per_dimension as usize;
+			if x > max || y > max {
+				return None;
+			}
+
+			let params = self
+				.device
+				.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+					label: None,
+					contents: params,
+					usage: wgpu::BufferUsages::UNIFORM,
+				});
+			let buffers = [&a.buffer, &b.buffer, &res.buffer, &params];
+			let entries: Vec<wgpu::BindGroupEntry> = buffers
+				.iter()
+				.enumerate()
+				.map(|(i, buffer)| wgpu::BindGroupEntry {
+					binding: i as u32,
+					resource: buffer.as_entire_binding(),
+				})
+				.collect();
+			let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+				label: None,
+				layout: &pipeline.get_bind_group_layout(0),
+				entries: &entries,
+			});
+
+			let mut encoder = self
+				.device
+				.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+			{
+				let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+				pass.set_pipeline(pipeline);
+				pass.set_bind_group(0, &bind_group, &[]);
+				pass.dispatch_workgroups(x as u32, y as u32, 1);
+			}
+			self.queue.submit([encoder.finish()]);
+			Some(())
+		}
+
+		// The buffer holding a matrix, uploaded unless it already is
+		fn upload(&self, uploads: &mut Uploads, m: &Matrix) -> Option<DeviceMatrix> {
+			let key = m.content_hash();
+			let uploaded = uploads
+				.buffers
+				.iter()
+				.position(|(k, uploaded, _)| *k == key && uploaded.same_content(m));
+			let buffer = match uploaded {
+				Some(i) => {
+					let entry = uploads.buffers.remove(i);
+					uploads.buffers.push(entry);
+					uploads.buffers.last().unwrap().2.clone()
+				},
+				None => {
+					self.fits(m.len())?;
+					let buffer =
+						self.device
+							.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+								label: None,
+								contents: bytemuck::cast_slice(m.as_slice()),
+								usage: wgpu::BufferUsages::STORAGE,
+							});
+					uploads.bytes += buffer.size();
+					uploads.buffers.push((key, m.clone(), buffer.clone()));
+					while uploads.bytes > UPLOADS_CAPACITY && uploads.buffers.len() > 1 {
+						let (_, _, evicted) = uploads.buffers.remove(0);
+						uploads.bytes -= evicted.size();
+					}
+					buffer
+				},
+			};
+
+			Some(DeviceMatrix {
+				buffer,
+				nrows: m.nrows(),
+				ncols: m.ncols(),
+			})
+		}
+
+		fn output(&self, nrows: usize, ncols: usize) -> Option<DeviceMatrix> {
+			let size = self.fits(nrows * ncols)?;
+			let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+				label: None,
+				size,
+				usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+				mapped_at_creation: false,
+			});
+			Some(DeviceMatrix {
+				buffer,
+				nrows,
+				ncols,
+			})
+		}
+
+		// The size in bytes of a buffer of `len` floats, if the device allows it
+		fn fits(&self, len: usize) -> Option<u64> {
+			let limits = self.device.limits();
+			let size = (len.max(1) * size_of::<f64>()) as u64;
+			let fits = size <= limits.max_storage_buffer_binding_size as u64
+				&& size <= limits.max_buffer_size
+				&& len <= u32::MAX as usize;
+			fits.then_some(size)
+		}
+
+		fn download(&self, m: &DeviceMatrix) -> Option<Matrix> {
+			let size = m.buffer.size();
+			let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+				label: None,
+				size,
+				usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+				mapped_at_creation: false,
+			});
+			let mut encoder = self
+				.device
+				.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+			encoder.copy_buffer_to_buffer(&m.buffer, 0, &staging, 0, size);
+			self.queue.submit([encoder.finish()]);
+
+			let slice = staging.slice(..);
+			let (sender, receiver) = mpsc::channel();
+			slice.map_async(wgpu::MapMode::Read, move |res| {
+				let _ = sender.send(res);
+			});
+			let _ = self.device.poll(wgpu::Maintain::Wait);
+			receiver.recv().ok()?.ok()?;
+
+			let data = slice
+				.get_mapped_range()
+				.chunks_exact(size_of::<f64>())
+				.take(m.nrows * m.ncols)
+				.map(|bytes| f64::from_le_bytes(bytes.try_into().unwrap()))
+				.collect();
+			Matrix::new(m.nrows, m.ncols, data)
+		}
+	}
+}
+
+#[cfg(not(feature = "gpu"))]
+mod device {
+	use {
+		super::{GpuError, Kernel},
+		crate::matrix::Matrix,
+	};
+
+	pub fn init() -> Result<String, GpuError> {
+		Err(GpuError::Unsupported)
+	}
+
+	pub fn matmul(_a: &Matrix, _b: &Matrix) -> Option<Matrix> {
+		None
+	}
+
+	pub fn elementwise(_kernel: Kernel, _a: &Matrix, _b: &Matrix) -> Option<Matrix> {
+		None
+	}
+
+	pub fn linear_combination(_terms: &[(f64, &Matrix)]) -> Option<Matrix> {
+		None
+	}
+}
+
+////////////////////////////////
+//       Error Handling       //
+////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub enum GpuError {
+	#[cfg(not(feature = "gpu"))]
+	Unsupported,
+	#[cfg(feature = "gpu")]
+	NoAdapter,
+	#[cfg(feature = "gpu")]
+	NoF64(String),
+	#[cfg(feature = "gpu")]
+	Device(String),
+}
+
+impl std::error::Error for GpuError {}
+impl std::fmt::Display for GpuError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			#[cfg(not(feature = "gpu"))]
+			Self::Unsupported => write!(
+				f,
+				"{}",
+				tr("The GPU backend needs nam built with the 'gpu' feature")
+			),
+			#[cfg(feature = "gpu")]
+			Self::NoAdapter => write!(f, "{}", tr("No GPU was found")),
+			#[cfg(feature = "gpu")]
+			Self::NoF64(name) => write!(
+				f,
+				"{}",
+				tr_with("The GPU {0} does not support 64-bit floats", &[name])
+			),
+			#[cfg(feature = "gpu")]
+			Self::Device(message) => {
+				write!(f, "{}", tr_with("Could not use the GPU: {0}", &[message]))
+			},
+		}
+	}
+}
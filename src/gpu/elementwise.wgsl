@@ -0,0 +1,59 @@
+// res = a op b element by element, broadcasting dimensions of 1 like
+// `Matrix::broadcast`. The operations are numbered like `gpu::Kernel`.
+
+struct Params {
+	nrows: u32,
+	ncols: u32,
+	a_rows: u32,
+	a_cols: u32,
+	b_rows: u32,
+	b_cols: u32,
+	op: u32,
+	_pad: u32,
+	coef_a: f64,
+	coef_b: f64,
+}
+
+@group(0) @binding(0) var<storage, read> a: array<f64>;
+@group(0) @binding(1) var<storage, read> b: array<f64>;
+@group(0) @binding(2) var<storage, read_write> res: array<f64>;
+@group(0) @binding(3) var<uniform> params: Params;
+
+const WORKGROUP_SIZE: u32 = 256u;
+
+fn truth(x: bool) -> f64 {
+	return select(0.0lf, 1.0lf, x);
+}
+
+@compute @workgroup_size(256)
+fn main(
+	@builtin(global_invocation_id) gid: vec3<u32>,
+	@builtin(num_workgroups) groups: vec3<u32>,
+) {
+	let k = gid.x + gid.y * groups.x * WORKGROUP_SIZE;
+	if (k >= params.nrows * params.ncols) {
+		return;
+	}
+	let i = k / params.ncols;
+	let j = k % params.ncols;
+	let x = a[min(i, params.a_rows - 1u) * params.a_cols + min(j, params.a_cols - 1u)];
+	let y = b[min(i, params.b_rows - 1u) * params.b_cols + min(j, params.b_cols - 1u)];
+
+	var r: f64;
+	switch params.op {
+		case 0u: { r = x + y; }
+		case 1u: { r = x - y; }
+		case 2u: { r = x * y; }
+		case 3u: { r = truth(x == y); }
+		case 4u: { r = truth(x != y); }
+		case 5u: { r = truth(x < y); }
+		case 6u: { r = truth(x > y); }
+		case 7u: { r = truth(x <= y); }
+		case 8u: { r = truth(x >= y); }
+		case 9u: { r = truth(x != 0.0lf && y != 0.0lf); }
+		case 10u: { r = truth(x != 0.0lf || y != 0.0lf); }
+		case 11u: { r = params.coef_a * x; }
+		default: { r = x + params.coef_b * y; }
+	}
+	res[k] = r;
+}
@@ -0,0 +1,55 @@
+// C = A * B for row-major A (m x k) and B (k x n), one 16x16 tile of C per
+// workgroup, reading A and B through tiles in workgroup memory
+
+struct Dims {
+	m: u32,
+	k: u32,
+	n: u32,
+	_pad: u32,
+}
+
+@group(0) @binding(0) var<storage, read> a: array<f64>;
+@group(0) @binding(1) var<storage, read> b: array<f64>;
+@group(0) @binding(2) var<storage, read_write> c: array<f64>;
+@group(0) @binding(3) var<uniform> dims: Dims;
+
+const TILE: u32 = 16u;
+
+var<workgroup> tile_a: array<array<f64, 16>, 16>;
+var<workgroup> tile_b: array<array<f64, 16>, 16>;
+
+@compute @workgroup_size(16, 16)
+fn main(
+	@builtin(global_invocation_id) gid: vec3<u32>,
+	@builtin(local_invocation_id) lid: vec3<u32>,
+) {
+	let row = gid.y;
+	let col = gid.x;
+
+	var acc = 0.0lf;
+	let tiles = (dims.k + TILE - 1u) / TILE;
+	for (var t = 0u; t < tiles; t++) {
+		let ka = t * TILE + lid.x;
+		let kb = t * TILE + lid.y;
+		if (row < dims.m && ka < dims.k) {
+			tile_a[lid.y][lid.x] = a[row * dims.k + ka];
+		} else {
+			tile_a[lid.y][lid.x] = 0.0lf;
+		}
+		if (kb < dims.k && col < dims.n) {
+			tile_b[lid.y][lid.x] = b[kb * dims.n + col];
+		} else {
+			tile_b[lid.y][lid.x] = 0.0lf;
+		}
+		workgroupBarrier();
+
+		for (var i = 0u; i < TILE; i++) {
+			acc += tile_a[lid.y][i] * tile_b[i][lid.x];
+		}
+		workgroupBarrier();
+	}
+
+	if (row < dims.m && col < dims.n) {
+		c[row * dims.n + col] = acc;
+	}
+}
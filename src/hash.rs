@@ -0,0 +1,186 @@
+use crate::{
+	function::Function,
+	matrix::{Labels, Matrix},
+	state::RuntimeVal,
+	table::Table,
+};
+
+/*
+	Hashes of values that only depend on their content, the same on every run and
+	platform unlike the hasher of HashMap, so they can be kept in files and compared
+	across sessions. They tell whether the inputs of a cached call changed, which
+	variables differ between two saved workspaces, and which values a save can store once.
+
+	Numbers are hashed by their bits, so 0 and -0 differ, except that every NaN
+	hashes the same. Equal hashes are very likely, but not certainly, equal values,
+	`same_content` tells for sure by comparing what was hashed.
+*/
+
+/// 64-bit FNV-1a.
+#[derive(Debug, Clone)]
+pub struct StableHasher {
+	state: u64,
+
+	// The bytes hashed, only kept to compare values
+	written: Option<Vec<u8>>,
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+impl StableHasher {
+	pub fn new() -> Self {
+		Self {
+			state: FNV_OFFSET,
+			written: None,
+		}
+	}
+
+	fn recording() -> Self {
+		Self {
+			written: Some(vec![]),
+			..Self::new()
+		}
+	}
+
+	pub fn write(&mut self, bytes: &[u8]) {
+		if let Some(written) = &mut self.written {
+			written.extend_from_slice(bytes);
+		}
+		for &byte in bytes {
+			self.state ^= byte as u64;
+			self.state = self.state.wrapping_mul(FNV_PRIME);
+		}
+	}
+
+	pub fn write_u8(&mut self, n: u8) {
+		self.write(&[n]);
+	}
+
+	pub fn write_len(&mut self, len: usize) {
+		self.write(&(len as u64).to_le_bytes());
+	}
+
+	pub fn write_f64(&mut self, x: f64) {
+		let x = if x.is_nan() { f64::NAN } else { x };
+		self.write(&x.to_bits().to_le_bytes());
+	}
+
+	pub fn write_str(&mut self, s: &str) {
+		self.write_len(s.len());
+		self.write(s.as_bytes());
+	}
+
+	pub fn finish(&self) -> u64 {
+		self.state
+	}
+}
+
+impl Default for StableHasher {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+pub trait ContentHash {
+	fn hash_content(&self, hasher: &mut StableHasher);
+
+	fn content_hash(&self) -> u64 {
+		let mut hasher = StableHasher::new();
+		self.hash_content(&mut hasher);
+		hasher.finish()
+	}
+
+	/// Whether the two values hash the same because they have the same content,
+	/// and not by a collision.
+	fn same_content(&self, other: &Self) -> bool {
+		let written = |value: &Self| {
+			let mut hasher = StableHasher::recording();
+			value.hash_content(&mut hasher);
+			hasher.written
+		};
+		written(self) == written(other)
+	}
+}
+
+// Each kind of value starts with its own tag, so a number and
+// a 1x1 matrix or a name and a string holding it differ
+impl ContentHash for RuntimeVal {
+	fn hash_content(&self, hasher: &mut StableHasher) {
+		match self {
+			Self::Number(n) => {
+				hasher.write_u8(0);
+				hasher.write_f64(*n);
+			},
+			Self::Matrix(m) => {
+				hasher.write_u8(1);
+				m.hash_content(hasher);
+			},
+			Self::String(s) => {
+				hasher.write_u8(2);
+				hasher.write_str(s);
+			},
+			Self::Table(t) => {
+				hasher.write_u8(3);
+				t.hash_content(hasher);
+			},
+			Self::BigInt(n) => {
+				hasher.write_u8(4);
+				hasher.write_str(&n.to_string());
+			},
+			Self::Function(function) => {
+				hasher.write_u8(5);
+				function.hash_content(hasher);
+			},
+		}
+	}
+}
+
+impl ContentHash for Matrix {
+	fn hash_content(&self, hasher: &mut StableHasher) {
+		hasher.write_len(self.nrows());
+		hasher.write_len(self.ncols());
+		for &x in self.as_slice() {
+			hasher.write_f64(x);
+		}
+
+		let no_names = Labels::default();
+		let labels = self.labels().unwrap_or(&no_names);
+		for names in [&labels.rows, &labels.cols] {
+			let names = names.as_deref().unwrap_or_default();
+			hasher.write_len(names.len());
+			for name in names {
+				hasher.write_str(name);
+			}
+		}
+	}
+}
+
+impl ContentHash for Table {
+	fn hash_content(&self, hasher: &mut StableHasher) {
+		hasher.write_len(self.names().len());
+		for name in self.names() {
+			hasher.write_str(name);
+		}
+		self.data().hash_content(hasher);
+	}
+}
+
+impl ContentHash for Function {
+	fn hash_content(&self, hasher: &mut StableHasher) {
+		match self {
+			Self::Named(name) => {
+				hasher.write_u8(0);
+				hasher.write_str(name);
+			},
+			Self::Bound { function, args } => {
+				hasher.write_u8(1);
+				function.hash_content(hasher);
+				hasher.write_len(args.len());
+				for arg in args {
+					arg.hash_content(hasher);
+				}
+			},
+		}
+	}
+}
@@ -0,0 +1,107 @@
+mod de;
+
+use std::{
+	collections::HashMap,
+	fmt::Display,
+	sync::{
+		atomic::{AtomicU8, Ordering},
+		OnceLock,
+	},
+};
+
+/*
+	User-facing text is written in English in the code and looked up in the
+	catalog of the current language, like gettext: the English text is the key,
+	and a message missing from a catalog is shown in English. Placeholders are
+	written `{0}`, `{1}`, ... so a translation can reorder them.
+	The language is chosen with `nam --lang <code>` or `:lang` in the configuration file.
+*/
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Locale {
+	#[default]
+	English,
+	German,
+}
+
+impl Locale {
+	pub const ALL: &'static [Self] = &[Self::English, Self::German];
+
+	pub fn code(self) -> &'static str {
+		match self {
+			Self::English => "en",
+			Self::German => "de",
+		}
+	}
+
+	pub fn from_code(code: &str) -> Option<Self> {
+		Self::ALL
+			.iter()
+			.copied()
+			.find(|locale| locale.code() == code)
+	}
+
+	fn catalog(self) -> Option<&'static HashMap<&'static str, &'static str>> {
+		static GERMAN: OnceLock<HashMap<&str, &str>> = OnceLock::new();
+
+		match self {
+			Self::English => None,
+			Self::German => Some(GERMAN.get_or_init(|| de::MESSAGES.iter().copied().collect())),
+		}
+	}
+}
+
+impl std::fmt::Display for Locale {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.code())
+	}
+}
+
+// The language is the same for every workspace of the process
+static LOCALE: AtomicU8 = AtomicU8::new(0);
+
+pub fn locale() -> Locale {
+	Locale::ALL[LOCALE.load(Ordering::Relaxed) as usize]
+}
+
+pub fn set_locale(locale: Locale) {
+	let index = Locale::ALL.iter().position(|&l| l == locale).unwrap();
+	LOCALE.store(index as u8, Ordering::Relaxed);
+}
+
+/// The text of a message in the current language.
+pub fn tr(message: &'static str) -> &'static str {
+	locale()
+		.catalog()
+		.and_then(|catalog| catalog.get(message).copied())
+		.unwrap_or(message)
+}
+
+/// The text of a message in the current language with its placeholders
+/// `{0}`, `{1}`, ... replaced by the arguments.
+pub fn tr_with(message: &'static str, args: &[&dyn Display]) -> String {
+	let text = tr(message);
+
+	let mut res = String::with_capacity(text.len());
+	let mut rest = text;
+	while let Some(start) = rest.find('{') {
+		res.push_str(&rest[..start]);
+		rest = &rest[start..];
+
+		let placeholder = rest
+			.find('}')
+			.and_then(|end| Some((end, rest[1..end].parse::<usize>().ok()?)));
+		match placeholder.and_then(|(end, i)| Some((end, args.get(i)?))) {
+			Some((end, arg)) => {
+				res.push_str(&arg.to_string());
+				rest = &rest[end + 1..];
+			},
+			None => {
+				res.push('{');
+				rest = &rest[1..];
+			},
+		}
+	}
+	res.push_str(rest);
+	res
+}
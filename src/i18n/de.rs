@@ -0,0 +1,315 @@
+// German messages, keyed by the English text. Commands, settings,
+// their values and the names of builtins are not translated.
+
+pub const MESSAGES: &[(&str, &str)] = &[
+	// Evaluation
+	("Variable {0} does not exist", "Die Variable {0} existiert nicht"),
+	("Function {0} does not exist", "Die Funktion {0} existiert nicht"),
+	("Some value was used as a number while it is not", "Ein Wert wurde als Zahl verwendet, ist aber keine"),
+	("Function {0} does not return a value", "Die Funktion {0} gibt keinen Wert zurück"),
+	("Expected {0} value(s) on the right hand side, found {1}", "Auf der rechten Seite wurden {0} Wert(e) erwartet, gefunden: {1}"),
+	("Field {0} does not exist", "Das Feld {0} existiert nicht"),
+	("Only tables have fields", "Nur Tabellen haben Felder"),
+	("A lone ':' can only be used as an index", "Ein alleinstehendes ':' ist nur als Index erlaubt"),
+	("'end' can only be used in the index of a variable", "'end' ist nur im Index einer Variablen erlaubt"),
+	("The named argument {0} can only be passed to a builtin", "Das benannte Argument {0} kann nur an eine eingebaute Funktion übergeben werden"),
+	("Index {0} is not a positive integer", "Der Index {0} ist keine positive ganze Zahl"),
+	("Index {0} out of bounds, the dimension is {1}", "Der Index {0} liegt außerhalb der Grenzen, die Dimension ist {1}"),
+	("The assignment would grow the matrix to {0}x{1}, more than {2} elements", "Die Zuweisung würde die Matrix auf {0}x{1} vergrößern, mehr als {2} Elemente"),
+	("Matrix is singular to machine precision", "Die Matrix ist im Rahmen der Maschinengenauigkeit singulär"),
+	("The power {0} of the matrix is not real: it has an eigenvalue that is negative or 0", "Die Potenz {0} der Matrix ist nicht reell: sie hat einen Eigenwert, der negativ oder 0 ist"),
+	("{0} produced {1} (strict mode)", "{0} ergab {1} (strikter Modus)"),
+	("Wrong number of subscripts ({0})", "Falsche Anzahl von Indizes ({0})"),
+	("Only whole rows or columns can be deleted, like A(2, :) = []", "Nur ganze Zeilen oder Spalten können gelöscht werden, wie A(2, :) = []"),
+	("NaN is neither true nor false", "NaN ist weder wahr noch falsch"),
+	("The operands of {0} have to be scalars, {1} works element by element", "Die Operanden von {0} müssen Skalare sein, {1} arbeitet elementweise"),
+	("The condition of {0} has to be a scalar, like all(x) or any(x) for a matrix", "Die Bedingung von {0} muss ein Skalar sein, wie all(x) oder any(x) für eine Matrix"),
+	("Interrupted", "Unterbrochen"),
+	("The loop was stopped after {0} iterations, the limit is changed with :set iterations", "Die Schleife wurde nach {0} Iterationen angehalten, die Grenze wird mit :set iterations geändert"),
+	("The aliases were stopped after {0} nested calls, a recursive alias needs a condition to end", "Die Aliase wurden nach {0} verschachtelten Aufrufen angehalten, ein rekursiver Alias braucht eine Bedingung zum Beenden"),
+	("{0} expects {1}, got {2} argument", "{0} erwartet {1}, erhielt {2} Argument"),
+	("{0} expects {1}, got {2} arguments", "{0} erwartet {1}, erhielt {2} Argumente"),
+	("{0} expects {1} as argument {2}, got {3}", "{0} erwartet {1} als Argument {2}, erhalten: {3}"),
+	("Invalid arguments to {0}: {1}", "Ungültige Argumente für {0}: {1}"),
+	// Shapes
+	("a {0}x{1} matrix", "{0}x{1}-Matrix"),
+	("they have {0} and {1} rows", "sie haben {0} und {1} Zeilen"),
+	("they have {0} and {1} columns", "sie haben {0} und {1} Spalten"),
+	("Cannot add {0} and {1}: {2}", "Addition von {0} und {1} nicht möglich: {2}"),
+	("Cannot subtract {1} from {0}: {2}", "Subtraktion von {1} von {0} nicht möglich: {2}"),
+	("Cannot compare {0} with {1}: {2}", "Vergleich von {0} mit {1} nicht möglich: {2}"),
+	("Cannot combine {0} with {1}: {2}", "Verknüpfung von {0} mit {1} nicht möglich: {2}"),
+	("Cannot multiply {0} by {1}: inner dimensions {2} and {3} differ", "Multiplikation von {0} mit {1} nicht möglich: die inneren Dimensionen {2} und {3} sind verschieden"),
+	("Cannot divide {0} by {1}: the divisor is not square", "Division von {0} durch {1} nicht möglich: der Divisor ist nicht quadratisch"),
+	("Cannot divide {0} by {1}: they have {2} and {3} columns", "Division von {0} durch {1} nicht möglich: sie haben {2} und {3} Spalten"),
+	("Cannot put {0} next to {1}: they have {2} and {3} rows", "Anordnung von {0} neben {1} nicht möglich: sie haben {2} und {3} Zeilen"),
+	("Cannot put {0} above {1}: they have {2} and {3} columns", "Anordnung von {0} über {1} nicht möglich: sie haben {2} und {3} Spalten"),
+	("Cannot assign {0} to {1}x{2} selected elements", "Zuweisung von {0} an {1}x{2} ausgewählte Elemente nicht möglich"),
+	("Cannot raise {0} to a power: it is not square", "Potenzieren von {0} nicht möglich: die Matrix ist nicht quadratisch"),
+	("Cannot raise {0} to {1}: the exponent is not a scalar", "Potenzieren von {0} mit {1} nicht möglich: der Exponent ist kein Skalar"),
+	// Arguments
+	("any value", "einen beliebigen Wert"),
+	("a number", "eine Zahl"),
+	("an integer", "eine ganze Zahl"),
+	("a string", "eine Zeichenkette"),
+	("a matrix", "eine Matrix"),
+	("a vector", "einen Vektor"),
+	("a square matrix", "eine quadratische Matrix"),
+	("a table", "eine Tabelle"),
+	("a function", "eine Funktion"),
+	("number", "Zahl"),
+	("integer", "Ganzzahl"),
+	("string", "Zeichenketten"),
+	("matrix", "Matrix"),
+	("vector", "Vektor"),
+	("square matrix", "Quadratmatrix"),
+	("table", "Tabellen"),
+	("function", "Funktions"),
+	("the number {0}", "die Zahl {0}"),
+	("the integer {0}", "die ganze Zahl {0}"),
+	("the function {0}", "die Funktion {0}"),
+	("at least {0} argument", "mindestens {0} Argument"),
+	("at least {0} arguments", "mindestens {0} Argumente"),
+	("{0} to {1} arguments", "{0} bis {1} Argumente"),
+	("{0} {1} argument", "{0} {1}-Argument"),
+	("{0} {1} arguments", "{0} {1}-Argumente"),
+	("{0} argument", "{0} Argument"),
+	("{0} arguments", "{0} Argumente"),
+	// Builtins
+	("expected a string", "erwartet wurde eine Zeichenkette"),
+	("expected a number", "erwartet wurde eine Zahl"),
+	("expected a matrix", "erwartet wurde eine Matrix"),
+	("expected a table", "erwartet wurde eine Tabelle"),
+	("expected a table or a matrix", "erwartet wurde eine Tabelle oder eine Matrix"),
+	("unknown named argument '{0}'", "unbekanntes benanntes Argument '{0}'"),
+	("'{0}' is given both by position and by name", "'{0}' wird sowohl per Position als auch per Name übergeben"),
+	("'{0}' is given twice", "'{0}' wird zweimal übergeben"),
+	("expected {0} positional argument(s) before the named ones", "erwartet wurden {0} Positionsargument(e) vor den benannten"),
+	("expected {0} argument(s), got {1}", "erwartet wurden {0} Argument(e), erhalten: {1}"),
+	("expected a non-negative tolerance, got {0}", "erwartet wurde eine nicht negative Toleranz, erhalten: {0}"),
+	("expected a positive tolerance, got {0}", "erwartet wurde eine positive Toleranz, erhalten: {0}"),
+	("expected a {0}x{1} matrix, got {2}x{3}", "erwartet wurde eine {0}x{1}-Matrix, erhalten: {2}x{3}"),
+	("{0} of {1} elements differ by more than {2}, first at ({3}, {4}): {5} instead of {6}, the largest error is {7}", "{0} von {1} Elementen weichen um mehr als {2} ab, zuerst bei ({3}, {4}): {5} statt {6}, der größte Fehler ist {7}"),
+	("expected a non-negative number of rows and columns, got {0}", "erwartet wurde eine nicht negative Anzahl von Zeilen und Spalten, erhalten: {0}"),
+	("expected a {0}x{1} value, got {2}x{3}", "erwartet wurde ein {0}x{1}-Wert, erhalten: {2}x{3}"),
+	("too many channels ({0})", "zu viele Kanäle ({0})"),
+	("invalid sample rate {0}", "ungültige Abtastrate {0}"),
+	("expected at least one sample", "erwartet wurde mindestens ein Abtastwert"),
+	("too many samples for a WAV file ({0})", "zu viele Abtastwerte für eine WAV-Datei ({0})"),
+	("expected a positive integer index, got {0}", "erwartet wurde ein positiver ganzzahliger Index, erhalten: {0}"),
+	("expected a non-negative integer size, got {0}", "erwartet wurde eine nicht negative ganzzahlige Größe, erhalten: {0}"),
+	("a {0}x{1} block at ({2}, {3}) does not fit in a {4}x{5} matrix", "ein {0}x{1}-Block bei ({2}, {3}) passt nicht in eine {4}x{5}-Matrix"),
+	("the format must be \"tsv\" or \"csv\", got \"{0}\"", "das Format muss \"tsv\" oder \"csv\" sein, erhalten: \"{0}\""),
+	("expected a permutation of 1 to {0}", "erwartet wurde eine Permutation von 1 bis {0}"),
+	("expected a non-negative integer, got {0}", "erwartet wurde eine nicht negative ganze Zahl, erhalten: {0}"),
+	("{0} is too large to compute exactly", "{0} ist zu groß, um exakt berechnet zu werden"),
+	("too many rows for a set of {0} elements", "zu viele Zeilen für eine Menge von {0} Elementen"),
+	("expected a nxn A, a nx1 B, a 1xn C and a scalar D, with n = {0}", "erwartet wurden eine nxn-Matrix A, eine nx1-Matrix B, eine 1xn-Matrix C und ein Skalar D, mit n = {0}"),
+	("the denominator is zero", "der Nenner ist null"),
+	("the numerator has a higher degree than the denominator", "der Zähler hat einen höheren Grad als der Nenner"),
+	("expected a nxn A and a nxm B", "erwartet wurden eine nxn-Matrix A und eine nxm-Matrix B"),
+	("expected a nxn A and a pxn C", "erwartet wurden eine nxn-Matrix A und eine pxn-Matrix C"),
+	("expected two square matrices of the same size", "erwartet wurden zwei quadratische Matrizen gleicher Größe"),
+	("no unique solution, A and -A' share an eigenvalue", "keine eindeutige Lösung, A und -A' haben einen gemeinsamen Eigenwert"),
+	("the eigenvalues did not converge", "die Eigenwerte sind nicht konvergiert"),
+	("invalid chunk size {0}", "ungültige Blockgröße {0}"),
+	("unknown reduction '{0}'", "unbekannte Reduktion '{0}'"),
+	("expected a function or the name of a reduction", "erwartet wurde eine Funktion oder der Name einer Reduktion"),
+	("{0} returned no matrix", "{0} hat keine Matrix zurückgegeben"),
+	("{0} returned a {1}x{2} matrix for {3} columns, expected a row", "{0} hat eine {1}x{2}-Matrix für {3} Spalten zurückgegeben, erwartet wurde eine Zeile"),
+	("{0} returned no value", "{0} hat keinen Wert zurückgegeben"),
+	("{0} returned a non scalar for element {1}", "{0} hat für Element {1} keinen Skalar zurückgegeben"),
+	("{0} returned {1} elements, expected {2}", "{0} hat {1} Elemente zurückgegeben, erwartet wurden {2}"),
+	("the delimiter must be a single character, got \"{0}\"", "das Trennzeichen muss ein einzelnes Zeichen sein, erhalten: \"{0}\""),
+	("unknown format \"{0}\", expected \"text\" or \"base64\"", "unbekanntes Format \"{0}\", erwartet wurde \"text\" oder \"base64\""),
+	("invalid base64", "ungültiges Base64"),
+	("the string holds no matrix", "die Zeichenkette enthält keine Matrix"),
+	("expected a matrix of numbers", "erwartet wurde eine Matrix aus Zahlen"),
+	("the rows do not have the same length", "die Zeilen haben nicht die gleiche Länge"),
+	("expected a target node after the source node", "erwartet wurde ein Zielknoten nach dem Startknoten"),
+	("edge weights must not be negative", "Kantengewichte dürfen nicht negativ sein"),
+	("expected a square adjacency matrix, got {0}x{1}", "erwartet wurde eine quadratische Adjazenzmatrix, erhalten: {0}x{1}"),
+	("{0} is not a node of a graph with {1} nodes", "{0} ist kein Knoten eines Graphen mit {1} Knoten"),
+	("expected a positive number of bins, got {0}", "erwartet wurde eine positive Anzahl von Klassen, erhalten: {0}"),
+	("expected increasing bin edges", "erwartet wurden aufsteigende Klassengrenzen"),
+	("unknown option \"{0}\", expected \"plot\"", "unbekannte Option \"{0}\", erwartet wurde \"plot\""),
+	("the image has {0} channel(s), got channel {1}", "das Bild hat {0} Kanal/Kanäle, angefordert wurde Kanal {1}"),
+	("cannot write an empty image", "ein leeres Bild kann nicht geschrieben werden"),
+	("{0} is {1}x{2}, expected {3}x{3} for b of length {3}", "{0} ist {1}x{2}, erwartet wurde {3}x{3} für b der Länge {3}"),
+	("the preconditioner is singular", "der Vorkonditionierer ist singulär"),
+	("expected a non-negative {0}, got {1}", "erwartet wurde ein nicht negativer Wert für {0}, erhalten: {1}"),
+	("expected a positive {0}, got {1}", "erwartet wurde ein positiver Wert für {0}, erhalten: {1}"),
+	("factor", "Faktor"),
+	("window length", "Fensterlänge"),
+	("there is no job {0}", "es gibt keinen Auftrag {0}"),
+	("job {0} failed: {1}", "Auftrag {0} ist fehlgeschlagen: {1}"),
+	("job {0} panicked", "Auftrag {0} ist abgestürzt"),
+	("expected 0 to {0} refinement passes, got {1}", "erwartet wurden 0 bis {0} Nachiterationen, erhalten: {1}"),
+	("expected a square A and b with as many rows, got {0}x{1} and {2}x{3}", "erwartet wurden eine quadratische Matrix A und b mit gleich vielen Zeilen, erhalten: {0}x{1} und {2}x{3}"),
+	("the matrix is singular", "die Matrix ist singulär"),
+	("expected a square matrix whose rows are probabilities summing to 1", "erwartet wurde eine quadratische Matrix, deren Zeilen Wahrscheinlichkeiten mit der Summe 1 sind"),
+	("the chain has more than one stationary distribution", "die Kette hat mehr als eine stationäre Verteilung"),
+	("expected a square matrix, got {0}x{1}", "erwartet wurde eine quadratische Matrix, erhalten: {0}x{1}"),
+	("expected an integer power, got {0}", "erwartet wurde eine ganzzahlige Potenz, erhalten: {0}"),
+	("the method \"constant\" expects the value to fill with", "die Methode \"constant\" erwartet den Füllwert"),
+	("the method \"{0}\" takes no value", "die Methode \"{0}\" nimmt keinen Wert an"),
+	("unknown method \"{0}\", expected \"previous\", \"next\", \"nearest\", \"linear\" or \"constant\"", "unbekannte Methode \"{0}\", erwartet wurde \"previous\", \"next\", \"nearest\", \"linear\" oder \"constant\""),
+	("A is {0}x{1}, expected {2}x{3} for {4} costs and {5} bounds", "A ist {0}x{1}, erwartet wurde {2}x{3} für {4} Kosten und {5} Schranken"),
+	("row {0}: {1}", "Zeile {0}: {1}"),
+	("row {0}: the result is not a number or a row", "Zeile {0}: das Ergebnis ist weder eine Zahl noch eine Zeile"),
+	("the results have different lengths ({0} vs {1})", "die Ergebnisse haben unterschiedliche Längen ({0} gegenüber {1})"),
+	("expected non-empty vectors", "erwartet wurden nicht leere Vektoren"),
+	("cannot take {0} elements from a permutation of {1}", "aus einer Permutation von {1} können nicht {0} Elemente genommen werden"),
+	("cannot sample from an empty set", "aus einer leeren Menge kann keine Stichprobe gezogen werden"),
+	("cannot draw {0} elements from {1} without replacement", "aus {1} können nicht {0} Elemente ohne Zurücklegen gezogen werden"),
+	("expected a finite condition number of at least 1, got {0}", "erwartet wurde eine endliche Konditionszahl von mindestens 1, erhalten: {0}"),
+	("expected a density between 0 and 1, got {0}", "erwartet wurde eine Dichte zwischen 0 und 1, erhalten: {0}"),
+	("expected a non-negative size, got {0}", "erwartet wurde eine nicht negative Größe, erhalten: {0}"),
+	("a {0}x{1} matrix is too large", "eine {0}x{1}-Matrix ist zu groß"),
+	("unknown aggregation '{0}'", "unbekannte Aggregation '{0}'"),
+	("the table has no column named '{0}'", "die Tabelle hat keine Spalte namens '{0}'"),
+	("{0} row and {1} column names for a {2}x{3} matrix", "{0} Zeilen- und {1} Spaltennamen für eine {2}x{3}-Matrix"),
+	("expected a quaternion of 4 elements, got {0}", "erwartet wurde ein Quaternion aus 4 Elementen, erhalten: {0}"),
+	("the quaternion has no direction", "das Quaternion hat keine Richtung"),
+	("expected a 3x3 rotation, got {0}x{1}", "erwartet wurde eine 3x3-Rotation, erhalten: {0}x{1}"),
+	// Warnings
+	("Matrix is close to singular, results may be inaccurate (rcond = {0})", "Die Matrix ist fast singulär, die Ergebnisse können ungenau sein (rcond = {0})"),
+	("The left operand was transposed to fit the operation", "Der linke Operand wurde für die Operation transponiert"),
+	("The right operand was transposed to fit the operation", "Der rechte Operand wurde für die Operation transponiert"),
+	// Lexer and parser
+	("Unexpected empty string", "Unerwartete leere Zeichenkette"),
+	("Could not parse as number", "Konnte nicht als Zahl gelesen werden"),
+	("Unexpected character '{0}'", "Unerwartetes Zeichen '{0}'"),
+	("Unsupported syntax '{0}'", "Nicht unterstützte Syntax '{0}'"),
+	("Missing the closing '\"' of a string literal", "Das schließende '\"' einer Zeichenkette fehlt"),
+	("Missing the closing '%}' of a block comment", "Das schließende '%}' eines Blockkommentars fehlt"),
+	("{0} at column {1}", "{0} in Spalte {1}"),
+	("{0} at line {1}, column {2}", "{0} in Zeile {1}, Spalte {2}"),
+	("Error found in string '{0}'", "Fehler in der Zeichenkette '{0}'"),
+	("Empty matrix elements are not allowed", "Leere Matrixelemente sind nicht erlaubt"),
+	("Only a variable, its elements, or a row of variable names can be assigned to", "Zuweisen lässt sich nur an eine Variable, ihre Elemente oder eine Zeile von Variablennamen"),
+	("Named arguments must come after the positional ones", "Benannte Argumente müssen nach den positionellen stehen"),
+	("'{0}' without a matching 'if'", "'{0}' ohne zugehöriges 'if'"),
+	("'{0}' without a matching 'end'", "'{0}' ohne zugehöriges 'end'"),
+	("Unexpected end of input tokens array", "Unerwartetes Ende der Eingabe"),
+	("Unexpected token", "Unerwartetes Token"),
+	("{0}, expected '{1}'", "{0}, erwartet: '{1}'"),
+	("{0}, found '{1}'", "{0}, gefunden: '{1}'"),
+	// Settings
+	("There is no setting named '{0}'", "Es gibt keine Einstellung namens '{0}'"),
+	("Invalid value '{0}' for the setting {1}, expected {2}", "Ungültiger Wert '{0}' für die Einstellung {1}, erwartet: {2}"),
+	("point or comma", "point oder comma"),
+	("short, eng, eng-si or frac", "short, eng, eng-si oder frac"),
+	("a number of digits or off", "eine Anzahl von Ziffern oder off"),
+	("a number of elements or off", "eine Anzahl von Elementen oder off"),
+	("a number of iterations or off", "eine Anzahl von Iterationen oder off"),
+	("strict, scalars or permissive", "strict, scalars oder permissive"),
+	("on or off", "on oder off"),
+	// Aliases
+	("Expected 'alias name = definition'", "Erwartet: 'alias name = definition'"),
+	("An alias is named 'name' or 'name(a, b, ...)'", "Ein Alias heißt 'name' oder 'name(a, b, ...)'"),
+	("The alias {0} refers to itself", "Der Alias {0} verweist auf sich selbst"),
+	// Workspaces and the clipboard
+	("The file is not a workspace file", "Die Datei ist keine Arbeitsbereichsdatei"),
+	("Unsupported workspace version {0}", "Nicht unterstützte Arbeitsbereichsversion {0}"),
+	("The workspace is compressed, but nam was built without the 'zstd' feature", "Der Arbeitsbereich ist komprimiert, aber nam wurde ohne das Feature 'zstd' gebaut"),
+	("The file is a saved workspace, not a checkpoint", "Die Datei ist ein gespeicherter Arbeitsbereich, kein Checkpoint"),
+	("The workspace file is corrupted", "Die Arbeitsbereichsdatei ist beschädigt"),
+	("The clipboard needs nam built with the 'clipboard' feature", "Die Zwischenablage benötigt nam mit dem Feature 'clipboard'"),
+	("Functions cannot be copied to the clipboard", "Funktionen können nicht in die Zwischenablage kopiert werden"),
+	("Could not use the clipboard: {0}", "Die Zwischenablage konnte nicht verwendet werden: {0}"),
+	// GPU
+	("The GPU backend needs nam built with the 'gpu' feature", "Das GPU-Backend benötigt nam mit dem Feature 'gpu'"),
+	("No GPU was found", "Es wurde keine GPU gefunden"),
+	("The GPU {0} does not support 64-bit floats", "Die GPU {0} unterstützt keine 64-Bit-Gleitkommazahlen"),
+	("Could not use the GPU: {0}", "Die GPU konnte nicht verwendet werden: {0}"),
+	("Using {0}", "Verwende {0}"),
+	// Command line
+	("--seed expects a non-negative integer", "--seed erwartet eine nichtnegative ganze Zahl"),
+	("--backend expects one of: {0}", "--backend erwartet eines von: {0}"),
+	("--lang expects one of: {0}", "--lang erwartet eines von: {0}"),
+	("Unknown argument '{0}'", "Unbekanntes Argument '{0}'"),
+	("--seed is only used with --deterministic", "--seed wird nur mit --deterministic verwendet"),
+	("JSON output needs nam built with the 'serde' feature", "Die JSON-Ausgabe benötigt nam mit dem Feature 'serde'"),
+	("--cell expects a positive integer", "--cell erwartet eine positive ganze Zahl"),
+	// Scripts
+	("Could not read {0}: {1}", "{0} konnte nicht gelesen werden: {1}"),
+	("There is no cell {0}, the script has {1}", "Es gibt keine Zelle {0}, das Skript hat {1}"),
+	("No script is open, use :cell <path>", "Kein Skript ist geöffnet, :cell <Pfad> öffnet eines"),
+	("line {0}", "Zeile {0}"),
+	("cell {0}", "Zelle {0}"),
+	("FAILED", "FEHLGESCHLAGEN"),
+	("{0} of {1} tests passed", "{0} von {1} Tests bestanden"),
+	// REPL
+	("Usage: {0}", "Verwendung: {0}"),
+	("error: {0}", "Fehler: {0}"),
+	("warning: {0}", "Warnung: {0}"),
+	("Goodbye!", "Auf Wiedersehen!"),
+	("Could not open the history file: {0}", "Die Verlaufsdatei konnte nicht geöffnet werden: {0}"),
+	("Could not read the configuration file: {0}", "Die Konfigurationsdatei konnte nicht gelesen werden: {0}"),
+	("Configuration file, line {0}: {1}", "Konfigurationsdatei, Zeile {0}: {1}"),
+	("{0}, line {1}: {2}", "{0}, Zeile {1}: {2}"),
+	("The previous session did not exit normally, restore its workspace? [y/N] ", "Die vorige Sitzung wurde nicht normal beendet, ihren Arbeitsbereich wiederherstellen? [j/N] "),
+	("y", "j"),
+	("yes", "ja"),
+	("Restored {0} variable(s)", "{0} Variable(n) wiederhergestellt"),
+	("Could not restore the workspace: {0}", "Der Arbeitsbereich konnte nicht wiederhergestellt werden: {0}"),
+	("Could not write the report: {0}", "Der Bericht konnte nicht geschrieben werden: {0}"),
+	("Could not autosave the workspace: {0}", "Der Arbeitsbereich konnte nicht automatisch gespeichert werden: {0}"),
+	("Could not remove the autosaved workspace: {0}", "Der automatisch gespeicherte Arbeitsbereich konnte nicht entfernt werden: {0}"),
+	("Unknown command ':{0}', type :help to list the commands", "Unbekannter Befehl ':{0}', :help listet die Befehle auf"),
+	("Unknown command ':{0}'", "Unbekannter Befehl ':{0}'"),
+	("{0} is not available in deterministic mode", "{0} ist im deterministischen Modus nicht verfügbar"),
+	("No variable named '{0}'", "Keine Variable namens '{0}'"),
+	("Saved {0} variable(s) to '{1}'", "{0} Variable(n) in '{1}' gespeichert"),
+	("Loaded {0} variable(s) from '{1}'", "{0} Variable(n) aus '{1}' geladen"),
+	("The workspaces hold the same variables", "Die Arbeitsbereiche enthalten dieselben Variablen"),
+	("No workspace was saved yet", "Es wurde noch kein Arbeitsbereich gespeichert"),
+	("Reloaded the configuration file", "Die Konfigurationsdatei wurde neu geladen"),
+	("No jobs", "Keine Jobs"),
+	("done", "fertig"),
+	("running", "läuft"),
+	("job {0}: {1} ({2})", "Job {0}: {1} ({2})"),
+	("No aliases", "Keine Aliase"),
+	("Could not save the alias to the configuration file: {0}", "Der Alias konnte nicht in der Konfigurationsdatei gespeichert werden: {0}"),
+	("No alias named '{0}'", "Kein Alias namens '{0}'"),
+	("Could not remove the alias from the configuration file: {0}", "Der Alias konnte nicht aus der Konfigurationsdatei entfernt werden: {0}"),
+	("Elapsed time: {0}", "Verstrichene Zeit: {0}"),
+	("Could not write the trace to '{0}': {1}", "Die Aufzeichnung konnte nicht nach '{0}' geschrieben werden: {1}"),
+	("Exported the trace to '{0}'", "Die Aufzeichnung wurde nach '{0}' exportiert"),
+	("Could not save the theme to the configuration file: {0}", "Das Farbschema konnte nicht in der Konfigurationsdatei gespeichert werden: {0}"),
+	("Could not save the language to the configuration file: {0}", "Die Sprache konnte nicht in der Konfigurationsdatei gespeichert werden: {0}"),
+	("'{0}' was not assigned an expression to recompute", "'{0}' wurde kein Ausdruck zum Neuberechnen zugewiesen"),
+	("Every variable is up to date", "Alle Variablen sind aktuell"),
+	("There is no result to copy yet", "Es gibt noch kein Ergebnis zum Kopieren"),
+	("Paste the rows, then press Enter on an empty line:", "Die Zeilen einfügen, dann Enter in einer leeren Zeile drücken:"),
+	("The pasted text is not rows of numbers of the same length", "Der eingefügte Text besteht nicht aus gleich langen Zeilen von Zahlen"),
+	("Unknown theme '{0}', expected one of: {1}", "Unbekanntes Farbschema '{0}', erwartet eines von: {1}"),
+	("Unknown element '{0}', expected one of: {1}", "Unbekanntes Element '{0}', erwartet eines von: {1}"),
+	("Invalid style '{0}', expected a color like red, light-blue, #ff8800 or 208, optionally after bold", "Ungültiger Stil '{0}', erwartet eine Farbe wie red, light-blue, #ff8800 oder 208, wahlweise nach bold"),
+	// Help of the commands
+	("Lists the commands, or describes one of them", "Listet die Befehle auf oder beschreibt einen davon"),
+	("Leaves the REPL", "Beendet die REPL"),
+	("Removes the given variables, or all of them", "Entfernt die angegebenen Variablen oder alle"),
+	("Saves every variable to a workspace file", "Speichert alle Variablen in einer Arbeitsbereichsdatei"),
+	("Loads the variables of a workspace file", "Lädt die Variablen einer Arbeitsbereichsdatei"),
+	("Lists the variables added, removed or changed from one workspace file to another, or to the current workspace from a file or the last one saved", "Listet die Variablen auf, die von einer Arbeitsbereichsdatei zur anderen hinzugefügt, entfernt oder geändert wurden, oder zum aktuellen Arbeitsbereich von einer Datei oder der zuletzt gespeicherten"),
+	("Runs the configuration file again, after restoring the default settings and removing the aliases", "Führt die Konfigurationsdatei erneut aus, nachdem die Standardeinstellungen wiederhergestellt und die Aliase entfernt wurden"),
+	("Lists the background jobs started with async", "Listet die mit async gestarteten Hintergrundjobs auf"),
+	("Changes a setting, or shows the settings", "Ändert eine Einstellung oder zeigt die Einstellungen an"),
+	("Prints numbers as short as possible, in engineering notation or as fractions, in scientific notation from n digits, or with thousands separators", "Gibt Zahlen so kurz wie möglich, in technischer Notation oder als Brüche, ab n Ziffern in wissenschaftlicher Notation oder mit Tausendertrennzeichen aus"),
+	("Turns the simplification of statements before evaluating them on or off", "Schaltet die Vereinfachung von Anweisungen vor ihrer Auswertung ein oder aus"),
+	("Shows the intermediate steps of algorithms like lu and rref", "Zeigt die Zwischenschritte von Algorithmen wie lu und rref"),
+	("Runs large matrix products and element by element operations on the GPU (experimental)", "Führt große Matrixprodukte und elementweise Operationen auf der GPU aus (experimentell)"),
+	("Defines and saves an alias like 'sq(x) = x*x', or lists them", "Definiert und speichert einen Alias wie 'sq(x) = x*x' oder listet sie auf"),
+	("Removes an alias", "Entfernt einen Alias"),
+	("Evaluates an expression and prints how long it took", "Wertet einen Ausdruck aus und gibt aus, wie lange es dauerte"),
+	("Places the last result on the clipboard, tab or comma separated", "Legt das letzte Ergebnis tabulator- oder kommagetrennt in die Zwischenablage"),
+	("Reads rows of numbers pasted from a spreadsheet into a matrix, ans by default", "Liest aus einer Tabellenkalkulation eingefügte Zahlenzeilen in eine Matrix, standardmäßig ans"),
+	("Records the time spent in each statement and builtin, prints it, or exports a trace (Chrome tracing for .json, folded stacks otherwise)", "Misst die Zeit jeder Anweisung und eingebauten Funktion, gibt sie aus oder exportiert eine Aufzeichnung (Chrome Tracing für .json, sonst Folded Stacks)"),
+	("Evaluates again the expression last assigned to a variable and the variables computed from it, or every variable computed from one that changed since", "Wertet den zuletzt einer Variablen zugewiesenen Ausdruck und die daraus berechneten Variablen erneut aus, oder jede Variable, die aus einer seitdem geänderten berechnet wurde"),
+	("Opens a script and lists its cells, separated by lines starting with %%, or runs one of them as it is now in the file", "Öffnet ein Skript und listet seine Zellen auf, getrennt durch Zeilen, die mit %% beginnen, oder führt eine davon so aus, wie sie jetzt in der Datei steht"),
+	("Prints the statements evaluated, the errors by kind and the time spent in matrix operations since the start of the session, kept locally", "Gibt die ausgewerteten Anweisungen, die Fehler nach Art und die Zeit in Matrixoperationen seit Beginn der Sitzung aus, nur lokal erfasst"),
+	("Chooses the language of the messages, or shows it", "Wählt die Sprache der Meldungen oder zeigt sie an"),
+	("Chooses the colors of the prompt, errors, warnings and input, or changes one of them (prompt, error, warning, number, string, name, operator)", "Wählt die Farben der Eingabeaufforderung, Fehler, Warnungen und Eingabe oder ändert eine davon (prompt, error, warning, number, string, name, operator)"),
+];
@@ -0,0 +1,80 @@
+use {
+	crate::{
+		ast::ASTNode,
+		eval::EvaluationError,
+		i18n::tr_with,
+		state::{RuntimeVal, State},
+	},
+	std::{
+		collections::BTreeMap,
+		thread::{self, JoinHandle},
+		time::{Duration, Instant},
+	},
+};
+
+/// The stack of the threads evaluating in the background, as large as the one of the
+/// main thread so aliases calling themselves reach the same depth.
+pub const WORKER_STACK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Expressions evaluated in the background by `async(expr)`.
+#[derive(Default)]
+pub struct Jobs {
+	next_id: usize,
+	running: BTreeMap<usize, Job>,
+}
+
+struct Job {
+	started: Instant,
+	handle: JoinHandle<Result<RuntimeVal, EvaluationError>>,
+}
+
+impl Jobs {
+	/// Starts evaluating `ast` on a new thread with its own copy of the workspace,
+	/// returns the id of the job.
+	pub fn spawn(&mut self, mut workspace: State, ast: ASTNode) -> usize {
+		let evaluator = workspace.evaluator();
+		let handle = thread::Builder::new()
+			.stack_size(WORKER_STACK_SIZE)
+			.spawn(move || evaluator.evaluate(ast, &mut workspace))
+			.expect("failed to spawn a job thread");
+
+		self.next_id += 1;
+		self.running.insert(
+			self.next_id,
+			Job {
+				started: Instant::now(),
+				handle,
+			},
+		);
+
+		self.next_id
+	}
+
+	/// Returns `None` if there is no such job.
+	pub fn is_finished(&self, id: usize) -> Option<bool> {
+		self.running.get(&id).map(|job| job.handle.is_finished())
+	}
+
+	/// Removes the job from the list and returns its result, blocking until it is finished,
+	/// returns `None` if there is no such job.
+	pub fn take_result(&mut self, id: usize) -> Option<Result<RuntimeVal, EvaluationError>> {
+		let job = self.running.remove(&id)?;
+
+		Some(
+			job.handle
+				.join()
+				.unwrap_or(Err(EvaluationError::BuiltinFailure(
+					String::from("async"),
+					tr_with("job {0} panicked", &[&id]),
+				))),
+		)
+	}
+
+	/// Lists the jobs by id, whether they are finished and for how long they have been running.
+	pub fn list(&self) -> Vec<(usize, bool, Duration)> {
+		self.running
+			.iter()
+			.map(|(&id, job)| (id, job.handle.is_finished(), job.started.elapsed()))
+			.collect()
+	}
+}
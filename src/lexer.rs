@@ -1,12 +1,27 @@
+use crate::i18n::{tr, tr_with};
 use color_eyre::eyre::Result;
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Token {
-	OpAdd,      // +
-	OpSubtract, // -
-	OpMultiply, // *
-	OpDivide,   // /
-	OpAssign,   // =
+	OpAdd,          // +
+	OpSubtract,     // -
+	OpMultiply,     // *
+	OpDivide,       // /
+	OpPower,        // ^
+	OpAssign,       // =
+	OpEqual,        // ==
+	OpNotEqual,     // ~= or !=
+	OpLess,         // <
+	OpGreater,      // >
+	OpLessEqual,    // <=
+	OpGreaterEqual, // >=
+	OpTranspose,    // ' (postfix)
+	OpNot,          // ~ or !
+	OpAnd,          // &
+	OpOr,           // |
+	OpShortAnd,     // &&
+	OpShortOr,      // ||
 
 	OpenParen,  // )
 	CloseParen, // (
@@ -16,22 +31,52 @@ pub enum Token {
 	CloseCurly, // }
 
 	NumericLiteral(f64),
+	StringLiteral(String),
 	Identifier(String),
 
 	Comma,     // ,
 	SemiColon, // ;
+	Colon,     // :
+	Dot,       // .
+	At,        // @
 	EndOfLine,
 	EndOfFile,
 }
 
 impl Token {
+	/// Whether the token can be the last of an operand, like a number or a closing parenthesis.
+	pub fn ends_value(&self) -> bool {
+		matches!(
+			self,
+			Self::NumericLiteral(_)
+				| Self::StringLiteral(_)
+				| Self::Identifier(_)
+				| Self::CloseParen
+				| Self::CloseBrace
+				| Self::OpTranspose
+		)
+	}
+
 	pub fn stringify(&self) -> String {
 		let res = match self {
 			Self::OpAdd => "OpAdd",
 			Self::OpSubtract => "OpSubstract",
 			Self::OpMultiply => "OpMultiply",
 			Self::OpDivide => "OpDivide",
+			Self::OpPower => "OpPower",
 			Self::OpAssign => "OpAssign",
+			Self::OpEqual => "OpEqual",
+			Self::OpNotEqual => "OpNotEqual",
+			Self::OpLess => "OpLess",
+			Self::OpGreater => "OpGreater",
+			Self::OpLessEqual => "OpLessEqual",
+			Self::OpGreaterEqual => "OpGreaterEqual",
+			Self::OpTranspose => "OpTranspose",
+			Self::OpNot => "OpNot",
+			Self::OpAnd => "OpAnd",
+			Self::OpOr => "OpOr",
+			Self::OpShortAnd => "OpShortAnd",
+			Self::OpShortOr => "OpShortOr",
 
 			Self::OpenParen => "OpenParen",
 			Self::CloseParen => "CloseParen",
@@ -48,6 +93,14 @@ impl Token {
 				}
 			},
 
+			Self::StringLiteral(string) => {
+				if string.is_empty() {
+					"StringLiteral"
+				} else {
+					return format!("StringLiteral: \"{string}\"");
+				}
+			},
+
 			Self::Identifier(name) => {
 				if name.is_empty() {
 					"Identifier"
@@ -58,6 +111,9 @@ impl Token {
 
 			Self::Comma => "Comma",
 			Self::SemiColon => "SemiColon",
+			Self::Colon => "Colon",
+			Self::Dot => "Dot",
+			Self::At => "At",
 			Self::EndOfLine => "EndOfLine",
 			Self::EndOfFile => "EndOfFile",
 		};
@@ -79,6 +135,7 @@ impl std::str::FromStr for Token {
 					kind: TokenizationErrorKind::EmptyString,
 					token_str: Some(input),
 					message: None,
+					position: None,
 				})
 			},
 		};
@@ -88,7 +145,20 @@ impl std::str::FromStr for Token {
 			'-' => Ok(Self::OpSubtract),
 			'*' => Ok(Self::OpMultiply),
 			'/' => Ok(Self::OpDivide),
+			'^' => Ok(Self::OpPower),
+			'=' if input == "==" => Ok(Self::OpEqual),
 			'=' => Ok(Self::OpAssign),
+			'~' | '!' if input.len() == 2 && input.ends_with('=') => Ok(Self::OpNotEqual),
+			'<' if input == "<=" => Ok(Self::OpLessEqual),
+			'<' => Ok(Self::OpLess),
+			'>' if input == ">=" => Ok(Self::OpGreaterEqual),
+			'>' => Ok(Self::OpGreater),
+			'\'' => Ok(Self::OpTranspose),
+			'~' | '!' => Ok(Self::OpNot),
+			'&' if input == "&&" => Ok(Self::OpShortAnd),
+			'&' => Ok(Self::OpAnd),
+			'|' if input == "||" => Ok(Self::OpShortOr),
+			'|' => Ok(Self::OpOr),
 
 			'(' => Ok(Self::OpenParen),
 			')' => Ok(Self::CloseParen),
@@ -103,6 +173,7 @@ impl std::str::FromStr for Token {
 					kind: TokenizationErrorKind::NotANumber,
 					token_str: Some(input),
 					message: Some(e.to_string()),
+					position: None,
 				}),
 			},
 
@@ -113,6 +184,7 @@ impl std::str::FromStr for Token {
 							kind: TokenizationErrorKind::UnexpectedChar(c),
 							token_str: Some(input),
 							message: None,
+							position: None,
 						});
 					}
 				}
@@ -121,11 +193,15 @@ impl std::str::FromStr for Token {
 
 			',' => Ok(Self::Comma),
 			';' => Ok(Self::SemiColon),
+			':' => Ok(Self::Colon),
+			'.' => Ok(Self::Dot),
+			'@' => Ok(Self::At),
 
 			first => Err(TokenizationError {
 				kind: TokenizationErrorKind::UnexpectedChar(first),
 				token_str: Some(input),
 				message: None,
+				position: None,
 			}),
 		}
 	}
@@ -139,9 +215,60 @@ pub fn tokenize(idx: usize, code: &str) -> Vec<Token> {
 	}
 }
 
-pub fn try_tokenize(mut idx: usize, code: &str) -> Result<Vec<Token>, TokenizationError> {
+/// How the source code is read, it follows the settings of the workspace.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LexerConfig {
+	/// Reads `1,5` as 1.5, arguments and matrix elements are then separated
+	/// by a comma followed by a space, like `[1,5, 2]`.
+	pub decimal_comma: bool,
+}
+
+/// Where a token is in the source: its start and end as character offsets,
+/// and the line and column of its start, from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Span {
+	pub start: usize,
+	pub end: usize,
+	pub line: usize,
+	pub column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedToken {
+	pub token: Token,
+	pub span: Span,
+}
+
+#[allow(unused)]
+pub fn try_tokenize(idx: usize, code: &str) -> Result<Vec<Token>, TokenizationError> {
+	try_tokenize_with(idx, code, &LexerConfig::default())
+}
+
+pub fn try_tokenize_with(
+	idx: usize,
+	code: &str,
+	config: &LexerConfig,
+) -> Result<Vec<Token>, TokenizationError> {
+	let tokens = try_tokenize_spanned(idx, code, config)?;
+	Ok(tokens.into_iter().map(|spanned| spanned.token).collect())
+}
+
+/// Tokenizes `code` from the character at `idx`, with where each token is.
+/// Tokens inserted by the lexer, like the comma in `[1 -2]`, span what they replace
+/// and the end of file is an empty span at the end. Errors tell where they happened.
+pub fn try_tokenize_spanned(
+	mut idx: usize,
+	code: &str,
+	config: &LexerConfig,
+) -> Result<Vec<SpannedToken>, TokenizationError> {
+	let lines = Lines::new(code);
 	let mut chars = code.chars().skip(idx).peekable();
 	let mut res = vec![];
+	let mut spans = vec![];
+	let mut token_start = idx;
+
+	// The open brackets and parentheses, a space separates the elements of a matrix
+	let mut nesting = vec![];
 
 	/*
 		Rust borrow checker dows not allow us to use these closures
@@ -154,76 +281,325 @@ pub fn try_tokenize(mut idx: usize, code: &str) -> Result<Vec<Token>, Tokenizati
 		let mut advance_once = || advance(1);
 	*/
 
-	while let Some(first) = chars.peek() {
-		match first {
-			'+' | '-' | '*' | '/' | '(' | ')' | '[' | ']' | '{' | '}' | '=' | ',' | ';' => {
-				idx += 1;
-				res.push(chars.next().unwrap().to_string().parse()?);
-			},
+	// In a closure so errors can be given the position of the token being read
+	let mut read = || -> Result<(), TokenizationError> {
+		while let Some(&first) = chars.peek() {
+			token_start = idx;
+
+			match first {
+				'=' | '~' | '!' | '<' | '>' if chars.clone().nth(1) == Some('=') => {
+					idx += 2;
+					let token: String = chars.by_ref().take(2).collect();
+					res.push(token.parse()?);
+				},
+
+				'&' | '|' if chars.clone().nth(1) == Some(first) => {
+					idx += 2;
+					let token: String = chars.by_ref().take(2).collect();
+					res.push(token.parse()?);
+				},
+
+				'+' | '-' | '*' | '/' | '^' | '(' | ')' | '[' | ']' | '{' | '}' | '=' | '<'
+				| '>' | '~' | '!' | '&' | '|' | ',' | ';' | ':' | '.' | '@' => {
+					idx += 1;
+					let token = chars.next().unwrap().to_string().parse()?;
+					match token {
+						Token::OpenBrace | Token::OpenParen | Token::OpenCurly => {
+							nesting.push(token.clone())
+						},
+						Token::CloseBrace | Token::CloseParen | Token::CloseCurly => {
+							nesting.pop();
+						},
+						_ => {},
+					}
+					res.push(token);
+				},
+
+				// Strings are written between double quotes, so a quote right after
+				// a value transposes it like in Octave, `A'` or `[1 2]'`
+				'\'' => {
+					let follows_value = res.last().is_some_and(Token::ends_value)
+						&& spans.len() == res.len()
+						&& spans.last().is_some_and(|span: &Span| span.end == idx);
+					if !follows_value {
+						return Err(TokenizationError {
+							kind: TokenizationErrorKind::UnspportedSyntax("'".to_string()),
+							token_str: None,
+							message: Some(String::from(
+								"Strings are written between double quotes, like \"text\".",
+							)),
+							position: None,
+						});
+					}
 
-			'0'..='9' => {
-				let (token_len, token) = try_tokenize_number(idx, code)?;
-				res.push(token);
+					idx += 1;
+					chars.next();
+					res.push(Token::OpTranspose);
+				},
+
+				// `%` and `#` comment out the rest of the line, and `%{` and `%}` alone on
+				// their lines the lines between them, which may hold other blocks, like Octave
+				'%' | '#' => {
+					let line: String = chars.clone().take_while(|&c| c != '\n').collect();
+					let alone = spans
+						.last()
+						.is_none_or(|span: &Span| span.line < lines.span(idx, idx).line);
+					let len = match alone && is_block_delimiter(&line, '{') {
+						true => block_comment_len(chars.clone()).ok_or(TokenizationError {
+							kind: TokenizationErrorKind::UnterminatedComment,
+							token_str: None,
+							message: None,
+							position: None,
+						})?,
+						false => line.trim_end_matches('\r').chars().count(),
+					};
+
+					idx += len;
+					chars.nth(len - 1);
+				},
+
+				'0'..='9' => {
+					let (token_len, token) = try_tokenize_number(idx, code, config)?;
+					res.push(token);
+
+					idx += token_len;
+					chars.nth(token_len - 1);
+				},
+
+				// `r"C:\data\file.csv"`, or `r#"say "hi""#` to contain quotes
+				'r' if raw_string_hashes(chars.clone()).is_some() => {
+					let hashes = raw_string_hashes(chars.clone()).unwrap();
+					idx += hashes + 2;
+					chars.nth(hashes + 1);
+
+					let closing: String = std::iter::once('"')
+						.chain("#".repeat(hashes).chars())
+						.collect();
+					let mut string = String::new();
+					while !string.ends_with(&closing) {
+						match chars.next() {
+							Some('\n') | Some('\r') | None => {
+								return Err(TokenizationError {
+									kind: TokenizationErrorKind::UnterminatedString,
+									token_str: Some(string),
+									message: None,
+									position: None,
+								})
+							},
+							Some(c) => string.push(c),
+						}
+						idx += 1;
+					}
+					string.truncate(string.len() - closing.len());
 
-				idx += token_len;
-				chars.nth(token_len - 1);
-			},
+					res.push(Token::StringLiteral(string));
+				},
 
-			'A'..='Z' | 'a'..='z' | '_' => {
-				idx += 1;
-				let mut token = chars.next().unwrap().to_string();
+				'A'..='Z' | 'a'..='z' | '_' => {
+					idx += 1;
+					let mut token = chars.next().unwrap().to_string();
+
+					while let Some(&next) = chars.peek() {
+						if !next.is_ascii_alphanumeric() && next != '_' {
+							break;
+						}
 
-				while let Some(&next) = chars.peek() {
-					if !next.is_ascii_alphanumeric() && next != '_' {
-						break;
+						idx += 1;
+						token.push(chars.next().unwrap());
 					}
 
+					res.push(token.parse()?);
+				},
+
+				'"' => {
 					idx += 1;
-					token.push(chars.next().unwrap());
-				}
+					chars.next();
 
-				res.push(token.parse()?);
-			},
+					let mut string = String::new();
+					loop {
+						match chars.next() {
+							Some('"') => break,
+							Some('\n') | Some('\r') | None => {
+								return Err(TokenizationError {
+									kind: TokenizationErrorKind::UnterminatedString,
+									token_str: Some(string),
+									message: None,
+									position: None,
+								})
+							},
+							Some(c) => string.push(c),
+						}
+						idx += 1;
+					}
+					idx += 1;
 
-			'\n' => {
-				res.push(Token::EndOfLine);
+					res.push(Token::StringLiteral(string));
+				},
 
-				idx += 1;
-				chars.next();
-			},
+				'\n' => {
+					idx += 1;
+					chars.next();
 
-			'\r' => {
-				idx += 1;
-				chars.next();
+					res.extend(line_break(&nesting, &res, chars.clone()));
+				},
 
-				if chars.peek() == Some(&'\n') {
-					chars.next();
+				'\r' => {
 					idx += 1;
-				}
+					chars.next();
 
-				res.push(Token::EndOfLine);
-			},
+					if chars.peek() == Some(&'\n') {
+						chars.next();
+						idx += 1;
+					}
 
-			' ' => {
-				idx += 1;
-				chars.next();
-			},
+					res.extend(line_break(&nesting, &res, chars.clone()));
+				},
 
-			&c => {
-				return Err(TokenizationError {
-					kind: TokenizationErrorKind::UnexpectedChar(c),
-					token_str: None,
-					message: None,
-				})
-			},
+				' ' => {
+					idx += 1;
+					chars.next();
+
+					// `[1 -2]` is two elements like in Octave, while `[1 - 2]` and `[1-2]` are one,
+					// and the same for `+` and the negation `[1 ~a]`, but not `[1 ~= a]`
+					let mut ahead = chars.clone();
+					let is_signed_element = nesting.last() == Some(&Token::OpenBrace)
+						&& res.last().is_some_and(Token::ends_value)
+						&& ahead
+							.next()
+							.is_some_and(|c| matches!(c, '-' | '+' | '~' | '!'))
+						&& ahead.next().is_some_and(|c| c != ' ' && c != '=');
+					if is_signed_element {
+						res.push(Token::Comma);
+					}
+				},
+
+				c => {
+					return Err(TokenizationError {
+						kind: TokenizationErrorKind::UnexpectedChar(c),
+						token_str: None,
+						message: None,
+						position: None,
+					})
+				},
+			}
+
+			spans.resize(res.len(), lines.span(token_start, idx));
 		}
+		Ok(())
+	};
+
+	if let Err(mut e) = read() {
+		let span = lines.span(token_start, token_start);
+		e.position = Some((span.line, span.column));
+		return Err(e);
 	}
 
 	res.push(Token::EndOfFile);
-	Ok(res)
+	spans.push(lines.span(idx, idx));
+
+	Ok(res
+		.into_iter()
+		.zip(spans)
+		.map(|(token, span)| SpannedToken { token, span })
+		.collect())
+}
+
+// The character offsets where the lines of a source start
+struct Lines(Vec<usize>);
+
+impl Lines {
+	fn new(code: &str) -> Self {
+		let breaks = code.chars().enumerate().filter(|&(_, c)| c == '\n');
+		Self(
+			std::iter::once(0)
+				.chain(breaks.map(|(i, _)| i + 1))
+				.collect(),
+		)
+	}
+
+	fn span(&self, start: usize, end: usize) -> Span {
+		let line = self.0.partition_point(|&line_start| line_start <= start);
+		Span {
+			start,
+			end,
+			line,
+			column: start - self.0[line - 1] + 1,
+		}
+	}
+}
+
+// Whether a line is only `%{` or `#{` for `bracket` '{', with spaces around
+fn is_block_delimiter(line: &str, bracket: char) -> bool {
+	let mut line = line.trim().chars();
+	matches!(line.next(), Some('%' | '#')) && line.next() == Some(bracket) && line.next().is_none()
 }
 
-pub fn try_tokenize_number(idx: usize, code: &str) -> Result<(usize, Token), TokenizationError> {
+// The length of a block comment from its opening `%{` to the end of its closing line
+fn block_comment_len(ahead: impl Iterator<Item = char>) -> Option<usize> {
+	let mut len = 0;
+	let mut depth = 0;
+	let mut line = String::new();
+	for c in ahead {
+		len += 1;
+		if c != '\n' {
+			line.push(c);
+			continue;
+		}
+
+		if is_block_delimiter(&line, '{') {
+			depth += 1;
+		} else if is_block_delimiter(&line, '}') {
+			depth -= 1;
+			if depth == 0 {
+				// The line break ends the statement before the comment
+				return Some(len - 1);
+			}
+		}
+		line.clear();
+	}
+
+	(depth == 1 && is_block_delimiter(&line, '}')).then_some(len)
+}
+
+// The number of `#` of a raw string starting at `r`, if it is one
+fn raw_string_hashes(mut ahead: impl Iterator<Item = char>) -> Option<usize> {
+	ahead.next();
+	let mut hashes = 0;
+	loop {
+		match ahead.next() {
+			Some('#') => hashes += 1,
+			Some('"') => return Some(hashes),
+			_ => return None,
+		}
+	}
+}
+
+// A line break ends the statement, or a row inside brackets like `[1 2
+// 3 4]`, and is ignored inside parentheses so arguments can span lines
+fn line_break(
+	nesting: &[Token],
+	res: &[Token],
+	ahead: impl Iterator<Item = char>,
+) -> Option<Token> {
+	match nesting.last() {
+		None => Some(Token::EndOfLine),
+		Some(Token::OpenBrace) => {
+			let mut ahead = ahead.skip_while(|c| c.is_whitespace());
+			let row_ended = matches!(
+				res.last(),
+				Some(Token::OpenBrace | Token::SemiColon | Token::Comma)
+			);
+			(!row_ended && ahead.next() != Some(']')).then_some(Token::SemiColon)
+		},
+		Some(_) => None,
+	}
+}
+
+pub fn try_tokenize_number(
+	idx: usize,
+	code: &str,
+	config: &LexerConfig,
+) -> Result<(usize, Token), TokenizationError> {
 	let mut chars = code.chars().skip(idx).peekable();
 	let mut token = String::new();
 	let mut is_frac = false;
@@ -237,8 +613,18 @@ pub fn try_tokenize_number(idx: usize, code: &str) -> Result<(usize, Token), Tok
 				token_len += 1;
 			},
 
-			'.' => {
-				token.push(chars.next().unwrap());
+			'.' | ',' => {
+				// A comma is only a decimal separator right before the decimals,
+				// `1,5,6` is 1.5 then 6
+				let is_decimal_comma =
+					config.decimal_comma
+						&& !is_frac && chars.clone().nth(1).is_some_and(|c| c.is_ascii_digit());
+				if next == ',' && !is_decimal_comma {
+					break;
+				}
+
+				chars.next();
+				token.push('.');
 				token_len += 1;
 
 				if is_expo {
@@ -248,6 +634,7 @@ pub fn try_tokenize_number(idx: usize, code: &str) -> Result<(usize, Token), Tok
 						message: Some(String::from(
 							"Could not parse a numeric literal with a dot after the 'e' in a scientific notation.",
 						)),
+						position: None,
 					});
 				}
 
@@ -258,6 +645,7 @@ pub fn try_tokenize_number(idx: usize, code: &str) -> Result<(usize, Token), Tok
 						message: Some(String::from(
 							"Could not parse a numeric literal with more than one dot.",
 						)),
+						position: None,
 					});
 				}
 
@@ -275,6 +663,7 @@ pub fn try_tokenize_number(idx: usize, code: &str) -> Result<(usize, Token), Tok
 						message: Some(String::from(
 							"Could not parse a numeric literal with more than one 'e' suffix,\n(Invalid scientific notation :)",
 						)),
+						position: None,
 					});
 				}
 
@@ -295,6 +684,7 @@ pub fn try_tokenize_number(idx: usize, code: &str) -> Result<(usize, Token), Tok
 							kind: TokenizationErrorKind::UnexpectedChar('e'),
 							token_str: Some(token),
 							message: Some(String::from("The scientific notation is not complete.")),
+							position: None,
 						})
 					},
 				}
@@ -310,6 +700,7 @@ pub fn try_tokenize_number(idx: usize, code: &str) -> Result<(usize, Token), Tok
 					),
 					token_str: Some(token),
 					message: Some(String::from("Suffixes other than 'e' are not supported.")),
+					position: None,
 				});
 			},
 
@@ -328,6 +719,7 @@ pub fn try_tokenize_number(idx: usize, code: &str) -> Result<(usize, Token), Tok
 			kind: TokenizationErrorKind::NotANumber,
 			token_str: Some(token),
 			message: Some(e.to_string()),
+			position: None,
 		}),
 	}
 }
@@ -341,6 +733,14 @@ pub struct TokenizationError {
 	kind: TokenizationErrorKind,
 	token_str: Option<String>,
 	message: Option<String>,
+	/// The line and column of the token that could not be read, from 1.
+	position: Option<(usize, usize)>,
+}
+
+impl TokenizationError {
+	pub fn position(&self) -> Option<(usize, usize)> {
+		self.position
+	}
 }
 
 #[derive(Debug)]
@@ -349,6 +749,8 @@ pub enum TokenizationErrorKind {
 	NotANumber,
 	UnexpectedChar(char),
 	UnspportedSyntax(String),
+	UnterminatedString,
+	UnterminatedComment,
 }
 
 impl std::error::Error for TokenizationError {}
@@ -357,16 +759,31 @@ impl std::fmt::Display for TokenizationError {
 		use TokenizationErrorKind::*;
 
 		let mut err_message = match &self.kind {
-			EmptyString => String::from("Unexpected empty string"),
-			NotANumber => String::from("Could not parse as number"),
-			UnexpectedChar(c) => format!("Unexpected character '{}'", c.escape_default()),
-			UnspportedSyntax(s) => format!("Unsupported syntax '{}'", s.escape_default()),
+			EmptyString => tr("Unexpected empty string").to_string(),
+			NotANumber => tr("Could not parse as number").to_string(),
+			UnexpectedChar(c) => tr_with("Unexpected character '{0}'", &[&c.escape_default()]),
+			UnspportedSyntax(s) => tr_with("Unsupported syntax '{0}'", &[&s.escape_default()]),
+			UnterminatedString => tr("Missing the closing '\"' of a string literal").to_string(),
+			UnterminatedComment => tr("Missing the closing '%}' of a block comment").to_string(),
 		};
 
+		match self.position {
+			Some((1, column)) => {
+				err_message = tr_with("{0} at column {1}", &[&err_message, &column])
+			},
+			Some((line, column)) => {
+				err_message = tr_with(
+					"{0} at line {1}, column {2}",
+					&[&err_message, &line, &column],
+				)
+			},
+			None => {},
+		}
+
 		if let Some(token) = &self.token_str {
 			err_message = format!(
-				"{err_message}\nError found in string '{}'",
-				token.escape_default()
+				"{err_message}\n{}",
+				tr_with("Error found in string '{0}'", &[&token.escape_default()])
 			);
 		}
 
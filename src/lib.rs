@@ -0,0 +1,43 @@
+/*
+	The interpreter as a library, used by the `nam` binary and
+	by C hosts through the `ffi` module when built as a cdylib.
+*/
+
+pub mod repl;
+pub mod settings;
+pub mod state;
+
+pub mod alias;
+pub mod arith;
+pub mod ast;
+pub mod backend;
+pub mod bigint;
+pub mod builtins;
+pub mod bytecode;
+pub mod clipboard;
+pub mod config;
+pub mod dependencies;
+pub mod dirs;
+pub mod engine;
+pub mod eval;
+pub mod ffi;
+pub mod format;
+pub mod function;
+pub mod gpu;
+pub mod hash;
+pub mod i18n;
+pub mod jobs;
+pub mod lexer;
+pub mod linalg;
+pub mod matrix;
+pub mod observer;
+pub mod optimizer;
+pub mod profiler;
+pub mod report;
+pub mod script;
+pub mod session;
+pub mod stats;
+pub mod steps;
+pub mod table;
+pub mod warning;
+pub mod workspace;
@@ -0,0 +1,1165 @@
+mod small;
+
+use crate::{
+	matrix::Matrix,
+	steps::{NoSteps, StepReporter},
+};
+
+/*
+	Numerical routines on matrices shared by the builtins.
+*/
+
+const MAX_QR_ITERATIONS: usize = 30;
+
+// Hager's estimator usually settles in two or three iterations
+const MAX_RCOND_ITERATIONS: usize = 5;
+
+// Products whose dimensions are all at least this large use Strassen's algorithm
+const STRASSEN_THRESHOLD: usize = 512;
+
+/// The matrix product, the inner dimensions must agree. Products of 2x2 to 4x4
+/// matrices use fixed-size kernels, see `small`, and large ones of finite matrices
+/// Strassen's algorithm, see `strassen`.
+pub fn matmul(a: &Matrix, b: &Matrix) -> Matrix {
+	assert_eq!(a.ncols(), b.nrows(), "Matrix dimensions mismatch");
+
+	if let Some(res) = small::matmul(a, b) {
+		return res;
+	}
+
+	if a.nrows().min(a.ncols()).min(b.ncols()) >= STRASSEN_THRESHOLD && finite(a) && finite(b) {
+		return strassen(a, b);
+	}
+	naive_matmul(a, b)
+}
+
+fn naive_matmul(a: &Matrix, b: &Matrix) -> Matrix {
+	let mut res = Matrix::zeros(a.nrows(), b.ncols());
+	for (i, row) in a.rows().enumerate() {
+		for (&x, b_row) in row.iter().zip(b.rows()) {
+			if x == 0.0 {
+				continue;
+			}
+			for (j, y) in b_row.iter().enumerate() {
+				res[(i, j)] += x * y;
+			}
+		}
+	}
+	res
+}
+
+/*
+	Strassen's algorithm computes the product from 7 products of half size
+	blocks instead of 8, odd dimensions are padded with zeros. Below the
+	threshold the naive product is faster. The rounding errors grow a little
+	faster than with the naive product, but stay within a few ulps times the
+	size for matrices of similar magnitudes. Inf and NaN would spread to blocks
+	the naive product keeps finite, through the differences of the products and
+	the zeros it skips, so `matmul` only uses it for finite matrices.
+*/
+fn strassen(a: &Matrix, b: &Matrix) -> Matrix {
+	let (n, m, p) = (a.nrows(), a.ncols(), b.ncols());
+	if n.min(m).min(p) < STRASSEN_THRESHOLD {
+		return naive_matmul(a, b);
+	}
+
+	let (hn, hm, hp) = (n.div_ceil(2), m.div_ceil(2), p.div_ceil(2));
+	let a = padded(a, 2 * hn, 2 * hm);
+	let b = padded(b, 2 * hm, 2 * hp);
+
+	let block = |x: &Matrix, i: usize, j: usize, h: usize, w: usize| {
+		x.get_block(i * h, j * w, h, w).unwrap()
+	};
+	let (a11, a12) = (block(&a, 0, 0, hn, hm), block(&a, 0, 1, hn, hm));
+	let (a21, a22) = (block(&a, 1, 0, hn, hm), block(&a, 1, 1, hn, hm));
+	let (b11, b12) = (block(&b, 0, 0, hm, hp), block(&b, 0, 1, hm, hp));
+	let (b21, b22) = (block(&b, 1, 0, hm, hp), block(&b, 1, 1, hm, hp));
+
+	let m1 = strassen(&add(&a11, &a22), &add(&b11, &b22));
+	let m2 = strassen(&add(&a21, &a22), &b11);
+	let m3 = strassen(&a11, &sub(&b12, &b22));
+	let m4 = strassen(&a22, &sub(&b21, &b11));
+	let m5 = strassen(&add(&a11, &a12), &b22);
+	let m6 = strassen(&sub(&a21, &a11), &add(&b11, &b12));
+	let m7 = strassen(&sub(&a12, &a22), &add(&b21, &b22));
+
+	let c11 = add(&sub(&add(&m1, &m4), &m5), &m7);
+	let c12 = add(&m3, &m5);
+	let c21 = add(&m2, &m4);
+	let c22 = add(&add(&sub(&m1, &m2), &m3), &m6);
+
+	let mut res = Matrix::zeros(2 * hn, 2 * hp);
+	res.set_block(0, 0, &c11).unwrap();
+	res.set_block(0, hp, &c12).unwrap();
+	res.set_block(hn, 0, &c21).unwrap();
+	res.set_block(hn, hp, &c22).unwrap();
+	res.get_block(0, 0, n, p).unwrap()
+}
+
+fn finite(a: &Matrix) -> bool {
+	a.as_slice().iter().all(|x| x.is_finite())
+}
+
+fn padded(a: &Matrix, nrows: usize, ncols: usize) -> Matrix {
+	if (a.nrows(), a.ncols()) == (nrows, ncols) {
+		return a.clone();
+	}
+	let mut res = Matrix::zeros(nrows, ncols);
+	res.set_block(0, 0, a).unwrap();
+	res
+}
+
+fn scale(a: &Matrix, c: f64) -> Matrix {
+	a.broadcast(&Matrix::from(c), |x, c| x * c).unwrap()
+}
+
+fn add(a: &Matrix, b: &Matrix) -> Matrix {
+	a.broadcast(b, |x, y| x + y).unwrap()
+}
+
+fn sub(a: &Matrix, b: &Matrix) -> Matrix {
+	a.broadcast(b, |x, y| x - y).unwrap()
+}
+
+/// Raises a square matrix to an integer power by repeated squaring,
+/// negative powers use the inverse. Returns `None` if the inverse does not exist.
+pub fn matpow(a: &Matrix, n: i64) -> Option<Matrix> {
+	let mut base = if n < 0 { inverse(a)? } else { a.clone() };
+
+	let mut res = Matrix::identity(a.nrows());
+	let mut n = n.unsigned_abs();
+	while n > 0 {
+		if n & 1 == 1 {
+			res = matmul(&res, &base);
+		}
+		n >>= 1;
+		if n > 0 {
+			base = matmul(&base, &base);
+		}
+	}
+
+	Some(res)
+}
+
+// Iterations of the matrix square root, it converges quadratically
+const MAX_SQRTM_ITERATIONS: usize = 100;
+
+/// The principal square root of a square `a`, the matrix `x` with `x * x = a` whose
+/// eigenvalues have positive real parts, by the Denman-Beavers iteration. It exists when
+/// no eigenvalue of `a` is negative or 0, returns `None` if the iteration does not converge.
+pub fn sqrtm(a: &Matrix) -> Option<Matrix> {
+	let n = a.nrows();
+	let (mut y, mut z) = (a.clone(), Matrix::identity(n));
+
+	for _ in 0..MAX_SQRTM_ITERATIONS {
+		let y_inv = solve(&y, &Matrix::identity(n))?;
+		let z_inv = solve(&z, &Matrix::identity(n))?;
+		let next = scale(&add(&y, &z_inv), 0.5);
+		z = scale(&add(&z, &y_inv), 0.5);
+
+		let change = norm1(&sub(&next, &y));
+		y = next;
+		if change <= f64::EPSILON * norm1(&y) * n as f64 {
+			return Some(y);
+		}
+	}
+
+	None
+}
+
+/// The principal logarithm of a square `a`, from square roots taken until `a` is close
+/// to the identity and the series of `log(I + x)`. Same conditions as `sqrtm`.
+pub fn logm(a: &Matrix) -> Option<Matrix> {
+	let n = a.nrows();
+	let identity = Matrix::identity(n);
+
+	// log(a) = 2^k log(a^(1/2^k))
+	let mut root = a.clone();
+	let mut k = 0;
+	while norm1(&sub(&root, &identity)) > 0.25 {
+		if k == 64 {
+			return None;
+		}
+		root = sqrtm(&root)?;
+		k += 1;
+	}
+
+	// log(I + x) = x - x^2/2 + x^3/3 - ...
+	let x = sub(&root, &identity);
+	let mut power = x.clone();
+	let mut res = x.clone();
+	for j in 2..200 {
+		power = matmul(&power, &x);
+		let sign = if j % 2 == 0 { -1.0 } else { 1.0 };
+		let term = scale(&power, sign / j as f64);
+		res = add(&res, &term);
+		if norm1(&term) <= f64::EPSILON * norm1(&res) {
+			break;
+		}
+	}
+
+	Some(scale(&res, 2f64.powi(k)))
+}
+
+/// The matrix exponential of a square `a`, by scaling and squaring its Taylor series.
+pub fn expm(a: &Matrix) -> Matrix {
+	let n = a.nrows();
+
+	// exp(a) = exp(a / 2^s)^(2^s), with the norm of a / 2^s below 1/2
+	let norm = norm1(a);
+	let s = if norm > 0.5 {
+		(norm / 0.5).log2().ceil() as i32
+	} else {
+		0
+	};
+	let scaled = scale(a, 2f64.powi(-s));
+
+	let mut term = Matrix::identity(n);
+	let mut res = Matrix::identity(n);
+	for j in 1..30 {
+		term = scale(&matmul(&term, &scaled), 1.0 / j as f64);
+		res = add(&res, &term);
+		if norm1(&term) <= f64::EPSILON * norm1(&res) {
+			break;
+		}
+	}
+
+	for _ in 0..s {
+		res = matmul(&res, &res);
+	}
+	res
+}
+
+/// `a^p` for a square `a` and a real `p`: the integer part by `matpow` and the
+/// fractional part by `sqrtm` for 1/2, `expm(f * logm(a))` otherwise.
+/// Returns `None` where `logm` does not exist.
+pub fn powm(a: &Matrix, p: f64) -> Option<Matrix> {
+	let (whole, fraction) = (p.floor(), p - p.floor());
+
+	let fractional = match fraction {
+		0.0 => return matpow(a, whole as i64),
+		0.5 => sqrtm(a)?,
+		_ => {
+			let log = logm(a)?;
+			expm(&scale(&log, fraction))
+		},
+	};
+
+	match whole {
+		0.0 => Some(fractional),
+		_ => Some(matmul(&matpow(a, whole as i64)?, &fractional)),
+	}
+}
+
+/// The determinant of a square matrix, the product of the pivots of its LU
+/// decomposition. Matrices up to 4x4 use closed forms, see `small`.
+pub fn det(a: &Matrix) -> f64 {
+	assert!(a.is_square(), "Matrix dimensions mismatch");
+
+	if small::fits(a) {
+		return small::det(a);
+	}
+
+	let n = a.nrows();
+	let mut u = a.clone();
+	let mut res = 1.0;
+	for k in 0..n {
+		let pivot = (k..n)
+			.max_by(|&i, &j| u[(i, k)].abs().total_cmp(&u[(j, k)].abs()))
+			.unwrap();
+		if u[(pivot, k)] == 0.0 {
+			return 0.0;
+		}
+		if pivot != k {
+			swap_rows(&mut u, k, pivot);
+			res = -res;
+		}
+		res *= u[(k, k)];
+
+		for i in k + 1..n {
+			let factor = u[(i, k)] / u[(k, k)];
+			for j in k + 1..n {
+				let delta = factor * u[(k, j)];
+				u[(i, j)] -= delta;
+			}
+		}
+	}
+	res
+}
+
+/// The inverse of a square matrix, `None` if it is singular.
+/// Matrices up to 4x4 use closed forms, see `small`.
+pub fn inverse(a: &Matrix) -> Option<Matrix> {
+	assert!(a.is_square(), "Matrix dimensions mismatch");
+
+	if small::fits(a) {
+		return small::inverse(a);
+	}
+	solve(a, &Matrix::identity(a.nrows()))
+}
+
+/// Solves `a * x = b` for a square `a`, returns `None` if `a` is singular.
+/// Triangular and banded matrices skip the general LU decomposition.
+pub fn solve(a: &Matrix, b: &Matrix) -> Option<Matrix> {
+	solve_with_rcond(a, b).map(|(x, _)| x)
+}
+
+/// Like `solve`, with an estimate of the reciprocal condition number of `a`: the ratio
+/// of its smallest pivot to its largest. Close to 0 means `x` may be inaccurate.
+pub fn solve_with_rcond(a: &Matrix, b: &Matrix) -> Option<(Matrix, f64)> {
+	assert!(
+		a.is_square() && a.nrows() == b.nrows(),
+		"Matrix dimensions mismatch"
+	);
+
+	if a.is_triangular() {
+		let x = match a.is_triu() {
+			true => solve_triu(a, b),
+			false => solve_tril(a, b),
+		}?;
+		return Some((x, pivot_ratio(a)));
+	}
+
+	let (lower, upper) = a.bandwidth();
+	if 2 * (lower + upper) < a.nrows() {
+		return lu_solve(a, b, lower, upper);
+	}
+
+	lu_solve(a, b, a.nrows(), a.nrows())
+}
+
+/// Like `solve`, followed by `passes` steps of iterative refinement: the residual
+/// `b - a * x` is computed with twice the working precision and the correction
+/// solved from it is added to `x`, which recovers digits lost to an ill-conditioned `a`.
+pub fn solve_refined(a: &Matrix, b: &Matrix, passes: usize) -> Option<(Matrix, f64)> {
+	let (mut x, rcond) = solve_with_rcond(a, b)?;
+
+	for _ in 0..passes {
+		let correction = solve(a, &residual(a, b, &x))?;
+		x = add(&x, &correction);
+	}
+
+	Some((x, rcond))
+}
+
+/// `b - a * x`, each element accumulated as a compensated dot product so it is
+/// accurate even when it cancels almost entirely.
+pub fn residual(a: &Matrix, b: &Matrix, x: &Matrix) -> Matrix {
+	let mut res = b.clone();
+	for i in 0..a.nrows() {
+		for j in 0..x.ncols() {
+			let (mut sum, mut error) = (b[(i, j)], 0.0);
+			for k in 0..a.ncols() {
+				let product = -a[(i, k)] * x[(k, j)];
+				let product_error = (-a[(i, k)]).mul_add(x[(k, j)], -product);
+				let total = sum + product;
+				let rounded = total - sum;
+				error += (sum - (total - rounded)) + (product - rounded) + product_error;
+				sum = total;
+			}
+			res[(i, j)] = sum + error;
+		}
+	}
+	res
+}
+
+/// An estimate of the reciprocal condition number of a square `a` in the 1-norm,
+/// `1 / (norm(a, 1) * norm(inv(a), 1))`, by Hager's method from its LU decomposition.
+/// It is 0 for a singular `a` and close to 0 when solving with `a` loses digits.
+pub fn rcond(a: &Matrix) -> f64 {
+	assert!(a.is_square(), "Matrix dimensions mismatch");
+
+	let n = a.nrows();
+	if n == 0 {
+		return f64::INFINITY;
+	}
+
+	let (l, u, p) = lu(a, &mut NoSteps);
+	let threshold = singular_threshold(a);
+	if (0..n).any(|i| u[(i, i)].abs() <= threshold) {
+		return 0.0;
+	}
+
+	// inv(a) = inv(u) * inv(l) * p and inv(a)' = p' * inv(l)' * inv(u)'
+	let (lt, ut) = (l.transpose(), u.transpose());
+	let solve_a = |x: &Matrix| solve_triu(&u, &solve_tril(&l, &matmul(&p, x))?);
+	let solve_at = |x: &Matrix| {
+		let y = solve_triu(&lt, &solve_tril(&ut, x)?)?;
+		Some(matmul(&p.transpose(), &y))
+	};
+
+	// The column of inv(a) with the largest 1-norm is searched from x, starting
+	// with the average of the columns
+	let mut x = Matrix::new(n, 1, vec![1.0 / n as f64; n]).unwrap();
+	let mut estimate = 0.0;
+	for _ in 0..MAX_RCOND_ITERATIONS {
+		let Some(y) = solve_a(&x) else {
+			return 0.0;
+		};
+		estimate = norm1(&y);
+
+		let signs = Matrix::new(n, 1, y.as_slice().iter().map(|v| v.signum()).collect());
+		let Some(z) = solve_at(&signs.unwrap()) else {
+			return 0.0;
+		};
+
+		let (j, max) = z
+			.as_slice()
+			.iter()
+			.map(|v| v.abs())
+			.enumerate()
+			.max_by(|(_, v), (_, w)| v.total_cmp(w))
+			.unwrap();
+		let current: f64 = z
+			.as_slice()
+			.iter()
+			.zip(x.as_slice())
+			.map(|(z, x)| z * x)
+			.sum();
+		if max <= current {
+			break;
+		}
+		x = Matrix::zeros(n, 1);
+		x[(j, 0)] = 1.0;
+	}
+
+	1.0 / (norm1(a) * estimate)
+}
+
+// The largest sum of the absolute values of a column
+fn norm1(a: &Matrix) -> f64 {
+	a.cols()
+		.map(|col| col.map(|v| v.abs()).sum::<f64>())
+		.fold(0.0, f64::max)
+}
+
+// The smallest element of the diagonal relative to the largest, in absolute value
+fn pivot_ratio(u: &Matrix) -> f64 {
+	let pivots = (0..u.nrows()).map(|i| u[(i, i)].abs());
+	let (min, max) = pivots.fold((f64::INFINITY, 0.0f64), |(min, max), p| {
+		(min.min(p), max.max(p))
+	});
+	if max == 0.0 {
+		return 0.0;
+	}
+	min / max
+}
+
+/// Solves `a * x = b` for an upper triangular `a` by back substitution,
+/// returns `None` if `a` is singular.
+pub fn solve_triu(a: &Matrix, b: &Matrix) -> Option<Matrix> {
+	assert!(
+		a.is_square() && a.nrows() == b.nrows(),
+		"Matrix dimensions mismatch"
+	);
+
+	let n = a.nrows();
+	let threshold = singular_threshold(a);
+	let mut x = b.clone();
+
+	for i in (0..n).rev() {
+		if a[(i, i)].abs() <= threshold {
+			return None;
+		}
+		for j in 0..x.ncols() {
+			let mut sum = x[(i, j)];
+			for k in i + 1..n {
+				sum -= a[(i, k)] * x[(k, j)];
+			}
+			x[(i, j)] = sum / a[(i, i)];
+		}
+	}
+
+	Some(x)
+}
+
+/// Solves `a * x = b` for a lower triangular `a` by forward substitution,
+/// returns `None` if `a` is singular.
+pub fn solve_tril(a: &Matrix, b: &Matrix) -> Option<Matrix> {
+	assert!(
+		a.is_square() && a.nrows() == b.nrows(),
+		"Matrix dimensions mismatch"
+	);
+
+	let n = a.nrows();
+	let threshold = singular_threshold(a);
+	let mut x = b.clone();
+
+	for i in 0..n {
+		if a[(i, i)].abs() <= threshold {
+			return None;
+		}
+		for j in 0..x.ncols() {
+			let mut sum = x[(i, j)];
+			for k in 0..i {
+				sum -= a[(i, k)] * x[(k, j)];
+			}
+			x[(i, j)] = sum / a[(i, i)];
+		}
+	}
+
+	Some(x)
+}
+
+/// Solves `a * x = b` for a square `a` with `lower` diagonals below the main one and
+/// `upper` above, by LU decomposition with partial pivoting restricted to the band.
+/// Returns `None` if `a` is singular. A full matrix has both bandwidths equal to its size.
+pub fn solve_banded(a: &Matrix, b: &Matrix, lower: usize, upper: usize) -> Option<Matrix> {
+	lu_solve(a, b, lower, upper).map(|(x, _)| x)
+}
+
+// `solve_banded`, with the pivot ratio of the decomposition
+fn lu_solve(a: &Matrix, b: &Matrix, lower: usize, upper: usize) -> Option<(Matrix, f64)> {
+	assert!(
+		a.is_square() && a.nrows() == b.nrows(),
+		"Matrix dimensions mismatch"
+	);
+
+	let n = a.nrows();
+	let threshold = singular_threshold(a);
+	let (mut a, mut x) = (a.clone(), b.clone());
+
+	// Row swaps within the band widen the upper band by `lower` at most
+	let width = (lower + upper).min(n);
+
+	for k in 0..n {
+		let last_row = (k + lower).min(n - 1);
+		let last_col = (k + width).min(n - 1);
+
+		let pivot = (k..=last_row).max_by(|&i, &j| a[(i, k)].abs().total_cmp(&a[(j, k)].abs()))?;
+		if a[(pivot, k)].abs() <= threshold {
+			return None;
+		}
+
+		if pivot != k {
+			for j in k..=last_col {
+				let tmp = a[(k, j)];
+				a[(k, j)] = a[(pivot, j)];
+				a[(pivot, j)] = tmp;
+			}
+			for j in 0..x.ncols() {
+				let tmp = x[(k, j)];
+				x[(k, j)] = x[(pivot, j)];
+				x[(pivot, j)] = tmp;
+			}
+		}
+
+		for i in k + 1..=last_row {
+			let factor = a[(i, k)] / a[(k, k)];
+			if factor == 0.0 {
+				continue;
+			}
+			for j in k..=last_col {
+				let delta = factor * a[(k, j)];
+				a[(i, j)] -= delta;
+			}
+			for j in 0..x.ncols() {
+				let delta = factor * x[(k, j)];
+				x[(i, j)] -= delta;
+			}
+		}
+	}
+
+	// Back substitution within the widened band
+	for j in 0..x.ncols() {
+		for i in (0..n).rev() {
+			let mut sum = x[(i, j)];
+			for k in i + 1..=(i + width).min(n - 1) {
+				sum -= a[(i, k)] * x[(k, j)];
+			}
+			x[(i, j)] = sum / a[(i, i)];
+		}
+	}
+
+	Some((x, pivot_ratio(&a)))
+}
+
+/// The LU decomposition with partial pivoting of a square `a`: `(l, u, p)` with
+/// `p * a = l * u`, `l` unit lower triangular, `u` upper triangular and `p` a permutation.
+/// A singular `a` gives a `u` with zeros on its diagonal.
+/// The rows swapped and the eliminations are reported to `steps`.
+pub fn lu(a: &Matrix, steps: &mut dyn StepReporter) -> (Matrix, Matrix, Matrix) {
+	assert!(a.is_square(), "Matrix dimensions mismatch");
+
+	let n = a.nrows();
+	let threshold = singular_threshold(a);
+	let mut u = a.clone();
+	let mut l = Matrix::identity(n);
+	let mut perm: Vec<usize> = (0..n).collect();
+
+	for k in 0..n {
+		let pivot = (k..n)
+			.max_by(|&i, &j| u[(i, k)].abs().total_cmp(&u[(j, k)].abs()))
+			.unwrap();
+
+		if pivot != k {
+			swap_rows(&mut u, k, pivot);
+			perm.swap(k, pivot);
+			// The multipliers found so far follow their rows
+			for j in 0..k {
+				let tmp = l[(k, j)];
+				l[(k, j)] = l[(pivot, j)];
+				l[(pivot, j)] = tmp;
+			}
+			steps.step(|| format!("Swap rows {} and {}", k + 1, pivot + 1), &u);
+		}
+
+		// Nothing to eliminate under a zero pivot
+		if u[(k, k)].abs() <= threshold {
+			continue;
+		}
+
+		let mut eliminated = false;
+		for i in k + 1..n {
+			let factor = u[(i, k)] / u[(k, k)];
+			l[(i, k)] = factor;
+			if factor == 0.0 {
+				continue;
+			}
+			for j in k..n {
+				let delta = factor * u[(k, j)];
+				u[(i, j)] -= delta;
+			}
+			// Exactly zero rather than rounding noise
+			u[(i, k)] = 0.0;
+			eliminated = true;
+		}
+		if eliminated {
+			steps.step(
+				|| {
+					format!(
+						"Eliminate below the pivot {} in column {}",
+						u[(k, k)],
+						k + 1
+					)
+				},
+				&u,
+			);
+		}
+	}
+
+	let p = Matrix::from_permutations_vector(&perm).unwrap();
+	(l, u, p)
+}
+
+/// The reduced row echelon form of `a` by Gauss-Jordan elimination with partial
+/// pivoting. The rows swapped, scaled and combined are reported to `steps`.
+pub fn rref(a: &Matrix, steps: &mut dyn StepReporter) -> Matrix {
+	let (nrows, ncols) = (a.nrows(), a.ncols());
+	let threshold = singular_threshold(a);
+	let mut a = a.clone();
+
+	let mut row = 0;
+	for col in 0..ncols {
+		if row == nrows {
+			break;
+		}
+
+		let pivot = (row..nrows)
+			.max_by(|&i, &j| a[(i, col)].abs().total_cmp(&a[(j, col)].abs()))
+			.unwrap();
+		if a[(pivot, col)].abs() <= threshold {
+			// Only rounding noise is left in this column
+			for i in row..nrows {
+				a[(i, col)] = 0.0;
+			}
+			continue;
+		}
+
+		if pivot != row {
+			swap_rows(&mut a, row, pivot);
+			steps.step(|| format!("Swap rows {} and {}", row + 1, pivot + 1), &a);
+		}
+
+		let scale = a[(row, col)];
+		if scale != 1.0 {
+			for j in col..ncols {
+				a[(row, j)] /= scale;
+			}
+			steps.step(|| format!("Divide row {} by {scale}", row + 1), &a);
+		}
+
+		let mut eliminated = false;
+		for i in (0..nrows).filter(|&i| i != row) {
+			let factor = a[(i, col)];
+			if factor == 0.0 {
+				continue;
+			}
+			for j in col..ncols {
+				let delta = factor * a[(row, j)];
+				a[(i, j)] -= delta;
+			}
+			a[(i, col)] = 0.0;
+			eliminated = true;
+		}
+		if eliminated {
+			steps.step(
+				|| format!("Eliminate column {} from the other rows", col + 1),
+				&a,
+			);
+		}
+
+		row += 1;
+	}
+
+	a
+}
+
+fn swap_rows(a: &mut Matrix, i: usize, k: usize) {
+	for j in 0..a.ncols() {
+		let tmp = a[(i, j)];
+		a[(i, j)] = a[(k, j)];
+		a[(k, j)] = tmp;
+	}
+}
+
+// Pivots this small relative to the largest element are treated as zero
+fn singular_threshold(a: &Matrix) -> f64 {
+	let scale = a.as_slice().iter().fold(0.0f64, |acc, v| acc.max(v.abs()));
+	f64::EPSILON * scale * a.nrows() as f64
+}
+
+/// The Householder reflection `h = I - 2 * u * u'` mapping `v` onto the first axis,
+/// `h * v = (alpha, 0, ..., 0)` with `|alpha| = norm(v)`, and the unit vector `u`.
+/// The sign of alpha is opposite to `v[0]` so that `u` is computed without cancellation.
+/// A zero `v` gives the identity and a zero `u`.
+pub fn householder(v: &[f64]) -> (Matrix, Vec<f64>) {
+	let n = v.len();
+	let norm = v.iter().map(|x| x * x).sum::<f64>().sqrt();
+
+	let mut u = v.to_vec();
+	if let Some(first) = u.first_mut() {
+		*first += norm.copysign(*first);
+	}
+	let u_norm = u.iter().map(|x| x * x).sum::<f64>().sqrt();
+	if u_norm == 0.0 {
+		return (Matrix::identity(n), u);
+	}
+	u.iter_mut().for_each(|x| *x /= u_norm);
+
+	let mut h = Matrix::identity(n);
+	for i in 0..n {
+		for j in 0..n {
+			h[(i, j)] -= 2.0 * u[i] * u[j];
+		}
+	}
+	(h, u)
+}
+
+/// The QR decomposition `q * r = a` by Householder reflections, with an orthogonal
+/// `q` and an upper triangular `r` of the shape of `a`.
+pub fn qr(a: &Matrix) -> (Matrix, Matrix) {
+	let (m, n) = (a.nrows(), a.ncols());
+	let mut q = Matrix::identity(m);
+	let mut r = a.clone();
+
+	for k in 0..n.min(m.saturating_sub(1)) {
+		let column: Vec<f64> = (k..m).map(|i| r[(i, k)]).collect();
+		let (_, u) = householder(&column);
+
+		// r = h * r and q = q * h, with h only acting on the rows and columns from k
+		for j in 0..n {
+			let dot: f64 = (k..m).map(|i| u[i - k] * r[(i, j)]).sum();
+			for i in k..m {
+				r[(i, j)] -= 2.0 * u[i - k] * dot;
+			}
+		}
+		for i in 0..m {
+			let dot: f64 = (k..m).map(|j| q[(i, j)] * u[j - k]).sum();
+			for j in k..m {
+				q[(i, j)] -= 2.0 * dot * u[j - k];
+			}
+		}
+		for i in k + 1..m {
+			r[(i, k)] = 0.0;
+		}
+	}
+
+	(q, r)
+}
+
+/// The Givens rotation `(c, s)` with `[c s; -s c] * [a; b] = [r; 0]` and `r = hypot(a, b)`.
+pub fn givens(a: f64, b: f64) -> (f64, f64) {
+	let r = a.hypot(b);
+	if r == 0.0 {
+		return (1.0, 0.0);
+	}
+	(a / r, b / r)
+}
+
+/// The lower triangular `l` with `l * l' = a` for a symmetric positive definite `a`,
+/// returns `None` if `a` is not positive definite. Only the lower half of `a` is read.
+pub fn cholesky(a: &Matrix) -> Option<Matrix> {
+	assert!(a.is_square(), "Matrix dimensions mismatch");
+
+	let n = a.nrows();
+	let mut l = Matrix::zeros(n, n);
+	for j in 0..n {
+		let diagonal = a[(j, j)] - (0..j).map(|k| l[(j, k)] * l[(j, k)]).sum::<f64>();
+		if diagonal <= 0.0 || diagonal.is_nan() {
+			return None;
+		}
+		l[(j, j)] = diagonal.sqrt();
+
+		for i in j + 1..n {
+			let sum = a[(i, j)] - (0..j).map(|k| l[(i, k)] * l[(j, k)]).sum::<f64>();
+			l[(i, j)] = sum / l[(j, j)];
+		}
+	}
+
+	Some(l)
+}
+
+/// Reduces a square matrix to upper Hessenberg form with the same eigenvalues,
+/// by Gaussian elimination with pivoting.
+pub fn hessenberg(mut a: Matrix) -> Matrix {
+	let n = a.nrows();
+
+	for m in 1..n.saturating_sub(1) {
+		let pivot = (m..n)
+			.max_by(|&i, &j| a[(i, m - 1)].abs().total_cmp(&a[(j, m - 1)].abs()))
+			.unwrap();
+		let x = a[(pivot, m - 1)];
+
+		// Swapping rows and the same columns is a similarity transform
+		if pivot != m {
+			for j in m - 1..n {
+				let tmp = a[(pivot, j)];
+				a[(pivot, j)] = a[(m, j)];
+				a[(m, j)] = tmp;
+			}
+			for i in 0..n {
+				let tmp = a[(i, pivot)];
+				a[(i, pivot)] = a[(i, m)];
+				a[(i, m)] = tmp;
+			}
+		}
+
+		if x == 0.0 {
+			continue;
+		}
+
+		for i in m + 1..n {
+			let y = a[(i, m - 1)] / x;
+			if y == 0.0 {
+				continue;
+			}
+			a[(i, m - 1)] = 0.0;
+			for j in m..n {
+				let delta = y * a[(m, j)];
+				a[(i, j)] -= delta;
+			}
+			for j in 0..n {
+				let delta = y * a[(j, i)];
+				a[(j, m)] += delta;
+			}
+		}
+	}
+
+	a
+}
+
+/// The eigenvalues of a square matrix as (real, imaginary) pairs,
+/// `None` if they do not converge.
+pub fn eigenvalues(a: &Matrix) -> Option<Vec<(f64, f64)>> {
+	hessenberg_eigenvalues(hessenberg(a.clone()))
+}
+
+/// The coefficients of `det(sI - a)`, highest power first.
+pub fn characteristic_polynomial(a: &Matrix) -> Option<Vec<f64>> {
+	let mut res = vec![1.0];
+
+	for (re, im) in eigenvalues(a)? {
+		// Conjugate pairs are multiplied together to stay real
+		let factor = if im == 0.0 {
+			vec![1.0, -re]
+		} else if im > 0.0 {
+			vec![1.0, -2.0 * re, re * re + im * im]
+		} else {
+			continue;
+		};
+
+		let mut next = vec![0.0; res.len() + factor.len() - 1];
+		for (i, x) in res.iter().enumerate() {
+			for (j, y) in factor.iter().enumerate() {
+				next[i + j] += x * y;
+			}
+		}
+		res = next;
+	}
+
+	Some(res)
+}
+
+/// The eigenvalues of an upper Hessenberg matrix as (real, imaginary) pairs,
+/// with the shifted QR algorithm (Francis double shift). Complex eigenvalues come
+/// in conjugate pairs. Returns `None` if an eigenvalue does not converge.
+pub fn hessenberg_eigenvalues(mut a: Matrix) -> Option<Vec<(f64, f64)>> {
+	let n = a.nrows();
+	let mut res = vec![(0.0, 0.0); n];
+	if n == 0 {
+		return Some(res);
+	}
+
+	let mut norm = 0.0;
+	for i in 0..n {
+		for j in i.saturating_sub(1)..n {
+			norm += a[(i, j)].abs();
+		}
+	}
+
+	// Indices are signed because `nn` goes below zero once every eigenvalue is found
+	let at = |i: isize, j: isize| (i as usize, j as usize);
+
+	let mut nn = n as isize - 1;
+	let mut t = 0.0;
+	while nn >= 0 {
+		let mut its = 0;
+		loop {
+			// Look for a single small subdiagonal element to split the matrix
+			let mut l = nn;
+			while l >= 1 {
+				let mut s = a[at(l - 1, l - 1)].abs() + a[at(l, l)].abs();
+				if s == 0.0 {
+					s = norm;
+				}
+				if a[at(l, l - 1)].abs() + s == s {
+					a[at(l, l - 1)] = 0.0;
+					break;
+				}
+				l -= 1;
+			}
+
+			let mut x = a[at(nn, nn)];
+			if l == nn {
+				// One root found
+				res[nn as usize] = (x + t, 0.0);
+				nn -= 1;
+			} else {
+				let mut y = a[at(nn - 1, nn - 1)];
+				let mut w = a[at(nn, nn - 1)] * a[at(nn - 1, nn)];
+
+				if l == nn - 1 {
+					// Two roots found
+					let p = 0.5 * (y - x);
+					let q = p * p + w;
+					let z = q.abs().sqrt();
+					x += t;
+
+					if q >= 0.0 {
+						let z = p + z.copysign(p);
+						let second = if z != 0.0 { x - w / z } else { x + z };
+						res[nn as usize - 1] = (x + z, 0.0);
+						res[nn as usize] = (second, 0.0);
+					} else {
+						res[nn as usize - 1] = (x + p, -z);
+						res[nn as usize] = (x + p, z);
+					}
+					nn -= 2;
+				} else {
+					if its == MAX_QR_ITERATIONS {
+						return None;
+					}
+
+					// Exceptional shift
+					if its == 10 || its == 20 {
+						t += x;
+						for i in 0..=nn {
+							a[at(i, i)] -= x;
+						}
+						let s = a[at(nn, nn - 1)].abs() + a[at(nn - 1, nn - 2)].abs();
+						x = 0.75 * s;
+						y = x;
+						w = -0.4375 * s * s;
+					}
+					its += 1;
+
+					// Look for two consecutive small subdiagonal elements
+					let (mut p, mut q, mut r);
+					let mut m = nn - 2;
+					loop {
+						let z = a[at(m, m)];
+						let rr = x - z;
+						let s = y - z;
+						p = (rr * s - w) / a[at(m + 1, m)] + a[at(m, m + 1)];
+						q = a[at(m + 1, m + 1)] - z - rr - s;
+						r = a[at(m + 2, m + 1)];
+
+						let s = p.abs() + q.abs() + r.abs();
+						p /= s;
+						q /= s;
+						r /= s;
+
+						if m == l {
+							break;
+						}
+						let u = a[at(m, m - 1)].abs() * (q.abs() + r.abs());
+						let v = p.abs()
+							* (a[at(m - 1, m - 1)].abs() + z.abs() + a[at(m + 1, m + 1)].abs());
+						if u + v == v {
+							break;
+						}
+						m -= 1;
+					}
+
+					for i in m + 2..=nn {
+						a[at(i, i - 2)] = 0.0;
+						if i != m + 2 {
+							a[at(i, i - 3)] = 0.0;
+						}
+					}
+
+					// Double QR step on rows l to nn and columns m to nn
+					for k in m..nn {
+						if k != m {
+							p = a[at(k, k - 1)];
+							q = a[at(k + 1, k - 1)];
+							r = if k != nn - 1 {
+								a[at(k + 2, k - 1)]
+							} else {
+								0.0
+							};
+							x = p.abs() + q.abs() + r.abs();
+							if x != 0.0 {
+								p /= x;
+								q /= x;
+								r /= x;
+							}
+						}
+
+						let s = (p * p + q * q + r * r).sqrt().copysign(p);
+						if s == 0.0 {
+							continue;
+						}
+
+						if k == m {
+							if l != m {
+								a[at(k, k - 1)] = -a[at(k, k - 1)];
+							}
+						} else {
+							a[at(k, k - 1)] = -s * x;
+						}
+
+						p += s;
+						x = p / s;
+						y = q / s;
+						let z = r / s;
+						q /= p;
+						r /= p;
+
+						for j in k..=nn {
+							let mut p = a[at(k, j)] + q * a[at(k + 1, j)];
+							if k != nn - 1 {
+								p += r * a[at(k + 2, j)];
+								a[at(k + 2, j)] -= p * z;
+							}
+							a[at(k + 1, j)] -= p * y;
+							a[at(k, j)] -= p * x;
+						}
+
+						for i in l..=nn.min(k + 3) {
+							let mut p = x * a[at(i, k)] + y * a[at(i, k + 1)];
+							if k != nn - 1 {
+								p += z * a[at(i, k + 2)];
+								a[at(i, k + 2)] -= p * r;
+							}
+							a[at(i, k + 1)] -= p * q;
+							a[at(i, k)] -= p;
+						}
+					}
+				}
+			}
+
+			if nn < 0 || l >= nn - 1 {
+				break;
+			}
+		}
+	}
+
+	Some(res)
+}
+
+#[cfg(test)]
+mod tests {
+	use {
+		super::*,
+		rand::{rngs::StdRng, Rng, SeedableRng},
+	};
+
+	fn random(rng: &mut StdRng, nrows: usize, ncols: usize) -> Matrix {
+		let data = (0..nrows * ncols)
+			.map(|_| rng.gen_range(-1.0..1.0))
+			.collect();
+		Matrix::new(nrows, ncols, data).unwrap()
+	}
+
+	// The largest difference relative to the bound on the rounding errors of the
+	// naive product, n ulps of the largest elements
+	fn assert_close(a: &Matrix, b: &Matrix) {
+		let naive = naive_matmul(a, b);
+		let res = matmul(a, b);
+		assert_eq!((res.nrows(), res.ncols()), (naive.nrows(), naive.ncols()));
+
+		let largest = |x: &Matrix| x.as_slice().iter().fold(0.0f64, |acc, y| acc.max(y.abs()));
+		let bound = 16.0 * f64::EPSILON * a.ncols() as f64 * largest(a) * largest(b);
+		for (x, y) in res.as_slice().iter().zip(naive.as_slice()) {
+			assert!(
+				(x - y).abs() <= bound,
+				"{x} and {y} differ by more than {bound}"
+			);
+		}
+	}
+
+	#[test]
+	fn strassen_square() {
+		let mut rng = StdRng::seed_from_u64(1);
+		let n = STRASSEN_THRESHOLD;
+		assert_close(&random(&mut rng, n, n), &random(&mut rng, n, n));
+	}
+
+	#[test]
+	fn strassen_odd() {
+		let mut rng = StdRng::seed_from_u64(2);
+		let n = STRASSEN_THRESHOLD + 1;
+		assert_close(&random(&mut rng, n, n), &random(&mut rng, n, n));
+	}
+
+	#[test]
+	fn strassen_rectangular() {
+		let mut rng = StdRng::seed_from_u64(3);
+		let (n, m, p) = (
+			STRASSEN_THRESHOLD + 7,
+			STRASSEN_THRESHOLD + 30,
+			STRASSEN_THRESHOLD + 2,
+		);
+		assert_close(&random(&mut rng, n, m), &random(&mut rng, m, p));
+	}
+
+	#[test]
+	fn strassen_magnitudes() {
+		let mut rng = StdRng::seed_from_u64(4);
+		let n = STRASSEN_THRESHOLD;
+		let a = scale(&random(&mut rng, n, n), 1e6);
+		let b = scale(&random(&mut rng, n, n), 1e-6);
+		assert_close(&a, &b);
+	}
+
+	#[test]
+	fn strassen_non_finite() {
+		let mut rng = StdRng::seed_from_u64(5);
+		let n = STRASSEN_THRESHOLD;
+		let mut a = random(&mut rng, n, n);
+		let mut b = random(&mut rng, n, n);
+		a[(0, 0)] = f64::INFINITY;
+		a[(n - 1, 1)] = 0.0;
+		b[(1, n - 1)] = f64::NEG_INFINITY;
+		b[(n - 1, 0)] = f64::NAN;
+
+		let naive = naive_matmul(&a, &b);
+		let res = matmul(&a, &b);
+		for (x, y) in res.as_slice().iter().zip(naive.as_slice()) {
+			assert!(x == y || (x.is_nan() && y.is_nan()), "{x} is not {y}");
+		}
+		assert!(naive[(n - 1, n - 1)].is_finite());
+	}
+}
@@ -0,0 +1,182 @@
+use super::Matrix;
+
+/*
+	Products, determinants and inverses of 2x2, 3x3 and 4x4 matrices with their
+	elements copied into arrays on the stack, so the loops are unrolled and no
+	decomposition is allocated. Rotations and homogeneous transforms, as used in
+	graphics and robotics, are of these sizes and often computed by the thousand.
+	Determinants and inverses come from cofactors, exact for integer matrices of
+	moderate size, and products add the terms in the same order as `matmul`.
+*/
+
+type Square<const N: usize> = [[f64; N]; N];
+
+/// Whether `a` is square and 2x2 to 4x4.
+pub fn fits(a: &Matrix) -> bool {
+	a.is_square() && (2..=4).contains(&a.nrows())
+}
+
+/// The product of a matrix that `fits` by a matrix of the same size or by a column,
+/// `None` for other shapes.
+pub fn matmul(a: &Matrix, b: &Matrix) -> Option<Matrix> {
+	if !fits(a) || b.nrows() != a.nrows() {
+		return None;
+	}
+
+	let res = match (a.nrows(), b.ncols()) {
+		(2, 1) => store(mul::<2, 1>(&load(a), &load(b))),
+		(2, 2) => store(mul::<2, 2>(&load(a), &load(b))),
+		(3, 1) => store(mul::<3, 1>(&load(a), &load(b))),
+		(3, 3) => store(mul::<3, 3>(&load(a), &load(b))),
+		(4, 1) => store(mul::<4, 1>(&load(a), &load(b))),
+		(4, 4) => store(mul::<4, 4>(&load(a), &load(b))),
+		_ => return None,
+	};
+	Some(res)
+}
+
+/// The determinant of a matrix that `fits`.
+pub fn det(a: &Matrix) -> f64 {
+	match a.nrows() {
+		2 => det2(&load(a)),
+		3 => cofactors3(&load(a)).0,
+		4 => cofactors4(&load(a)).0,
+		n => unreachable!("no closed form for {n}x{n} matrices"),
+	}
+}
+
+/// The inverse of a matrix that `fits`, `None` if it is singular: when its
+/// determinant is as small as with a pivot `lu` would take for zero.
+pub fn inverse(a: &Matrix) -> Option<Matrix> {
+	let n = a.nrows();
+	let (det, adjugate) = match n {
+		2 => {
+			let m: Square<2> = load(a);
+			let det = det2(&m);
+			(det, store([[m[1][1], -m[0][1]], [-m[1][0], m[0][0]]]))
+		},
+		3 => {
+			let (det, adjugate) = cofactors3(&load(a));
+			(det, store(adjugate))
+		},
+		4 => {
+			let (det, adjugate) = cofactors4(&load(a));
+			(det, store(adjugate))
+		},
+		n => unreachable!("no closed form for {n}x{n} matrices"),
+	};
+
+	let scale = a.as_slice().iter().fold(0.0f64, |acc, v| acc.max(v.abs()));
+	if det.abs() <= f64::EPSILON * n as f64 * scale.powi(n as i32) {
+		return None;
+	}
+	let data = adjugate.as_slice().iter().map(|x| x / det).collect();
+	Matrix::new(n, n, data)
+}
+
+fn load<const R: usize, const C: usize>(m: &Matrix) -> [[f64; C]; R] {
+	let mut res = [[0.0; C]; R];
+	for (row, values) in res.iter_mut().zip(m.as_slice().chunks_exact(C)) {
+		row.copy_from_slice(values);
+	}
+	res
+}
+
+fn store<const R: usize, const C: usize>(m: [[f64; C]; R]) -> Matrix {
+	Matrix::new(R, C, m.as_flattened().to_vec()).unwrap()
+}
+
+// Zeros of `a` are skipped like in `naive_matmul`, so 0 * Inf is left out the same way
+fn mul<const N: usize, const M: usize>(a: &Square<N>, b: &[[f64; M]; N]) -> [[f64; M]; N] {
+	let mut res = [[0.0; M]; N];
+	for (res_row, a_row) in res.iter_mut().zip(a) {
+		for (&x, b_row) in a_row.iter().zip(b) {
+			if x == 0.0 {
+				continue;
+			}
+			for (r, y) in res_row.iter_mut().zip(b_row) {
+				*r += x * y;
+			}
+		}
+	}
+	res
+}
+
+fn det2(m: &Square<2>) -> f64 {
+	m[0][0] * m[1][1] - m[0][1] * m[1][0]
+}
+
+// The determinant and the adjugate, expanding along the first row
+fn cofactors3(m: &Square<3>) -> (f64, Square<3>) {
+	let c00 = m[1][1] * m[2][2] - m[1][2] * m[2][1];
+	let c01 = m[1][2] * m[2][0] - m[1][0] * m[2][2];
+	let c02 = m[1][0] * m[2][1] - m[1][1] * m[2][0];
+	let det = m[0][0] * c00 + m[0][1] * c01 + m[0][2] * c02;
+
+	let adjugate = [
+		[
+			c00,
+			m[0][2] * m[2][1] - m[0][1] * m[2][2],
+			m[0][1] * m[1][2] - m[0][2] * m[1][1],
+		],
+		[
+			c01,
+			m[0][0] * m[2][2] - m[0][2] * m[2][0],
+			m[0][2] * m[1][0] - m[0][0] * m[1][2],
+		],
+		[
+			c02,
+			m[0][1] * m[2][0] - m[0][0] * m[2][1],
+			m[0][0] * m[1][1] - m[0][1] * m[1][0],
+		],
+	];
+	(det, adjugate)
+}
+
+// The determinant and the adjugate, from the 2x2 minors of the top two rows (s)
+// and of the bottom two (c) by Laplace's expansion
+fn cofactors4(m: &Square<4>) -> (f64, Square<4>) {
+	let s0 = m[0][0] * m[1][1] - m[1][0] * m[0][1];
+	let s1 = m[0][0] * m[1][2] - m[1][0] * m[0][2];
+	let s2 = m[0][0] * m[1][3] - m[1][0] * m[0][3];
+	let s3 = m[0][1] * m[1][2] - m[1][1] * m[0][2];
+	let s4 = m[0][1] * m[1][3] - m[1][1] * m[0][3];
+	let s5 = m[0][2] * m[1][3] - m[1][2] * m[0][3];
+
+	let c0 = m[2][0] * m[3][1] - m[3][0] * m[2][1];
+	let c1 = m[2][0] * m[3][2] - m[3][0] * m[2][2];
+	let c2 = m[2][0] * m[3][3] - m[3][0] * m[2][3];
+	let c3 = m[2][1] * m[3][2] - m[3][1] * m[2][2];
+	let c4 = m[2][1] * m[3][3] - m[3][1] * m[2][3];
+	let c5 = m[2][2] * m[3][3] - m[3][2] * m[2][3];
+
+	let det = s0 * c5 - s1 * c4 + s2 * c3 + s3 * c2 - s4 * c1 + s5 * c0;
+
+	let adjugate = [
+		[
+			m[1][1] * c5 - m[1][2] * c4 + m[1][3] * c3,
+			-m[0][1] * c5 + m[0][2] * c4 - m[0][3] * c3,
+			m[3][1] * s5 - m[3][2] * s4 + m[3][3] * s3,
+			-m[2][1] * s5 + m[2][2] * s4 - m[2][3] * s3,
+		],
+		[
+			-m[1][0] * c5 + m[1][2] * c2 - m[1][3] * c1,
+			m[0][0] * c5 - m[0][2] * c2 + m[0][3] * c1,
+			-m[3][0] * s5 + m[3][2] * s2 - m[3][3] * s1,
+			m[2][0] * s5 - m[2][2] * s2 + m[2][3] * s1,
+		],
+		[
+			m[1][0] * c4 - m[1][1] * c2 + m[1][3] * c0,
+			-m[0][0] * c4 + m[0][1] * c2 - m[0][3] * c0,
+			m[3][0] * s4 - m[3][1] * s2 + m[3][3] * s0,
+			-m[2][0] * s4 + m[2][1] * s2 - m[2][3] * s0,
+		],
+		[
+			-m[1][0] * c3 + m[1][1] * c1 - m[1][2] * c0,
+			m[0][0] * c3 - m[0][1] * c1 + m[0][2] * c0,
+			-m[3][0] * s3 + m[3][1] * s1 - m[3][2] * s0,
+			m[2][0] * s3 - m[2][1] * s1 + m[2][2] * s0,
+		],
+	];
+	(det, adjugate)
+}
@@ -1,15 +1,167 @@
-mod repl;
-mod state;
-
-mod ast;
-mod eval;
-mod lexer;
-
-use color_eyre::eyre::Result;
+use {
+	color_eyre::eyre::{eyre, Result},
+	nam::{
+		ast::ASTNode,
+		backend::Backend,
+		i18n::{tr, tr_with, Locale},
+		lexer, repl,
+	},
+};
 
 fn main() -> Result<()> {
 	color_eyre::install()?;
 
+	let args: Vec<String> = std::env::args().skip(1).collect();
+	if args.first().is_some_and(|arg| arg == "ast") {
+		return print_ast(&args[1..]);
+	}
+
+	// `nam run [--cell <n>] <path> [options]` runs a script instead of the REPL,
+	// and `nam test <path> [options]` runs each of its cells as a test
+	let (command, args) = match args.first().map(String::as_str) {
+		Some(command @ ("run" | "test")) => (Some(command), &args[1..]),
+		_ => (None, &args[..]),
+	};
+	let options = Options::parse(args, command)?;
+
 	let mut my_repl = repl::Repl::new();
+	my_repl.state.set_backend(options.backend);
+	if let Some(locale) = options.locale {
+		my_repl.set_locale(locale);
+	}
+	if let Some(seed) = options.deterministic_seed {
+		my_repl.state.make_deterministic(seed);
+	}
+
+	if let Some(path) = options.script {
+		let success = match command {
+			Some("test") => my_repl.run_tests(&path),
+			_ => my_repl.run_script(&path, options.cell),
+		};
+		if !success {
+			std::process::exit(1);
+		}
+		return Ok(());
+	}
 	my_repl.run()
 }
+
+struct Options {
+	/// `--deterministic [--seed <n>]` seeds the random generator, 0 by default,
+	/// and turns off the configuration file and the commands depending on the time.
+	deterministic_seed: Option<u64>,
+	/// `--backend <name>` chooses how statements are evaluated.
+	backend: Backend,
+	/// `--lang <code>` chooses the language of the messages, over the configuration file.
+	locale: Option<Locale>,
+	/// The script given to `nam run` or `nam test`.
+	script: Option<String>,
+	/// `--cell <n>` only runs the cell n of the script, from 1.
+	cell: Option<usize>,
+}
+
+impl Options {
+	fn parse(args: &[String], command: Option<&str>) -> Result<Self> {
+		let mut deterministic = false;
+		let mut seed = 0;
+		let mut backend = Backend::default();
+		let mut locale = None;
+		let mut script = None;
+		let mut cell = None;
+
+		let mut args = args.iter();
+		while let Some(arg) = args.next() {
+			match arg.as_str() {
+				"--deterministic" => deterministic = true,
+				"--seed" => {
+					seed = args
+						.next()
+						.and_then(|seed| seed.parse().ok())
+						.ok_or_else(|| eyre!(tr("--seed expects a non-negative integer")))?;
+				},
+				"--backend" => {
+					backend = args
+						.next()
+						.and_then(|name| Backend::from_name(name))
+						.ok_or_else(|| {
+							let names: Vec<&str> = Backend::ALL.iter().map(|b| b.name()).collect();
+							eyre!(tr_with(
+								"--backend expects one of: {0}",
+								&[&names.join(", ")]
+							))
+						})?;
+				},
+				"--lang" => {
+					locale = args
+						.next()
+						.and_then(|code| Locale::from_code(code))
+						.ok_or_else(|| {
+							let codes: Vec<&str> = Locale::ALL.iter().map(|l| l.code()).collect();
+							eyre!(tr_with("--lang expects one of: {0}", &[&codes.join(", ")]))
+						})
+						.map(Some)?;
+				},
+				"--cell" if command == Some("run") => {
+					cell = args
+						.next()
+						.and_then(|n| n.parse().ok())
+						.filter(|&n| n > 0)
+						.ok_or_else(|| eyre!(tr("--cell expects a positive integer")))
+						.map(Some)?;
+				},
+				path if command.is_some() && script.is_none() && !path.starts_with("--") => {
+					script = Some(path.to_string());
+				},
+				_ => return Err(eyre!(tr_with("Unknown argument '{0}'", &[arg]))),
+			}
+		}
+
+		if !deterministic && seed != 0 {
+			return Err(eyre!(tr("--seed is only used with --deterministic")));
+		}
+
+		if let Some(command) = command.filter(|_| script.is_none()) {
+			let usage = match command {
+				"test" => "nam test <path>",
+				_ => "nam run [--cell <n>] <path>",
+			};
+			return Err(eyre!(tr_with("Usage: {0}", &[&usage])));
+		}
+
+		Ok(Self {
+			deterministic_seed: deterministic.then_some(seed),
+			backend,
+			locale,
+			script,
+			cell,
+		})
+	}
+}
+
+/// `nam ast [--json] <code>` prints the syntax tree of a statement.
+fn print_ast(args: &[String]) -> Result<()> {
+	let (json, code) = match args.first().map(String::as_str) {
+		Some("--json") => (true, &args[1..]),
+		_ => (false, args),
+	};
+	if code.is_empty() {
+		return Err(eyre!(tr_with("Usage: {0}", &[&"nam ast [--json] <code>"])));
+	}
+
+	let tokens = lexer::try_tokenize(0, &code.join(" "))?;
+	let ast = ASTNode::try_from(&tokens)?;
+
+	if json {
+		#[cfg(not(feature = "serde"))]
+		return Err(eyre!(tr(
+			"JSON output needs nam built with the 'serde' feature"
+		)));
+
+		#[cfg(feature = "serde")]
+		println!("{}", serde_json::to_string_pretty(&ast)?);
+	} else {
+		println!("{ast:#?}");
+	}
+
+	Ok(())
+}
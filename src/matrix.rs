@@ -0,0 +1,580 @@
+#[cfg(any(feature = "nalgebra", feature = "ndarray"))]
+mod interop;
+
+use crate::{
+	format::{self, NumberFormat},
+	linalg,
+};
+
+/// A dense matrix of numbers stored in row-major order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Matrix {
+	nrows: usize,
+	ncols: usize,
+	data: Vec<f64>,
+
+	// Boxed so unlabeled matrices, nearly all of them, only pay for a pointer
+	labels: Option<Box<Labels>>,
+}
+
+/// Names of the rows and columns of a matrix, printed as headers. Indexing and
+/// transposing keep them, the results of computations have none.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Labels {
+	pub rows: Option<Vec<String>>,
+	pub cols: Option<Vec<String>>,
+}
+
+impl Matrix {
+	/// Creates a matrix from its shape and row-major elements,
+	/// returns `None` if the number of elements does not fit the shape.
+	pub fn new(nrows: usize, ncols: usize, data: Vec<f64>) -> Option<Self> {
+		if nrows * ncols != data.len() {
+			return None;
+		}
+
+		Some(Self {
+			nrows,
+			ncols,
+			data,
+			labels: None,
+		})
+	}
+
+	/// Names the rows and columns, returns `None` if there is not one name
+	/// per row or per column. Labels without row nor column names are removed.
+	pub fn with_labels(mut self, labels: Labels) -> Option<Self> {
+		let fits = |names: &Option<Vec<String>>, len| names.as_ref().is_none_or(|n| n.len() == len);
+		if !fits(&labels.rows, self.nrows) || !fits(&labels.cols, self.ncols) {
+			return None;
+		}
+
+		self.labels = match labels {
+			Labels {
+				rows: None,
+				cols: None,
+			} => None,
+			labels => Some(Box::new(labels)),
+		};
+		Some(self)
+	}
+
+	pub fn labels(&self) -> Option<&Labels> {
+		self.labels.as_deref()
+	}
+
+	pub fn zeros(nrows: usize, ncols: usize) -> Self {
+		Self {
+			nrows,
+			ncols,
+			data: vec![0.0; nrows * ncols],
+			labels: None,
+		}
+	}
+
+	pub fn identity(n: usize) -> Self {
+		let mut res = Self::zeros(n, n);
+		for i in 0..n {
+			res[(i, i)] = 1.0;
+		}
+		res
+	}
+
+	/// The permutation matrix with a 1 at `(i, p[i])` in every row, so that `P * A`
+	/// reorders the rows of `A` as `A(p, :)`. Returns `None` if `p` is not a
+	/// permutation of the (0-based) indices.
+	pub fn from_permutations_vector(p: &[usize]) -> Option<Self> {
+		let n = p.len();
+		let mut seen = vec![false; n];
+		let mut res = Self::zeros(n, n);
+
+		for (i, &j) in p.iter().enumerate() {
+			if j >= n || seen[j] {
+				return None;
+			}
+			seen[j] = true;
+			res[(i, j)] = 1.0;
+		}
+
+		Some(res)
+	}
+
+	pub fn nrows(&self) -> usize {
+		self.nrows
+	}
+
+	pub fn ncols(&self) -> usize {
+		self.ncols
+	}
+
+	pub fn len(&self) -> usize {
+		self.data.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.data.is_empty()
+	}
+
+	pub fn as_slice(&self) -> &[f64] {
+		&self.data
+	}
+
+	pub fn into_vec(self) -> Vec<f64> {
+		self.data
+	}
+
+	/// The elements of the `i`-th (0-based) row.
+	pub fn row(&self, i: usize) -> &[f64] {
+		assert!(i < self.nrows, "Matrix index out of bounds");
+		&self.data[i * self.ncols..(i + 1) * self.ncols]
+	}
+
+	/// The elements of the `j`-th (0-based) column, read in place.
+	pub fn col(&self, j: usize) -> std::iter::StepBy<std::slice::Iter<'_, f64>> {
+		assert!(j < self.ncols, "Matrix index out of bounds");
+		// Without rows there is no element to skip to
+		let start = if self.nrows == 0 { 0 } else { j };
+		self.data[start..].iter().step_by(self.ncols)
+	}
+
+	/// The rows from top to bottom, as slices of the matrix.
+	pub fn rows(&self) -> impl ExactSizeIterator<Item = &[f64]> + '_ {
+		(0..self.nrows).map(|i| self.row(i))
+	}
+
+	/// The columns from left to right, as iterators over the matrix.
+	pub fn cols(
+		&self,
+	) -> impl ExactSizeIterator<Item = std::iter::StepBy<std::slice::Iter<'_, f64>>> + '_ {
+		(0..self.ncols).map(|j| self.col(j))
+	}
+
+	pub fn is_square(&self) -> bool {
+		self.nrows == self.ncols
+	}
+
+	/// Whether the matrix is square and equal to its transpose,
+	/// up to `tolerance` times its largest element.
+	pub fn is_symmetric(&self, tolerance: f64) -> bool {
+		if !self.is_square() {
+			return false;
+		}
+
+		let scale = self.data.iter().fold(0.0f64, |acc, x| acc.max(x.abs()));
+		(0..self.nrows).all(|i| {
+			self.row(i)
+				.iter()
+				.zip(self.col(i))
+				.all(|(x, y)| (x - y).abs() <= tolerance * scale)
+		})
+	}
+
+	/// Whether every element off the main diagonal is zero.
+	pub fn is_diag(&self) -> bool {
+		self.is_triu() && self.is_tril()
+	}
+
+	/// Whether every element below the main diagonal is zero.
+	pub fn is_triu(&self) -> bool {
+		(0..self.nrows).all(|i| (0..i.min(self.ncols)).all(|j| self[(i, j)] == 0.0))
+	}
+
+	/// Whether every element above the main diagonal is zero.
+	pub fn is_tril(&self) -> bool {
+		(0..self.nrows).all(|i| (i + 1..self.ncols).all(|j| self[(i, j)] == 0.0))
+	}
+
+	/// Whether the matrix is upper or lower triangular.
+	pub fn is_triangular(&self) -> bool {
+		self.is_triu() || self.is_tril()
+	}
+
+	/// The number of non-zero diagonals below and above the main diagonal.
+	pub fn bandwidth(&self) -> (usize, usize) {
+		let (mut lower, mut upper) = (0, 0);
+		for i in 0..self.nrows {
+			for j in 0..self.ncols {
+				if self[(i, j)] != 0.0 {
+					lower = lower.max(i.saturating_sub(j));
+					upper = upper.max(j.saturating_sub(i));
+				}
+			}
+		}
+		(lower, upper)
+	}
+
+	/// Whether the matrix is symmetric positive definite,
+	/// checked by attempting a Cholesky decomposition.
+	pub fn is_posdef(&self, tolerance: f64) -> bool {
+		self.is_symmetric(tolerance) && linalg::cholesky(self).is_some()
+	}
+
+	/// Returns the sub-matrix made of the given (0-based) rows and columns, in order.
+	pub fn select(&self, rows: &[usize], cols: &[usize]) -> Self {
+		let mut data = Vec::with_capacity(rows.len() * cols.len());
+		for &i in rows {
+			for &j in cols {
+				data.push(self[(i, j)]);
+			}
+		}
+
+		let pick = |names: &Option<Vec<String>>, indices: &[usize]| {
+			names
+				.as_ref()
+				.map(|names| indices.iter().map(|&i| names[i].clone()).collect())
+		};
+		let labels = self.labels.as_ref().map(|labels| {
+			Box::new(Labels {
+				rows: pick(&labels.rows, rows),
+				cols: pick(&labels.cols, cols),
+			})
+		});
+
+		Self {
+			nrows: rows.len(),
+			ncols: cols.len(),
+			data,
+			labels,
+		}
+	}
+
+	/// Pads the matrix with zeros at the bottom and on the right to be at least
+	/// `nrows`x`ncols`. The labels are dropped if the shape changes.
+	pub fn grow_to(self, nrows: usize, ncols: usize) -> Self {
+		let (nrows, ncols) = (nrows.max(self.nrows), ncols.max(self.ncols));
+		if (nrows, ncols) == (self.nrows, self.ncols) {
+			return self;
+		}
+
+		let mut res = Self::zeros(nrows, ncols);
+		res.set_block(0, 0, &self).unwrap();
+		res
+	}
+
+	/// The matrix without the given (0-based) rows, which may repeat.
+	pub fn remove_rows(&self, rows: &[usize]) -> Self {
+		let kept: Vec<usize> = (0..self.nrows).filter(|i| !rows.contains(i)).collect();
+		self.select(&kept, &(0..self.ncols).collect::<Vec<_>>())
+	}
+
+	/// The matrix without the given (0-based) columns, which may repeat.
+	pub fn remove_cols(&self, cols: &[usize]) -> Self {
+		let kept: Vec<usize> = (0..self.ncols).filter(|j| !cols.contains(j)).collect();
+		self.select(&(0..self.nrows).collect::<Vec<_>>(), &kept)
+	}
+
+	pub fn transpose(&self) -> Self {
+		let mut res = Self::zeros(self.ncols, self.nrows);
+		for (i, row) in self.rows().enumerate() {
+			for (j, &x) in row.iter().enumerate() {
+				res[(j, i)] = x;
+			}
+		}
+
+		res.labels = self.labels.as_ref().map(|labels| {
+			Box::new(Labels {
+				rows: labels.cols.clone(),
+				cols: labels.rows.clone(),
+			})
+		});
+		res
+	}
+
+	/// Applies `f` to the elements of two matrices with the same shape, where a
+	/// dimension of 1 is repeated along the other matrix like in Octave, so a
+	/// scalar applies to every element and a row to every row. Returns the
+	/// mismatching dimensions if the shapes are not compatible.
+	pub fn broadcast(
+		&self,
+		other: &Self,
+		f: impl Fn(f64, f64) -> f64,
+	) -> Result<Self, (usize, usize)> {
+		let nrows = broadcast_dim(self.nrows, other.nrows)?;
+		let ncols = broadcast_dim(self.ncols, other.ncols)?;
+
+		let mut data = Vec::with_capacity(nrows * ncols);
+		for i in 0..nrows {
+			for j in 0..ncols {
+				let x = self[(i.min(self.nrows - 1), j.min(self.ncols - 1))];
+				let y = other[(i.min(other.nrows - 1), j.min(other.ncols - 1))];
+				data.push(f(x, y));
+			}
+		}
+
+		Ok(Self {
+			nrows,
+			ncols,
+			data,
+			labels: None,
+		})
+	}
+
+	/// Returns the `h`x`w` block whose top left element is at (0-based) `(r0, c0)`,
+	/// `None` if it does not fit in the matrix.
+	pub fn get_block(&self, r0: usize, c0: usize, h: usize, w: usize) -> Option<Self> {
+		if r0.checked_add(h)? > self.nrows || c0.checked_add(w)? > self.ncols {
+			return None;
+		}
+
+		let mut data = Vec::with_capacity(h * w);
+		for i in r0..r0 + h {
+			data.extend_from_slice(&self.data[i * self.ncols + c0..i * self.ncols + c0 + w]);
+		}
+
+		Some(Self {
+			nrows: h,
+			ncols: w,
+			data,
+			labels: None,
+		})
+	}
+
+	/// Overwrites the block whose top left element is at (0-based) `(r0, c0)` with `block`,
+	/// returns `None` and leaves the matrix unchanged if it does not fit.
+	pub fn set_block(&mut self, r0: usize, c0: usize, block: &Self) -> Option<()> {
+		if r0.checked_add(block.nrows)? > self.nrows || c0.checked_add(block.ncols)? > self.ncols {
+			return None;
+		}
+
+		for i in 0..block.nrows {
+			let start = (r0 + i) * self.ncols + c0;
+			self.data[start..start + block.ncols]
+				.copy_from_slice(&block.data[i * block.ncols..(i + 1) * block.ncols]);
+		}
+
+		Some(())
+	}
+
+	/// Concatenates `other` to the right of `self`,
+	/// empty matrices are skipped like in Octave.
+	pub fn hcat(self, other: Self) -> Result<Self, (usize, usize)> {
+		if self.is_empty() {
+			return Ok(other);
+		}
+		if other.is_empty() {
+			return Ok(self);
+		}
+		if self.nrows != other.nrows {
+			return Err((self.nrows, other.nrows));
+		}
+
+		let ncols = self.ncols + other.ncols;
+		let mut data = Vec::with_capacity(self.nrows * ncols);
+		for (row, other_row) in self.rows().zip(other.rows()) {
+			data.extend_from_slice(row);
+			data.extend_from_slice(other_row);
+		}
+
+		Ok(Self {
+			nrows: self.nrows,
+			ncols,
+			data,
+			labels: None,
+		})
+	}
+
+	/// Concatenates `other` below `self`,
+	/// empty matrices are skipped like in Octave.
+	pub fn vcat(mut self, other: Self) -> Result<Self, (usize, usize)> {
+		if self.is_empty() {
+			return Ok(other);
+		}
+		if other.is_empty() {
+			return Ok(self);
+		}
+		if self.ncols != other.ncols {
+			return Err((self.ncols, other.ncols));
+		}
+
+		self.nrows += other.nrows;
+		self.data.extend(other.data);
+		self.labels = None;
+		Ok(self)
+	}
+}
+
+fn broadcast_dim(a: usize, b: usize) -> Result<usize, (usize, usize)> {
+	match (a, b) {
+		_ if a == b => Ok(a),
+		(1, _) => Ok(b),
+		(_, 1) => Ok(a),
+		_ => Err((a, b)),
+	}
+}
+
+impl std::ops::Index<(usize, usize)> for Matrix {
+	type Output = f64;
+
+	fn index(&self, (i, j): (usize, usize)) -> &Self::Output {
+		assert!(
+			i < self.nrows && j < self.ncols,
+			"Matrix index out of bounds"
+		);
+		&self.data[i * self.ncols + j]
+	}
+}
+
+impl std::ops::IndexMut<(usize, usize)> for Matrix {
+	fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut Self::Output {
+		assert!(
+			i < self.nrows && j < self.ncols,
+			"Matrix index out of bounds"
+		);
+		&mut self.data[i * self.ncols + j]
+	}
+}
+
+impl From<f64> for Matrix {
+	fn from(value: f64) -> Self {
+		Self {
+			nrows: 1,
+			ncols: 1,
+			data: vec![value],
+			labels: None,
+		}
+	}
+}
+
+/// Builds a matrix from `(i, j, value)` triplets with 0-based indices, just large enough
+/// to hold them. Missing elements are 0 and repeated positions are added up.
+impl FromIterator<(usize, usize, f64)> for Matrix {
+	fn from_iter<I: IntoIterator<Item = (usize, usize, f64)>>(iter: I) -> Self {
+		let triplets: Vec<(usize, usize, f64)> = iter.into_iter().collect();
+		let nrows = triplets.iter().map(|&(i, _, _)| i + 1).max().unwrap_or(0);
+		let ncols = triplets.iter().map(|&(_, j, _)| j + 1).max().unwrap_or(0);
+
+		let mut res = Self::zeros(nrows, ncols);
+		for (i, j, value) in triplets {
+			res[(i, j)] += value;
+		}
+		res
+	}
+}
+
+/// Builds a matrix from its rows, which must all have the same length.
+impl TryFrom<Vec<Vec<f64>>> for Matrix {
+	type Error = MatrixError;
+
+	fn try_from(rows: Vec<Vec<f64>>) -> Result<Self, Self::Error> {
+		let ncols = rows.first().map_or(0, Vec::len);
+		let nrows = rows.len();
+
+		let mut data = Vec::with_capacity(nrows * ncols);
+		for (i, row) in rows.into_iter().enumerate() {
+			if row.len() != ncols {
+				return Err(MatrixError::RaggedRows {
+					row: i,
+					expected: ncols,
+					found: row.len(),
+				});
+			}
+			data.extend(row);
+		}
+
+		Ok(Self {
+			nrows,
+			ncols,
+			data,
+			labels: None,
+		})
+	}
+}
+
+/// Builds a matrix from its rows.
+impl<const M: usize, const N: usize> From<[[f64; N]; M]> for Matrix {
+	fn from(rows: [[f64; N]; M]) -> Self {
+		Self {
+			nrows: M,
+			ncols: N,
+			data: rows.into_iter().flatten().collect(),
+			labels: None,
+		}
+	}
+}
+
+/// Builds a matrix from `(nrows, ncols, data)` with row-major elements.
+impl TryFrom<(usize, usize, Vec<f64>)> for Matrix {
+	type Error = MatrixError;
+
+	fn try_from((nrows, ncols, data): (usize, usize, Vec<f64>)) -> Result<Self, Self::Error> {
+		let len = data.len();
+		Self::new(nrows, ncols, data).ok_or(MatrixError::ShapeMismatch { nrows, ncols, len })
+	}
+}
+
+/// Splits a matrix into `(nrows, ncols, data)` with row-major elements.
+impl From<Matrix> for (usize, usize, Vec<f64>) {
+	fn from(matrix: Matrix) -> Self {
+		(matrix.nrows, matrix.ncols, matrix.data)
+	}
+}
+
+/// The row-major elements.
+impl From<Matrix> for Vec<f64> {
+	fn from(matrix: Matrix) -> Self {
+		matrix.data
+	}
+}
+
+/// Iterates over the elements in row-major order.
+impl IntoIterator for Matrix {
+	type Item = f64;
+	type IntoIter = std::vec::IntoIter<f64>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.data.into_iter()
+	}
+}
+
+/// Iterates over the elements in row-major order.
+impl<'a> IntoIterator for &'a Matrix {
+	type Item = &'a f64;
+	type IntoIter = std::slice::Iter<'a, f64>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.data.iter()
+	}
+}
+
+impl std::fmt::Display for Matrix {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", format::matrix(self, NumberFormat::default()))
+	}
+}
+
+////////////////////////////////
+//       Error Handling       //
+////////////////////////////////
+
+#[derive(Debug)]
+pub enum MatrixError {
+	RaggedRows {
+		row: usize,
+		expected: usize,
+		found: usize,
+	},
+	ShapeMismatch {
+		nrows: usize,
+		ncols: usize,
+		len: usize,
+	},
+}
+
+impl std::error::Error for MatrixError {}
+impl std::fmt::Display for MatrixError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::RaggedRows {
+				row,
+				expected,
+				found,
+			} => write!(
+				f,
+				"Row {row} has {found} element(s), expected {expected} like the first row"
+			),
+			Self::ShapeMismatch { nrows, ncols, len } => {
+				write!(f, "A {nrows}x{ncols} matrix cannot hold {len} element(s)")
+			},
+		}
+	}
+}
@@ -0,0 +1,51 @@
+/*
+	Conversions with the matrix types of other crates, each behind
+	the cargo feature of the same name.
+*/
+
+use super::Matrix;
+
+#[cfg(feature = "nalgebra")]
+impl From<nalgebra::DMatrix<f64>> for Matrix {
+	fn from(matrix: nalgebra::DMatrix<f64>) -> Self {
+		let (nrows, ncols) = matrix.shape();
+		// nalgebra stores its elements column by column
+		let data = matrix.transpose().as_slice().to_vec();
+		Self {
+			nrows,
+			ncols,
+			data,
+			labels: None,
+		}
+	}
+}
+
+#[cfg(feature = "nalgebra")]
+impl From<Matrix> for nalgebra::DMatrix<f64> {
+	fn from(matrix: Matrix) -> Self {
+		nalgebra::DMatrix::from_row_slice(matrix.nrows, matrix.ncols, &matrix.data)
+	}
+}
+
+#[cfg(feature = "ndarray")]
+impl From<ndarray::Array2<f64>> for Matrix {
+	fn from(array: ndarray::Array2<f64>) -> Self {
+		let (nrows, ncols) = array.dim();
+		// Iterating follows the logical row-major order whatever the memory layout
+		let data = array.into_iter().collect();
+		Self {
+			nrows,
+			ncols,
+			data,
+			labels: None,
+		}
+	}
+}
+
+#[cfg(feature = "ndarray")]
+impl From<Matrix> for ndarray::Array2<f64> {
+	fn from(matrix: Matrix) -> Self {
+		ndarray::Array2::from_shape_vec((matrix.nrows, matrix.ncols), matrix.data)
+			.expect("the shape of a matrix always fits its elements")
+	}
+}
@@ -0,0 +1,47 @@
+use {
+	crate::{eval::EvaluationError, state::RuntimeVal, steps::Step, warning::Warning},
+	std::sync::{Arc, Mutex, PoisonError},
+};
+
+/// Callbacks for hosts that want to follow what the interpreter does,
+/// e.g. to keep a live view of the variables or to log the session.
+/// Every method does nothing by default.
+pub trait EngineObserver: Send {
+	/// Called whenever a variable is assigned, including `ans`.
+	fn on_assign(&mut self, _var_name: &str, _value: &RuntimeVal) {}
+
+	/// Called after a statement is evaluated successfully, with its result.
+	fn on_statement(&mut self, _result: &RuntimeVal) {}
+
+	/// Called when a statement fails to evaluate.
+	fn on_error(&mut self, _error: &EvaluationError) {}
+
+	/// Called when a result may be inaccurate, the statement goes on.
+	fn on_warning(&mut self, _warning: &Warning) {}
+
+	/// Called for each intermediate result of an algorithm while `:steps` is on.
+	fn on_step(&mut self, _step: &Step) {}
+
+	/// Called with the text a statement prints, like `name = value` or the table of
+	/// `summary`. The interpreter never writes to the standard output itself.
+	fn on_output(&mut self, _text: &str) {}
+}
+
+/// Keeps the printed texts for later, e.g. to print the outputs of a scope that
+/// ran on its own workspace in the workspace of the caller. Clones share the texts.
+#[derive(Clone, Default)]
+pub struct CapturedOutput(Arc<Mutex<Vec<String>>>);
+
+impl CapturedOutput {
+	/// The texts printed so far, in order, which are no longer kept.
+	pub fn take(&self) -> Vec<String> {
+		std::mem::take(&mut self.0.lock().unwrap_or_else(PoisonError::into_inner))
+	}
+}
+
+impl EngineObserver for CapturedOutput {
+	fn on_output(&mut self, text: &str) {
+		let mut texts = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+		texts.push(text.to_string());
+	}
+}
@@ -0,0 +1,258 @@
+use crate::ast::{ASTNode, ASTNodeKind, BinaryOpKind};
+
+/*
+	Simplifies a statement before it is evaluated: arithmetic on literals is
+	computed once, and adding 0 or multiplying by 1 is dropped when the other
+	operand is known to be a number or a matrix of numbers, as a string, a table
+	or a big integer would change type. Multiplying by 0 is kept, since NaN * 0
+	is NaN. It is turned off with `:optimize off` to see the statement evaluated
+	as written.
+
+	Nothing is hoisted out of `while` loops yet: an invariant expression would
+	need a variable of its own, seen in the workspace, and would be evaluated
+	even when the loop does not run.
+*/
+
+pub fn optimize(ast: ASTNode) -> ASTNode {
+	let ASTNode {
+		kind,
+		store_in_ans,
+		print_result,
+		span,
+	} = ast;
+
+	let kind = match kind {
+		ASTNodeKind::BinaryExpr(op, lhs, rhs) => simplify(
+			op,
+			Box::new(optimize(*lhs)),
+			Box::new(optimize(*rhs)),
+			store_in_ans,
+		),
+
+		ASTNodeKind::Negate(operand) => match optimize(*operand) {
+			ASTNode {
+				kind: ASTNodeKind::Number(n),
+				..
+			} => ASTNodeKind::Number(-n),
+			operand => ASTNodeKind::Negate(Box::new(operand)),
+		},
+		ASTNodeKind::Plus(operand) => match optimize(*operand) {
+			ASTNode {
+				kind: ASTNodeKind::Number(n),
+				..
+			} => ASTNodeKind::Number(n),
+			operand => ASTNodeKind::Plus(Box::new(operand)),
+		},
+		ASTNodeKind::Transpose(operand) => match optimize(*operand) {
+			ASTNode {
+				kind: ASTNodeKind::Number(n),
+				..
+			} => ASTNodeKind::Number(n),
+			operand => ASTNodeKind::Transpose(Box::new(operand)),
+		},
+
+		// NaN has no truth value, the evaluator reports it
+		ASTNodeKind::Not(operand) => match optimize(*operand) {
+			ASTNode {
+				kind: ASTNodeKind::Number(n),
+				..
+			} if !n.is_nan() => ASTNodeKind::Number((n == 0.0) as u8 as f64),
+			operand => ASTNodeKind::Not(Box::new(operand)),
+		},
+		ASTNodeKind::ShortAnd(lhs, rhs) => {
+			ASTNodeKind::ShortAnd(Box::new(optimize(*lhs)), Box::new(optimize(*rhs)))
+		},
+		ASTNodeKind::ShortOr(lhs, rhs) => {
+			ASTNodeKind::ShortOr(Box::new(optimize(*lhs)), Box::new(optimize(*rhs)))
+		},
+
+		ASTNodeKind::Matrix(rows) => ASTNodeKind::Matrix(
+			rows.into_iter()
+				.map(|row| row.into_iter().map(optimize).collect())
+				.collect(),
+		),
+		ASTNodeKind::Range(start, step, end) => ASTNodeKind::Range(
+			Box::new(optimize(*start)),
+			step.map(|step| Box::new(optimize(*step))),
+			Box::new(optimize(*end)),
+		),
+		ASTNodeKind::Assignment(var_name, value) => {
+			ASTNodeKind::Assignment(var_name, Box::new(optimize(*value)))
+		},
+		ASTNodeKind::IndexedAssignment(var_name, args, value) => ASTNodeKind::IndexedAssignment(
+			var_name,
+			args.into_iter().map(optimize).collect(),
+			Box::new(optimize(*value)),
+		),
+		ASTNodeKind::MultiAssignment(var_names, value) => {
+			ASTNodeKind::MultiAssignment(var_names, Box::new(optimize(*value)))
+		},
+		ASTNodeKind::Call(func_name, args) => {
+			ASTNodeKind::Call(func_name, args.into_iter().map(optimize).collect())
+		},
+		ASTNodeKind::KeywordArg(name, value) => {
+			ASTNodeKind::KeywordArg(name, Box::new(optimize(*value)))
+		},
+		ASTNodeKind::Field(base, field_name) => {
+			ASTNodeKind::Field(Box::new(optimize(*base)), field_name)
+		},
+		ASTNodeKind::If(branches, otherwise) => ASTNodeKind::If(
+			branches
+				.into_iter()
+				.map(|(condition, body)| {
+					(
+						optimize(condition),
+						body.into_iter().map(optimize).collect(),
+					)
+				})
+				.collect(),
+			otherwise.into_iter().map(optimize).collect(),
+		),
+		ASTNodeKind::While(condition, body) => ASTNodeKind::While(
+			Box::new(optimize(*condition)),
+			body.into_iter().map(optimize).collect(),
+		),
+
+		kind @ (ASTNodeKind::Variable(_)
+		| ASTNodeKind::Number(_)
+		| ASTNodeKind::String(_)
+		| ASTNodeKind::Colon
+		| ASTNodeKind::End
+		| ASTNodeKind::FunctionRef(_)) => kind,
+	};
+
+	ASTNode {
+		kind,
+		store_in_ans,
+		print_result,
+		span,
+	}
+}
+
+/// The simplified form of `lhs op rhs`, the expression itself if there is none.
+fn simplify(
+	op: BinaryOpKind,
+	lhs: Box<ASTNode>,
+	rhs: Box<ASTNode>,
+	store_in_ans: bool,
+) -> ASTNodeKind {
+	if let (Some(x), Some(y)) = (number(&lhs), number(&rhs)) {
+		let folded = match op {
+			BinaryOpKind::Add => x + y,
+			BinaryOpKind::Subtract => x - y,
+			BinaryOpKind::Multiply => x * y,
+			BinaryOpKind::Divide => x / y,
+			BinaryOpKind::Power => x.powf(y),
+			BinaryOpKind::Equal => (x == y) as u8 as f64,
+			BinaryOpKind::NotEqual => (x != y) as u8 as f64,
+			BinaryOpKind::Less => (x < y) as u8 as f64,
+			BinaryOpKind::Greater => (x > y) as u8 as f64,
+			BinaryOpKind::LessEqual => (x <= y) as u8 as f64,
+			BinaryOpKind::GreaterEqual => (x >= y) as u8 as f64,
+			// Left to the evaluator, which fails on NaN
+			BinaryOpKind::And | BinaryOpKind::Or if x.is_nan() || y.is_nan() => f64::NAN,
+			BinaryOpKind::And => (x != 0.0 && y != 0.0) as u8 as f64,
+			BinaryOpKind::Or => (x != 0.0 || y != 0.0) as u8 as f64,
+		};
+		// Left to the evaluator, which fails on them in strict mode
+		if folded.is_finite() {
+			return ASTNodeKind::Number(folded);
+		}
+	}
+
+	let keep_lhs = match op {
+		BinaryOpKind::Add | BinaryOpKind::Subtract => number(&rhs) == Some(0.0) && numeric(&lhs),
+		BinaryOpKind::Multiply | BinaryOpKind::Divide => number(&rhs) == Some(1.0) && numeric(&lhs),
+		// `A^1` fails for a matrix that is not square
+		BinaryOpKind::Power => number(&rhs) == Some(1.0) && square(&lhs),
+		BinaryOpKind::Equal
+		| BinaryOpKind::NotEqual
+		| BinaryOpKind::Less
+		| BinaryOpKind::Greater
+		| BinaryOpKind::LessEqual
+		| BinaryOpKind::GreaterEqual
+		| BinaryOpKind::And
+		| BinaryOpKind::Or => false,
+	};
+	let keep_rhs = match op {
+		BinaryOpKind::Add => number(&lhs) == Some(0.0) && numeric(&rhs),
+		BinaryOpKind::Multiply => number(&lhs) == Some(1.0) && numeric(&rhs),
+		BinaryOpKind::Subtract
+		| BinaryOpKind::Divide
+		| BinaryOpKind::Power
+		| BinaryOpKind::Equal
+		| BinaryOpKind::NotEqual
+		| BinaryOpKind::Less
+		| BinaryOpKind::Greater
+		| BinaryOpKind::LessEqual
+		| BinaryOpKind::GreaterEqual
+		| BinaryOpKind::And
+		| BinaryOpKind::Or => false,
+	};
+
+	// A variable on its own prints as `x = ...` and is not stored in ans
+	let liftable =
+		|node: &ASTNode| !(store_in_ans && matches!(node.kind, ASTNodeKind::Variable(_)));
+
+	match (keep_lhs, keep_rhs) {
+		(true, _) if liftable(&lhs) => lhs.kind,
+		(_, true) if liftable(&rhs) => rhs.kind,
+		_ => ASTNodeKind::BinaryExpr(op, lhs, rhs),
+	}
+}
+
+fn number(node: &ASTNode) -> Option<f64> {
+	match node.kind {
+		ASTNodeKind::Number(n) => Some(n),
+		_ => None,
+	}
+}
+
+// Whether the node evaluates to a number or a matrix of numbers, if it evaluates,
+// e.g. a variable may hold a string
+fn numeric(node: &ASTNode) -> bool {
+	match &node.kind {
+		ASTNodeKind::Number(_) => true,
+		ASTNodeKind::Negate(operand)
+		| ASTNodeKind::Plus(operand)
+		| ASTNodeKind::Transpose(operand) => numeric(operand),
+		ASTNodeKind::BinaryExpr(op, lhs, rhs) => arithmetic(op) && numeric(lhs) && numeric(rhs),
+		ASTNodeKind::Matrix(rows) => rows.iter().flatten().all(numeric),
+		ASTNodeKind::Range(start, step, end) => {
+			numeric(start) && step.as_deref().is_none_or(numeric) && numeric(end)
+		},
+		_ => false,
+	}
+}
+
+fn scalar(node: &ASTNode) -> bool {
+	match &node.kind {
+		ASTNodeKind::Number(_) => true,
+		ASTNodeKind::Negate(operand)
+		| ASTNodeKind::Plus(operand)
+		| ASTNodeKind::Transpose(operand) => scalar(operand),
+		ASTNodeKind::BinaryExpr(op, lhs, rhs) => arithmetic(op) && scalar(lhs) && scalar(rhs),
+		_ => false,
+	}
+}
+
+// A number, or a matrix literal of as many numbers on each row as there are rows
+fn square(node: &ASTNode) -> bool {
+	match &node.kind {
+		ASTNodeKind::Matrix(rows) => rows
+			.iter()
+			.all(|row| row.len() == rows.len() && row.iter().all(scalar)),
+		_ => scalar(node),
+	}
+}
+
+fn arithmetic(op: &BinaryOpKind) -> bool {
+	matches!(
+		op,
+		BinaryOpKind::Add
+			| BinaryOpKind::Subtract
+			| BinaryOpKind::Multiply
+			| BinaryOpKind::Divide
+			| BinaryOpKind::Power
+	)
+}
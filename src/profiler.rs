@@ -0,0 +1,258 @@
+use {
+	crate::ffi::json_string,
+	std::{
+		collections::{BTreeMap, HashMap},
+		fmt::Write,
+		time::{Duration, Instant},
+	},
+};
+
+/// Cumulative time and call counts of the statements and builtins evaluated
+/// while profiling is on, see `:profile`. Every statement, builtin call and
+/// arithmetic operation is also kept as a span to be exported as a trace.
+#[derive(Debug, Default)]
+pub struct Profiler {
+	enabled: bool,
+	statements: HashMap<String, Entry>,
+	builtins: HashMap<String, Entry>,
+
+	// The spans are timed from the first time profiling was turned on
+	origin: Option<Instant>,
+	spans: Vec<Span>,
+}
+
+/// What a span of the trace measures.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpanKind {
+	Statement,
+	Builtin,
+	/// An arithmetic operation on numbers or matrices, like `A * B`.
+	Operation,
+}
+
+impl SpanKind {
+	fn name(self) -> &'static str {
+		match self {
+			Self::Statement => "statement",
+			Self::Builtin => "builtin",
+			Self::Operation => "operation",
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct Span {
+	pub kind: SpanKind,
+	pub name: String,
+	/// Since profiling was first turned on.
+	pub start: Duration,
+	pub duration: Duration,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Entry {
+	pub count: usize,
+	pub total: Duration,
+}
+
+impl Entry {
+	fn add(&mut self, elapsed: Duration) {
+		self.count += 1;
+		self.total += elapsed;
+	}
+}
+
+// Longer statements are cut in the report
+const MAX_STATEMENT_WIDTH: usize = 40;
+
+// Later spans are dropped, so a long loop does not fill the memory
+const MAX_SPANS: usize = 1_000_000;
+
+impl Profiler {
+	pub fn is_enabled(&self) -> bool {
+		self.enabled
+	}
+
+	/// Starts or stops recording, what was recorded so far is kept.
+	pub fn set_enabled(&mut self, enabled: bool) {
+		self.enabled = enabled;
+		if enabled {
+			self.origin.get_or_insert_with(Instant::now);
+		}
+	}
+
+	/// Forgets everything recorded.
+	pub fn clear(&mut self) {
+		self.statements.clear();
+		self.builtins.clear();
+		self.spans.clear();
+		self.origin = self.enabled.then(Instant::now);
+	}
+
+	/// Records a statement that started at `start` and just ended.
+	pub fn record_statement(&mut self, source: &str, start: Instant) {
+		if self.enabled {
+			let elapsed = self.record_span(SpanKind::Statement, source, start);
+			self.statements
+				.entry(source.to_string())
+				.or_default()
+				.add(elapsed);
+		}
+	}
+
+	/// Records a call to a builtin, the time includes the builtins it called.
+	pub fn record_builtin(&mut self, func_name: &str, start: Instant) {
+		if self.enabled {
+			let elapsed = self.record_span(SpanKind::Builtin, func_name, start);
+			self.builtins
+				.entry(func_name.to_string())
+				.or_default()
+				.add(elapsed);
+		}
+	}
+
+	/// Records an arithmetic operation, only kept in the trace.
+	pub fn record_operation(&mut self, operation: &str, start: Instant) {
+		if self.enabled {
+			self.record_span(SpanKind::Operation, operation, start);
+		}
+	}
+
+	fn record_span(&mut self, kind: SpanKind, name: &str, start: Instant) -> Duration {
+		let duration = start.elapsed();
+		if self.spans.len() < MAX_SPANS {
+			let origin = *self.origin.get_or_insert(start);
+			self.spans.push(Span {
+				kind,
+				name: name.to_string(),
+				start: start.saturating_duration_since(origin),
+				duration,
+			});
+		}
+		duration
+	}
+
+	/// The spans by start time, the outer ones before the spans they contain.
+	pub fn spans(&self) -> Vec<&Span> {
+		let mut spans: Vec<&Span> = self.spans.iter().collect();
+		spans.sort_by(|a, b| {
+			a.start
+				.cmp(&b.start)
+				.then_with(|| b.duration.cmp(&a.duration))
+		});
+		spans
+	}
+
+	pub fn statements(&self) -> &HashMap<String, Entry> {
+		&self.statements
+	}
+
+	pub fn builtins(&self) -> &HashMap<String, Entry> {
+		&self.builtins
+	}
+
+	/// The statements and the builtins as two tables, the slowest first.
+	pub fn report(&self) -> String {
+		let mut res = String::new();
+		res.push_str(&table("statement", &self.statements));
+		res.push('\n');
+		res.push_str(&table("builtin", &self.builtins));
+		res
+	}
+
+	/// The spans in the Chrome tracing format, for chrome://tracing or Perfetto.
+	pub fn chrome_trace(&self) -> String {
+		let events: Vec<String> = self
+			.spans()
+			.into_iter()
+			.map(|span| {
+				format!(
+					"{{\"name\": {}, \"cat\": \"{}\", \"ph\": \"X\", \"ts\": {}, \"dur\": {}, \"pid\": 1, \"tid\": 1}}",
+					json_string(&span.name),
+					span.kind.name(),
+					span.start.as_micros(),
+					span.duration.as_micros()
+				)
+			})
+			.collect();
+		format!("{{\"traceEvents\": [\n{}\n]}}\n", events.join(",\n"))
+	}
+
+	/// The spans as folded stacks, one `outer;inner self_time` line per stack with
+	/// the time in microseconds spent in the innermost span itself, for flamegraph tools.
+	pub fn folded_stacks(&self) -> String {
+		let mut totals: BTreeMap<String, u128> = BTreeMap::new();
+		let mut add = |stack: String, time: u128| *totals.entry(stack).or_default() += time;
+
+		// The open spans, with their end, their stack and their time minus their children's
+		let mut open: Vec<(Duration, String, u128)> = vec![];
+		for span in self.spans() {
+			while open.last().is_some_and(|(end, _, _)| *end <= span.start) {
+				let (_, stack, time) = open.pop().unwrap();
+				add(stack, time);
+			}
+
+			let duration = span.duration.as_micros();
+			let name = span.name.replace([';', '\n'], " ");
+			let stack = match open.last_mut() {
+				Some((_, parent, parent_time)) => {
+					*parent_time = parent_time.saturating_sub(duration);
+					format!("{parent};{name}")
+				},
+				None => name,
+			};
+			open.push((span.start + span.duration, stack, duration));
+		}
+		while let Some((_, stack, time)) = open.pop() {
+			add(stack, time);
+		}
+
+		let mut res = String::new();
+		for (stack, time) in totals {
+			let _ = writeln!(res, "{stack} {time}");
+		}
+		res
+	}
+}
+
+fn table(title: &str, entries: &HashMap<String, Entry>) -> String {
+	let mut entries: Vec<(String, Entry)> = entries
+		.iter()
+		.map(|(name, entry)| (shorten(name), *entry))
+		.collect();
+	entries.sort_by(|a, b| b.1.total.cmp(&a.1.total).then_with(|| a.0.cmp(&b.0)));
+
+	let width = entries
+		.iter()
+		.map(|(name, _)| name.chars().count())
+		.chain([title.len()])
+		.max()
+		.unwrap();
+
+	let mut res = String::new();
+	let _ = writeln!(
+		res,
+		"  {title:<width$}  {:>8}  {:>12}  {:>12}",
+		"calls", "total (ms)", "mean (ms)"
+	);
+	if entries.is_empty() {
+		let _ = writeln!(res, "  (none)");
+	}
+	for (name, entry) in entries {
+		let total = entry.total.as_secs_f64() * 1e3;
+		let _ = writeln!(
+			res,
+			"  {name:<width$}  {:>8}  {total:>12.3}  {:>12.3}",
+			entry.count,
+			total / entry.count as f64
+		);
+	}
+	res
+}
+
+fn shorten(source: &str) -> String {
+	match source.char_indices().nth(MAX_STATEMENT_WIDTH) {
+		Some((end, _)) => format!("{}...", &source[..end]),
+		None => source.to_string(),
+	}
+}
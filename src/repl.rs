@@ -1,30 +1,119 @@
+mod autosave;
+mod commands;
+mod paste;
+mod theme;
+
 use {
-	crate::{ast, eval, lexer, state::State},
-	color_eyre::eyre::Result,
+	crate::{
+		ast, config, dirs, format,
+		i18n::{self, tr, tr_with, Locale},
+		lexer::{self, LexerConfig},
+		matrix::Matrix,
+		observer::EngineObserver,
+		script::Script,
+		state::{RuntimeVal, State},
+		steps::Step,
+		warning::Warning,
+	},
+	autosave::Autosave,
+	color_eyre::eyre::{eyre, Report, Result},
+	commands::{Command, Commands},
 	reedline::Signal,
+	std::{
+		sync::{atomic::Ordering, Arc, Mutex, PoisonError},
+		time::Instant,
+	},
+	theme::{Palette, SharedPalette, SyntaxHighlighter},
 };
 
 pub struct Repl {
 	pub is_running: bool,
 	pub state: State,
+	pub commands: Commands,
+
+	// Set while running the configuration file, so it is not written back to
+	loading_config: bool,
+
+	// Not used in deterministic mode
+	autosave: Option<Autosave>,
+
+	// The workspace file of the last `:save`, which `:diff` compares with
+	last_save: Option<String>,
+
+	// The script opened with `:cell <path>` and the cell last run, from 1
+	script: Option<String>,
+	cell: usize,
+
+	// Set by `nam --lang`, the configuration file does not change the language then
+	locale_fixed: bool,
+
+	theme: &'static str,
+	palette: SharedPalette,
+	// What the syntax highlighter tokenizes with, updated before reading each line
+	lexer_config: Arc<Mutex<LexerConfig>>,
 }
 
 impl Repl {
 	pub fn new() -> Self {
+		let palette = Arc::new(Mutex::new(Palette::initial()));
+		let mut state = State::new();
+		state.add_observer(Box::new(Printer {
+			palette: Arc::clone(&palette),
+		}));
+
 		Repl {
 			is_running: false,
-			state: State::new(),
+			state,
+			commands: Commands::default(),
+			loading_config: false,
+			autosave: None,
+			last_save: None,
+			script: None,
+			cell: 0,
+			locale_fixed: false,
+			theme: "default",
+			palette,
+			lexer_config: Arc::default(),
 		}
 	}
 
+	/// Chooses the language of the messages, over the one of the configuration file.
+	pub fn set_locale(&mut self, locale: Locale) {
+		i18n::set_locale(locale);
+		self.locale_fixed = true;
+	}
+
 	pub fn run(&mut self) -> Result<()> {
-		let mut line_editor = reedline::Reedline::create();
-		let prompt = Prompt::default();
+		// Pasted text arrives at once, so blocks of numbers can be recognized
+		let mut line_editor = reedline::Reedline::create()
+			.use_bracketed_paste(true)
+			.with_highlighter(Box::new(SyntaxHighlighter {
+				palette: Arc::clone(&self.palette),
+				lexer_config: Arc::clone(&self.lexer_config),
+			}))
+			.with_validator(Box::new(BlockValidator {
+				lexer_config: Arc::clone(&self.lexer_config),
+			}));
+		if let Some(history) = self.history() {
+			line_editor = line_editor.with_history(Box::new(history));
+		}
+
+		// While reading a line the terminal is in raw mode and Ctrl-C is a key press,
+		// while evaluating it is a signal that only stops the foreground evaluation
+		let interrupt_flag = self.state.interrupt_flag();
+		ctrlc::set_handler(move || interrupt_flag.store(true, Ordering::Relaxed))?;
 
 		println!("\nNamLang v{}", env!("CARGO_PKG_VERSION"));
+		self.run_config();
+		self.start_autosave();
 
 		self.is_running = true;
 		while self.is_running {
+			*self.lexer_config.lock().unwrap() = self.state.settings().lexer_config();
+			let prompt = Prompt {
+				color: theme::prompt_color(theme::lock(&self.palette).prompt),
+			};
+
 			let sig = line_editor.read_line(&prompt)?;
 			match sig {
 				Signal::CtrlD => break,
@@ -32,62 +121,433 @@ impl Repl {
 				Signal::Success(input) => {
 					let r = self.on_prompt(input);
 					if let Err(e) = r {
-						eprintln!("{e:?}");
+						self.print_error(&e);
 					}
+					self.autosave();
 				},
 			}
 		}
 
-		println!("\nGoodbye!");
+		// An error above leaves the autosaved workspace, to be restored at the next start
+		if let Some(autosave) = &self.autosave {
+			if let Err(e) = autosave.discard() {
+				eprintln!(
+					"{}",
+					tr_with("Could not remove the autosaved workspace: {0}", &[&e])
+				);
+			}
+		}
+
+		println!("\n{}", tr("Goodbye!"));
 		std::io::Write::flush(&mut std::io::stdout())?;
 		Ok(())
 	}
 
+	/// Runs a script file after the configuration file, or only its cell `cell`.
+	/// Returns whether every statement ran, the first error is printed and stops the script.
+	pub fn run_script(&mut self, path: &str, cell: Option<usize>) -> bool {
+		self.run_config();
+
+		let res = Script::read(path).map_err(Into::into).and_then(|script| {
+			let lines: Vec<(usize, String)> = match cell {
+				Some(n) => script.cell(n)?.lines.clone(),
+				None => script.lines().cloned().collect(),
+			};
+			self.run_lines(path, &lines)
+		});
+		if let Err(e) = &res {
+			self.print_error(e);
+		}
+		res.is_ok()
+	}
+
+	/// Runs every cell of a script as a test, in order and in the same workspace, after
+	/// the configuration file. Every statement of a cell runs, even several on a line,
+	/// and a cell fails at its first error, which is printed, while the next cells
+	/// still run. Returns whether every cell passed.
+	pub fn run_tests(&mut self, path: &str) -> bool {
+		self.run_config();
+
+		let script = match Script::read(path) {
+			Ok(script) => script,
+			Err(e) => {
+				self.print_error(&e.into());
+				return false;
+			},
+		};
+
+		let mut passed = 0;
+		for (i, cell) in script.cells.iter().enumerate() {
+			let name = match cell.title.is_empty() {
+				true => tr_with("cell {0}", &[&(i + 1)]),
+				false => cell.title.clone(),
+			};
+			match self.run_lines(path, &cell.lines) {
+				Ok(()) => {
+					println!("\n{name} ... ok");
+					passed += 1;
+				},
+				Err(e) => {
+					println!("\n{name} ... {}", tr("FAILED"));
+					self.print_error(&e);
+				},
+			}
+		}
+
+		let count = script.cells.len();
+		println!(
+			"\n{}",
+			tr_with("{0} of {1} tests passed", &[&passed, &count])
+		);
+		passed == count
+	}
+
+	// Stops at the first statement that fails, the error tells its line. The lines
+	// between meta-commands are parsed together, so a statement like a matrix or an
+	// `if ... end` may span several lines and a line may hold several statements
+	fn run_lines(&mut self, path: &str, lines: &[(usize, String)]) -> Result<()> {
+		let mut code: Vec<(usize, String)> = vec![];
+		for (line_number, line) in lines {
+			if self.is_command(line) && !self.opens_block(&code) {
+				self.run_code(path, &std::mem::take(&mut code))?;
+				self.run_line(path, *line_number, line.clone())?;
+				continue;
+			}
+			code.push((*line_number, line.clone()));
+		}
+
+		self.run_code(path, &code)
+	}
+
+	fn run_code(&mut self, path: &str, code: &[(usize, String)]) -> Result<()> {
+		let source: Vec<&str> = code.iter().map(|(_, line)| line.as_str()).collect();
+		self.evaluate(&source.join("\n")).map_err(|(line, e)| {
+			let line_number = code.get(line - 1).map_or(line, |(number, _)| *number);
+			line_error(path, line_number, e)
+		})
+	}
+
+	fn run_line(&mut self, path: &str, line_number: usize, input: String) -> Result<()> {
+		self.on_prompt(input)
+			.map_err(|e| line_error(path, line_number, e))
+	}
+
+	fn is_command(&self, input: &str) -> bool {
+		let input = input.trim();
+		input.starts_with(':') || self.find_command(input).is_some()
+	}
+
+	fn opens_block(&self, code: &[(usize, String)]) -> bool {
+		let source: Vec<&str> = code.iter().map(|(_, line)| line.as_str()).collect();
+		opens_block(&source.join("\n"), &self.state.settings().lexer_config())
+	}
+
+	// The history of the prompt is kept between sessions, but not in deterministic mode
+	fn history(&self) -> Option<reedline::FileBackedHistory> {
+		if self.state.is_deterministic() {
+			return None;
+		}
+
+		let path = dirs::history_file()?;
+		match reedline::FileBackedHistory::with_file(reedline::HISTORY_SIZE, path) {
+			Ok(history) => Some(history),
+			Err(e) => {
+				eprintln!("{}", tr_with("Could not open the history file: {0}", &[&e]));
+				None
+			},
+		}
+	}
+
+	// Offers to restore the workspace of a session that did not exit normally
+	fn start_autosave(&mut self) {
+		if self.state.is_deterministic() {
+			return;
+		}
+		let Some(autosave) = Autosave::new() else {
+			return;
+		};
+
+		if autosave.exists() {
+			let restore = ask(tr(
+				"The previous session did not exit normally, restore its workspace? [y/N] ",
+			));
+			let res = match restore {
+				true => autosave.restore(&mut self.state).map(|count| {
+					println!("\n{}", tr_with("Restored {0} variable(s)", &[&count]));
+				}),
+				false => autosave.discard(),
+			};
+			if let Err(e) = res {
+				eprintln!("{}", tr_with("Could not restore the workspace: {0}", &[&e]));
+			}
+		}
+
+		self.autosave = Some(autosave);
+	}
+
+	fn autosave(&mut self) {
+		if let Some(autosave) = &mut self.autosave {
+			if let Err(e) = autosave.tick(&self.state) {
+				eprintln!(
+					"{}",
+					tr_with("Could not autosave the workspace: {0}", &[&e])
+				);
+			}
+		}
+	}
+
+	fn run_config(&mut self) {
+		// The configuration file is found from the environment
+		if self.state.is_deterministic() {
+			return;
+		}
+
+		let lines = match config::read() {
+			Ok(lines) => lines,
+			Err(e) => {
+				eprintln!(
+					"{}",
+					tr_with("Could not read the configuration file: {0}", &[&e])
+				);
+				return;
+			},
+		};
+
+		self.loading_config = true;
+		for (line_number, line) in lines {
+			if let Err(e) = self.on_prompt(line) {
+				eprintln!(
+					"{}",
+					tr_with("Configuration file, line {0}: {1}", &[&line_number, &e])
+				);
+			}
+		}
+		self.loading_config = false;
+	}
+
 	fn on_prompt(&mut self, input: String) -> Result<()> {
-		if input.trim() == "exit" {
-			self.is_running = false;
+		let input = input.trim();
+		if input.is_empty() {
 			return Ok(());
 		}
 
-		if input.trim().is_empty() {
-			return Ok(());
+		// Rows of numbers pasted from a spreadsheet become a matrix
+		if paste::looks_pasted(input) {
+			let decimal_comma = self.state.settings().decimal_comma;
+			if let Some(mat) = paste::numeric_block(input, decimal_comma) {
+				self.assign_pasted(String::from("ans"), mat);
+				return Ok(());
+			}
+		}
+
+		if let Some((command, args)) = self.find_command(input) {
+			return (command.handler)(self, args);
+		}
+
+		if let Some(name) = input.strip_prefix(':') {
+			let name = name.split_whitespace().next().unwrap_or("");
+			return Err(eyre!(tr_with(
+				"Unknown command ':{0}', type :help to list the commands",
+				&[&name]
+			)));
 		}
 
-		let tokens = lexer::try_tokenize(0, input.as_str())?;
-		let ast = ast::ASTNode::try_from(&tokens)?;
-		eval::evaluate(ast, &mut self.state)?;
+		self.evaluate(input).map_err(|(_, e)| e)
+	}
+
+	/// Looks for a meta-command, either `:name args` or `name args` for the commands
+	/// that allow it, but not an assignment to or a use of a variable named like a command.
+	fn find_command<'a>(&self, input: &'a str) -> Option<(&Command, &'a str)> {
+		let (name, args) = input.split_once(' ').unwrap_or((input, ""));
+		let args = args.trim();
+
+		match name.strip_prefix(':') {
+			Some(name) => self.commands.get(name).map(|c| (c, args)),
+			None if !args.starts_with('=') && !self.is_var(name) => self
+				.commands
+				.get(name)
+				.filter(|c| c.bare)
+				.map(|c| (c, args)),
+			None => None,
+		}
+	}
+
+	// Aliases are written to the configuration file, unless they come from it
+	fn saves_config(&self) -> bool {
+		!self.loading_config && !self.state.is_deterministic()
+	}
+
+	// Errors may quote the input, which could hold escape sequences
+	fn print_error(&self, error: &color_eyre::Report) {
+		let message = tr_with("error: {0}", &[&format!("{error:#}")]);
+		let style = theme::stderr_style(theme::lock(&self.palette).error);
+		eprintln!("\n{}", style.paint(format::escape_controls(&message)));
+	}
+
+	fn assign_pasted(&mut self, var_name: String, mat: Matrix) {
+		let value = RuntimeVal::Matrix(mat);
+		println!("\n{var_name} = {}", self.state.display(&value));
+		self.state.assign_var(var_name, value);
+	}
+
+	fn is_var(&self, name: &str) -> bool {
+		self.state.variables().any(|(var_name, _)| var_name == name)
+	}
+
+	// Like `a = 1; b = 2`, the statements run in order up to the first error, which
+	// comes with its line in the input, from 1
+	fn evaluate(&mut self, input: &str) -> std::result::Result<(), (usize, Report)> {
+		self.state.interrupt_flag().store(false, Ordering::Relaxed);
+
+		let config = self.state.settings().lexer_config();
+		let (tokens, spans): (Vec<_>, Vec<_>) = lexer::try_tokenize_spanned(0, input, &config)
+			.map_err(|e| (e.position().map_or(1, |(line, _)| line), e.into()))?
+			.into_iter()
+			.map(|spanned| (spanned.token, spanned.span))
+			.unzip();
+		let stmts = ast::ASTNode::parse_statements(&tokens)
+			.map_err(|(idx, e)| (spans[idx].line, e.into()))?;
+
+		for (range, ast) in stmts {
+			let (first, last) = (spans[range.start], spans[range.end - 1]);
+			let source: String = input
+				.chars()
+				.skip(first.start)
+				.take(last.end - first.start)
+				.collect();
+
+			let start = Instant::now();
+			let res = self.state.evaluator().evaluate_stmt(ast, &mut self.state);
+			self.state.profiler_mut().record_statement(&source, start);
+
+			if let Some(report) = self.state.report_mut() {
+				report.end_statement(&source, res.as_ref().err().map(|e| e.to_string()));
+				if let Err(e) = report.write() {
+					eprintln!("{}", tr_with("Could not write the report: {0}", &[&e]));
+				}
+			}
+			res.map_err(|e| (first.line, e.into()))?;
+		}
 
 		Ok(())
 	}
 }
 
-#[derive(Default)]
-pub struct Prompt {}
+impl Default for Repl {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+fn line_error(path: &str, line_number: usize, error: Report) -> Report {
+	eyre!(tr_with(
+		"{0}, line {1}: {2}",
+		&[&path, &line_number, &format!("{error:#}")]
+	))
+}
+
+// Whether the input leaves a block like `if` open, it then goes on on the next lines
+fn opens_block(input: &str, config: &LexerConfig) -> bool {
+	lexer::try_tokenize_with(0, input, config).is_ok_and(|tokens| ast::open_blocks(&tokens) > 0)
+}
+
+// Enter starts a new line instead of evaluating while a block is open
+struct BlockValidator {
+	lexer_config: Arc<Mutex<LexerConfig>>,
+}
+
+impl reedline::Validator for BlockValidator {
+	fn validate(&self, line: &str) -> reedline::ValidationResult {
+		let config = *self
+			.lexer_config
+			.lock()
+			.unwrap_or_else(PoisonError::into_inner);
+		match opens_block(line, &config) {
+			true => reedline::ValidationResult::Incomplete,
+			false => reedline::ValidationResult::Complete,
+		}
+	}
+}
+
+// Read before the line editor starts, so the terminal is not in raw mode yet
+fn ask(question: &str) -> bool {
+	print!("\n{question}");
+	let _ = std::io::Write::flush(&mut std::io::stdout());
+
+	let mut answer = String::new();
+	if std::io::stdin().read_line(&mut answer).is_err() {
+		return false;
+	}
+	let answer = answer.trim().to_lowercase();
+	["y", "yes", tr("y"), tr("yes")].contains(&answer.as_str())
+}
+
+// The REPL is where what statements print goes to the standard output, warnings
+// and steps as they come, before the result of the statement
+struct Printer {
+	palette: SharedPalette,
+}
+
+impl EngineObserver for Printer {
+	fn on_warning(&mut self, warning: &Warning) {
+		let message = tr_with("warning: {0}", &[warning]);
+		let style = theme::stderr_style(theme::lock(&self.palette).warning);
+		eprintln!("\n{}", style.paint(format::escape_controls(&message)));
+	}
+
+	fn on_step(&mut self, step: &Step) {
+		println!("\n{}:\n{}", step.description, step.matrix);
+	}
+
+	fn on_output(&mut self, text: &str) {
+		println!("\n{text}");
+	}
+}
+
+pub struct Prompt {
+	pub color: reedline::Color,
+}
+
+impl Default for Prompt {
+	fn default() -> Self {
+		Self {
+			color: theme::prompt_color(Palette::initial().prompt),
+		}
+	}
+}
 
 impl reedline::Prompt for Prompt {
-	fn render_prompt_left(&self) -> std::borrow::Cow<str> {
+	fn get_prompt_color(&self) -> reedline::Color {
+		self.color
+	}
+
+	fn get_indicator_color(&self) -> reedline::Color {
+		self.color
+	}
+
+	fn render_prompt_left(&self) -> std::borrow::Cow<'_, str> {
 		std::borrow::Cow::Borrowed(" \nnam")
 	}
 
-	fn render_prompt_right(&self) -> std::borrow::Cow<str> {
+	fn render_prompt_right(&self) -> std::borrow::Cow<'_, str> {
 		std::borrow::Cow::Borrowed("")
 	}
 
 	fn render_prompt_indicator(
 		&self,
 		_prompt_mode: reedline::PromptEditMode,
-	) -> std::borrow::Cow<str> {
+	) -> std::borrow::Cow<'_, str> {
 		std::borrow::Cow::Borrowed(" > ")
 	}
 
-	fn render_prompt_multiline_indicator(&self) -> std::borrow::Cow<str> {
+	fn render_prompt_multiline_indicator(&self) -> std::borrow::Cow<'_, str> {
 		std::borrow::Cow::Borrowed(" > ")
 	}
 
 	fn render_prompt_history_search_indicator(
 		&self,
 		_history_search: reedline::PromptHistorySearch,
-	) -> std::borrow::Cow<str> {
+	) -> std::borrow::Cow<'_, str> {
 		std::borrow::Cow::Borrowed(" > ")
 	}
 }
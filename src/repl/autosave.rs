@@ -0,0 +1,76 @@
+use {
+	crate::{
+		dirs,
+		state::State,
+		workspace::{self, WorkspaceError},
+	},
+	std::{
+		path::PathBuf,
+		time::{Duration, Instant},
+	},
+};
+
+/*
+	The workspace is saved as a checkpoint after a statement, at most once a minute,
+	while the REPL runs, and the file is removed when it exits normally. A file left at the next start
+	means the previous session crashed or was killed, so restoring it is offered.
+*/
+
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+pub struct Autosave {
+	path: PathBuf,
+	last_save: Option<Instant>,
+}
+
+impl Autosave {
+	pub fn new() -> Option<Self> {
+		// The workspace functions take the path as a string
+		let path = dirs::autosave_file().filter(|path| path.to_str().is_some())?;
+
+		Some(Self {
+			path,
+			last_save: None,
+		})
+	}
+
+	/// Whether the previous session left its workspace behind.
+	pub fn exists(&self) -> bool {
+		self.path.exists()
+	}
+
+	/// Saves the workspace after the first statement, then if the last save is old enough.
+	pub fn tick(&mut self, state: &State) -> Result<(), WorkspaceError> {
+		if self
+			.last_save
+			.is_some_and(|last_save| last_save.elapsed() < AUTOSAVE_INTERVAL)
+		{
+			return Ok(());
+		}
+		self.last_save = Some(Instant::now());
+		self.save(state)
+	}
+
+	// Written next to the file then renamed, so a crash while saving keeps the previous save
+	fn save(&self, state: &State) -> Result<(), WorkspaceError> {
+		if let Some(dir) = self.path.parent() {
+			std::fs::create_dir_all(dir).map_err(WorkspaceError::Io)?;
+		}
+
+		let partial = self.path.with_extension("partial");
+		workspace::checkpoint(state, partial.to_str().unwrap())?;
+		std::fs::rename(&partial, &self.path).map_err(WorkspaceError::Io)
+	}
+
+	pub fn restore(&self, state: &mut State) -> Result<usize, WorkspaceError> {
+		workspace::resume(state, self.path.to_str().unwrap())
+	}
+
+	/// Removes the file, once the session exits normally or the user declined to restore it.
+	pub fn discard(&self) -> Result<(), WorkspaceError> {
+		match std::fs::remove_file(&self.path) {
+			Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(WorkspaceError::Io(e)),
+			_ => Ok(()),
+		}
+	}
+}
@@ -0,0 +1,839 @@
+use {
+	super::{
+		paste,
+		theme::{self, Palette, ThemeError},
+		Repl,
+	},
+	crate::{
+		alias::Alias,
+		ast::{ASTNode, ASTNodeKind},
+		clipboard::{self, ClipboardFormat},
+		config, gpu,
+		i18n::{self, tr, tr_with, Locale},
+		lexer::LexerConfig,
+		script::Script,
+		settings::Settings,
+		workspace,
+	},
+	color_eyre::eyre::{eyre, Report, Result},
+	std::{sync::atomic::Ordering, time::Instant},
+};
+
+/// Handles the arguments of a meta-command, the rest of the line after its name.
+pub type Handler = fn(&mut Repl, &str) -> Result<()>;
+
+pub struct Command {
+	pub name: &'static str,
+	pub usage: &'static str,
+	pub help: &'static str,
+	pub handler: Handler,
+
+	/// Whether the command may also be typed without the leading `:`, like `exit`.
+	pub bare: bool,
+}
+
+/// The meta-commands of the REPL, typed as `:name args` instead of an expression.
+pub struct Commands {
+	commands: Vec<Command>,
+}
+
+impl Commands {
+	pub fn new() -> Self {
+		Commands { commands: vec![] }
+	}
+
+	/// Adds a command, replacing any command with the same name.
+	pub fn register(&mut self, command: Command) {
+		self.commands.retain(|c| c.name != command.name);
+		self.commands.push(command);
+	}
+
+	pub fn get(&self, name: &str) -> Option<&Command> {
+		self.commands.iter().find(|c| c.name == name)
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = &Command> {
+		self.commands.iter()
+	}
+}
+
+impl Default for Commands {
+	fn default() -> Self {
+		let mut res = Commands::new();
+
+		res.register(Command {
+			name: "help",
+			usage: ":help [command]",
+			help: "Lists the commands, or describes one of them",
+			handler: help,
+			bare: false,
+		});
+		res.register(Command {
+			name: "exit",
+			usage: ":exit",
+			help: "Leaves the REPL",
+			handler: exit,
+			bare: true,
+		});
+		res.register(Command {
+			name: "clear",
+			usage: ":clear [names...]",
+			help: "Removes the given variables, or all of them",
+			handler: clear,
+			bare: false,
+		});
+		res.register(Command {
+			name: "save",
+			usage: ":save <path>",
+			help: "Saves every variable to a workspace file",
+			handler: save,
+			bare: true,
+		});
+		res.register(Command {
+			name: "load",
+			usage: ":load <path>",
+			help: "Loads the variables of a workspace file",
+			handler: load,
+			bare: true,
+		});
+		res.register(Command {
+			name: "diff",
+			usage: ":diff [path [path]]",
+			help:
+				"Lists the variables added, removed or changed from one workspace file to another, \
+			       or to the current workspace from a file or the last one saved",
+			handler: diff,
+			bare: true,
+		});
+		res.register(Command {
+			name: "reload",
+			usage: ":reload config",
+			help: "Runs the configuration file again, after restoring the default \
+			       settings and removing the aliases",
+			handler: reload,
+			bare: true,
+		});
+		res.register(Command {
+			name: "jobs",
+			usage: ":jobs",
+			help: "Lists the background jobs started with async",
+			handler: jobs,
+			bare: true,
+		});
+		res.register(Command {
+			name: "set",
+			usage: ":set [name [value]]",
+			help: "Changes a setting, or shows the settings",
+			handler: set,
+			bare: false,
+		});
+		res.register(Command {
+			name: "format",
+			usage:
+				":format [short | eng | eng si | frac | exponent <n | off> | thousands <on | off>]",
+			help: "Prints numbers as short as possible, in engineering notation or as \
+			       fractions, in scientific notation from n digits, or with thousands separators",
+			handler: format,
+			bare: true,
+		});
+		res.register(Command {
+			name: "optimize",
+			usage: ":optimize [on | off]",
+			help: "Turns the simplification of statements before evaluating them on or off",
+			handler: optimize,
+			bare: true,
+		});
+		res.register(Command {
+			name: "steps",
+			usage: ":steps [on | off]",
+			help: "Shows the intermediate steps of algorithms like lu and rref",
+			handler: steps,
+			bare: true,
+		});
+		res.register(Command {
+			name: "gpu",
+			usage: ":gpu [on | off]",
+			help: "Runs large matrix products and element by element operations on the GPU \
+			       (experimental)",
+			handler: gpu,
+			bare: true,
+		});
+		res.register(Command {
+			name: "alias",
+			usage: ":alias [name = definition]",
+			help: "Defines and saves an alias like 'sq(x) = x*x', or lists them",
+			handler: alias,
+			bare: true,
+		});
+		res.register(Command {
+			name: "unalias",
+			usage: ":unalias <name>",
+			help: "Removes an alias",
+			handler: unalias,
+			bare: true,
+		});
+		res.register(Command {
+			name: "time",
+			usage: ":time <expr>",
+			help: "Evaluates an expression and prints how long it took",
+			handler: time,
+			bare: false,
+		});
+		res.register(Command {
+			name: "copy",
+			usage: ":copy [tsv | csv]",
+			help: "Places the last result on the clipboard, tab or comma separated",
+			handler: copy,
+			bare: true,
+		});
+		res.register(Command {
+			name: "pastemat",
+			usage: ":pastemat [name]",
+			help: "Reads rows of numbers pasted from a spreadsheet into a matrix, ans by default",
+			handler: pastemat,
+			bare: true,
+		});
+		res.register(Command {
+			name: "profile",
+			usage: ":profile [on | off | report | clear | export <path>]",
+			help: "Records the time spent in each statement and builtin, prints it, or \
+			       exports a trace (Chrome tracing for .json, folded stacks otherwise)",
+			handler: profile,
+			bare: true,
+		});
+		res.register(Command {
+			name: "recompute",
+			usage: ":recompute [name]",
+			help: "Evaluates again the expression last assigned to a variable and the variables \
+			       computed from it, or every variable computed from one that changed since",
+			handler: recompute,
+			bare: true,
+		});
+		res.register(Command {
+			name: "cell",
+			usage: ":cell [<path> | <n> | next | previous]",
+			help: "Opens a script and lists its cells, separated by lines starting with %%, \
+			       or runs one of them as it is now in the file",
+			handler: cell,
+			bare: false,
+		});
+		res.register(Command {
+			name: "stats",
+			usage: ":stats",
+			help: "Prints the statements evaluated, the errors by kind and the time spent \
+			       in matrix operations since the start of the session, kept locally",
+			handler: stats,
+			bare: true,
+		});
+		res.register(Command {
+			name: "lang",
+			usage: ":lang [en | de]",
+			help: "Chooses the language of the messages, or shows it",
+			handler: lang,
+			bare: false,
+		});
+		res.register(Command {
+			name: "theme",
+			usage: ":theme [default | monochrome | high-contrast | <element> <color>]",
+			help: "Chooses the colors of the prompt, errors, warnings and input, or changes \
+			       one of them (prompt, error, warning, number, string, name, operator)",
+			handler: theme,
+			bare: true,
+		});
+
+		res
+	}
+}
+
+fn help(repl: &mut Repl, args: &str) -> Result<()> {
+	if !args.is_empty() {
+		let name = args.trim_start_matches(':');
+		let command = repl
+			.commands
+			.get(name)
+			.ok_or_else(|| eyre!(tr_with("Unknown command ':{0}'", &[&name])))?;
+
+		println!("\n{}\n    {}", command.usage, tr(command.help));
+		return Ok(());
+	}
+
+	let width = repl
+		.commands
+		.iter()
+		.map(|c| c.usage.len())
+		.max()
+		.unwrap_or(0);
+
+	println!();
+	for command in repl.commands.iter() {
+		println!("{:width$}    {}", command.usage, tr(command.help));
+	}
+
+	Ok(())
+}
+
+fn usage(usage: &str) -> Report {
+	eyre!(tr_with("Usage: {0}", &[&usage]))
+}
+
+fn not_deterministic(command: &str) -> Report {
+	eyre!(tr_with(
+		"{0} is not available in deterministic mode",
+		&[&command]
+	))
+}
+
+fn exit(repl: &mut Repl, _args: &str) -> Result<()> {
+	repl.is_running = false;
+	Ok(())
+}
+
+fn clear(repl: &mut Repl, args: &str) -> Result<()> {
+	if args.is_empty() {
+		repl.state.clear_vars();
+		return Ok(());
+	}
+
+	for var_name in args.split_whitespace() {
+		if repl.state.remove_var(var_name).is_none() {
+			println!("\n{}", tr_with("No variable named '{0}'", &[&var_name]));
+		}
+	}
+
+	Ok(())
+}
+
+fn save(repl: &mut Repl, path: &str) -> Result<()> {
+	if path.is_empty() {
+		return Err(usage(":save <path>"));
+	}
+
+	let count = workspace::save(&repl.state, path)?;
+	repl.last_save = Some(path.to_string());
+	println!(
+		"\n{}",
+		tr_with("Saved {0} variable(s) to '{1}'", &[&count, &path])
+	);
+	Ok(())
+}
+
+fn load(repl: &mut Repl, path: &str) -> Result<()> {
+	if path.is_empty() {
+		return Err(usage(":load <path>"));
+	}
+
+	let count = workspace::load(&mut repl.state, path)?;
+	println!(
+		"\n{}",
+		tr_with("Loaded {0} variable(s) from '{1}'", &[&count, &path])
+	);
+	Ok(())
+}
+
+fn diff(repl: &mut Repl, args: &str) -> Result<()> {
+	let current = || {
+		repl.state
+			.variables()
+			.map(|(name, value)| (name.clone(), value.clone()))
+			.collect()
+	};
+
+	let mut args = args.split_whitespace();
+	let diff = match (args.next(), args.next(), args.next()) {
+		(None, _, _) => match &repl.last_save {
+			Some(path) => workspace::diff(workspace::read(path)?, current()),
+			None => return Err(eyre!(tr("No workspace was saved yet"))),
+		},
+		(Some(old), None, _) => workspace::diff(workspace::read(old)?, current()),
+		(Some(old), Some(new), None) => {
+			workspace::diff(workspace::read(old)?, workspace::read(new)?)
+		},
+		_ => return Err(usage(":diff [path [path]]")),
+	};
+
+	match diff.is_empty() {
+		true => println!("\n{}", tr("The workspaces hold the same variables")),
+		false => print!("\n{}", diff.report(repl.state.settings().number_format)),
+	}
+	Ok(())
+}
+
+fn reload(repl: &mut Repl, args: &str) -> Result<()> {
+	if args != "config" {
+		return Err(usage(":reload config"));
+	}
+
+	// The configuration file is found from the environment
+	if repl.state.is_deterministic() {
+		return Err(not_deterministic(":reload"));
+	}
+
+	// Settings and aliases removed from the file should not survive the reload
+	*repl.state.settings_mut() = Settings::default();
+	let names: Vec<String> = repl.state.aliases().map(|(name, _)| name.clone()).collect();
+	for name in names {
+		repl.state.remove_alias(&name);
+	}
+
+	repl.run_config();
+	println!("\n{}", tr("Reloaded the configuration file"));
+	Ok(())
+}
+
+fn jobs(repl: &mut Repl, _args: &str) -> Result<()> {
+	// The status of the jobs depends on how fast they run
+	if repl.state.is_deterministic() {
+		return Err(not_deterministic(":jobs"));
+	}
+
+	let jobs = repl.state.jobs().list();
+	if jobs.is_empty() {
+		println!("\n{}", tr("No jobs"));
+	}
+
+	for (id, is_finished, elapsed) in jobs {
+		let status = if is_finished {
+			tr("done")
+		} else {
+			tr("running")
+		};
+		let elapsed = format!("{:.1}s", elapsed.as_secs_f64());
+		println!(
+			"\n{}",
+			tr_with("job {0}: {1} ({2})", &[&id, &status, &elapsed])
+		);
+	}
+
+	Ok(())
+}
+
+fn set(repl: &mut Repl, args: &str) -> Result<()> {
+	let mut args = args.split_whitespace();
+
+	match (args.next(), args.next(), args.next()) {
+		(Some(name), Some(value), None) => repl.state.settings_mut().set(name, value)?,
+
+		(name, None, None) => {
+			let settings = repl.state.settings().list();
+			let settings: Vec<_> = match name {
+				Some(name) => settings.into_iter().filter(|(n, _)| *n == name).collect(),
+				None => settings,
+			};
+
+			if settings.is_empty() {
+				return Err(eyre!(tr_with(
+					"There is no setting named '{0}'",
+					&[&name.unwrap_or("")]
+				)));
+			}
+			for (name, value) in settings {
+				println!("\n{name} = {value}");
+			}
+		},
+
+		_ => return Err(usage(":set [name [value]]")),
+	}
+
+	Ok(())
+}
+
+fn format(repl: &mut Repl, args: &str) -> Result<()> {
+	let (name, value) =
+		match args.split_whitespace().collect::<Vec<_>>()[..] {
+			[] | ["short"] => ("format", "short"),
+			["eng"] => ("format", "eng"),
+			["eng", "si"] => ("format", "eng-si"),
+			["frac"] => ("format", "frac"),
+			["exponent", threshold] => ("exponent", threshold),
+			["thousands", value] => ("thousands", value),
+			_ => return Err(usage(
+				":format [short | eng | eng si | frac | exponent <n | off> | thousands <on | off>]",
+			)),
+		};
+
+	repl.state.settings_mut().set(name, value)?;
+	Ok(())
+}
+
+fn optimize(repl: &mut Repl, args: &str) -> Result<()> {
+	match args {
+		"" => {
+			let state = if repl.state.settings().optimize {
+				"on"
+			} else {
+				"off"
+			};
+			println!("\noptimize = {state}");
+		},
+		"on" | "off" => repl.state.settings_mut().set("optimize", args)?,
+		_ => return Err(usage(":optimize [on | off]")),
+	}
+	Ok(())
+}
+
+fn steps(repl: &mut Repl, args: &str) -> Result<()> {
+	match args {
+		"" => {
+			let state = if repl.state.settings().steps {
+				"on"
+			} else {
+				"off"
+			};
+			println!("\nsteps = {state}");
+		},
+		"on" | "off" => repl.state.settings_mut().set("steps", args)?,
+		_ => return Err(usage(":steps [on | off]")),
+	}
+	Ok(())
+}
+
+fn gpu(repl: &mut Repl, args: &str) -> Result<()> {
+	match args {
+		"" => {
+			let state = if repl.state.settings().gpu {
+				"on"
+			} else {
+				"off"
+			};
+			println!("\ngpu = {state}");
+		},
+		"on" => {
+			let device = gpu::init()?;
+			println!("\n{}", tr_with("Using {0}", &[&device]));
+			repl.state.settings_mut().set("gpu", args)?;
+		},
+		"off" => repl.state.settings_mut().set("gpu", args)?,
+		_ => return Err(usage(":gpu [on | off]")),
+	}
+	Ok(())
+}
+
+fn alias(repl: &mut Repl, definition: &str) -> Result<()> {
+	if definition.is_empty() {
+		let mut aliases: Vec<_> = repl.state.aliases().collect();
+		aliases.sort_by(|a, b| a.0.cmp(b.0));
+
+		if aliases.is_empty() {
+			println!("\n{}", tr("No aliases"));
+		}
+		for (name, alias) in aliases {
+			match alias {
+				Alias::Expr { params, .. } if !params.is_empty() => {
+					println!("\n{name}({}) = {alias}", params.join(", "))
+				},
+				_ => println!("\n{name} = {alias}"),
+			}
+		}
+		return Ok(());
+	}
+
+	let config = repl.state.settings().lexer_config();
+	let (name, alias) = Alias::parse(definition, &config)?;
+	repl.state.define_alias(name.clone(), alias)?;
+
+	if repl.saves_config() {
+		config::update(
+			|line| defines_alias(line, &name, &config),
+			Some(&format!("alias {definition}")),
+		)
+		.map_err(|e| {
+			eyre!(tr_with(
+				"Could not save the alias to the configuration file: {0}",
+				&[&e]
+			))
+		})?;
+	}
+
+	Ok(())
+}
+
+fn unalias(repl: &mut Repl, name: &str) -> Result<()> {
+	if repl.state.remove_alias(name).is_none() {
+		return Err(eyre!(tr_with("No alias named '{0}'", &[&name])));
+	}
+
+	if repl.saves_config() {
+		let config = repl.state.settings().lexer_config();
+		config::update(|line| defines_alias(line, name, &config), None).map_err(|e| {
+			eyre!(tr_with(
+				"Could not remove the alias from the configuration file: {0}",
+				&[&e]
+			))
+		})?;
+	}
+
+	Ok(())
+}
+
+fn defines_alias(line: &str, name: &str, config: &LexerConfig) -> bool {
+	line.strip_prefix("alias ")
+		.and_then(|definition| Alias::parse(definition, config).ok())
+		.is_some_and(|(alias_name, _)| alias_name == name)
+}
+
+fn time(repl: &mut Repl, expr: &str) -> Result<()> {
+	if repl.state.is_deterministic() {
+		return Err(not_deterministic(":time"));
+	}
+
+	let start = Instant::now();
+	let res = repl.evaluate(expr);
+	let elapsed = format!("{:.6}s", start.elapsed().as_secs_f64());
+	println!("\n{}", tr_with("Elapsed time: {0}", &[&elapsed]));
+
+	res.map_err(|(_, e)| e)
+}
+
+fn profile(repl: &mut Repl, args: &str) -> Result<()> {
+	if repl.state.is_deterministic() {
+		return Err(not_deterministic(":profile"));
+	}
+
+	let profiler = repl.state.profiler_mut();
+	match args {
+		"" => {
+			let state = if profiler.is_enabled() { "on" } else { "off" };
+			println!("\nprofile = {state}");
+		},
+		"on" => profiler.set_enabled(true),
+		"off" => profiler.set_enabled(false),
+		"report" => print!("\n{}", profiler.report()),
+		"clear" => profiler.clear(),
+		_ => {
+			let Some(path) = args.strip_prefix("export ").map(str::trim) else {
+				return Err(usage(
+					":profile [on | off | report | clear | export <path>]",
+				));
+			};
+
+			let trace = match path.ends_with(".json") {
+				true => profiler.chrome_trace(),
+				false => profiler.folded_stacks(),
+			};
+			std::fs::write(path, trace).map_err(|e| {
+				eyre!(tr_with(
+					"Could not write the trace to '{0}': {1}",
+					&[&path, &e]
+				))
+			})?;
+			println!("\n{}", tr_with("Exported the trace to '{0}'", &[&path]));
+		},
+	}
+	Ok(())
+}
+
+fn recompute(repl: &mut Repl, args: &str) -> Result<()> {
+	let names = match args {
+		"" => repl.state.stale_vars(),
+		name if name.contains(char::is_whitespace) => return Err(usage(":recompute [name]")),
+		name => {
+			let definitions = repl.state.definitions();
+			if definitions.get(name).is_none() {
+				return Err(eyre!(tr_with(
+					"'{0}' was not assigned an expression to recompute",
+					&[&name]
+				)));
+			}
+			let mut names = definitions.dependents(name);
+			names.insert(name.to_string());
+			definitions.sorted(&names)
+		},
+	};
+
+	if names.is_empty() {
+		println!("\n{}", tr("Every variable is up to date"));
+		return Ok(());
+	}
+
+	repl.state.interrupt_flag().store(false, Ordering::Relaxed);
+	for name in names {
+		let Some(definition) = repl.state.definitions().get(&name) else {
+			continue;
+		};
+		let mut ast = ASTNode::from(ASTNodeKind::Assignment(
+			name,
+			Box::new(definition.expr.clone()),
+		));
+		ast.print_result = true;
+		repl.state.evaluator().evaluate_stmt(ast, &mut repl.state)?;
+	}
+	Ok(())
+}
+
+fn cell(repl: &mut Repl, args: &str) -> Result<()> {
+	let n = match args {
+		"" => return list_cells(repl),
+		"next" => repl.cell + 1,
+		"previous" => repl.cell.saturating_sub(1),
+		n if n.parse::<usize>().is_ok() => n.parse().unwrap(),
+		path => {
+			Script::read(path)?;
+			repl.script = Some(path.to_string());
+			repl.cell = 0;
+			return list_cells(repl);
+		},
+	};
+
+	// Read again so edits made since are run
+	let path = repl
+		.script
+		.clone()
+		.ok_or_else(|| eyre!(tr("No script is open, use :cell <path>")))?;
+	let script = Script::read(&path)?;
+	let lines = script.cell(n)?.lines.clone();
+
+	repl.cell = n;
+	repl.run_lines(&path, &lines)
+}
+
+// The cell last run is marked with `>`
+fn list_cells(repl: &Repl) -> Result<()> {
+	let path = repl
+		.script
+		.as_deref()
+		.ok_or_else(|| eyre!(tr("No script is open, use :cell <path>")))?;
+	let script = Script::read(path)?;
+
+	let width = script.cells.len().to_string().len();
+	println!();
+	for (i, cell) in script.cells.iter().enumerate() {
+		let marker = if i + 1 == repl.cell { '>' } else { ' ' };
+		let line = tr_with("line {0}", &[&cell.line]);
+		let row = format!("{marker} {:>width$}  {line:<10} {}", i + 1, cell.title);
+		println!("{}", row.trim_end());
+	}
+	Ok(())
+}
+
+fn stats(repl: &mut Repl, args: &str) -> Result<()> {
+	if !args.is_empty() {
+		return Err(usage(":stats"));
+	}
+	if repl.state.is_deterministic() {
+		return Err(not_deterministic(":stats"));
+	}
+
+	print!("\n{}", repl.state.stats().report());
+	Ok(())
+}
+
+fn theme(repl: &mut Repl, args: &str) -> Result<()> {
+	if args.is_empty() {
+		println!("\ntheme = {}", repl.theme);
+		return Ok(());
+	}
+
+	let mut palette = theme::lock(&repl.palette);
+	let replaces: fn(&str, &str) -> bool = match args.split_once(' ') {
+		// A whole theme replaces the elements changed before it
+		None => {
+			let name = theme::THEMES
+				.iter()
+				.find(|&&name| name == args)
+				.ok_or_else(|| ThemeError::UnknownTheme(args.to_string()))?;
+			palette = Palette::from_name(name).unwrap();
+			repl.theme = name;
+			|line, _| line.starts_with(":theme ")
+		},
+		Some((element, style)) => {
+			palette.set(element, style.trim())?;
+			|line, element| line.split_whitespace().take(2).eq([":theme", element])
+		},
+	};
+	*repl.palette.lock().unwrap() = palette;
+
+	if repl.saves_config() {
+		let element = args.split_whitespace().next().unwrap();
+		config::update(
+			|line| replaces(line, element),
+			Some(&format!(":theme {args}")),
+		)
+		.map_err(|e| {
+			eyre!(tr_with(
+				"Could not save the theme to the configuration file: {0}",
+				&[&e]
+			))
+		})?;
+	}
+
+	Ok(())
+}
+
+fn lang(repl: &mut Repl, args: &str) -> Result<()> {
+	if args.is_empty() {
+		println!("\nlang = {}", i18n::locale());
+		return Ok(());
+	}
+
+	let locale = Locale::from_code(args).ok_or_else(|| usage(":lang [en | de]"))?;
+	// `nam --lang` wins over the configuration file
+	if !(repl.loading_config && repl.locale_fixed) {
+		i18n::set_locale(locale);
+	}
+
+	if repl.saves_config() {
+		config::update(
+			|line| line.starts_with(":lang "),
+			Some(&format!(":lang {args}")),
+		)
+		.map_err(|e| {
+			eyre!(tr_with(
+				"Could not save the language to the configuration file: {0}",
+				&[&e]
+			))
+		})?;
+	}
+
+	Ok(())
+}
+
+fn copy(repl: &mut Repl, args: &str) -> Result<()> {
+	let format = match args {
+		"" => ClipboardFormat::default(),
+		_ => ClipboardFormat::from_name(args).ok_or_else(|| usage(":copy [tsv | csv]"))?,
+	};
+
+	let value = repl
+		.state
+		.lookup_var("ans")
+		.ok_or_else(|| eyre!(tr("There is no result to copy yet")))?;
+	clipboard::copy(&repl.state, value, format)?;
+	Ok(())
+}
+
+fn pastemat(repl: &mut Repl, args: &str) -> Result<()> {
+	let var_name = match args {
+		"" => "ans",
+		_ => args,
+	};
+	let is_identifier = var_name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+		&& var_name
+			.chars()
+			.all(|c| c.is_ascii_alphanumeric() || c == '_');
+	if !is_identifier {
+		return Err(usage(":pastemat [name]"));
+	}
+
+	println!(
+		"\n{}",
+		tr("Paste the rows, then press Enter on an empty line:")
+	);
+	let mut text = String::new();
+	for line in std::io::stdin().lines() {
+		let line = line?;
+		if line.trim().is_empty() {
+			break;
+		}
+		text.push_str(&line);
+		text.push('\n');
+	}
+
+	let decimal_comma = repl.state.settings().decimal_comma;
+	let mat = paste::numeric_block(&text, decimal_comma).ok_or_else(|| {
+		eyre!(tr(
+			"The pasted text is not rows of numbers of the same length"
+		))
+	})?;
+	repl.assign_pasted(var_name.to_string(), mat);
+	Ok(())
+}
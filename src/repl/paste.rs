@@ -0,0 +1,50 @@
+use crate::matrix::Matrix;
+
+/// Reads a block of numbers copied from a spreadsheet or a text file, one row per line
+/// with the cells separated by tabs, or by spaces when a line has no tab.
+/// Empty cells between tabs are read as NaN. Returns `None` if a cell is not a number
+/// or the rows do not all have the same length.
+pub fn numeric_block(text: &str, decimal_comma: bool) -> Option<Matrix> {
+	let mut ncols = None;
+	let mut data = vec![];
+	let mut nrows = 0;
+
+	for line in text.lines() {
+		let line = line.trim_end_matches('\r');
+		if line.trim().is_empty() {
+			continue;
+		}
+
+		let cells: Vec<&str> = match line.contains('\t') {
+			true => line.split('\t').map(str::trim).collect(),
+			false => line.split_whitespace().collect(),
+		};
+		if *ncols.get_or_insert(cells.len()) != cells.len() {
+			return None;
+		}
+
+		for cell in cells {
+			data.push(parse_cell(cell, decimal_comma)?);
+		}
+		nrows += 1;
+	}
+
+	Matrix::new(nrows, ncols?, data)
+}
+
+/// Whether the input of the prompt is pasted data rather than a statement:
+/// several lines, or cells separated by tabs, which no statement has.
+pub fn looks_pasted(input: &str) -> bool {
+	input.trim().contains(['\n', '\t'])
+}
+
+fn parse_cell(cell: &str, decimal_comma: bool) -> Option<f64> {
+	if cell.is_empty() {
+		return Some(f64::NAN);
+	}
+
+	match decimal_comma {
+		true => cell.replace(',', ".").parse().ok(),
+		false => cell.parse().ok(),
+	}
+}
@@ -0,0 +1,328 @@
+use {
+	crate::{
+		i18n::tr_with,
+		lexer::{self, LexerConfig, Token},
+	},
+	nu_ansi_term::{Color, Style},
+	reedline::{Highlighter, StyledText},
+	std::{
+		io::IsTerminal,
+		sync::{Arc, Mutex, PoisonError},
+	},
+};
+
+/*
+	The colors of the REPL come from a palette, one of the themes below with
+	single elements changed by `:theme <element> <color>`, both written to the
+	configuration file. Without a configuration, NO_COLOR or an output that is
+	not a terminal asks for monochrome. Errors and warnings are written plain
+	when the standard error is not a terminal, whatever the theme.
+*/
+
+pub const THEMES: &[&str] = &["default", "monochrome", "high-contrast"];
+
+pub const ELEMENTS: &[&str] = &[
+	"prompt", "error", "warning", "number", "string", "name", "operator",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+	pub prompt: Style,
+	pub error: Style,
+	pub warning: Style,
+
+	// Syntax highlighting of the input
+	pub number: Style,
+	pub string: Style,
+	pub name: Style,
+	pub operator: Style,
+}
+
+impl Palette {
+	pub fn from_name(name: &str) -> Option<Self> {
+		let plain = Style::new();
+		let res = match name {
+			"default" => Self {
+				prompt: Color::LightGreen.normal(),
+				error: Color::LightRed.normal(),
+				warning: Color::Yellow.normal(),
+				number: Color::Cyan.normal(),
+				string: Color::Green.normal(),
+				name: plain,
+				operator: Color::Yellow.normal(),
+			},
+			"monochrome" => Self {
+				prompt: plain,
+				error: plain,
+				warning: plain,
+				number: plain,
+				string: plain,
+				name: plain,
+				operator: plain,
+			},
+			"high-contrast" => Self {
+				prompt: Color::LightGreen.bold(),
+				error: Color::LightRed.bold(),
+				warning: Color::LightYellow.bold(),
+				number: Color::LightCyan.bold(),
+				string: Color::LightGreen.bold(),
+				name: Color::LightGray.bold(),
+				operator: Color::LightYellow.bold(),
+			},
+			_ => return None,
+		};
+		Some(res)
+	}
+
+	/// The theme used until `:theme` chooses one.
+	pub fn initial() -> Self {
+		let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+		match no_color || !std::io::stdout().is_terminal() {
+			true => Self::from_name("monochrome").unwrap(),
+			false => Self::from_name("default").unwrap(),
+		}
+	}
+
+	/// Changes one element to a style like `red`, `bold light-blue`, `#ff8800` or `208`.
+	pub fn set(&mut self, element: &str, style: &str) -> Result<(), ThemeError> {
+		let style =
+			parse_style(style).ok_or_else(|| ThemeError::InvalidStyle(style.to_string()))?;
+		let target = match element {
+			"prompt" => &mut self.prompt,
+			"error" => &mut self.error,
+			"warning" => &mut self.warning,
+			"number" => &mut self.number,
+			"string" => &mut self.string,
+			"name" => &mut self.name,
+			"operator" => &mut self.operator,
+			_ => return Err(ThemeError::UnknownElement(element.to_string())),
+		};
+		*target = style;
+		Ok(())
+	}
+
+	fn token_style(&self, token: &Token) -> Style {
+		match token {
+			Token::NumericLiteral(_) => self.number,
+			Token::StringLiteral(_) => self.string,
+			Token::Identifier(_) => self.name,
+			Token::OpAdd
+			| Token::OpSubtract
+			| Token::OpMultiply
+			| Token::OpDivide
+			| Token::OpPower
+			| Token::OpTranspose
+			| Token::OpAssign
+			| Token::OpEqual
+			| Token::OpNotEqual
+			| Token::OpLess
+			| Token::OpGreater
+			| Token::OpLessEqual
+			| Token::OpGreaterEqual
+			| Token::OpNot
+			| Token::OpAnd
+			| Token::OpOr
+			| Token::OpShortAnd
+			| Token::OpShortOr
+			| Token::Colon
+			| Token::Dot
+			| Token::At => self.operator,
+			_ => Style::new(),
+		}
+	}
+}
+
+/// The palette is shared by the REPL, its prompt and the observer printing warnings.
+pub type SharedPalette = Arc<Mutex<Palette>>;
+
+/// The style of a text written to the standard error, plain when it is redirected.
+pub fn stderr_style(style: Style) -> Style {
+	match std::io::stderr().is_terminal() {
+		true => style,
+		false => Style::new(),
+	}
+}
+
+pub fn lock(palette: &SharedPalette) -> Palette {
+	*palette.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn parse_style(text: &str) -> Option<Style> {
+	let mut words = text.split_whitespace();
+	let (bold, color) = match (words.next()?, words.next(), words.next()) {
+		("bold", Some(color), None) => (true, color),
+		(color, None, None) => (false, color),
+		_ => return None,
+	};
+
+	let color = match color {
+		"default" => None,
+		_ => Some(parse_color(color)?),
+	};
+	let style = Style {
+		foreground: color,
+		..Style::new()
+	};
+	Some(if bold { style.bold() } else { style })
+}
+
+fn parse_color(text: &str) -> Option<Color> {
+	if let Some(hex) = text.strip_prefix('#') {
+		let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+		return match hex.len() {
+			6 => Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?)),
+			_ => None,
+		};
+	}
+	if let Ok(index) = text.parse() {
+		return Some(Color::Fixed(index));
+	}
+
+	let res = match text {
+		"black" => Color::Black,
+		"red" => Color::Red,
+		"green" => Color::Green,
+		"yellow" => Color::Yellow,
+		"blue" => Color::Blue,
+		"magenta" => Color::Magenta,
+		"cyan" => Color::Cyan,
+		"white" => Color::White,
+		"gray" => Color::DarkGray,
+		"light-red" => Color::LightRed,
+		"light-green" => Color::LightGreen,
+		"light-yellow" => Color::LightYellow,
+		"light-blue" => Color::LightBlue,
+		"light-magenta" => Color::LightMagenta,
+		"light-cyan" => Color::LightCyan,
+		"light-gray" => Color::LightGray,
+		_ => return None,
+	};
+	Some(res)
+}
+
+/// The color of the prompt as the line editor takes it, which has no bold.
+pub fn prompt_color(style: Style) -> reedline::Color {
+	use reedline::Color as Term;
+
+	match style.foreground {
+		None => Term::Reset,
+		Some(Color::Black) => Term::Black,
+		Some(Color::DarkGray) => Term::DarkGrey,
+		Some(Color::Red) => Term::DarkRed,
+		Some(Color::LightRed) => Term::Red,
+		Some(Color::Green) => Term::DarkGreen,
+		Some(Color::LightGreen) => Term::Green,
+		Some(Color::Yellow) => Term::DarkYellow,
+		Some(Color::LightYellow) => Term::Yellow,
+		Some(Color::Blue) => Term::DarkBlue,
+		Some(Color::LightBlue) => Term::Blue,
+		Some(Color::Purple | Color::Magenta) => Term::DarkMagenta,
+		Some(Color::LightPurple | Color::LightMagenta) => Term::Magenta,
+		Some(Color::Cyan) => Term::DarkCyan,
+		Some(Color::LightCyan) => Term::Cyan,
+		Some(Color::White) => Term::Grey,
+		Some(Color::LightGray) => Term::White,
+		Some(Color::Fixed(index)) => Term::AnsiValue(index),
+		Some(Color::Rgb(r, g, b)) => Term::Rgb { r, g, b },
+		Some(Color::Default) => Term::Reset,
+	}
+}
+
+/// Colors the input with the palette as it is typed. Lines that do not
+/// tokenize yet, like an unterminated string, and commands are left plain.
+pub struct SyntaxHighlighter {
+	pub palette: SharedPalette,
+	pub lexer_config: Arc<Mutex<LexerConfig>>,
+}
+
+impl Highlighter for SyntaxHighlighter {
+	fn highlight(&self, line: &str, _cursor: usize) -> StyledText {
+		let mut res = StyledText::new();
+
+		let config = *self
+			.lexer_config
+			.lock()
+			.unwrap_or_else(PoisonError::into_inner);
+		let tokens = match line.trim_start().starts_with(':') {
+			true => None,
+			false => lexer::try_tokenize_spanned(0, line, &config).ok(),
+		};
+		let Some(tokens) = tokens else {
+			res.push((Style::new(), line.to_string()));
+			return res;
+		};
+
+		// The spans count characters, the styled text is cut at bytes
+		let bytes: Vec<usize> = line
+			.char_indices()
+			.map(|(i, _)| i)
+			.chain([line.len()])
+			.collect();
+		let byte = |c: usize| bytes[c.min(bytes.len() - 1)];
+
+		let palette = lock(&self.palette);
+		let mut end = 0;
+		for token in tokens {
+			let (start, stop) = (byte(token.span.start), byte(token.span.end));
+			if start < end || stop <= start {
+				continue;
+			}
+			if start > end {
+				res.push((Style::new(), line[end..start].to_string()));
+			}
+			res.push((
+				palette.token_style(&token.token),
+				line[start..stop].to_string(),
+			));
+			end = stop;
+		}
+		if end < line.len() {
+			res.push((Style::new(), line[end..].to_string()));
+		}
+		res
+	}
+}
+
+////////////////////////////////
+//       Error Handling       //
+////////////////////////////////
+
+#[derive(Debug)]
+pub enum ThemeError {
+	UnknownTheme(String),
+	UnknownElement(String),
+	InvalidStyle(String),
+}
+
+impl std::error::Error for ThemeError {}
+impl std::fmt::Display for ThemeError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::UnknownTheme(name) => write!(
+				f,
+				"{}",
+				tr_with(
+					"Unknown theme '{0}', expected one of: {1}",
+					&[name, &THEMES.join(", ")]
+				)
+			),
+			Self::UnknownElement(name) => write!(
+				f,
+				"{}",
+				tr_with(
+					"Unknown element '{0}', expected one of: {1}",
+					&[name, &ELEMENTS.join(", ")]
+				)
+			),
+			Self::InvalidStyle(style) => write!(
+				f,
+				"{}",
+				tr_with(
+					"Invalid style '{0}', expected a color like red, light-blue, #ff8800 \
+					 or 208, optionally after bold",
+					&[style]
+				)
+			),
+		}
+	}
+}
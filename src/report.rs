@@ -0,0 +1,91 @@
+use std::{fmt::Write, fs, io};
+
+/*
+	A Markdown document of the statements evaluated at the prompt or by a script,
+	started with `report("out.md")` and finished with `report("off")`. Each statement
+	is written in a code block followed by what it printed, or the error it failed
+	with, so running a script with a report turns it into a document to share.
+	The file is written again after every statement, it can be previewed meanwhile.
+*/
+
+#[derive(Debug, Clone)]
+pub struct Report {
+	path: String,
+	entries: Vec<Entry>,
+
+	// What the statement being evaluated printed so far
+	outputs: Vec<String>,
+	// Whether the statement that started the report ended, it is not part of it
+	started: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+	source: String,
+	outputs: Vec<String>,
+	error: Option<String>,
+}
+
+impl Report {
+	pub fn new(path: String) -> Self {
+		Self {
+			path,
+			entries: vec![],
+			outputs: vec![],
+			started: false,
+		}
+	}
+
+	pub fn path(&self) -> &str {
+		&self.path
+	}
+
+	/// Adds text printed by the statement being evaluated.
+	pub fn add_output(&mut self, text: &str) {
+		self.outputs.push(text.trim_end().to_string());
+	}
+
+	/// Adds the statement that was just evaluated, with the error it failed with if any.
+	pub fn end_statement(&mut self, source: &str, error: Option<String>) {
+		let outputs = std::mem::take(&mut self.outputs);
+		if !self.started {
+			self.started = true;
+			return;
+		}
+
+		self.entries.push(Entry {
+			source: source.trim().to_string(),
+			outputs,
+			error,
+		});
+	}
+
+	/// The document, statements printing nothing share the code block of the ones after them.
+	pub fn markdown(&self) -> String {
+		let mut res = String::new();
+		let mut sources: Vec<&str> = vec![];
+		for entry in &self.entries {
+			sources.push(&entry.source);
+			if entry.outputs.is_empty() && entry.error.is_none() {
+				continue;
+			}
+
+			let _ = writeln!(res, "```matlab\n{}\n```\n", sources.join("\n"));
+			sources.clear();
+			if !entry.outputs.is_empty() {
+				let _ = writeln!(res, "```\n{}\n```\n", entry.outputs.join("\n\n"));
+			}
+			if let Some(error) = &entry.error {
+				let _ = writeln!(res, "> **error:** {}\n", error.replace('\n', " "));
+			}
+		}
+		if !sources.is_empty() {
+			let _ = writeln!(res, "```matlab\n{}\n```\n", sources.join("\n"));
+		}
+		res
+	}
+
+	pub fn write(&self) -> io::Result<()> {
+		fs::write(&self.path, self.markdown())
+	}
+}
@@ -0,0 +1,114 @@
+use {
+	crate::i18n::tr_with,
+	std::{fs, io},
+};
+
+/*
+	A script file is REPL input, statements and commands, run with
+	`nam run file.nam`. Lines starting with `%%` split it into cells numbered from 1,
+	the rest of the marker line being the title of the cell, so a section of a long
+	script can be run on its own with `nam run --cell N` or `:cell N` at the prompt.
+	The lines before the first marker are a cell too, unless they are all empty.
+	Commands take a line each, so block comments `%{ ... %}` are left out here, while
+	the REPL parses the lines between commands together: a matrix or a block like
+	`if ... end` may span several lines, and a line may hold several statements.
+*/
+
+#[derive(Debug, Clone)]
+pub struct Script {
+	pub cells: Vec<Cell>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Cell {
+	pub title: String,
+	/// The line of the marker, or 1 for the lines before the first marker.
+	pub line: usize,
+	/// The non-empty lines with their line numbers.
+	pub lines: Vec<(usize, String)>,
+}
+
+impl Script {
+	pub fn read(path: &str) -> Result<Self, ScriptError> {
+		let content =
+			fs::read_to_string(path).map_err(|e| ScriptError::Read(path.to_string(), e))?;
+		Ok(Self::parse(&content))
+	}
+
+	pub fn parse(content: &str) -> Self {
+		let mut cells = vec![Cell {
+			line: 1,
+			..Cell::default()
+		}];
+		let mut comment_depth = 0;
+		for (i, line) in content.lines().enumerate() {
+			let line = line.trim();
+			match line {
+				"%{" | "#{" => comment_depth += 1,
+				"%}" | "#}" if comment_depth > 0 => {
+					comment_depth -= 1;
+					continue;
+				},
+				_ => {},
+			}
+			if comment_depth > 0 {
+				continue;
+			}
+
+			if let Some(title) = line.strip_prefix("%%") {
+				cells.push(Cell {
+					title: title.trim().to_string(),
+					line: i + 1,
+					lines: vec![],
+				});
+			} else if !line.is_empty() {
+				cells
+					.last_mut()
+					.unwrap()
+					.lines
+					.push((i + 1, line.to_string()));
+			}
+		}
+
+		if cells.len() > 1 && cells[0].lines.is_empty() {
+			cells.remove(0);
+		}
+		Self { cells }
+	}
+
+	/// Every line of the script, as when run without `--cell`.
+	pub fn lines(&self) -> impl Iterator<Item = &(usize, String)> {
+		self.cells.iter().flat_map(|cell| &cell.lines)
+	}
+
+	/// The cell numbered `n`, from 1.
+	pub fn cell(&self, n: usize) -> Result<&Cell, ScriptError> {
+		n.checked_sub(1)
+			.and_then(|i| self.cells.get(i))
+			.ok_or(ScriptError::NoSuchCell(n, self.cells.len()))
+	}
+}
+
+////////////////////////////////
+//       Error Handling       //
+////////////////////////////////
+
+#[derive(Debug)]
+pub enum ScriptError {
+	Read(String, io::Error),
+	NoSuchCell(usize, usize),
+}
+
+impl std::error::Error for ScriptError {}
+impl std::fmt::Display for ScriptError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Read(path, e) => write!(f, "{}", tr_with("Could not read {0}: {1}", &[path, e])),
+			Self::NoSuchCell(n, count) => write!(
+				f,
+				"{}",
+				tr_with("There is no cell {0}, the script has {1}", &[n, count])
+			),
+		}
+	}
+}
@@ -0,0 +1,141 @@
+use {
+	crate::{
+		alias::Alias,
+		builtins::{Builtin, Signature},
+		engine::Engine,
+		state::{RuntimeVal, State},
+	},
+	std::{
+		collections::HashMap,
+		sync::{Arc, Mutex, OnceLock},
+	},
+};
+
+/// Constants, aliases and functions available in every workspace. They are never
+/// modified once built, so workspaces share them instead of each having a copy.
+/// Variables and aliases of the workspace with the same name shadow them, and
+/// the functions of the host cannot replace builtins.
+#[derive(Debug, Clone, Default)]
+pub struct Stdlib {
+	constants: HashMap<String, RuntimeVal>,
+	aliases: HashMap<String, Alias>,
+	functions: HashMap<String, (Builtin, Signature)>,
+}
+
+impl Stdlib {
+	/// `pi`, `e`, `Inf`, `NaN`, `eps`, and `true` and `false` as 1 and 0.
+	pub fn standard() -> Self {
+		Self::default()
+			.with_constant("true", RuntimeVal::Number(1.0))
+			.with_constant("false", RuntimeVal::Number(0.0))
+			.with_constant("pi", RuntimeVal::Number(std::f64::consts::PI))
+			.with_constant("e", RuntimeVal::Number(std::f64::consts::E))
+			.with_constant("Inf", RuntimeVal::Number(f64::INFINITY))
+			.with_constant("NaN", RuntimeVal::Number(f64::NAN))
+			.with_constant("eps", RuntimeVal::Number(f64::EPSILON))
+	}
+
+	/// The standard library shared by every workspace created with `State::new`.
+	pub fn shared() -> Arc<Self> {
+		static SHARED: OnceLock<Arc<Stdlib>> = OnceLock::new();
+		Arc::clone(SHARED.get_or_init(|| Arc::new(Self::standard())))
+	}
+
+	pub fn with_constant(mut self, name: &str, value: RuntimeVal) -> Self {
+		self.constants.insert(name.to_string(), value);
+		self
+	}
+
+	pub fn with_alias(mut self, name: &str, alias: Alias) -> Self {
+		self.aliases.insert(name.to_string(), alias);
+		self
+	}
+
+	/// Adds a function of the host, called like a builtin once its
+	/// arguments are checked against `signature`.
+	pub fn with_function(mut self, name: &str, func: Builtin, signature: Signature) -> Self {
+		self.functions.insert(name.to_string(), (func, signature));
+		self
+	}
+
+	pub fn get_constant(&self, name: &str) -> Option<&RuntimeVal> {
+		self.constants.get(name)
+	}
+
+	pub fn get_alias(&self, name: &str) -> Option<&Alias> {
+		self.aliases.get(name)
+	}
+
+	pub fn get_function(&self, name: &str) -> Option<(Builtin, Signature)> {
+		self.functions.get(name).copied()
+	}
+}
+
+/// Identifies a session of a `SessionManager`.
+pub type SessionId = u64;
+
+/// Hosts many users at once, e.g. a server or a notebook kernel, giving each session
+/// its own engine. Sessions are isolated from each other, only the standard library
+/// is shared, so opening one costs about as much as an empty workspace.
+pub struct SessionManager {
+	stdlib: Arc<Stdlib>,
+	sessions: Mutex<HashMap<SessionId, Engine>>,
+	next_id: Mutex<SessionId>,
+}
+
+impl SessionManager {
+	pub fn new() -> Self {
+		Self::with_stdlib(Stdlib::shared())
+	}
+
+	/// A manager whose sessions all see `stdlib`, e.g. with constants specific to the host.
+	pub fn with_stdlib(stdlib: Arc<Stdlib>) -> Self {
+		Self {
+			stdlib,
+			sessions: Mutex::new(HashMap::new()),
+			next_id: Mutex::new(1),
+		}
+	}
+
+	/// Creates an engine with an empty workspace, not tracked by the manager.
+	pub fn spawn(&self) -> Engine {
+		Engine::from_state(State::with_stdlib(Arc::clone(&self.stdlib)))
+	}
+
+	/// Opens a new session, kept until it is closed.
+	pub fn open(&self) -> (SessionId, Engine) {
+		let id = {
+			let mut next_id = self.next_id.lock().unwrap();
+			let id = *next_id;
+			*next_id += 1;
+			id
+		};
+
+		let engine = self.spawn();
+		self.sessions.lock().unwrap().insert(id, engine.clone());
+		(id, engine)
+	}
+
+	pub fn get(&self, id: SessionId) -> Option<Engine> {
+		self.sessions.lock().unwrap().get(&id).cloned()
+	}
+
+	/// Closes a session, its workspace is dropped once the engines handed out are too.
+	pub fn close(&self, id: SessionId) -> Option<Engine> {
+		self.sessions.lock().unwrap().remove(&id)
+	}
+
+	pub fn len(&self) -> usize {
+		self.sessions.lock().unwrap().len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+}
+
+impl Default for SessionManager {
+	fn default() -> Self {
+		Self::new()
+	}
+}
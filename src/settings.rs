@@ -0,0 +1,268 @@
+use crate::{
+	format::{Notation, NumberFormat},
+	i18n::{tr, tr_with},
+	lexer::LexerConfig,
+};
+
+/// The options of a workspace, changed with `:set name value`.
+#[derive(Debug, Clone)]
+pub struct Settings {
+	/// Use a comma as the decimal separator in literals and CSV files,
+	/// CSV cells are then separated by semicolons.
+	pub decimal_comma: bool,
+
+	/// How numbers are printed, see `format`.
+	pub number_format: NumberFormat,
+
+	/// Simplify the statements before evaluating them, see `optimizer`.
+	pub optimize: bool,
+
+	/// Reuse the results of calls to pure functions whose arguments did not change.
+	pub cache: bool,
+
+	/// Fail when an operation on finite values produces NaN or Inf.
+	pub strict: bool,
+
+	/// Matrices with more elements are printed as a summary, `full(A)` prints them anyway.
+	pub summary_limit: Option<usize>,
+
+	/// How freely the shapes of results and operands are adjusted.
+	pub coercion: Coercion,
+
+	/// Assigning past the end of a matrix pads it with zeros instead of failing,
+	/// `A(5, 5) = 1` makes A at least 5x5.
+	pub grow: bool,
+
+	/// Algorithms like `lu` and `rref` report their intermediate steps, for teaching.
+	pub steps: bool,
+
+	/// Run large matrix operations on the GPU, see `gpu`.
+	pub gpu: bool,
+
+	/// A `while` loop fails after this many iterations, as it probably never ends.
+	pub max_iterations: Option<usize>,
+}
+
+/// How freely shapes are adjusted, changed with `:set coercion`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Coercion {
+	/// Shapes are kept as they are, `A(1, 1)` is a 1x1 matrix.
+	Strict,
+	/// 1x1 results of indexing and of some builtins become numbers.
+	#[default]
+	Scalars,
+	/// 1x1 results become numbers, and a vector is transposed when
+	/// that is the only way an operation fits, with a warning.
+	Permissive,
+}
+
+impl Coercion {
+	pub fn name(self) -> &'static str {
+		match self {
+			Self::Strict => "strict",
+			Self::Scalars => "scalars",
+			Self::Permissive => "permissive",
+		}
+	}
+
+	pub fn from_name(name: &str) -> Option<Self> {
+		match name {
+			"strict" => Some(Self::Strict),
+			"scalars" => Some(Self::Scalars),
+			"permissive" => Some(Self::Permissive),
+			_ => None,
+		}
+	}
+}
+
+const DEFAULT_SUMMARY_LIMIT: usize = 10_000;
+const DEFAULT_MAX_ITERATIONS: usize = 10_000_000;
+
+impl Default for Settings {
+	fn default() -> Self {
+		Self {
+			decimal_comma: false,
+			number_format: NumberFormat::default(),
+			optimize: true,
+			cache: false,
+			strict: false,
+			summary_limit: Some(DEFAULT_SUMMARY_LIMIT),
+			coercion: Coercion::default(),
+			grow: false,
+			steps: false,
+			gpu: false,
+			max_iterations: Some(DEFAULT_MAX_ITERATIONS),
+		}
+	}
+}
+
+impl Settings {
+	pub fn lexer_config(&self) -> LexerConfig {
+		LexerConfig {
+			decimal_comma: self.decimal_comma,
+		}
+	}
+
+	/// The names and current values of all the settings.
+	pub fn list(&self) -> Vec<(&'static str, String)> {
+		let decimal = if self.decimal_comma { "comma" } else { "point" };
+
+		vec![
+			("decimal", decimal.to_string()),
+			("format", self.number_format.notation.name().to_string()),
+			(
+				"exponent",
+				self.number_format
+					.exponent_threshold
+					.map_or(String::from("off"), |threshold| threshold.to_string()),
+			),
+			(
+				"thousands",
+				on_off(self.number_format.thousands).to_string(),
+			),
+			("optimize", on_off(self.optimize).to_string()),
+			("cache", on_off(self.cache).to_string()),
+			("strict", on_off(self.strict).to_string()),
+			(
+				"summary",
+				self.summary_limit
+					.map_or(String::from("off"), |limit| limit.to_string()),
+			),
+			("coercion", self.coercion.name().to_string()),
+			("grow", on_off(self.grow).to_string()),
+			("steps", on_off(self.steps).to_string()),
+			("gpu", on_off(self.gpu).to_string()),
+			(
+				"iterations",
+				self.max_iterations
+					.map_or(String::from("off"), |limit| limit.to_string()),
+			),
+		]
+	}
+
+	pub fn set(&mut self, name: &str, value: &str) -> Result<(), SettingsError> {
+		match name {
+			"decimal" => {
+				self.decimal_comma = match value {
+					"point" => false,
+					"comma" => true,
+					_ => return Err(SettingsError::invalid_value(name, value, "point or comma")),
+				}
+			},
+
+			"format" => {
+				self.number_format.notation = Notation::from_name(value).ok_or_else(|| {
+					SettingsError::invalid_value(name, value, "short, eng, eng-si or frac")
+				})?
+			},
+
+			"exponent" => {
+				self.number_format.exponent_threshold = match value {
+					"off" => None,
+					_ => Some(value.parse().map_err(|_| {
+						SettingsError::invalid_value(name, value, "a number of digits or off")
+					})?),
+				}
+			},
+			"thousands" => self.number_format.thousands = parse_on_off(name, value)?,
+
+			"optimize" => self.optimize = parse_on_off(name, value)?,
+			"cache" => self.cache = parse_on_off(name, value)?,
+			"strict" => self.strict = parse_on_off(name, value)?,
+			"summary" => {
+				self.summary_limit = match value {
+					"off" => None,
+					_ => Some(value.parse().map_err(|_| {
+						SettingsError::invalid_value(name, value, "a number of elements or off")
+					})?),
+				}
+			},
+
+			"coercion" => {
+				self.coercion = Coercion::from_name(value).ok_or_else(|| {
+					SettingsError::invalid_value(name, value, "strict, scalars or permissive")
+				})?
+			},
+
+			"grow" => self.grow = parse_on_off(name, value)?,
+			"steps" => self.steps = parse_on_off(name, value)?,
+			"gpu" => self.gpu = parse_on_off(name, value)?,
+			"iterations" => {
+				self.max_iterations = match value {
+					"off" => None,
+					_ => Some(value.parse().map_err(|_| {
+						SettingsError::invalid_value(name, value, "a number of iterations or off")
+					})?),
+				}
+			},
+
+			_ => return Err(SettingsError::UnknownSetting(name.to_string())),
+		}
+
+		Ok(())
+	}
+}
+
+fn parse_on_off(name: &str, value: &str) -> Result<bool, SettingsError> {
+	match value {
+		"on" => Ok(true),
+		"off" => Ok(false),
+		_ => Err(SettingsError::invalid_value(name, value, "on or off")),
+	}
+}
+
+fn on_off(value: bool) -> &'static str {
+	if value {
+		"on"
+	} else {
+		"off"
+	}
+}
+
+////////////////////////////////
+//       Error Handling       //
+////////////////////////////////
+
+#[derive(Debug)]
+pub enum SettingsError {
+	UnknownSetting(String),
+	InvalidValue {
+		name: String,
+		value: String,
+		/// The accepted values, like "on or off".
+		expected: &'static str,
+	},
+}
+
+impl SettingsError {
+	fn invalid_value(name: &str, value: &str, expected: &'static str) -> Self {
+		Self::InvalidValue {
+			name: name.to_string(),
+			value: value.to_string(),
+			expected,
+		}
+	}
+}
+
+impl std::error::Error for SettingsError {}
+impl std::fmt::Display for SettingsError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::UnknownSetting(name) => {
+				write!(f, "{}", tr_with("There is no setting named '{0}'", &[name]))
+			},
+			Self::InvalidValue {
+				name,
+				value,
+				expected,
+			} => write!(
+				f,
+				"{}",
+				tr_with(
+					"Invalid value '{0}' for the setting {1}, expected {2}",
+					&[value, name, &tr(expected)]
+				)
+			),
+		}
+	}
+}
@@ -1,49 +1,528 @@
-use std::collections::HashMap;
+use {
+	crate::{
+		alias::{Alias, AliasError},
+		ast::ASTNode,
+		backend::{Backend, Evaluator},
+		bigint::BigUint,
+		builtins::{self, Builtin, Signature},
+		dependencies::{Definition, Definitions},
+		format::Displayed,
+		function::Function,
+		hash::ContentHash,
+		jobs::Jobs,
+		matrix::Matrix,
+		observer::EngineObserver,
+		profiler::Profiler,
+		report::Report,
+		session::Stdlib,
+		settings::Settings,
+		stats::SessionStats,
+		steps::{Step, StepReporter},
+		table::Table,
+		warning::Warning,
+	},
+	rand::{rngs::StdRng, Rng, SeedableRng},
+	std::{
+		collections::{BTreeMap, BTreeSet, HashMap},
+		sync::{
+			atomic::{AtomicBool, Ordering},
+			Arc,
+		},
+	},
+};
 
 #[derive(Debug, Clone)]
 pub enum RuntimeVal {
 	Number(f64),
-	Matrix(Vec<Vec<Self>>),
+	/// An exact integer too large for a f64, from `factorial` or `nchoosek`,
+	/// it becomes a Number when used in arithmetic.
+	BigInt(BigUint),
+	String(String),
+	Matrix(Matrix),
+	Table(Table),
+	Function(Function),
 }
 
 impl std::fmt::Display for RuntimeVal {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		match self {
 			Self::Number(n) => write!(f, "{n}"),
-
-			Self::Matrix(s) => {
-				let mut buffer = String::new();
-				buffer.push('[');
-				for i in s {
-					buffer.push_str("\n   ");
-					for j in i {
-						buffer.push_str("  ");
-						buffer.push_str(j.to_string().as_str());
-					}
-				}
-				buffer.push_str("\n]");
-				write!(f, "{buffer}")
-			},
+			Self::BigInt(n) => write!(f, "{n}"),
+			Self::String(s) => write!(f, "{s}"),
+			Self::Matrix(m) => write!(f, "{m}"),
+			Self::Table(t) => write!(f, "{t}"),
+			Self::Function(func) => write!(f, "{func}"),
 		}
 	}
 }
 
 pub struct State {
-	variables: HashMap<String, RuntimeVal>,
+	// Ordered so listings and saved workspaces do not depend on hashing
+	variables: BTreeMap<String, RuntimeVal>,
+	aliases: BTreeMap<String, Alias>,
+	settings: Settings,
+	jobs: Jobs,
+	interrupted: Arc<AtomicBool>,
+	observers: Vec<Box<dyn EngineObserver>>,
+	rng: StdRng,
+	stdlib: Arc<Stdlib>,
+	backend: Backend,
+	profiler: Profiler,
+	stats: SessionStats,
+
+	// What `end` stands for in the subscripts being evaluated, the innermost last
+	index_ends: Vec<usize>,
+
+	// How many alias calls deep the workspace is the scope of, see `eval::call_alias`
+	alias_depth: usize,
+
+	// The seed of the random generator in deterministic mode
+	seed: Option<u64>,
+
+	// Incremented on every change of a variable or alias, `changed_at` keeps the
+	// generation of the last change of each name so cached results can be checked
+	generation: u64,
+	changed_at: HashMap<String, u64>,
+	cache: HashMap<String, CachedOutputs>,
+
+	// How the variables were assigned, for `:recompute`
+	definitions: Definitions,
+
+	// The report being written, see `report`
+	report: Option<Report>,
+}
+
+/// The outputs of a pure call, valid while none of the names it depends on change
+/// or the variables among them are assigned the same values again.
+struct CachedOutputs {
+	generation: u64,
+	// The values of the variables, with their content hash to compare them quickly
+	inputs: HashMap<String, (u64, RuntimeVal)>,
+	outputs: Vec<RuntimeVal>,
 }
 
+// The cache is emptied when it grows past this many calls
+const MAX_CACHED_CALLS: usize = 256;
+
 impl State {
 	pub fn new() -> Self {
+		Self::with_stdlib(Stdlib::shared())
+	}
+
+	/// An empty workspace seeing the constants, aliases and functions of `stdlib`.
+	pub fn with_stdlib(stdlib: Arc<Stdlib>) -> Self {
 		Self {
-			variables: HashMap::new(),
+			variables: BTreeMap::new(),
+			aliases: BTreeMap::new(),
+			settings: Settings::default(),
+			jobs: Jobs::default(),
+			interrupted: Arc::new(AtomicBool::new(false)),
+			observers: vec![],
+			rng: StdRng::from_entropy(),
+			stdlib,
+			backend: Backend::default(),
+			profiler: Profiler::default(),
+			stats: SessionStats::default(),
+			index_ends: vec![],
+			alias_depth: 0,
+			seed: None,
+			generation: 0,
+			changed_at: HashMap::new(),
+			cache: HashMap::new(),
+			definitions: Definitions::default(),
+			report: None,
 		}
 	}
 
+	/// Copies the variables, aliases, settings and backend into a new state,
+	/// with no jobs, observers, profile, statistics, cached calls nor definitions of the variables,
+	/// its own interrupt flag and random generator.
+	/// The standard library is shared. In deterministic mode the seed of the new generator
+	/// is drawn from this one, so every copy gets its own numbers and the same ones each run.
+	pub fn snapshot(&mut self) -> Self {
+		let mut res = Self {
+			variables: self.variables.clone(),
+			aliases: self.aliases.clone(),
+			settings: self.settings.clone(),
+			backend: self.backend,
+			..Self::with_stdlib(Arc::clone(&self.stdlib))
+		};
+		if self.is_deterministic() {
+			res.make_deterministic(self.rng.gen());
+		}
+		res
+	}
+
+	/// Empties the workspace as if it was just created: no variables, aliases, jobs,
+	/// profile nor statistics, and the default settings. The observers, interrupt flag, backend and
+	/// deterministic seed are kept. Without `keep_builtins` the constants of the standard
+	/// library (`pi`, `Inf`, ...), its aliases and functions are gone too, only the builtins remain.
+	pub fn reset(&mut self, keep_builtins: bool) {
+		let stdlib = match keep_builtins {
+			true => Arc::clone(&self.stdlib),
+			false => Arc::new(Stdlib::default()),
+		};
+
+		*self = Self {
+			observers: std::mem::take(&mut self.observers),
+			interrupted: Arc::clone(&self.interrupted),
+			backend: self.backend,
+			seed: self.seed,
+			..Self::with_stdlib(stdlib)
+		};
+		if let Some(seed) = self.seed {
+			self.make_deterministic(seed);
+		}
+	}
+
+	/// Seeds the random generator and turns off everything depending on the time
+	/// or the environment, so the same statements always print the same results.
+	pub fn make_deterministic(&mut self, seed: u64) {
+		self.seed = Some(seed);
+		self.rng = StdRng::seed_from_u64(seed);
+	}
+
+	pub fn is_deterministic(&self) -> bool {
+		self.seed.is_some()
+	}
+
+	pub fn backend(&self) -> Backend {
+		self.backend
+	}
+
+	pub fn set_backend(&mut self, backend: Backend) {
+		self.backend = backend;
+	}
+
+	/// Evaluates the statements of this workspace.
+	pub fn evaluator(&self) -> &'static dyn Evaluator {
+		self.backend.evaluator()
+	}
+
+	pub fn alias_depth(&self) -> usize {
+		self.alias_depth
+	}
+
+	pub fn set_alias_depth(&mut self, depth: usize) {
+		self.alias_depth = depth;
+	}
+
+	/// Makes `end` stand for the given index while evaluating a subscript, until `pop_index_end`.
+	pub fn push_index_end(&mut self, end: usize) {
+		self.index_ends.push(end);
+	}
+
+	pub fn pop_index_end(&mut self) {
+		self.index_ends.pop();
+	}
+
+	/// The value of `end` in the innermost subscript being evaluated.
+	pub fn index_end(&self) -> Option<usize> {
+		self.index_ends.last().copied()
+	}
+
 	pub fn assign_var(&mut self, var_name: String, var_value: RuntimeVal) -> Option<RuntimeVal> {
+		for observer in &mut self.observers {
+			observer.on_assign(&var_name, &var_value);
+		}
+
+		self.mark_changed(&var_name);
+		self.definitions.remove(&var_name);
 		self.variables.insert(var_name, var_value)
 	}
 
+	/// Remembers that a variable was just assigned the value of `expr`,
+	/// which read the variables in `inputs`.
+	pub fn define_var(&mut self, var_name: String, expr: ASTNode, inputs: BTreeSet<String>) {
+		let definition = Definition {
+			expr,
+			inputs,
+			generation: self.generation,
+		};
+		self.definitions.insert(var_name, definition);
+	}
+
+	pub fn definitions(&self) -> &Definitions {
+		&self.definitions
+	}
+
+	/// The variables to compute again since a variable they were computed from changed,
+	/// with the ones computed from them, in the order to compute them.
+	pub fn stale_vars(&self) -> Vec<String> {
+		let mut stale = BTreeSet::new();
+		for (name, definition) in self.definitions.iter() {
+			let changed = definition.inputs.iter().any(|input| {
+				self.changed_at.get(input).copied().unwrap_or(0) > definition.generation
+			});
+			if changed {
+				stale.insert(name.clone());
+				stale.extend(self.definitions.dependents(name));
+			}
+		}
+		self.definitions.sorted(&stale)
+	}
+
 	pub fn get_var(&mut self, var_name: &String) -> Option<&mut RuntimeVal> {
 		self.variables.get_mut(var_name)
 	}
+
+	/// The value of a variable, or of the constant of the standard library it would shadow.
+	pub fn lookup_var(&self, var_name: &str) -> Option<&RuntimeVal> {
+		self.variables
+			.get(var_name)
+			.or_else(|| self.stdlib.get_constant(var_name))
+	}
+
+	pub fn has_var(&self, var_name: &str) -> bool {
+		self.lookup_var(var_name).is_some()
+	}
+
+	pub fn remove_var(&mut self, var_name: &str) -> Option<RuntimeVal> {
+		self.mark_changed(var_name);
+		self.definitions.remove(var_name);
+		self.variables.remove(var_name)
+	}
+
+	pub fn clear_vars(&mut self) {
+		let names: Vec<String> = self.variables.keys().cloned().collect();
+		for name in names {
+			self.mark_changed(&name);
+		}
+		self.variables.clear();
+		self.definitions.clear();
+	}
+
+	pub fn variables(&self) -> impl Iterator<Item = (&String, &RuntimeVal)> {
+		self.variables.iter()
+	}
+
+	/// Defines an alias, unless it would lead back to itself through other aliases.
+	pub fn define_alias(&mut self, name: String, alias: Alias) -> Result<(), AliasError> {
+		let mut target = &alias;
+		while let Alias::Function(target_name) = target {
+			if *target_name == name {
+				return Err(AliasError::Cycle(name));
+			}
+			match self.aliases.get(target_name) {
+				Some(next) => target = next,
+				None => break,
+			}
+		}
+
+		self.mark_changed(&name);
+		self.aliases.insert(name, alias);
+		Ok(())
+	}
+
+	pub fn remove_alias(&mut self, name: &str) -> Option<Alias> {
+		self.mark_changed(name);
+		self.aliases.remove(name)
+	}
+
+	pub fn get_alias(&self, name: &str) -> Option<&Alias> {
+		self.aliases
+			.get(name)
+			.or_else(|| self.stdlib.get_alias(name))
+	}
+
+	/// The builtin of that name, or else the function of the standard library.
+	pub fn get_function(&self, name: &str) -> Option<(Builtin, Signature)> {
+		builtins::get(name).or_else(|| self.stdlib.get_function(name))
+	}
+
+	pub fn aliases(&self) -> impl Iterator<Item = (&String, &Alias)> {
+		self.aliases.iter()
+	}
+
+	pub fn settings(&self) -> &Settings {
+		&self.settings
+	}
+
+	/// The settings to change, which empties the cache of calls since settings like
+	/// the coercion change what the calls return.
+	pub fn settings_mut(&mut self) -> &mut Settings {
+		self.cache.clear();
+		&mut self.settings
+	}
+
+	/// Prints a value following the display settings, like `println!("{}", state.display(&x))`.
+	pub fn display<'a>(&self, value: &'a RuntimeVal) -> Displayed<'a> {
+		Displayed {
+			value,
+			format: self.settings.number_format,
+			summary_limit: self.settings.summary_limit,
+		}
+	}
+
+	/// Prints the output of a statement, like a value or a summary, through the
+	/// observers, see `EngineObserver::on_output`, and adds it to the report.
+	pub fn print(&mut self, text: &str) {
+		for observer in &mut self.observers {
+			observer.on_output(text);
+		}
+		if let Some(report) = &mut self.report {
+			report.add_output(text);
+		}
+	}
+
+	/// Prints `name = value` following the display settings.
+	pub fn print_value(&mut self, name: &str, value: &RuntimeVal) {
+		let text = format!("{name} = {}", self.display(value));
+		self.print(&text);
+	}
+
+	pub fn report_mut(&mut self) -> Option<&mut Report> {
+		self.report.as_mut()
+	}
+
+	/// Starts writing a report, returning the one it replaces.
+	pub fn start_report(&mut self, report: Report) -> Option<Report> {
+		self.report.replace(report)
+	}
+
+	pub fn take_report(&mut self) -> Option<Report> {
+		self.report.take()
+	}
+
+	pub fn profiler(&self) -> &Profiler {
+		&self.profiler
+	}
+
+	pub fn profiler_mut(&mut self) -> &mut Profiler {
+		&mut self.profiler
+	}
+
+	pub fn stats(&self) -> &SessionStats {
+		&self.stats
+	}
+
+	pub fn stats_mut(&mut self) -> &mut SessionStats {
+		&mut self.stats
+	}
+
+	pub fn jobs(&self) -> &Jobs {
+		&self.jobs
+	}
+
+	pub fn jobs_mut(&mut self) -> &mut Jobs {
+		&mut self.jobs
+	}
+
+	/// The flag checked by the evaluator to stop early, set on Ctrl-C.
+	pub fn interrupt_flag(&self) -> Arc<AtomicBool> {
+		Arc::clone(&self.interrupted)
+	}
+
+	pub fn set_interrupt_flag(&mut self, flag: Arc<AtomicBool>) {
+		self.interrupted = flag;
+	}
+
+	pub fn is_interrupted(&self) -> bool {
+		self.interrupted.load(Ordering::Relaxed)
+	}
+
+	pub fn generation(&self) -> u64 {
+		self.generation
+	}
+
+	/// The outputs cached for `key`, if none of the names in `dependencies`
+	/// changed since they were computed, or only variables that hold the same
+	/// values as then, compared by their content hash and then their content.
+	pub fn cached(&mut self, key: &str, dependencies: &[String]) -> Option<&[RuntimeVal]> {
+		let cached = self.cache.get(key)?;
+		let unchanged = dependencies.iter().all(|name| {
+			if self.changed_at.get(name).copied().unwrap_or(0) <= cached.generation {
+				return true;
+			}
+			let same_value = || {
+				let value = self.variables.get(name)?;
+				let (hash, input) = cached.inputs.get(name)?;
+				Some(*hash == value.content_hash() && input.same_content(value))
+			};
+			!self.aliases.contains_key(name) && same_value() == Some(true)
+		});
+		if !unchanged {
+			return None;
+		}
+
+		// Every name was checked, so the values need not be hashed again next time
+		let generation = self.generation;
+		let cached = self.cache.get_mut(key)?;
+		cached.generation = generation;
+		Some(&cached.outputs[..])
+	}
+
+	/// Caches the outputs of a pure call evaluated at `generation`,
+	/// with the values of the variables among its `dependencies`.
+	pub fn cache(
+		&mut self,
+		key: String,
+		generation: u64,
+		dependencies: &[String],
+		outputs: Vec<RuntimeVal>,
+	) {
+		if self.cache.len() >= MAX_CACHED_CALLS {
+			self.cache.clear();
+		}
+
+		let inputs = dependencies
+			.iter()
+			.filter_map(|name| {
+				let value = self.variables.get(name)?;
+				Some((name.clone(), (value.content_hash(), value.clone())))
+			})
+			.collect();
+		self.cache.insert(
+			key,
+			CachedOutputs {
+				generation,
+				inputs,
+				outputs,
+			},
+		);
+	}
+
+	fn mark_changed(&mut self, name: &str) {
+		self.generation += 1;
+		self.changed_at.insert(name.to_string(), self.generation);
+	}
+
+	/// The random generator used by the builtins.
+	pub fn rng(&mut self) -> &mut StdRng {
+		&mut self.rng
+	}
+
+	/// Reports a dubious result to the observers, the REPL prints it.
+	pub fn warn(&mut self, warning: Warning) {
+		for observer in &mut self.observers {
+			observer.on_warning(&warning);
+		}
+	}
+
+	pub fn add_observer(&mut self, observer: Box<dyn EngineObserver>) {
+		self.observers.push(observer);
+	}
+
+	pub fn observers_mut(&mut self) -> &mut [Box<dyn EngineObserver>] {
+		&mut self.observers
+	}
+}
+
+// Algorithms given the workspace report their steps to the observers, see `:steps`
+impl StepReporter for State {
+	fn is_enabled(&self) -> bool {
+		self.settings.steps
+	}
+
+	fn report(&mut self, step: Step) {
+		for observer in &mut self.observers {
+			observer.on_step(&step);
+		}
+	}
+}
+
+impl Default for State {
+	fn default() -> Self {
+		Self::new()
+	}
 }
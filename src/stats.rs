@@ -0,0 +1,80 @@
+use {
+	crate::eval::EvaluationError,
+	std::{
+		collections::BTreeMap,
+		fmt::Write,
+		time::{Duration, Instant},
+	},
+};
+
+/// Counters of what a workspace evaluated since it was created, see `:stats`.
+/// They are always on and never leave the process.
+#[derive(Debug)]
+pub struct SessionStats {
+	started: Instant,
+	statements: usize,
+	errors: BTreeMap<&'static str, usize>,
+	matrix_ops: Entry,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Entry {
+	count: usize,
+	total: Duration,
+}
+
+impl Default for SessionStats {
+	fn default() -> Self {
+		Self {
+			started: Instant::now(),
+			statements: 0,
+			errors: BTreeMap::new(),
+			matrix_ops: Entry::default(),
+		}
+	}
+}
+
+impl SessionStats {
+	/// Counts a top level statement, and its error by kind if it failed.
+	pub fn record_statement(&mut self, error: Option<&EvaluationError>) {
+		self.statements += 1;
+		if let Some(error) = error {
+			*self.errors.entry(error.kind()).or_default() += 1;
+		}
+	}
+
+	/// Counts an arithmetic operation with a matrix operand that started at `start` and just ended.
+	pub fn record_matrix_op(&mut self, start: Instant) {
+		self.matrix_ops.count += 1;
+		self.matrix_ops.total += start.elapsed();
+	}
+
+	pub fn statements(&self) -> usize {
+		self.statements
+	}
+
+	pub fn errors(&self) -> usize {
+		self.errors.values().sum()
+	}
+
+	pub fn report(&self) -> String {
+		let mut res = String::new();
+		let _ = writeln!(
+			res,
+			"  session length      {:.1} s",
+			self.started.elapsed().as_secs_f64()
+		);
+		let _ = writeln!(res, "  statements          {}", self.statements);
+		let _ = writeln!(res, "  errors              {}", self.errors());
+		for (kind, count) in &self.errors {
+			let _ = writeln!(res, "    {kind:<18}{count}");
+		}
+		let _ = writeln!(res, "  matrix operations   {}", self.matrix_ops.count);
+		let _ = writeln!(
+			res,
+			"  time in them        {:.3} ms",
+			self.matrix_ops.total.as_secs_f64() * 1e3
+		);
+		res
+	}
+}
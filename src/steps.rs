@@ -0,0 +1,40 @@
+use crate::matrix::Matrix;
+
+/// An intermediate result of an algorithm, like the matrix after an elimination step.
+#[derive(Debug, Clone)]
+pub struct Step {
+	pub description: String,
+	pub matrix: Matrix,
+}
+
+/// Receives the steps of the algorithms that can explain themselves, like `lu` and
+/// `rref`. A workspace reports them to its observers while `:steps` is on.
+pub trait StepReporter {
+	/// Whether the steps are wanted, they are not built otherwise.
+	fn is_enabled(&self) -> bool;
+
+	fn report(&mut self, step: Step);
+}
+
+impl dyn StepReporter + '_ {
+	/// Reports a step, the description and the copy of the matrix are only made if enabled.
+	pub fn step(&mut self, description: impl FnOnce() -> String, matrix: &Matrix) {
+		if self.is_enabled() {
+			self.report(Step {
+				description: description(),
+				matrix: matrix.clone(),
+			});
+		}
+	}
+}
+
+/// Ignores the steps, for callers that only want the result.
+pub struct NoSteps;
+
+impl StepReporter for NoSteps {
+	fn is_enabled(&self) -> bool {
+		false
+	}
+
+	fn report(&mut self, _step: Step) {}
+}
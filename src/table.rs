@@ -0,0 +1,51 @@
+use crate::{
+	format::{self, NumberFormat},
+	matrix::Matrix,
+};
+
+/// A matrix with named columns, as read by `readcsv` from a file with a header.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+	names: Vec<String>,
+	data: Matrix,
+}
+
+impl Table {
+	/// Returns `None` if there is not exactly one name per column.
+	pub fn new(names: Vec<String>, data: Matrix) -> Option<Self> {
+		if names.len() != data.ncols() {
+			return None;
+		}
+
+		Some(Self { names, data })
+	}
+
+	pub fn names(&self) -> &[String] {
+		&self.names
+	}
+
+	pub fn data(&self) -> &Matrix {
+		&self.data
+	}
+
+	/// Returns the column with the given name as a column vector.
+	pub fn column(&self, name: &str) -> Option<Matrix> {
+		let j = self.names.iter().position(|n| n == name)?;
+		let rows: Vec<_> = (0..self.data.nrows()).collect();
+		Some(self.data.select(&rows, &[j]))
+	}
+
+	/// Returns the sub-table made of the given (0-based) rows and columns, in order.
+	pub fn select(&self, rows: &[usize], cols: &[usize]) -> Self {
+		Self {
+			names: cols.iter().map(|&j| self.names[j].clone()).collect(),
+			data: self.data.select(rows, cols),
+		}
+	}
+}
+
+impl std::fmt::Display for Table {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", format::table(self, NumberFormat::default()))
+	}
+}
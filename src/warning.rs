@@ -0,0 +1,40 @@
+use crate::i18n::{tr, tr_with};
+
+/// A result computed anyway but likely to be inaccurate, reported to the
+/// observers of the workspace instead of failing, see `State::warn`.
+#[derive(Debug, Clone)]
+pub enum Warning {
+	/// A system was solved with a matrix close to singular, `rcond` estimates
+	/// its reciprocal condition number.
+	NearlySingular { rcond: f64 },
+	/// A vector operand was transposed so the operation fits, see `Coercion::Permissive`.
+	Transposed { operand: &'static str },
+}
+
+// Systems whose reciprocal condition number is below this lose most of their digits
+const NEARLY_SINGULAR_RCOND: f64 = 1e-12;
+
+impl Warning {
+	/// The warning for a system solved with this reciprocal condition number, if any.
+	pub fn check_rcond(rcond: f64) -> Option<Self> {
+		(rcond < NEARLY_SINGULAR_RCOND).then_some(Self::NearlySingular { rcond })
+	}
+}
+
+impl std::fmt::Display for Warning {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let message = match self {
+			Self::NearlySingular { rcond } => tr_with(
+				"Matrix is close to singular, results may be inaccurate (rcond = {0})",
+				&[&format!("{rcond:.1e}")],
+			),
+			Self::Transposed { operand: "left" } => {
+				tr("The left operand was transposed to fit the operation").to_string()
+			},
+			Self::Transposed { .. } => {
+				tr("The right operand was transposed to fit the operation").to_string()
+			},
+		};
+		write!(f, "{message}")
+	}
+}
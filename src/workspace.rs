@@ -0,0 +1,712 @@
+use {
+	crate::{
+		alias::Alias,
+		format::{escape_controls, number, NumberFormat},
+		function::Function,
+		hash::ContentHash,
+		i18n::{tr, tr_with},
+		matrix::{Labels, Matrix},
+		state::{RuntimeVal, State},
+		table::Table,
+	},
+	std::{
+		borrow::Cow,
+		collections::{BTreeMap, HashMap},
+	},
+};
+
+/*
+	Binary workspace format used by `save` and `load`,
+	all integers and floats are stored in little endian.
+
+	magic    b"NAMW"
+	version  u8
+	flags    u8   (bit 0: the body is zstd compressed, bit 1: the body is a checkpoint)
+	body:
+		count  u64
+		count times:
+			name_len  u64
+			name      [u8; name_len] (UTF-8)
+			tag       u8 (0: number, 1: matrix, 2: string, 3: table, 4: big integer,
+					  5: function, 6: bound function, 7: labeled matrix, 8: same value)
+			number => value f64
+			matrix => nrows u64, ncols u64, [f64; nrows * ncols] (row-major)
+			string => len u64, [u8; len] (UTF-8)
+			table  => ncols u64, ncols times a string (the column names), then a matrix
+			big integer => a string of its decimal digits
+			function => a string of its name
+			bound function => the function with its tag, count u64, count values
+			labeled matrix => nrows u64, nrows times a string (the row names), then
+					  the same for the columns, then a matrix (no names when the count is 0)
+			same value => index u64, the value is the one of an earlier variable, so
+					  a value held by several variables is stored once (version 2)
+		checkpoints continue with the rest of the workspace:
+		count  u64
+		count times:
+			name   a string (the setting)
+			value  a string, as given to `:set`
+		count  u64
+		count times:
+			definition  a string (the alias, as given to `alias`)
+*/
+
+const MAGIC: &[u8; 4] = b"NAMW";
+const VERSION: u8 = 2;
+
+// Files written before values were stored once can still be read
+const OLDEST_VERSION: u8 = 1;
+
+const FLAG_ZSTD: u8 = 0b0000_0001;
+const FLAG_CHECKPOINT: u8 = 0b0000_0010;
+
+const TAG_NUMBER: u8 = 0;
+const TAG_MATRIX: u8 = 1;
+const TAG_STRING: u8 = 2;
+const TAG_TABLE: u8 = 3;
+const TAG_BIGINT: u8 = 4;
+const TAG_FUNCTION: u8 = 5;
+const TAG_BOUND: u8 = 6;
+const TAG_LABELED: u8 = 7;
+const TAG_SAME: u8 = 8;
+
+pub fn save(state: &State, path: &str) -> Result<usize, WorkspaceError> {
+	let count = state.variables().count();
+
+	let bytes = encode(state.variables())?;
+	std::fs::write(path, bytes).map_err(WorkspaceError::Io)?;
+
+	Ok(count)
+}
+
+pub fn load(state: &mut State, path: &str) -> Result<usize, WorkspaceError> {
+	let bytes = std::fs::read(path).map_err(WorkspaceError::Io)?;
+
+	let vars = decode(&bytes)?;
+	let count = vars.len();
+
+	for (var_name, var_value) in vars {
+		state.assign_var(var_name, var_value);
+	}
+
+	Ok(count)
+}
+
+/// Saves everything needed to carry on a computation later with `resume`:
+/// the variables, like `save`, but also the settings and the aliases.
+/// The random generator is not saved, it starts over when resuming.
+pub fn checkpoint(state: &State, path: &str) -> Result<usize, WorkspaceError> {
+	let count = state.variables().count();
+
+	let mut body = encode_variables(state.variables());
+
+	let settings = state.settings().list();
+	body.extend_from_slice(&(settings.len() as u64).to_le_bytes());
+	for (name, value) in settings {
+		encode_str(&mut body, name);
+		encode_str(&mut body, &value);
+	}
+
+	let aliases: Vec<_> = state.aliases().collect();
+	body.extend_from_slice(&(aliases.len() as u64).to_le_bytes());
+	for (name, alias) in aliases {
+		let definition = match alias {
+			Alias::Function(target) => format!("{name} = {target}"),
+			Alias::Expr { params, source, .. } => {
+				format!("{name}({}) = {source}", params.join(", "))
+			},
+		};
+		encode_str(&mut body, &definition);
+	}
+
+	let bytes = finish(body, FLAG_CHECKPOINT)?;
+	std::fs::write(path, bytes).map_err(WorkspaceError::Io)?;
+
+	Ok(count)
+}
+
+/// Replaces the variables, settings and aliases with the ones of a checkpoint.
+pub fn resume(state: &mut State, path: &str) -> Result<usize, WorkspaceError> {
+	let bytes = std::fs::read(path).map_err(WorkspaceError::Io)?;
+
+	let (flags, body) = decode_header(&bytes)?;
+	if flags & FLAG_CHECKPOINT == 0 {
+		return Err(WorkspaceError::NotACheckpoint);
+	}
+
+	let mut body = Reader::new(&body);
+	let vars = body.read_variables()?;
+
+	let mut settings = state.settings().clone();
+	for _ in 0..body.read_len()? {
+		let name = body.read_string()?;
+		let value = body.read_string()?;
+		settings
+			.set(&name, &value)
+			.map_err(|_| WorkspaceError::Corrupted)?;
+	}
+
+	let mut aliases = vec![];
+	for _ in 0..body.read_len()? {
+		let definition = body.read_string()?;
+		aliases.push(
+			Alias::parse(&definition, &settings.lexer_config())
+				.map_err(|_| WorkspaceError::Corrupted)?,
+		);
+	}
+
+	// Nothing is replaced unless the whole checkpoint could be read
+	*state.settings_mut() = settings;
+	state.clear_vars();
+	let names: Vec<String> = state.aliases().map(|(name, _)| name.clone()).collect();
+	for name in names {
+		state.remove_alias(&name);
+	}
+
+	let count = vars.len();
+	for (var_name, var_value) in vars {
+		state.assign_var(var_name, var_value);
+	}
+	for (name, alias) in aliases {
+		state
+			.define_alias(name, alias)
+			.map_err(|_| WorkspaceError::Corrupted)?;
+	}
+
+	Ok(count)
+}
+
+/// The variables that differ between two workspaces, by name.
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceDiff {
+	pub variables: Vec<VariableDiff>,
+}
+
+/// A variable added, removed, or holding a value of a different content hash.
+#[derive(Debug, Clone)]
+pub struct VariableDiff {
+	pub name: String,
+	/// The value in the first workspace, `None` if the variable was added.
+	pub old: Option<RuntimeVal>,
+	/// The value in the second workspace, `None` if the variable was removed.
+	pub new: Option<RuntimeVal>,
+}
+
+impl VariableDiff {
+	/// The largest absolute difference between the elements of the old and
+	/// new values, when both are numeric and of the same shape. NaN elements
+	/// are equal to each other and infinitely far from the numbers.
+	pub fn max_abs_difference(&self) -> Option<f64> {
+		let (old, new) = (numeric(self.old.as_ref()?)?, numeric(self.new.as_ref()?)?);
+		if (old.nrows(), old.ncols()) != (new.nrows(), new.ncols()) {
+			return None;
+		}
+
+		let res = old
+			.as_slice()
+			.iter()
+			.zip(new.as_slice())
+			.map(|(a, b)| match (a.is_nan(), b.is_nan()) {
+				(true, true) => 0.0,
+				(false, false) if a == b => 0.0,
+				(false, false) => (a - b).abs(),
+				_ => f64::INFINITY,
+			})
+			.fold(0.0, f64::max);
+		Some(res)
+	}
+}
+
+fn numeric(value: &RuntimeVal) -> Option<Cow<'_, Matrix>> {
+	match value {
+		RuntimeVal::Number(n) => Some(Cow::Owned(Matrix::from(*n))),
+		RuntimeVal::BigInt(n) => Some(Cow::Owned(Matrix::from(n.to_f64()))),
+		RuntimeVal::Matrix(m) => Some(Cow::Borrowed(m)),
+		RuntimeVal::Table(t) => Some(Cow::Borrowed(t.data())),
+		RuntimeVal::String(_) | RuntimeVal::Function(_) => None,
+	}
+}
+
+// The shape of a value in the report, or its kind when it has none
+fn shape(value: &RuntimeVal) -> String {
+	match value {
+		RuntimeVal::Number(_) | RuntimeVal::BigInt(_) => String::from("1x1"),
+		RuntimeVal::Matrix(m) => format!("{}x{}", m.nrows(), m.ncols()),
+		RuntimeVal::Table(t) => format!("{}x{} table", t.data().nrows(), t.data().ncols()),
+		RuntimeVal::String(_) => String::from("string"),
+		RuntimeVal::Function(_) => String::from("function"),
+	}
+}
+
+impl WorkspaceDiff {
+	pub fn is_empty(&self) -> bool {
+		self.variables.is_empty()
+	}
+
+	/// One line per variable with its change, its shape before and after,
+	/// and the largest absolute difference of its elements.
+	pub fn report(&self, format: NumberFormat) -> String {
+		let rows: Vec<[String; 5]> = self
+			.variables
+			.iter()
+			.map(|variable| {
+				let change = match (&variable.old, &variable.new) {
+					(None, _) => "added",
+					(_, None) => "removed",
+					_ => "changed",
+				};
+				[
+					escape_controls(&variable.name).into_owned(),
+					change.to_string(),
+					variable.old.as_ref().map(shape).unwrap_or_default(),
+					variable.new.as_ref().map(shape).unwrap_or_default(),
+					variable
+						.max_abs_difference()
+						.map(|d| number(d, format))
+						.unwrap_or_default(),
+				]
+			})
+			.collect();
+
+		let header = ["variable", "change", "before", "after", "max |diff|"];
+		let mut widths = header.map(str::len);
+		for row in &rows {
+			for (width, cell) in widths.iter_mut().zip(row) {
+				*width = (*width).max(cell.chars().count());
+			}
+		}
+
+		let mut res = String::new();
+		for row in std::iter::once(header.map(String::from)).chain(rows) {
+			let cells: Vec<String> = row
+				.iter()
+				.zip(widths)
+				.map(|(cell, width)| format!("{cell:<width$}"))
+				.collect();
+			res.push_str("  ");
+			res.push_str(cells.join("  ").trim_end());
+			res.push('\n');
+		}
+		res
+	}
+}
+
+/// Compares two lists of variables, like the ones of a workspace file and of
+/// the current workspace. The values are compared by their content hash.
+pub fn diff(old: Vec<(String, RuntimeVal)>, new: Vec<(String, RuntimeVal)>) -> WorkspaceDiff {
+	let mut old: BTreeMap<String, RuntimeVal> = old.into_iter().collect();
+	let new: BTreeMap<String, RuntimeVal> = new.into_iter().collect();
+
+	let mut res = WorkspaceDiff::default();
+	for (name, new_value) in new {
+		match old.remove(&name) {
+			Some(old_value) if old_value.content_hash() == new_value.content_hash() => {},
+			old_value => res.variables.push(VariableDiff {
+				name,
+				old: old_value,
+				new: Some(new_value),
+			}),
+		}
+	}
+	for (name, old_value) in old {
+		res.variables.push(VariableDiff {
+			name,
+			old: Some(old_value),
+			new: None,
+		});
+	}
+
+	res.variables.sort_by(|a, b| a.name.cmp(&b.name));
+	res
+}
+
+/// The variables saved in a workspace file, or a checkpoint, without loading them.
+pub fn read(path: &str) -> Result<Vec<(String, RuntimeVal)>, WorkspaceError> {
+	let bytes = std::fs::read(path).map_err(WorkspaceError::Io)?;
+	decode(&bytes)
+}
+
+pub fn encode<'a>(
+	vars: impl IntoIterator<Item = (&'a String, &'a RuntimeVal)>,
+) -> Result<Vec<u8>, WorkspaceError> {
+	finish(encode_variables(vars), 0)
+}
+
+fn encode_variables<'a>(vars: impl IntoIterator<Item = (&'a String, &'a RuntimeVal)>) -> Vec<u8> {
+	let vars: Vec<_> = vars.into_iter().collect();
+
+	let mut body = vec![];
+	body.extend_from_slice(&(vars.len() as u64).to_le_bytes());
+
+	// The variables stored so far by the hash of their value, with where the value is
+	let mut stored: HashMap<u64, Vec<(usize, std::ops::Range<usize>)>> = HashMap::new();
+
+	for (index, (var_name, var_value)) in vars.into_iter().enumerate() {
+		body.extend_from_slice(&(var_name.len() as u64).to_le_bytes());
+		body.extend_from_slice(var_name.as_bytes());
+
+		let start = body.len();
+		encode_value(&mut body, var_value);
+		let encoded = start..body.len();
+
+		// Numbers take less room than a reference to them
+		if matches!(var_value, RuntimeVal::Number(_)) {
+			continue;
+		}
+
+		// Equal hashes are checked against the bytes, a collision only costs room
+		let same = stored.entry(var_value.content_hash()).or_default();
+		let earlier = same
+			.iter()
+			.find(|(_, range)| body[range.clone()] == body[encoded.clone()])
+			.map(|(earlier, _)| *earlier);
+		match earlier {
+			Some(earlier) => {
+				body.truncate(start);
+				body.push(TAG_SAME);
+				body.extend_from_slice(&(earlier as u64).to_le_bytes());
+			},
+			None => same.push((index, encoded)),
+		}
+	}
+
+	body
+}
+
+// Compresses the body if possible and puts the header in front of it
+fn finish(body: Vec<u8>, flags: u8) -> Result<Vec<u8>, WorkspaceError> {
+	#[cfg(feature = "zstd")]
+	let body = zstd::encode_all(body.as_slice(), 0).map_err(WorkspaceError::Io)?;
+
+	let flags = if cfg!(feature = "zstd") {
+		flags | FLAG_ZSTD
+	} else {
+		flags
+	};
+
+	let mut res = Vec::with_capacity(MAGIC.len() + 2 + body.len());
+	res.extend_from_slice(MAGIC);
+	res.push(VERSION);
+	res.push(flags);
+	res.extend(body);
+
+	Ok(res)
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Vec<(String, RuntimeVal)>, WorkspaceError> {
+	let (_, body) = decode_header(bytes)?;
+	Reader::new(&body).read_variables()
+}
+
+// Checks the header, returns the flags and the decompressed body
+fn decode_header(bytes: &[u8]) -> Result<(u8, Cow<'_, [u8]>), WorkspaceError> {
+	let mut header = Reader::new(bytes);
+
+	if header.take(MAGIC.len())? != MAGIC {
+		return Err(WorkspaceError::NotAWorkspace);
+	}
+
+	let version = header.read_u8()?;
+	if !(OLDEST_VERSION..=VERSION).contains(&version) {
+		return Err(WorkspaceError::UnsupportedVersion(version));
+	}
+
+	let flags = header.read_u8()?;
+	let body = header.rest();
+
+	if flags & FLAG_ZSTD == 0 {
+		return Ok((flags, Cow::Borrowed(body)));
+	}
+
+	#[cfg(not(feature = "zstd"))]
+	return Err(WorkspaceError::CompressionUnsupported);
+
+	#[cfg(feature = "zstd")]
+	Ok((
+		flags,
+		Cow::Owned(zstd::decode_all(body).map_err(WorkspaceError::Io)?),
+	))
+}
+
+fn encode_value(body: &mut Vec<u8>, value: &RuntimeVal) {
+	match value {
+		RuntimeVal::Number(n) => {
+			body.push(TAG_NUMBER);
+			body.extend_from_slice(&n.to_le_bytes());
+		},
+
+		RuntimeVal::BigInt(n) => {
+			body.push(TAG_BIGINT);
+			encode_str(body, &n.to_string());
+		},
+
+		RuntimeVal::String(s) => {
+			body.push(TAG_STRING);
+			encode_str(body, s);
+		},
+
+		RuntimeVal::Matrix(m) => match m.labels() {
+			Some(labels) => {
+				body.push(TAG_LABELED);
+				for names in [&labels.rows, &labels.cols] {
+					let names = names.as_deref().unwrap_or_default();
+					body.extend_from_slice(&(names.len() as u64).to_le_bytes());
+					for name in names {
+						encode_str(body, name);
+					}
+				}
+				encode_matrix(body, m);
+			},
+			None => {
+				body.push(TAG_MATRIX);
+				encode_matrix(body, m);
+			},
+		},
+
+		RuntimeVal::Table(t) => {
+			body.push(TAG_TABLE);
+			body.extend_from_slice(&(t.names().len() as u64).to_le_bytes());
+			for name in t.names() {
+				encode_str(body, name);
+			}
+			encode_matrix(body, t.data());
+		},
+
+		RuntimeVal::Function(function) => encode_function(body, function),
+	}
+}
+
+fn encode_function(body: &mut Vec<u8>, function: &Function) {
+	match function {
+		Function::Named(name) => {
+			body.push(TAG_FUNCTION);
+			encode_str(body, name);
+		},
+
+		Function::Bound { function, args } => {
+			body.push(TAG_BOUND);
+			encode_function(body, function);
+			body.extend_from_slice(&(args.len() as u64).to_le_bytes());
+			for arg in args {
+				encode_value(body, arg);
+			}
+		},
+	}
+}
+
+fn encode_str(body: &mut Vec<u8>, s: &str) {
+	body.extend_from_slice(&(s.len() as u64).to_le_bytes());
+	body.extend_from_slice(s.as_bytes());
+}
+
+fn encode_matrix(body: &mut Vec<u8>, m: &Matrix) {
+	body.extend_from_slice(&(m.nrows() as u64).to_le_bytes());
+	body.extend_from_slice(&(m.ncols() as u64).to_le_bytes());
+
+	body.reserve(m.len() * 8);
+	for n in m.as_slice() {
+		body.extend_from_slice(&n.to_le_bytes());
+	}
+}
+
+struct Reader<'a> {
+	bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+	fn new(bytes: &'a [u8]) -> Self {
+		Self { bytes }
+	}
+
+	fn take(&mut self, len: usize) -> Result<&'a [u8], WorkspaceError> {
+		if self.bytes.len() < len {
+			return Err(WorkspaceError::Corrupted);
+		}
+
+		let (res, rest) = self.bytes.split_at(len);
+		self.bytes = rest;
+		Ok(res)
+	}
+
+	fn rest(self) -> &'a [u8] {
+		self.bytes
+	}
+
+	fn read_u8(&mut self) -> Result<u8, WorkspaceError> {
+		Ok(self.take(1)?[0])
+	}
+
+	fn read_u64(&mut self) -> Result<u64, WorkspaceError> {
+		Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+	}
+
+	fn read_f64(&mut self) -> Result<f64, WorkspaceError> {
+		Ok(f64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+	}
+
+	fn read_str(&mut self, len: usize) -> Result<String, WorkspaceError> {
+		match std::str::from_utf8(self.take(len)?) {
+			Ok(s) => Ok(s.to_string()),
+			Err(_) => Err(WorkspaceError::Corrupted),
+		}
+	}
+
+	fn read_string(&mut self) -> Result<String, WorkspaceError> {
+		let len = self.read_len()?;
+		self.read_str(len)
+	}
+
+	fn read_variables(&mut self) -> Result<Vec<(String, RuntimeVal)>, WorkspaceError> {
+		let count = self.read_u64()?;
+		let mut res: Vec<(String, RuntimeVal)> = vec![];
+
+		for _ in 0..count {
+			let var_name = self.read_string()?;
+			let var_value = match self.bytes.first() {
+				Some(&TAG_SAME) => {
+					self.read_u8()?;
+					let earlier = self.read_len()?;
+					match res.get(earlier) {
+						Some((_, value)) => value.clone(),
+						None => return Err(WorkspaceError::Corrupted),
+					}
+				},
+				_ => self.read_value()?,
+			};
+			res.push((var_name, var_value));
+		}
+
+		Ok(res)
+	}
+
+	fn read_matrix(&mut self) -> Result<Matrix, WorkspaceError> {
+		let nrows = self.read_len()?;
+		let ncols = self.read_len()?;
+		let len = nrows.checked_mul(ncols).ok_or(WorkspaceError::Corrupted)?;
+		let payload = self.take(len.checked_mul(8).ok_or(WorkspaceError::Corrupted)?)?;
+
+		let data = payload
+			.chunks_exact(8)
+			.map(|n| f64::from_le_bytes(n.try_into().unwrap()))
+			.collect();
+
+		Ok(Matrix::new(nrows, ncols, data).unwrap())
+	}
+
+	fn read_value(&mut self) -> Result<RuntimeVal, WorkspaceError> {
+		let res = match self.read_u8()? {
+			TAG_NUMBER => RuntimeVal::Number(self.read_f64()?),
+
+			TAG_STRING => {
+				let len = self.read_len()?;
+				RuntimeVal::String(self.read_str(len)?)
+			},
+
+			TAG_BIGINT => {
+				let len = self.read_len()?;
+				let digits = self.read_str(len)?;
+				RuntimeVal::BigInt(digits.parse().map_err(|_| WorkspaceError::Corrupted)?)
+			},
+
+			TAG_MATRIX => RuntimeVal::Matrix(self.read_matrix()?),
+
+			TAG_TABLE => {
+				let ncols = self.read_len()?;
+				let mut names = vec![];
+				for _ in 0..ncols {
+					let len = self.read_len()?;
+					names.push(self.read_str(len)?);
+				}
+
+				let data = self.read_matrix()?;
+				RuntimeVal::Table(Table::new(names, data).ok_or(WorkspaceError::Corrupted)?)
+			},
+
+			TAG_LABELED => {
+				let mut read_names = || -> Result<Option<Vec<String>>, WorkspaceError> {
+					let count = self.read_len()?;
+					let mut names = vec![];
+					for _ in 0..count {
+						let len = self.read_len()?;
+						names.push(self.read_str(len)?);
+					}
+					Ok((count > 0).then_some(names))
+				};
+				let labels = Labels {
+					rows: read_names()?,
+					cols: read_names()?,
+				};
+
+				let data = self.read_matrix()?;
+				RuntimeVal::Matrix(data.with_labels(labels).ok_or(WorkspaceError::Corrupted)?)
+			},
+
+			TAG_FUNCTION => {
+				let len = self.read_len()?;
+				RuntimeVal::Function(Function::Named(self.read_str(len)?))
+			},
+
+			TAG_BOUND => {
+				let RuntimeVal::Function(function) = self.read_value()? else {
+					return Err(WorkspaceError::Corrupted);
+				};
+				let count = self.read_len()?;
+				let mut args = vec![];
+				for _ in 0..count {
+					args.push(self.read_value()?);
+				}
+				RuntimeVal::Function(Function::Bound {
+					function: Box::new(function),
+					args,
+				})
+			},
+
+			_ => return Err(WorkspaceError::Corrupted),
+		};
+
+		Ok(res)
+	}
+
+	fn read_len(&mut self) -> Result<usize, WorkspaceError> {
+		usize::try_from(self.read_u64()?).map_err(|_| WorkspaceError::Corrupted)
+	}
+}
+
+////////////////////////////////
+//       Error Handling       //
+////////////////////////////////
+
+#[derive(Debug)]
+pub enum WorkspaceError {
+	Io(std::io::Error),
+	NotAWorkspace,
+	UnsupportedVersion(u8),
+	#[cfg(not(feature = "zstd"))]
+	CompressionUnsupported,
+	NotACheckpoint,
+	Corrupted,
+}
+
+impl std::error::Error for WorkspaceError {}
+impl std::fmt::Display for WorkspaceError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Io(e) => write!(f, "{e}"),
+			Self::NotAWorkspace => write!(f, "{}", tr("The file is not a workspace file")),
+			Self::UnsupportedVersion(v) => {
+				write!(f, "{}", tr_with("Unsupported workspace version {0}", &[v]))
+			},
+			#[cfg(not(feature = "zstd"))]
+			Self::CompressionUnsupported => write!(
+				f,
+				"{}",
+				tr("The workspace is compressed, but nam was built without the 'zstd' feature")
+			),
+			Self::NotACheckpoint => write!(
+				f,
+				"{}",
+				tr("The file is a saved workspace, not a checkpoint")
+			),
+			Self::Corrupted => write!(f, "{}", tr("The workspace file is corrupted")),
+		}
+	}
+}